@@ -2,7 +2,9 @@
 //!
 //! Detection and classification of UWP/Appx packages.
 
-use pieuvre_common::{AppxCategory, AppxInfo, RemovalRisk, Result};
+use pieuvre_common::{
+    AppxCategory, AppxInfo, NativeRegistry, RegistryBackend, RegistryPath, RemovalRisk, Result,
+};
 
 /// List of known bloatware packages
 const KNOWN_BLOATWARE: &[&str] = &[
@@ -52,89 +54,76 @@ const SYSTEM_CRITICAL: &[&str] = &[
     "Microsoft.WindowsAppRuntime",
 ];
 
+/// Per-user package repository (HKCU, backed by `UsrClass.dat`)
+const REPOSITORY_PACKAGES: &str = r"Software\Classes\Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages";
+
+/// Machine-wide package store: `Applications` (provisioned) and one subkey per user SID
+const ALL_USER_STORE: &str = r"SOFTWARE\Microsoft\Windows\CurrentVersion\Appx\AppxAllUserStore";
+
+/// Limit to 200 packages to avoid explosion
+const MAX_PACKAGES: usize = 200;
+
 /// Scans installed Appx packages
 pub fn scan_packages() -> Result<Vec<AppxInfo>> {
-    let mut packages = Vec::new();
-
-    // For now, retrieve from registry
-    use windows::core::PCWSTR;
-    use windows::Win32::System::Registry::{
-        RegCloseKey, RegEnumKeyExW, RegOpenKeyExW, HKEY_CURRENT_USER, KEY_READ,
-    };
-
-    unsafe {
-        let subkey: Vec<u16> = r"Software\Classes\Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages"
-            .encode_utf16()
-            .chain(std::iter::once(0))
-            .collect();
-
-        let mut hkey = Default::default();
-        if RegOpenKeyExW(
-            HKEY_CURRENT_USER,
-            PCWSTR(subkey.as_ptr()),
-            Some(0),
-            KEY_READ,
-            &mut hkey,
-        )
-        .is_ok()
-        {
-            let mut index = 0u32;
-            loop {
-                let mut name_buffer = vec![0u16; 512];
-                let mut name_len = name_buffer.len() as u32;
-
-                let result = RegEnumKeyExW(
-                    hkey,
-                    index,
-                    Some(windows::core::PWSTR(name_buffer.as_mut_ptr())),
-                    &mut name_len,
-                    None,
-                    None,
-                    None,
-                    None,
-                );
-
-                if result.is_err() {
-                    break;
-                }
-
-                let full_name = String::from_utf16_lossy(&name_buffer[..name_len as usize]);
-
-                // Extract package name (part before the _)
-                let name = full_name
-                    .split('_')
-                    .next()
-                    .unwrap_or(&full_name)
-                    .to_string();
-
-                let category = categorize_package(&name);
-                let removal_risk = assess_removal_risk(&name);
-
-                packages.push(AppxInfo {
-                    name: name.clone(),
-                    full_name,
-                    publisher: String::new(),
-                    version: String::new(),
-                    is_provisioned: false,
-                    category,
-                    removal_risk,
-                });
-
-                index += 1;
-
-                // Limit to 200 packages to avoid explosion
-                if index > 200 {
-                    break;
-                }
-            }
+    scan_packages_with(&NativeRegistry)
+}
+
+/// Packages of the current user's repository
+pub fn scan_packages_with(backend: &dyn RegistryBackend) -> Result<Vec<AppxInfo>> {
+    // Dépôt absent (ruche UsrClass.dat non montée) : aucun paquet
+    let names = backend
+        .list_subkeys(&RegistryPath::hkcu(REPOSITORY_PACKAGES))
+        .unwrap_or_default();
+    Ok(names
+        .into_iter()
+        .take(MAX_PACKAGES)
+        .map(|full_name| package_info(full_name, false))
+        .collect())
+}
 
-            let _ = RegCloseKey(hkey);
+/// Packages recorded machine-wide: provisioned for new users, then installed
+/// for an existing user
+pub fn scan_all_users_with(backend: &dyn RegistryBackend) -> Result<Vec<AppxInfo>> {
+    let store = RegistryPath::hklm(ALL_USER_STORE);
+    let mut packages: Vec<AppxInfo> = backend
+        .list_subkeys(&store.join("Applications"))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|full_name| package_info(full_name, true))
+        .collect();
+
+    let users = backend.list_subkeys(&store).unwrap_or_default();
+    for sid in users.iter().filter(|k| k.starts_with("S-1-5-21-")) {
+        for full_name in backend.list_subkeys(&store.join(sid)).unwrap_or_default() {
+            if !packages
+                .iter()
+                .any(|p| p.full_name.eq_ignore_ascii_case(&full_name))
+            {
+                packages.push(package_info(full_name, false));
+            }
         }
     }
-
+    packages.truncate(MAX_PACKAGES);
     Ok(packages)
 }
 
+/// `Name_Version_Arch_ResourceId_PublisherId`
+fn package_info(full_name: String, is_provisioned: bool) -> AppxInfo {
+    let mut parts = full_name.split('_');
+    let name = parts.next().unwrap_or(&full_name).to_string();
+    let version = parts.next().unwrap_or_default().to_string();
+
+    AppxInfo {
+        category: categorize_package(&name),
+        removal_risk: assess_removal_risk(&name),
+        name,
+        full_name,
+        publisher: String::new(),
+        version,
+        is_provisioned,
+    }
+}
+
 fn categorize_package(name: &str) -> AppxCategory {
     let lower = name.to_lowercase();
 
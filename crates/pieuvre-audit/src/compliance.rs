@@ -1,6 +1,29 @@
-use crate::registry::read_dword_value;
-use pieuvre_common::Result;
-use windows::Win32::System::Registry::HKEY_LOCAL_MACHINE;
+//! Compliance Benchmarks
+//!
+//! Rule sets (TOML or JSON) describe the expected state of registry values,
+//! service start types and scheduled tasks. [`ComplianceEvaluator`] reads the
+//! actual state through a [`RegistryBackend`] and a [`ServiceManager`], so the
+//! same rules run on the live system and on offline hives.
+
+use pieuvre_common::{
+    NativeRegistry, NativeServiceManager, PieuvreError, RegistryBackend, RegistryPath,
+    RegistryValue, Result, ServiceManager, ServiceStartType,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Rule sets shipped with pieuvre: (name, TOML)
+pub const BUNDLED_RULESETS: &[(&str, &str)] = &[
+    (
+        "privacy",
+        include_str!("../../../config/compliance/privacy.toml"),
+    ),
+    (
+        "performance",
+        include_str!("../../../config/compliance/performance.toml"),
+    ),
+];
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ComplianceCheck {
@@ -9,6 +32,15 @@ pub struct ComplianceCheck {
     pub status: ComplianceStatus,
     pub expected: String,
     pub actual: String,
+    /// Registry value, service or scheduled task inspected
+    #[serde(default)]
+    pub location: String,
+    #[serde(default)]
+    pub severity: ComplianceSeverity,
+    #[serde(default)]
+    pub rationale: String,
+    #[serde(default)]
+    pub references: Vec<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
@@ -16,29 +48,503 @@ pub enum ComplianceStatus {
     Compliant,
     NonCompliant,
     Error(String),
+    /// Not evaluated on this source (scheduled tasks without a task scheduler)
+    NotApplicable(String),
 }
 
-pub fn check_compliance() -> Result<Vec<ComplianceCheck>> {
-    let mut checks = Vec::new();
-
-    // Exemple : Vérification de la télémétrie
-    let telemetry_val = read_dword_value(
-        HKEY_LOCAL_MACHINE,
-        r"SOFTWARE\Policies\Microsoft\Windows\DataCollection",
-        "AllowTelemetry",
-    )
-    .unwrap_or(1);
-    checks.push(ComplianceCheck {
-        id: "COMP-001".to_string(),
-        name: "Telemetry Disabled".to_string(),
-        status: if telemetry_val == 0 {
-            ComplianceStatus::Compliant
+/// Impact of a failed rule
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ComplianceSeverity {
+    Info,
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+/// Expected value as written in a rule (`expected = 0`, `"Disabled"`, `false`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RuleValue {
+    Bool(bool),
+    Number(u64),
+    Text(String),
+}
+
+impl std::fmt::Display for RuleValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleValue::Bool(b) => write!(f, "{}", b),
+            RuleValue::Number(n) => write!(f, "{}", n),
+            RuleValue::Text(s) => f.write_str(s),
+        }
+    }
+}
+
+impl RuleValue {
+    fn from_registry(value: RegistryValue) -> Self {
+        match value {
+            RegistryValue::Dword(v) => RuleValue::Number(v.into()),
+            RegistryValue::Qword(v) => RuleValue::Number(v),
+            RegistryValue::String(s) | RegistryValue::ExpandString(s) => RuleValue::Text(s),
+            RegistryValue::MultiString(items) => RuleValue::Text(items.join(";")),
+            other => RuleValue::Text(format!("{:?}", other)),
+        }
+    }
+
+    /// Texts are compared case-insensitively, like registry and service names
+    fn same(&self, other: &RuleValue) -> bool {
+        match (self, other) {
+            (RuleValue::Text(a), RuleValue::Text(b)) => a.eq_ignore_ascii_case(b),
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// State inspected by a rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RuleTarget {
+    /// Registry value (`key = 'HKLM\SOFTWARE\...'`)
+    Registry { key: String, value: String },
+    /// Start type of a service (`Automatic`, `Manual`, `Disabled`...)
+    Service { name: String },
+    /// Enabled state of a scheduled task (`path = '\Microsoft\...'`)
+    Task { path: String },
+}
+
+impl RuleTarget {
+    /// `HKLM\...\Value`, `Service DiagTrack`, `Task \Microsoft\...`
+    pub fn location(&self) -> String {
+        match self {
+            RuleTarget::Registry { key, value } => format!("{}\\{}", key, value),
+            RuleTarget::Service { name } => format!("Service {}", name),
+            RuleTarget::Task { path } => format!("Task {}", path),
+        }
+    }
+}
+
+/// How the actual value is compared to `expected`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparator {
+    #[default]
+    Equals,
+    NotEquals,
+    /// Numeric, actual <= expected
+    AtMost,
+    /// Numeric, actual >= expected
+    AtLeast,
+    /// Value, service or task present (no `expected`)
+    Exists,
+    /// Value, service or task missing (no `expected`)
+    Absent,
+}
+
+impl Comparator {
+    fn test(self, actual: Option<&RuleValue>, expected: Option<&RuleValue>) -> bool {
+        match (self, actual, expected) {
+            (Comparator::Exists, actual, _) => actual.is_some(),
+            (Comparator::Absent, actual, _) => actual.is_none(),
+            (_, None, _) | (_, _, None) => false,
+            (Comparator::Equals, Some(a), Some(e)) => a.same(e),
+            (Comparator::NotEquals, Some(a), Some(e)) => !a.same(e),
+            (Comparator::AtMost, Some(RuleValue::Number(a)), Some(RuleValue::Number(e))) => a <= e,
+            (Comparator::AtLeast, Some(RuleValue::Number(a)), Some(RuleValue::Number(e))) => a >= e,
+            _ => false,
+        }
+    }
+
+    fn describe(self, expected: Option<&RuleValue>) -> String {
+        let expected = expected.map(ToString::to_string).unwrap_or_default();
+        match self {
+            Comparator::Equals => expected,
+            Comparator::NotEquals => format!("not {}", expected),
+            Comparator::AtMost => format!("<= {}", expected),
+            Comparator::AtLeast => format!(">= {}", expected),
+            Comparator::Exists => "present".to_string(),
+            Comparator::Absent => "absent".to_string(),
+        }
+    }
+}
+
+/// Benchmark rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComplianceRule {
+    /// `PRIV-001`, `PERF-004`...
+    pub id: String,
+    pub title: String,
+    pub target: RuleTarget,
+    #[serde(default)]
+    pub comparator: Comparator,
+    #[serde(default)]
+    pub expected: Option<RuleValue>,
+    /// Value assumed when the target is not set (Windows default)
+    #[serde(default)]
+    pub default: Option<RuleValue>,
+    #[serde(default)]
+    pub severity: ComplianceSeverity,
+    #[serde(default)]
+    pub rationale: String,
+    /// Benchmark sections, documentation links
+    #[serde(default)]
+    pub references: Vec<String>,
+}
+
+/// Named set of rules
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, rename = "rule")]
+    rules: Vec<ComplianceRule>,
+}
+
+impl RuleSet {
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let rules: Self = toml::from_str(content)
+            .map_err(|e| PieuvreError::Config(format!("Invalid rule set: {}", e)))?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    pub fn from_json(content: &str) -> Result<Self> {
+        let rules: Self = serde_json::from_str(content)
+            .map_err(|e| PieuvreError::Config(format!("Invalid rule set: {}", e)))?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    /// Loads a `.toml` or `.json` file, errors are prefixed with the file path
+    pub fn load_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let rules = if is_json {
+            Self::from_json(&content)
         } else {
-            ComplianceStatus::NonCompliant
-        },
-        expected: "0".to_string(),
-        actual: telemetry_val.to_string(),
-    });
+            Self::from_toml(&content)
+        };
+        rules.map_err(|e| match e {
+            PieuvreError::Config(msg) => {
+                PieuvreError::Config(format!("{}: {}", path.display(), msg))
+            }
+            other => other,
+        })
+    }
+
+    /// Bundled rule set by name (`privacy`, `performance`)
+    pub fn bundled(name: &str) -> Option<Result<Self>> {
+        BUNDLED_RULESETS
+            .iter()
+            .find(|(bundled, _)| bundled.eq_ignore_ascii_case(name))
+            .map(|(_, content)| Self::from_toml(content))
+    }
+
+    /// Bundled name, or path to a rule set file
+    pub fn resolve(name_or_path: &str) -> Result<Self> {
+        if let Some(rules) = Self::bundled(name_or_path) {
+            return rules;
+        }
+        let path = Path::new(name_or_path);
+        if path.is_file() {
+            return Self::load_file(path);
+        }
+        let names: Vec<&str> = BUNDLED_RULESETS.iter().map(|(name, _)| *name).collect();
+        Err(PieuvreError::Config(format!(
+            "Unknown rule set '{}' (bundled: {}, or a .toml/.json file)",
+            name_or_path,
+            names.join(", ")
+        )))
+    }
+
+    pub fn rules(&self) -> &[ComplianceRule] {
+        &self.rules
+    }
+
+    /// Checks ids, targets and expected values; all errors are reported together
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+        let mut seen = HashSet::new();
+
+        if self.name.trim().is_empty() {
+            errors.push("rule set name must not be empty".to_string());
+        }
+        for rule in &self.rules {
+            let id = &rule.id;
+            if id.trim().is_empty() || id.contains(char::is_whitespace) {
+                errors.push(format!("'{}': id must be non-empty, without spaces", id));
+            }
+            if !seen.insert(id.as_str()) {
+                errors.push(format!("'{}': duplicate id", id));
+            }
+            if rule.title.trim().is_empty() {
+                errors.push(format!("'{}': title must not be empty", id));
+            }
+            if let Err(e) = validate_rule(rule) {
+                errors.push(format!("'{}': {}", id, e));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PieuvreError::Config(format!(
+                "Invalid rule set:\n  - {}",
+                errors.join("\n  - ")
+            )))
+        }
+    }
+}
+
+fn validate_rule(rule: &ComplianceRule) -> std::result::Result<(), String> {
+    match &rule.target {
+        RuleTarget::Registry { key, value } => {
+            let path = RegistryPath::parse(key).map_err(|e| e.to_string())?;
+            if path.is_root() {
+                return Err(format!("registry key '{}' targets a hive root", key));
+            }
+            if value.is_empty() {
+                return Err(format!("empty value name under '{}'", key));
+            }
+        }
+        RuleTarget::Service { name } => {
+            if name.trim().is_empty() {
+                return Err("empty service name".to_string());
+            }
+            for value in rule.expected.iter().chain(&rule.default) {
+                let is_start_type =
+                    matches!(value, RuleValue::Text(s) if parse_start_type(s).is_some());
+                if !is_start_type {
+                    return Err(format!(
+                        "service '{}': '{}' is not a start type (Automatic, Manual, Disabled...)",
+                        name, value
+                    ));
+                }
+            }
+        }
+        RuleTarget::Task { path } => {
+            if !path.starts_with('\\') {
+                return Err(format!(
+                    "scheduled task '{}' must be an absolute path (\\...)",
+                    path
+                ));
+            }
+            if rule
+                .expected
+                .iter()
+                .chain(&rule.default)
+                .any(|v| !matches!(v, RuleValue::Bool(_)))
+            {
+                return Err(format!("scheduled task '{}': expected true or false", path));
+            }
+        }
+    }
+
+    match rule.comparator {
+        Comparator::Exists | Comparator::Absent => {
+            if rule.expected.is_some() {
+                return Err("exists/absent take no expected value".to_string());
+            }
+        }
+        Comparator::AtMost | Comparator::AtLeast => {
+            if !matches!(rule.expected, Some(RuleValue::Number(_))) {
+                return Err("at_most/at_least need a numeric expected value".to_string());
+            }
+        }
+        Comparator::Equals | Comparator::NotEquals => {
+            if rule.expected.is_none() {
+                return Err("missing expected value".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_start_type(name: &str) -> Option<ServiceStartType> {
+    [
+        ServiceStartType::Boot,
+        ServiceStartType::System,
+        ServiceStartType::Automatic,
+        ServiceStartType::Manual,
+        ServiceStartType::Disabled,
+    ]
+    .into_iter()
+    .find(|t| format!("{:?}", t).eq_ignore_ascii_case(name))
+}
+
+/// Scheduled task state: `Some(enabled)`, `None` when the task does not exist
+pub type TaskStateFn<'a> = &'a dyn Fn(&str) -> Option<bool>;
+
+/// Evaluates rules against a registry, a service manager and, optionally,
+/// the task scheduler (task rules are not applicable without it)
+pub struct ComplianceEvaluator<'a> {
+    registry: &'a dyn RegistryBackend,
+    services: &'a dyn ServiceManager,
+    tasks: Option<TaskStateFn<'a>>,
+}
+
+impl<'a> ComplianceEvaluator<'a> {
+    pub fn new(registry: &'a dyn RegistryBackend, services: &'a dyn ServiceManager) -> Self {
+        Self {
+            registry,
+            services,
+            tasks: None,
+        }
+    }
+
+    /// Resolves scheduled task states (the crate has no task scheduler access)
+    pub fn with_tasks(mut self, tasks: TaskStateFn<'a>) -> Self {
+        self.tasks = Some(tasks);
+        self
+    }
+
+    pub fn evaluate_all(&self, rules: &RuleSet) -> Vec<ComplianceCheck> {
+        rules.rules.iter().map(|rule| self.evaluate(rule)).collect()
+    }
+
+    /// Results of `rules`, named after the rule set
+    pub fn report(&self, rules: &RuleSet) -> ComplianceReport {
+        ComplianceReport {
+            ruleset: rules.name.clone(),
+            checks: self.evaluate_all(rules),
+        }
+    }
+
+    pub fn evaluate(&self, rule: &ComplianceRule) -> ComplianceCheck {
+        let (status, actual) = match (&rule.target, self.tasks) {
+            // Aucune source pour les tâches planifiées (ruches hors-ligne)
+            (RuleTarget::Task { .. }, None) => (
+                ComplianceStatus::NotApplicable("Scheduled task states unavailable".to_string()),
+                "-".to_string(),
+            ),
+            _ => self.status(rule),
+        };
+
+        ComplianceCheck {
+            id: rule.id.clone(),
+            name: rule.title.clone(),
+            status,
+            expected: rule.comparator.describe(rule.expected.as_ref()),
+            actual,
+            location: rule.target.location(),
+            severity: rule.severity,
+            rationale: rule.rationale.clone(),
+            references: rule.references.clone(),
+        }
+    }
+
+    /// Status and displayed value of an applicable rule
+    fn status(&self, rule: &ComplianceRule) -> (ComplianceStatus, String) {
+        match self.actual(&rule.target) {
+            Ok(actual) => {
+                let value = actual.clone().or_else(|| rule.default.clone());
+                let status = if rule.comparator.test(value.as_ref(), rule.expected.as_ref()) {
+                    ComplianceStatus::Compliant
+                } else {
+                    ComplianceStatus::NonCompliant
+                };
+                let actual = match (actual, &rule.default) {
+                    (Some(actual), _) => actual.to_string(),
+                    (None, Some(default)) => format!("not set (default {})", default),
+                    (None, None) => "not set".to_string(),
+                };
+                (status, actual)
+            }
+            Err(e) => (ComplianceStatus::Error(e.to_string()), "-".to_string()),
+        }
+    }
+
+    /// Current state of a target, `None` when not set / not installed
+    fn actual(&self, target: &RuleTarget) -> Result<Option<RuleValue>> {
+        match target {
+            RuleTarget::Registry { key, value } => Ok(self
+                .registry
+                .read_value(&RegistryPath::parse(key)?, value)?
+                .map(RuleValue::from_registry)),
+            RuleTarget::Service { name } => match self.services.start_type(name) {
+                Ok(raw) => Ok(Some(RuleValue::Text(format!(
+                    "{:?}",
+                    ServiceStartType::from_raw(raw)
+                )))),
+                Err(PieuvreError::ServiceNotFound(_)) => Ok(None),
+                Err(e) => Err(e),
+            },
+            RuleTarget::Task { path } => {
+                let tasks = self.tasks.ok_or_else(|| {
+                    PieuvreError::Unsupported("Scheduled task states unavailable".to_string())
+                })?;
+                Ok(tasks(path).map(RuleValue::Bool))
+            }
+        }
+    }
+}
+
+/// Pass/fail totals of an evaluation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComplianceSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub errors: usize,
+    /// Rules not applicable to the audited source
+    #[serde(default)]
+    pub skipped: usize,
+}
+
+impl ComplianceSummary {
+    pub fn from_checks(checks: &[ComplianceCheck]) -> Self {
+        let mut summary = Self::default();
+        for check in checks {
+            match check.status {
+                ComplianceStatus::Compliant => summary.passed += 1,
+                ComplianceStatus::NonCompliant => summary.failed += 1,
+                ComplianceStatus::Error(_) => summary.errors += 1,
+                ComplianceStatus::NotApplicable(_) => summary.skipped += 1,
+            }
+        }
+        summary
+    }
+
+    pub fn total(&self) -> usize {
+        self.passed + self.failed + self.errors + self.skipped
+    }
+
+    /// Every applicable rule passed (errors count as failures, skipped rules
+    /// do not)
+    pub fn is_compliant(&self) -> bool {
+        self.failed == 0 && self.errors == 0
+    }
+}
+
+/// Results of one rule set, stored and rendered next to the audit report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplianceReport {
+    pub ruleset: String,
+    pub checks: Vec<ComplianceCheck>,
+}
+
+impl ComplianceReport {
+    pub fn summary(&self) -> ComplianceSummary {
+        ComplianceSummary::from_checks(&self.checks)
+    }
+}
+
+/// Bundled privacy baseline on the live system; scheduled task rules are
+/// reported as not applicable
+pub fn check_compliance() -> Result<Vec<ComplianceCheck>> {
+    let rules = RuleSet::bundled("privacy").expect("bundled privacy rule set")?;
+    Ok(ComplianceEvaluator::new(&NativeRegistry, &NativeServiceManager).evaluate_all(&rules))
+}
 
-    Ok(checks)
+/// Bundled privacy baseline on a given registry backend; services are read
+/// from its `Services` keys, scheduled task rules are not applicable
+pub fn check_compliance_with(backend: &dyn RegistryBackend) -> Result<Vec<ComplianceCheck>> {
+    let rules = RuleSet::bundled("privacy").expect("bundled privacy rule set")?;
+    let services = crate::offline::RegistryServiceManager::new(backend);
+    Ok(ComplianceEvaluator::new(backend, &services).evaluate_all(&rules))
 }
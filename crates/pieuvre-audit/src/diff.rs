@@ -0,0 +1,220 @@
+//! Audit Diff
+//!
+//! Structural comparison of two audit reports (an older one, then a newer
+//! one): services, Appx packages, telemetry and security settings, hardware
+//! and OS version. Sections missing from one report (offline audit without
+//! hardware, module disabled in `[audit]`) are not compared.
+
+use chrono::{DateTime, Utc};
+use pieuvre_common::{
+    AppxInfo, AuditReport, HardwareInfo, ServiceInfo, ServiceStartType, ServiceStatus,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Field whose value differs between the two reports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Service present in both reports whose start type or status changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceChange {
+    pub name: String,
+    pub display_name: String,
+    pub before_start_type: ServiceStartType,
+    pub after_start_type: ServiceStartType,
+    pub before_status: ServiceStatus,
+    pub after_status: ServiceStatus,
+}
+
+/// Differences from the `old` report to the `new` one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditDiff {
+    pub old_id: Uuid,
+    pub new_id: Uuid,
+    pub old_timestamp: DateTime<Utc>,
+    pub new_timestamp: DateTime<Utc>,
+    /// Version, build, edition and hostname
+    pub system: Vec<FieldChange>,
+    pub services: Vec<ServiceChange>,
+    /// Only in `new` (same name and scope: a version update is not listed)
+    pub appx_added: Vec<AppxInfo>,
+    /// Only in `old`
+    pub appx_removed: Vec<AppxInfo>,
+    pub telemetry: Vec<FieldChange>,
+    pub security: Vec<FieldChange>,
+    pub hardware: Vec<FieldChange>,
+}
+
+impl AuditDiff {
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of differences, all sections included
+    pub fn len(&self) -> usize {
+        self.system.len()
+            + self.services.len()
+            + self.appx_added.len()
+            + self.appx_removed.len()
+            + self.telemetry.len()
+            + self.security.len()
+            + self.hardware.len()
+    }
+}
+
+/// Compares two audit reports, `old` first
+pub fn diff_reports(old: &AuditReport, new: &AuditReport) -> AuditDiff {
+    // Liste vide : module désactivé, pas « tous les paquets retirés »
+    let compare_appx = !old.appx.is_empty() && !new.appx.is_empty();
+
+    AuditDiff {
+        old_id: old.id,
+        new_id: new.id,
+        old_timestamp: old.timestamp,
+        new_timestamp: new.timestamp,
+        system: compare_fields("", &old.system, &new.system),
+        services: diff_services(&old.services, &new.services),
+        appx_added: if compare_appx {
+            missing_packages(&new.appx, &old.appx)
+        } else {
+            Vec::new()
+        },
+        appx_removed: if compare_appx {
+            missing_packages(&old.appx, &new.appx)
+        } else {
+            Vec::new()
+        },
+        telemetry: compare_fields("", &old.telemetry, &new.telemetry),
+        security: compare_fields("", &old.security, &new.security),
+        hardware: diff_hardware(&old.hardware, &new.hardware),
+    }
+}
+
+/// Services matched by name (case-insensitive)
+fn diff_services(old: &[ServiceInfo], new: &[ServiceInfo]) -> Vec<ServiceChange> {
+    old.iter()
+        .filter_map(|before| {
+            let after = new
+                .iter()
+                .find(|s| s.name.eq_ignore_ascii_case(&before.name))?;
+            (after.start_type != before.start_type || after.status != before.status).then(|| {
+                ServiceChange {
+                    name: before.name.clone(),
+                    display_name: after.display_name.clone(),
+                    before_start_type: before.start_type,
+                    after_start_type: after.start_type,
+                    before_status: before.status,
+                    after_status: after.status,
+                }
+            })
+        })
+        .collect()
+}
+
+/// Packages of `packages` without a package of the same name and scope in `other`
+fn missing_packages(packages: &[AppxInfo], other: &[AppxInfo]) -> Vec<AppxInfo> {
+    packages
+        .iter()
+        .filter(|p| {
+            !other
+                .iter()
+                .any(|o| o.name == p.name && o.is_provisioned == p.is_provisioned)
+        })
+        .cloned()
+        .collect()
+}
+
+fn diff_hardware(old: &HardwareInfo, new: &HardwareInfo) -> Vec<FieldChange> {
+    // Audit hors-ligne ou module désactivé : pas de matériel
+    if old.cpu.model_name.is_empty() || new.cpu.model_name.is_empty() {
+        return Vec::new();
+    }
+
+    let mut changes = compare_fields("cpu.", &old.cpu, &new.cpu);
+    // La mémoire disponible varie d'un audit à l'autre : seule la capacité compte
+    if old.memory.total_bytes != new.memory.total_bytes {
+        changes.push(FieldChange {
+            field: "memory.total_bytes".to_string(),
+            before: old.memory.total_bytes.to_string(),
+            after: new.memory.total_bytes.to_string(),
+        });
+    }
+    changes.extend(diff_keyed("storage", &old.storage, &new.storage, |s| {
+        s.device_id.as_str()
+    }));
+    changes.extend(diff_keyed("gpu", &old.gpu, &new.gpu, |g| g.name.as_str()));
+    changes
+}
+
+/// Items matched on `key`: fields changed, item added or removed
+fn diff_keyed<T: Serialize>(
+    section: &str,
+    old: &[T],
+    new: &[T],
+    key: impl Fn(&T) -> &str,
+) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    for item in old {
+        match new.iter().find(|other| key(other) == key(item)) {
+            Some(other) => changes.extend(compare_fields(
+                &format!("{}[{}].", section, key(item)),
+                item,
+                other,
+            )),
+            None => changes.push(presence_change(section, key(item), false)),
+        }
+    }
+    for item in new {
+        if !old.iter().any(|other| key(other) == key(item)) {
+            changes.push(presence_change(section, key(item), true));
+        }
+    }
+    changes
+}
+
+fn presence_change(section: &str, key: &str, added: bool) -> FieldChange {
+    let (before, after) = if added {
+        ("absent", "present")
+    } else {
+        ("present", "absent")
+    };
+    FieldChange {
+        field: format!("{}[{}]", section, key),
+        before: before.to_string(),
+        after: after.to_string(),
+    }
+}
+
+/// Fields of two values of the same type, compared through their JSON form
+fn compare_fields<T: Serialize>(prefix: &str, old: &T, new: &T) -> Vec<FieldChange> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+
+    old.iter()
+        .filter_map(|(field, before)| {
+            let after = new.get(field).unwrap_or(&Value::Null);
+            (before != after).then(|| FieldChange {
+                field: format!("{}{}", prefix, field),
+                before: display(before),
+                after: display(after),
+            })
+        })
+        .collect()
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "-".to_string(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,297 @@
+//! Backend registre hors-ligne (lecture seule)
+//!
+//! Lit des fichiers de ruche (SOFTWARE, SYSTEM, NTUSER.DAT...) via `nt_hive2`,
+//! sans dépendre du registre de la machine courante.
+
+use memmap2::Mmap;
+use nt_hive2::{CleanHive, Hive, HiveParseMode, KeyNode, KeyValue, SubPath};
+use pieuvre_common::{
+    PieuvreError, RegistryBackend, RegistryHive, RegistryPath, RegistryValue, Result,
+};
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::Cursor;
+use std::path::Path;
+use std::rc::Rc;
+
+enum HiveData {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl HiveData {
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            HiveData::Mapped(m) => m,
+            HiveData::Owned(v) => v,
+        }
+    }
+}
+
+/// Fichier de ruche monté sous un préfixe (ex: HKLM\SOFTWARE)
+struct HiveMount {
+    hive: RegistryHive,
+    prefix: String,
+    data: HiveData,
+}
+
+/// Registre reconstitué à partir de fichiers de ruche
+#[derive(Default)]
+pub struct OfflineHiveRegistry {
+    mounts: Vec<HiveMount>,
+}
+
+type ParsedHive<'a> = Hive<Cursor<&'a [u8]>, CleanHive>;
+
+fn hive_err(e: impl std::fmt::Display) -> PieuvreError {
+    PieuvreError::Registry(format!("Offline hive: {}", e))
+}
+
+fn read_only() -> PieuvreError {
+    PieuvreError::Unsupported("Offline hive backend is read-only".to_string())
+}
+
+/// Types dont `nt_hive2` n'expose pas les données (REG_RESOURCE_LIST,
+/// REG_FILETIME, type inconnu) : une erreur plutôt qu'une valeur vide
+fn convert_value(value: &KeyValue) -> Result<RegistryValue> {
+    use nt_hive2::RegistryValue as Hv;
+    Ok(match value.value() {
+        Hv::RegDWord(v) | Hv::RegDWordBigEndian(v) => RegistryValue::Dword(*v),
+        Hv::RegSZ(s) => RegistryValue::String(s.clone()),
+        Hv::RegExpandSZ(s) => RegistryValue::ExpandString(s.clone()),
+        Hv::RegBinary(b) => RegistryValue::Binary(b.clone()),
+        Hv::RegQWord(v) => RegistryValue::Qword(*v),
+        Hv::RegMultiSZ(list) => RegistryValue::MultiString(list.clone()),
+        // Avec des données : type non décodé par nt_hive2 (REG_RESOURCE_LIST...)
+        Hv::RegNone if value.data_size() == 0 => RegistryValue::None(Vec::new()),
+        // REG_LINK : cible UTF-16 sans terminateur
+        Hv::RegLink(s) => RegistryValue::Raw {
+            value_type: 6,
+            data: s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect(),
+        },
+        _ => {
+            let value_type = value
+                .data_type()
+                .map_or_else(|| "unknown".to_string(), |t| t.to_string());
+            return Err(PieuvreError::Unsupported(format!(
+                "Offline hive: data of value {} ({}) cannot be read",
+                value.name(),
+                value_type
+            )));
+        }
+    })
+}
+
+/// `key` relatif à `prefix`, comparé sans casse ASCII sur la même chaîne
+fn relative_key(key: &str, prefix: &str) -> Option<String> {
+    if prefix.is_empty() {
+        return Some(key.to_string());
+    }
+    let head = key.get(..prefix.len())?;
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    let rest = &key[prefix.len()..];
+    if rest.is_empty() {
+        Some(String::new())
+    } else {
+        rest.strip_prefix('\\').map(str::to_string)
+    }
+}
+
+impl OfflineHiveRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Monte un fichier de ruche sous `hive\prefix`
+    pub fn mount_file(mut self, hive: RegistryHive, prefix: &str, path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: le fichier est ouvert en lecture seule et n'est pas modifié pendant l'audit
+        let map = unsafe { Mmap::map(&file)? };
+        self.push(hive, prefix, HiveData::Mapped(map))?;
+        Ok(self)
+    }
+
+    /// Monte une ruche déjà chargée en mémoire
+    pub fn mount_bytes(mut self, hive: RegistryHive, prefix: &str, data: Vec<u8>) -> Result<Self> {
+        self.push(hive, prefix, HiveData::Owned(data))?;
+        Ok(self)
+    }
+
+    /// Monte les ruches machine d'un dossier `Windows\System32\config`
+    pub fn from_config_dir(config_dir: &Path) -> Result<Self> {
+        let mut registry = Self::new();
+        for name in ["SOFTWARE", "SYSTEM"] {
+            let path = config_dir.join(name);
+            if path.exists() {
+                registry = registry.mount_file(RegistryHive::Hklm, name, &path)?;
+            }
+        }
+        if registry.mounts.is_empty() {
+            return Err(PieuvreError::Config(format!(
+                "No registry hive found in {}",
+                config_dir.display()
+            )));
+        }
+        Ok(registry)
+    }
+
+    fn push(&mut self, hive: RegistryHive, prefix: &str, data: HiveData) -> Result<()> {
+        // nt_hive2 panique sur une base block invalide : on vérifie la signature avant
+        if data.as_bytes().len() < nt_hive2::BASEBLOCK_SIZE || &data.as_bytes()[..4] != b"regf" {
+            return Err(hive_err(format!(
+                "{}\\{} is not a valid hive file",
+                hive.short_name(),
+                prefix
+            )));
+        }
+        self.mounts.push(HiveMount {
+            hive,
+            prefix: prefix.trim_matches('\\').to_string(),
+            data,
+        });
+        Ok(())
+    }
+
+    /// Trouve la ruche montée contenant `path` et le chemin relatif à sa racine
+    fn resolve(&self, path: &RegistryPath) -> Option<(&HiveMount, String)> {
+        self.mounts
+            .iter()
+            .filter(|m| m.hive == path.hive)
+            .filter_map(|m| Some((m, relative_key(&path.key, &m.prefix)?)))
+            .max_by_key(|(m, _)| m.prefix.len())
+    }
+
+    fn parse(mount: &HiveMount) -> Result<ParsedHive<'_>> {
+        Hive::new(
+            Cursor::new(mount.data.as_bytes()),
+            HiveParseMode::NormalWithBaseBlock,
+        )
+        .map_err(hive_err)
+    }
+
+    fn find_node(hive: &mut ParsedHive<'_>, subpath: &str) -> Result<Option<Rc<RefCell<KeyNode>>>> {
+        let root = hive.root_key_node().map_err(hive_err)?;
+        if subpath.is_empty() {
+            return Ok(Some(Rc::new(RefCell::new(root))));
+        }
+        root.subpath(subpath, hive).map_err(hive_err)
+    }
+
+    /// `CurrentControlSet` n'existe pas dans un fichier SYSTEM : on suit `Select\Current`
+    fn resolve_control_set(hive: &mut ParsedHive<'_>, subpath: &str) -> Result<String> {
+        let (first, rest) = subpath.split_once('\\').unwrap_or((subpath, ""));
+        if !first.eq_ignore_ascii_case("CurrentControlSet") {
+            return Ok(subpath.to_string());
+        }
+        let current = Self::find_node(hive, "Select")?
+            .and_then(|node| {
+                let node = node.borrow();
+                let value = node
+                    .values()
+                    .iter()
+                    .find(|v| v.name().eq_ignore_ascii_case("Current"))
+                    .and_then(|v| convert_value(v).ok());
+                value
+            })
+            .and_then(|v| match v {
+                RegistryValue::Dword(n) => Some(n),
+                _ => None,
+            })
+            .unwrap_or(1);
+        let control_set = format!("ControlSet{:03}", current);
+        Ok(if rest.is_empty() {
+            control_set
+        } else {
+            format!("{}\\{}", control_set, rest)
+        })
+    }
+
+    /// Exécute `f` sur le nœud correspondant à `path`, `None` si la clé est absente
+    fn with_node<T>(
+        &self,
+        path: &RegistryPath,
+        f: impl FnOnce(&KeyNode, &mut ParsedHive<'_>) -> Result<T>,
+    ) -> Result<Option<T>> {
+        let Some((mount, subpath)) = self.resolve(path) else {
+            return Ok(None);
+        };
+        let mut hive = Self::parse(mount)?;
+        let subpath = if mount.prefix.eq_ignore_ascii_case("SYSTEM") {
+            Self::resolve_control_set(&mut hive, &subpath)?
+        } else {
+            subpath
+        };
+        let Some(node) = Self::find_node(&mut hive, &subpath)? else {
+            return Ok(None);
+        };
+        let node = node.borrow();
+        let result = f(&node, &mut hive)?;
+        Ok(Some(result))
+    }
+}
+
+impl RegistryBackend for OfflineHiveRegistry {
+    fn name(&self) -> &str {
+        "offline-hive"
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn key_exists(&self, path: &RegistryPath) -> bool {
+        matches!(self.with_node(path, |_, _| Ok(())), Ok(Some(())))
+    }
+
+    fn read_value(&self, path: &RegistryPath, name: &str) -> Result<Option<RegistryValue>> {
+        Ok(self
+            .with_node(path, |node, _| {
+                node.values()
+                    .iter()
+                    .find(|v| v.name().eq_ignore_ascii_case(name))
+                    .map(convert_value)
+                    .transpose()
+            })?
+            .flatten())
+    }
+
+    fn write_value(&self, _path: &RegistryPath, _name: &str, _value: &RegistryValue) -> Result<()> {
+        Err(read_only())
+    }
+
+    fn delete_value(&self, _path: &RegistryPath, _name: &str) -> Result<()> {
+        Err(read_only())
+    }
+
+    fn create_key(&self, _path: &RegistryPath) -> Result<()> {
+        Err(read_only())
+    }
+
+    fn delete_key(&self, _path: &RegistryPath) -> Result<()> {
+        Err(read_only())
+    }
+
+    fn list_subkeys(&self, path: &RegistryPath) -> Result<Vec<String>> {
+        self.with_node(path, |node, hive| {
+            Ok(node
+                .subkeys(hive)
+                .map_err(hive_err)?
+                .iter()
+                .map(|sk| sk.borrow().name().to_string())
+                .collect())
+        })?
+        .ok_or_else(|| PieuvreError::Registry(format!("Cannot open key {}", path)))
+    }
+
+    fn list_values(&self, path: &RegistryPath) -> Result<Vec<(String, RegistryValue)>> {
+        self.with_node(path, |node, _| {
+            node.values()
+                .iter()
+                .map(|v| Ok((v.name().to_string(), convert_value(v)?)))
+                .collect()
+        })?
+        .ok_or_else(|| PieuvreError::Registry(format!("Cannot open key {}", path)))
+    }
+}
@@ -1,63 +1,92 @@
 pub mod appx;
 pub mod compliance;
+pub mod diff;
 pub mod etw;
 pub mod hardware;
+pub mod hive;
 pub mod network;
+pub mod offline;
 pub mod registry;
+pub mod scoring;
 pub mod security;
 pub mod services;
 
-pub use compliance::{check_compliance, ComplianceCheck, ComplianceStatus};
+#[cfg(test)]
+mod tests;
+
+pub use compliance::{
+    check_compliance, ComplianceCheck, ComplianceEvaluator, ComplianceReport, ComplianceStatus,
+    ComplianceSummary, RuleSet,
+};
+pub use diff::{diff_reports, AuditDiff};
+pub use hive::OfflineHiveRegistry;
+pub use offline::offline_audit;
+pub use scoring::score_report;
 pub use security::run_security_audit;
 
 use chrono::Utc;
-use pieuvre_common::{AuditReport, Result, SystemInfo};
+use pieuvre_common::{
+    AuditConfig, AuditReport, HardwareInfo, NativeRegistry, RegistryBackend, RegistryPath, Result,
+    SystemInfo,
+};
 use uuid::Uuid;
 
+/// Audit complet selon la configuration chargée, scores compris
 pub fn full_audit() -> Result<AuditReport> {
-    let hardware = hardware::probe_hardware()?;
+    let config = pieuvre_common::config();
+    let mut report = full_audit_with(&config.audit)?;
+    report.scores = Some(scoring::score_report(&report, &config.scoring));
+    Ok(report)
+}
+
+/// Audit limité aux modules activés dans la section `[audit]`
+pub fn full_audit_with(modules: &AuditConfig) -> Result<AuditReport> {
+    let hardware = if modules.hardware {
+        hardware::probe_hardware()?
+    } else {
+        HardwareInfo::default()
+    };
     let security = security::run_security_audit()?;
     let telemetry = security::get_telemetry_status()?;
-    let services = services::inspect_services()?;
-    let appx = appx::scan_packages()?;
-
-    // System Info detection (Détection réelle via Registre)
-    let (os_version, build_number) = {
-        let hklm = winreg::RegKey::predef(winreg::enums::HKEY_LOCAL_MACHINE);
-        let key = hklm
-            .open_subkey(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion")
-            .ok();
-
-        let ver = key
-            .as_ref()
-            .and_then(|k: &winreg::RegKey| k.get_value("ProductName").ok())
-            .unwrap_or_else(|| "Windows 11".to_string());
-
-        let build = key
-            .as_ref()
-            .and_then(|k: &winreg::RegKey| k.get_value("CurrentBuildNumber").ok())
-            .and_then(|s: String| s.parse::<u32>().ok())
-            .unwrap_or(22631);
-
-        (ver, build)
+    let services = if modules.services {
+        services::inspect_services()?
+    } else {
+        Vec::new()
+    };
+    let appx = if modules.appx {
+        appx::scan_packages()?
+    } else {
+        Vec::new()
     };
 
     let hostname = std::env::var("COMPUTERNAME").unwrap_or_else(|_| "Unknown".into());
+    let system = system_info_with(&NativeRegistry, hostname);
 
     Ok(AuditReport {
         id: Uuid::new_v4(),
         timestamp: Utc::now(),
-        system: SystemInfo {
-            os_version,
-            build_number,
-            edition: "Pro".to_string(),
-            hostname,
-        },
+        system,
         hardware,
         services,
         telemetry,
         security,
         latency: None, // ETW latency is optional and heavy
         appx,
+        scores: None,
     })
 }
+
+/// Version, build et édition depuis `SOFTWARE\Microsoft\Windows NT\CurrentVersion`
+pub fn system_info_with(backend: &dyn RegistryBackend, hostname: String) -> SystemInfo {
+    let path = RegistryPath::hklm(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion");
+    let string = |name: &str| backend.read_string(&path, name).ok().flatten();
+
+    SystemInfo {
+        os_version: string("ProductName").unwrap_or_else(|| "Windows 11".to_string()),
+        build_number: string("CurrentBuildNumber")
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(22631),
+        edition: string("EditionID").unwrap_or_else(|| "Pro".to_string()),
+        hostname,
+    }
+}
@@ -0,0 +1,302 @@
+//! Offline Audit
+//!
+//! Audit report built from copies of the SYSTEM, SOFTWARE and NTUSER.DAT
+//! hives (mounted image, backup, broken machine) instead of the live system.
+//! Hardware and latency are not available offline; services are read from
+//! the `Services` keys of the SYSTEM hive.
+
+use crate::hive::OfflineHiveRegistry;
+use chrono::Utc;
+use pieuvre_common::{
+    AuditConfig, AuditReport, HardwareInfo, PieuvreError, RegistryBackend, RegistryHive,
+    RegistryPath, RegistryValue, Result, ServiceEntry, ServiceManager, ServiceStartType,
+    ServiceStatus, ServiceTrigger,
+};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+const SERVICES_KEY: &str = r"SYSTEM\CurrentControlSet\Services";
+const COMPUTER_NAME_KEY: &str = r"SYSTEM\CurrentControlSet\Control\ComputerName\ComputerName";
+
+/// `SERVICE_WIN32_OWN_PROCESS | SERVICE_WIN32_SHARE_PROCESS`
+const SERVICE_WIN32: u32 = 0x30;
+
+/// Hive files found for an offline audit
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OfflineHives {
+    pub system: Option<PathBuf>,
+    pub software: Option<PathBuf>,
+    pub ntuser: Option<PathBuf>,
+    /// `UsrClass.dat` (HKCU\Software\Classes), holds the user's Appx repository
+    pub usrclass: Option<PathBuf>,
+}
+
+impl OfflineHives {
+    /// Looks in `dir`, then in `dir\Windows\System32\config` (root of a
+    /// mounted image). Names are matched case-insensitively; SYSTEM and
+    /// SOFTWARE are required.
+    pub fn locate(dir: &Path) -> Result<Self> {
+        if !dir.is_dir() {
+            return Err(PieuvreError::Config(format!(
+                "{} is not a directory",
+                dir.display()
+            )));
+        }
+
+        let config_dir = dir.join("Windows").join("System32").join("config");
+        let machine = |name: &str| find_file(dir, name).or_else(|| find_file(&config_dir, name));
+        let hives = Self {
+            system: machine("SYSTEM"),
+            software: machine("SOFTWARE"),
+            ntuser: find_file(dir, "NTUSER.DAT"),
+            usrclass: find_file(dir, "UsrClass.dat"),
+        };
+
+        let missing: Vec<&str> = [("SYSTEM", &hives.system), ("SOFTWARE", &hives.software)]
+            .into_iter()
+            .filter(|(_, path)| path.is_none())
+            .map(|(name, _)| name)
+            .collect();
+        if !missing.is_empty() {
+            return Err(PieuvreError::Config(format!(
+                "{} hive not found in {}",
+                missing.join(" and "),
+                dir.display()
+            )));
+        }
+        if hives.ntuser.is_none() {
+            tracing::warn!(dir = %dir.display(), "No NTUSER.DAT: per-user settings use machine values");
+        }
+        Ok(hives)
+    }
+
+    /// SYSTEM and SOFTWARE under HKLM, NTUSER.DAT as HKCU
+    pub fn mount(&self) -> Result<OfflineHiveRegistry> {
+        let mut registry = OfflineHiveRegistry::new();
+        for (hive, prefix, path) in [
+            (RegistryHive::Hklm, "SYSTEM", &self.system),
+            (RegistryHive::Hklm, "SOFTWARE", &self.software),
+            (RegistryHive::Hkcu, "", &self.ntuser),
+            (RegistryHive::Hkcu, r"Software\Classes", &self.usrclass),
+        ] {
+            if let Some(path) = path {
+                registry = registry.mount_file(hive, prefix, path)?;
+            }
+        }
+        Ok(registry)
+    }
+}
+
+fn find_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .find(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .eq_ignore_ascii_case(name)
+                && entry.path().is_file()
+        })
+        .map(|entry| entry.path())
+}
+
+/// Audits the hives exported to `dir`, with the `[audit]` modules and the
+/// `[scoring]` weights of the configuration
+pub fn offline_audit(dir: &Path) -> Result<AuditReport> {
+    let registry = OfflineHives::locate(dir)?.mount()?;
+    let config = pieuvre_common::config();
+    let mut report = audit_registry(&registry, &config.audit)?;
+    report.scores = Some(crate::scoring::score_report(&report, &config.scoring));
+    Ok(report)
+}
+
+/// Audit report read from `registry` alone
+pub fn audit_registry(
+    registry: &dyn RegistryBackend,
+    modules: &AuditConfig,
+) -> Result<AuditReport> {
+    let services = RegistryServiceManager::new(registry);
+    let hostname = registry
+        .read_string(&RegistryPath::hklm(COMPUTER_NAME_KEY), "ComputerName")
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "Unknown".into());
+
+    Ok(AuditReport {
+        id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        system: crate::system_info_with(registry, hostname),
+        hardware: HardwareInfo::default(),
+        services: if modules.services {
+            crate::services::inspect_services_with(&services)?
+        } else {
+            Vec::new()
+        },
+        telemetry: crate::security::get_telemetry_status_with(registry, &services)?,
+        security: crate::security::run_security_audit_with(registry)?,
+        latency: None,
+        appx: if modules.appx {
+            let mut packages = crate::appx::scan_all_users_with(registry)?;
+            for package in crate::appx::scan_packages_with(registry)? {
+                if !packages
+                    .iter()
+                    .any(|p| p.full_name.eq_ignore_ascii_case(&package.full_name))
+                {
+                    packages.push(package);
+                }
+            }
+            packages
+        } else {
+            Vec::new()
+        },
+        scores: None,
+    })
+}
+
+/// Read-only service manager over the `Services` keys of a registry, for
+/// systems that are not running (status is always `Stopped`)
+pub struct RegistryServiceManager<'a> {
+    registry: &'a dyn RegistryBackend,
+}
+
+impl<'a> RegistryServiceManager<'a> {
+    pub fn new(registry: &'a dyn RegistryBackend) -> Self {
+        Self { registry }
+    }
+
+    fn key(&self, service: &str) -> Result<RegistryPath> {
+        let path = RegistryPath::hklm(SERVICES_KEY).join(service);
+        if !self.registry.key_exists(&path) {
+            return Err(PieuvreError::ServiceNotFound(service.to_string()));
+        }
+        Ok(path)
+    }
+
+    fn dword(&self, path: &RegistryPath, name: &str) -> Option<u32> {
+        self.registry.read_dword(path, name).ok().flatten()
+    }
+
+    fn read_only(&self) -> PieuvreError {
+        PieuvreError::Unsupported("Offline services are read-only".to_string())
+    }
+}
+
+impl ServiceManager for RegistryServiceManager<'_> {
+    fn name(&self) -> &str {
+        "offline-registry"
+    }
+
+    fn exists(&self, service: &str) -> bool {
+        self.key(service).is_ok()
+    }
+
+    fn list_services(&self) -> Result<Vec<ServiceEntry>> {
+        let root = RegistryPath::hklm(SERVICES_KEY);
+        let mut entries = Vec::new();
+        for name in self.registry.list_subkeys(&root)? {
+            let path = root.join(&name);
+            // Pilotes et groupes exclus, comme SERVICE_WIN32 pour le SCM
+            if self.dword(&path, "Type").unwrap_or(0) & SERVICE_WIN32 == 0 {
+                continue;
+            }
+            let display_name = self
+                .registry
+                .read_string(&path, "DisplayName")
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| name.clone());
+            entries.push(ServiceEntry {
+                start_type: self
+                    .dword(&path, "Start")
+                    .map(ServiceStartType::from_raw)
+                    .unwrap_or(ServiceStartType::Unknown),
+                name,
+                display_name,
+                status: ServiceStatus::Stopped,
+                pid: None,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn start_type(&self, service: &str) -> Result<u32> {
+        let path = self.key(service)?;
+        self.dword(&path, "Start")
+            .ok_or_else(|| PieuvreError::Parse(format!("Service {} has no Start value", service)))
+    }
+
+    fn set_start_type(&self, _service: &str, _start_type: u32) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn status(&self, service: &str) -> Result<ServiceStatus> {
+        self.key(service)?;
+        Ok(ServiceStatus::Stopped)
+    }
+
+    fn security_descriptor(&self, service: &str) -> Result<String> {
+        self.key(service)?;
+        Err(PieuvreError::Unsupported(
+            "Service security descriptors are not read from offline hives".to_string(),
+        ))
+    }
+
+    fn set_security_descriptor(&self, _service: &str, _sddl: &str) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn dependencies(&self, service: &str) -> Result<Vec<String>> {
+        let path = self.key(service)?;
+        let list = |name: &str| match self.registry.read_value(&path, name) {
+            Ok(Some(RegistryValue::MultiString(items))) => items,
+            Ok(Some(RegistryValue::String(item))) if !item.is_empty() => vec![item],
+            _ => Vec::new(),
+        };
+        let mut dependencies = list("DependOnService");
+        dependencies.extend(list("DependOnGroup").into_iter().map(|g| format!("+{}", g)));
+        Ok(dependencies)
+    }
+
+    fn triggers(&self, service: &str) -> Result<Vec<ServiceTrigger>> {
+        let root = self.key(service)?.join("TriggerInfo");
+        let Ok(names) = self.registry.list_subkeys(&root) else {
+            return Ok(Vec::new());
+        };
+        Ok(names
+            .iter()
+            .map(|name| root.join(name))
+            .filter_map(|path| {
+                let subtype = match self.registry.read_value(&path, "GUID") {
+                    Ok(Some(RegistryValue::Binary(bytes))) => guid_string(&bytes),
+                    _ => None,
+                };
+                Some(ServiceTrigger {
+                    trigger_type: self.dword(&path, "Type")?,
+                    action: self.dword(&path, "Action")?,
+                    subtype,
+                })
+            })
+            .collect())
+    }
+}
+
+/// 16-byte little-endian GUID, formatted like the native manager
+/// (`XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX`)
+fn guid_string(bytes: &[u8]) -> Option<String> {
+    let b: &[u8; 16] = bytes.try_into().ok()?;
+    Some(format!(
+        "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+        u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        u16::from_le_bytes([b[4], b[5]]),
+        u16::from_le_bytes([b[6], b[7]]),
+        b[8],
+        b[9],
+        b[10],
+        b[11],
+        b[12],
+        b[13],
+        b[14],
+        b[15]
+    ))
+}
@@ -1,8 +1,9 @@
-use pieuvre_common::{PieuvreError, Result};
+use pieuvre_common::{
+    NativeRegistry, PieuvreError, RegistryBackend, RegistryHive, RegistryPath, Result,
+};
 use std::path::Path;
 use windows::Win32::System::Registry::{
-    RegCloseKey, RegOpenKeyExW, RegQueryValueExW, RegSaveKeyExW, HKEY, HKEY_LOCAL_MACHINE,
-    KEY_READ, REG_DWORD, REG_SAVE_FORMAT, REG_VALUE_TYPE,
+    RegCloseKey, RegOpenKeyExW, RegSaveKeyExW, HKEY, KEY_READ, REG_SAVE_FORMAT,
 };
 
 pub struct RegistryCleaner;
@@ -60,76 +61,30 @@ impl RegistryCleaner {
 }
 
 /// Vérifie si une clé de registre existe.
-pub fn key_exists(hkey: HKEY, subkey: &str) -> bool {
-    unsafe {
-        let mut hkey_result = HKEY::default();
-        let subkey_u16: Vec<u16> = subkey.encode_utf16().chain(std::iter::once(0)).collect();
-        let res = RegOpenKeyExW(
-            hkey,
-            windows::core::PCWSTR(subkey_u16.as_ptr()),
-            None,
-            KEY_READ,
-            &mut hkey_result,
-        );
-        if res.is_ok() {
-            let _ = RegCloseKey(hkey_result);
-            true
-        } else {
-            false
-        }
-    }
+pub fn key_exists(hive: RegistryHive, subkey: &str) -> bool {
+    NativeRegistry.key_exists(&RegistryPath::new(hive, subkey))
 }
 
 /// Lit une valeur DWORD (u32) du registre.
-pub fn read_dword_value(hkey: HKEY, subkey: &str, value_name: &str) -> Result<u32> {
-    unsafe {
-        let mut hkey_result = HKEY::default();
-        let subkey_u16: Vec<u16> = subkey.encode_utf16().chain(std::iter::once(0)).collect();
-        RegOpenKeyExW(
-            hkey,
-            windows::core::PCWSTR(subkey_u16.as_ptr()),
-            None,
-            KEY_READ,
-            &mut hkey_result,
-        )
-        .ok()
-        .map_err(|e| PieuvreError::System(format!("Failed to open key: {}", e)))?;
-
-        let mut data = 0u32;
-        let mut data_size = std::mem::size_of::<u32>() as u32;
-        let mut value_type = REG_VALUE_TYPE::default();
-        let value_name_u16: Vec<u16> = value_name
-            .encode_utf16()
-            .chain(std::iter::once(0))
-            .collect();
-
-        let res = RegQueryValueExW(
-            hkey_result,
-            windows::core::PCWSTR(value_name_u16.as_ptr()),
-            None,
-            Some(&mut value_type),
-            Some(&mut data as *mut u32 as *mut u8),
-            Some(&mut data_size),
-        );
-
-        let _ = RegCloseKey(hkey_result);
-
-        res.ok().map_err(|e| {
-            PieuvreError::System(format!("Failed to query value {}: {}", value_name, e))
-        })?;
-
-        if value_type != REG_DWORD {
-            return Err(PieuvreError::Parse(format!(
-                "Value {} is not a DWORD",
-                value_name
-            )));
-        }
+pub fn read_dword_value(hive: RegistryHive, subkey: &str, value_name: &str) -> Result<u32> {
+    read_dword_with(&NativeRegistry, hive, subkey, value_name)
+}
 
-        Ok(data)
-    }
+/// Lit une valeur DWORD via un backend arbitraire (natif, mémoire, ruche hors-ligne).
+pub fn read_dword_with(
+    backend: &dyn RegistryBackend,
+    hive: RegistryHive,
+    subkey: &str,
+    value_name: &str,
+) -> Result<u32> {
+    backend
+        .read_dword(&RegistryPath::new(hive, subkey), value_name)?
+        .ok_or_else(|| {
+            PieuvreError::System(format!("Value {} not found in {}", value_name, subkey))
+        })
 }
 
 /// Version simplifiée pour HKLM.
 pub fn read_hklm_dword(subkey: &str, value_name: &str) -> Result<u32> {
-    read_dword_value(HKEY_LOCAL_MACHINE, subkey, value_name)
+    read_dword_value(RegistryHive::Hklm, subkey, value_name)
 }
@@ -0,0 +1,234 @@
+//! Audit Scoring
+//!
+//! Privacy, performance and security scores (0-100) computed from an audit
+//! report. Each finding earns up to its weight from the `[scoring]` section;
+//! a finding whose data is missing from the report (services or Appx module
+//! disabled, latency not measured) is left out of the score.
+
+use crate::appx::get_bloatware;
+use pieuvre_common::{
+    AuditReport, AuditScores, DimensionScore, ScoreFinding, ScoringConfig, ServiceCategory,
+    ServiceStartType,
+};
+
+/// Bloatware packages at which the bloatware finding earns nothing
+const BLOATWARE_CEILING: usize = 10;
+/// Full points at or below this DPC/ISR latency (µs)
+const LATENCY_GOOD_US: u64 = 100;
+/// No points at or above this DPC/ISR latency (µs)
+const LATENCY_BAD_US: u64 = 1000;
+
+/// Scores of `report` with the given weights
+pub fn score_report(report: &AuditReport, weights: &ScoringConfig) -> AuditScores {
+    AuditScores {
+        privacy: DimensionScore::from_findings(privacy_findings(report, weights)),
+        performance: DimensionScore::from_findings(performance_findings(report, weights)),
+        security: DimensionScore::from_findings(security_findings(report, weights)),
+    }
+}
+
+fn privacy_findings(report: &AuditReport, w: &ScoringConfig) -> Vec<ScoreFinding> {
+    let t = &report.telemetry;
+    let level = t.data_collection_level.min(3);
+    let mut findings = vec![
+        toggle(
+            "diagtrack",
+            "DiagTrack disabled",
+            w.diagtrack,
+            t.diagtrack_enabled,
+            false,
+        ),
+        graded(
+            "data_collection",
+            "Diagnostic data at minimum",
+            w.data_collection,
+            f64::from(3 - level) / 3.0,
+            format!("level {}", t.data_collection_level),
+        ),
+        toggle(
+            "advertising_id",
+            "Advertising ID disabled",
+            w.advertising_id,
+            t.advertising_id_enabled,
+            false,
+        ),
+        toggle(
+            "location",
+            "Location disabled",
+            w.location,
+            t.location_enabled,
+            false,
+        ),
+        toggle(
+            "activity_history",
+            "Activity history disabled",
+            w.activity_history,
+            t.activity_history_enabled,
+            false,
+        ),
+        toggle(
+            "cortana",
+            "Cortana disabled",
+            w.cortana,
+            t.cortana_enabled,
+            false,
+        ),
+        toggle(
+            "web_search",
+            "Web search in Start disabled",
+            w.web_search,
+            t.web_search_enabled,
+            false,
+        ),
+        toggle(
+            "error_reporting",
+            "Error reporting disabled",
+            w.error_reporting,
+            t.error_reporting_enabled,
+            false,
+        ),
+    ];
+
+    // Type de démarrage plutôt que statut : valable aussi hors-ligne
+    let telemetry: Vec<_> = report
+        .services
+        .iter()
+        .filter(|s| s.category == ServiceCategory::Telemetry)
+        .collect();
+    if !telemetry.is_empty() {
+        let disabled = telemetry
+            .iter()
+            .filter(|s| s.start_type == ServiceStartType::Disabled)
+            .count();
+        findings.push(graded(
+            "telemetry_services",
+            "Telemetry services disabled",
+            w.telemetry_services,
+            disabled as f64 / telemetry.len() as f64,
+            format!("{}/{} disabled", disabled, telemetry.len()),
+        ));
+    }
+    findings.into_iter().flatten().collect()
+}
+
+fn performance_findings(report: &AuditReport, w: &ScoringConfig) -> Vec<ScoreFinding> {
+    let mut findings = Vec::new();
+
+    // Liste vide : module Appx désactivé
+    if !report.appx.is_empty() {
+        // Paquet provisionné et installé : compté une fois
+        let mut names: Vec<&str> = get_bloatware(&report.appx)
+            .into_iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        findings.push(graded(
+            "bloatware",
+            "Bloatware removed",
+            w.bloatware,
+            1.0 - names.len().min(BLOATWARE_CEILING) as f64 / BLOATWARE_CEILING as f64,
+            format!("{} package(s)", names.len()),
+        ));
+    }
+
+    if let Some(latency) = &report.latency {
+        findings.push(graded(
+            "dpc_latency",
+            "DPC latency",
+            w.dpc_latency,
+            latency_ratio(latency.dpc_max_us),
+            format!("{} µs max", latency.dpc_max_us),
+        ));
+        findings.push(graded(
+            "isr_latency",
+            "ISR latency",
+            w.isr_latency,
+            latency_ratio(latency.isr_max_us),
+            format!("{} µs max", latency.isr_max_us),
+        ));
+    }
+    findings.into_iter().flatten().collect()
+}
+
+fn security_findings(report: &AuditReport, w: &ScoringConfig) -> Vec<ScoreFinding> {
+    let s = &report.security;
+    [
+        toggle(
+            "defender",
+            "Defender enabled",
+            w.defender,
+            s.defender_enabled,
+            true,
+        ),
+        toggle(
+            "tamper_protection",
+            "Tamper protection enabled",
+            w.tamper_protection,
+            s.tamper_protection,
+            true,
+        ),
+        toggle(
+            "firewall",
+            "Firewall enabled",
+            w.firewall,
+            s.firewall_enabled,
+            true,
+        ),
+        // ConsentPromptBehaviorAdmin = 0 : élévation sans invite
+        graded(
+            "uac",
+            "UAC prompts for elevation",
+            w.uac,
+            if s.uac_level > 0 { 1.0 } else { 0.0 },
+            format!("level {}", s.uac_level),
+        ),
+        toggle(
+            "secure_boot",
+            "Secure Boot enabled",
+            w.secure_boot,
+            s.secure_boot,
+            true,
+        ),
+        toggle(
+            "credential_guard",
+            "Credential Guard enabled",
+            w.credential_guard,
+            s.credential_guard,
+            true,
+        ),
+        toggle(
+            "bitlocker",
+            "BitLocker active",
+            w.bitlocker,
+            s.bitlocker_active,
+            true,
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Full points when `enabled` matches `wanted`
+fn toggle(id: &str, title: &str, weight: u32, enabled: bool, wanted: bool) -> Option<ScoreFinding> {
+    let detail = if enabled { "enabled" } else { "disabled" };
+    let ratio = if enabled == wanted { 1.0 } else { 0.0 };
+    graded(id, title, weight, ratio, detail.to_string())
+}
+
+/// `ratio` of the weight (0.0-1.0); `None` for a finding weighted 0
+fn graded(id: &str, title: &str, weight: u32, ratio: f64, detail: String) -> Option<ScoreFinding> {
+    (weight > 0).then(|| ScoreFinding {
+        id: id.to_string(),
+        title: title.to_string(),
+        detail,
+        weight,
+        earned: (f64::from(weight) * ratio.clamp(0.0, 1.0)).round() as u32,
+    })
+}
+
+fn latency_ratio(max_us: u64) -> f64 {
+    let clamped = max_us.clamp(LATENCY_GOOD_US, LATENCY_BAD_US);
+    (LATENCY_BAD_US - clamped) as f64 / (LATENCY_BAD_US - LATENCY_GOOD_US) as f64
+}
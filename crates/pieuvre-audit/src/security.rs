@@ -1,43 +1,51 @@
-use crate::registry::{key_exists, read_hklm_dword};
-use pieuvre_common::{Result, SecurityAudit, TelemetryStatus};
-use windows::Win32::System::Registry::HKEY_LOCAL_MACHINE;
+use crate::registry::read_dword_with;
+use pieuvre_common::{
+    NativeRegistry, NativeServiceManager, RegistryBackend, RegistryHive, RegistryPath, Result,
+    SecurityAudit, ServiceManager, ServiceStartType, TelemetryStatus,
+};
 
 pub fn run_security_audit() -> Result<SecurityAudit> {
-    let defender_enabled = read_hklm_dword(
+    run_security_audit_with(&NativeRegistry)
+}
+
+/// Audit de sécurité sur un backend registre donné
+pub fn run_security_audit_with(backend: &dyn RegistryBackend) -> Result<SecurityAudit> {
+    let hklm_dword =
+        |subkey: &str, value: &str| read_dword_with(backend, RegistryHive::Hklm, subkey, value);
+    let defender_enabled = hklm_dword(
         r"SOFTWARE\Microsoft\Windows Defender\Real-Time Protection",
         "DisableRealtimeMonitoring",
     )
     .unwrap_or(0)
         == 0;
-    let tamper_protection = read_hklm_dword(
+    let tamper_protection = hklm_dword(
         r"SOFTWARE\Microsoft\Windows Defender\Features",
         "TamperProtection",
     )
     .unwrap_or(0)
         != 0;
-    let firewall_enabled = read_hklm_dword(
+    let firewall_enabled = hklm_dword(
         r"SYSTEM\CurrentControlSet\Services\SharedAccess\Parameters\FirewallPolicy\StandardProfile",
         "EnableFirewall",
     )
     .unwrap_or(0)
         == 1;
-    let uac_level = read_hklm_dword(
+    let uac_level = hklm_dword(
         r"SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\System",
         "ConsentPromptBehaviorAdmin",
     )
     .unwrap_or(0);
-    let secure_boot = read_hklm_dword(
+    let secure_boot = hklm_dword(
         r"SYSTEM\CurrentControlSet\Control\SecureBoot\State",
         "UEFISecureBootEnabled",
     )
     .unwrap_or(0)
         == 1;
     let credential_guard =
-        read_hklm_dword(r"SYSTEM\CurrentControlSet\Control\Lsa", "LsaCfgFlags").unwrap_or(0) >= 1;
-    let bitlocker_active = key_exists(
-        HKEY_LOCAL_MACHINE,
+        hklm_dword(r"SYSTEM\CurrentControlSet\Control\Lsa", "LsaCfgFlags").unwrap_or(0) >= 1;
+    let bitlocker_active = backend.key_exists(&RegistryPath::hklm(
         r"SYSTEM\CurrentControlSet\Control\BitlockerStatus",
-    );
+    ));
 
     Ok(SecurityAudit {
         defender_enabled,
@@ -51,9 +59,28 @@ pub fn run_security_audit() -> Result<SecurityAudit> {
 }
 
 pub fn get_telemetry_status() -> Result<TelemetryStatus> {
-    let diagtrack_enabled = crate::services::get_service_start_type_by_name("DiagTrack")
-        != pieuvre_common::ServiceStartType::Disabled;
-    let data_collection_level = read_hklm_dword(
+    get_telemetry_status_with(&NativeRegistry, &NativeServiceManager)
+}
+
+/// Télémétrie sur un backend registre et un gestionnaire de services donnés
+pub fn get_telemetry_status_with(
+    backend: &dyn RegistryBackend,
+    services: &dyn ServiceManager,
+) -> Result<TelemetryStatus> {
+    let hklm_dword =
+        |subkey: &str, value: &str| read_dword_with(backend, RegistryHive::Hklm, subkey, value);
+    // Réglage utilisateur (HKCU) prioritaire sur la valeur machine
+    let user_dword = |subkey: &str, value: &str| {
+        read_dword_with(backend, RegistryHive::Hkcu, subkey, value)
+            .or_else(|_| hklm_dword(subkey, value))
+    };
+
+    let diagtrack_enabled = services
+        .start_type("DiagTrack")
+        .map(ServiceStartType::from_raw)
+        .unwrap_or(ServiceStartType::Unknown)
+        != ServiceStartType::Disabled;
+    let data_collection_level = hklm_dword(
         r"SOFTWARE\Policies\Microsoft\Windows\DataCollection",
         "AllowTelemetry",
     )
@@ -62,11 +89,11 @@ pub fn get_telemetry_status() -> Result<TelemetryStatus> {
     Ok(TelemetryStatus {
         diagtrack_enabled,
         data_collection_level,
-        advertising_id_enabled: read_hklm_dword(r"SOFTWARE\Microsoft\Windows\CurrentVersion\AdvertisingInfo", "Enabled").unwrap_or(1) == 1,
-        location_enabled: read_hklm_dword(r"SOFTWARE\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\location", "Value").unwrap_or(0) == 1,
-        activity_history_enabled: read_hklm_dword(r"SOFTWARE\Policies\Microsoft\Windows\System", "PublishUserActivities").unwrap_or(1) == 1,
-        cortana_enabled: read_hklm_dword(r"SOFTWARE\Policies\Microsoft\Windows\Windows Search", "AllowCortana").unwrap_or(1) == 1,
-        web_search_enabled: read_hklm_dword(r"SOFTWARE\Policies\Microsoft\Windows\Windows Search", "ConnectedSearchUseWeb").unwrap_or(1) == 1,
-        error_reporting_enabled: read_hklm_dword(r"SOFTWARE\Policies\Microsoft\Windows\Windows Error Reporting", "Disabled").unwrap_or(0) == 0,
+        advertising_id_enabled: user_dword(r"SOFTWARE\Microsoft\Windows\CurrentVersion\AdvertisingInfo", "Enabled").unwrap_or(1) == 1,
+        location_enabled: hklm_dword(r"SOFTWARE\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore\location", "Value").unwrap_or(0) == 1,
+        activity_history_enabled: hklm_dword(r"SOFTWARE\Policies\Microsoft\Windows\System", "PublishUserActivities").unwrap_or(1) == 1,
+        cortana_enabled: hklm_dword(r"SOFTWARE\Policies\Microsoft\Windows\Windows Search", "AllowCortana").unwrap_or(1) == 1,
+        web_search_enabled: hklm_dword(r"SOFTWARE\Policies\Microsoft\Windows\Windows Search", "ConnectedSearchUseWeb").unwrap_or(1) == 1,
+        error_reporting_enabled: hklm_dword(r"SOFTWARE\Policies\Microsoft\Windows\Windows Error Reporting", "Disabled").unwrap_or(0) == 0,
     })
 }
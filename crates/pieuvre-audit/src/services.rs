@@ -2,12 +2,9 @@
 //!
 //! Enumeration and categorization of services.
 
-use pieuvre_common::{Result, ServiceCategory, ServiceInfo, ServiceStartType, ServiceStatus};
-use windows::core::PCWSTR;
-use windows::Win32::System::Services::{
-    CloseServiceHandle, EnumServicesStatusExW, OpenSCManagerW, OpenServiceW, QueryServiceConfigW,
-    ENUM_SERVICE_STATUS_PROCESSW, QUERY_SERVICE_CONFIGW, SC_ENUM_PROCESS_INFO,
-    SC_MANAGER_ENUMERATE_SERVICE, SERVICE_QUERY_CONFIG, SERVICE_STATE_ALL, SERVICE_WIN32,
+use pieuvre_common::{
+    NativeServiceManager, PieuvreError, Result, ServiceCategory, ServiceInfo, ServiceManager,
+    ServiceStartType, ServiceStatus,
 };
 
 /// Services known as telemetry
@@ -90,145 +87,27 @@ const SYSTEM_SERVICES: &[&str] = &[
 
 /// Inspects all system services with start_type detection
 pub fn inspect_services() -> Result<Vec<ServiceInfo>> {
-    let mut services = Vec::new();
-
-    unsafe {
-        let scm = match OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_ENUMERATE_SERVICE)
-        {
-            Ok(handle) => handle,
-            Err(_) => return Ok(Vec::new()),
-        };
-
-        let mut bytes_needed = 0u32;
-        let mut services_returned = 0u32;
-        let mut resume_handle = 0u32;
-
-        // First call to get size
-        let _ = EnumServicesStatusExW(
-            scm,
-            SC_ENUM_PROCESS_INFO,
-            SERVICE_WIN32,
-            SERVICE_STATE_ALL,
-            None,
-            &mut bytes_needed,
-            &mut services_returned,
-            Some(&mut resume_handle),
-            None,
-        );
-
-        if bytes_needed > 0 {
-            let mut buffer = vec![0u8; bytes_needed as usize];
-
-            let result = EnumServicesStatusExW(
-                scm,
-                SC_ENUM_PROCESS_INFO,
-                SERVICE_WIN32,
-                SERVICE_STATE_ALL,
-                Some(&mut buffer),
-                &mut bytes_needed,
-                &mut services_returned,
-                Some(&mut resume_handle),
-                None,
-            );
-
-            if result.is_ok() {
-                let entries = std::slice::from_raw_parts(
-                    buffer.as_ptr() as *const ENUM_SERVICE_STATUS_PROCESSW,
-                    services_returned as usize,
-                );
-
-                for entry in entries {
-                    let name = pwstr_to_string(entry.lpServiceName);
-                    let display_name = pwstr_to_string(entry.lpDisplayName);
-
-                    // Service status
-                    let status = match entry.ServiceStatusProcess.dwCurrentState.0 {
-                        1 => ServiceStatus::Stopped,
-                        2 => ServiceStatus::StartPending,
-                        3 => ServiceStatus::StopPending,
-                        4 => ServiceStatus::Running,
-                        5 => ServiceStatus::ContinuePending,
-                        6 => ServiceStatus::PausePending,
-                        7 => ServiceStatus::Paused,
-                        _ => ServiceStatus::Unknown,
-                    };
-
-                    // Get real start_type via QueryServiceConfigW
-                    let start_type = get_service_start_type(scm, &name);
-
-                    // PID if running
-                    let pid = if status == ServiceStatus::Running {
-                        Some(entry.ServiceStatusProcess.dwProcessId)
-                    } else {
-                        None
-                    };
-
-                    let category = categorize_service(&name);
-
-                    services.push(ServiceInfo {
-                        name,
-                        display_name,
-                        status,
-                        start_type,
-                        category,
-                        pid,
-                    });
-                }
-            }
-        }
-
-        let _ = CloseServiceHandle(scm);
+    // Ne pas échouer si le SCM est inaccessible : audit partiel
+    match inspect_services_with(&NativeServiceManager) {
+        Err(PieuvreError::Permission(_)) => Ok(Vec::new()),
+        result => result,
     }
-
-    Ok(services)
 }
 
-/// Retrieves the real start type of a service via QueryServiceConfigW
-pub(crate) fn get_service_start_type(
-    scm: windows::Win32::System::Services::SC_HANDLE,
-    name: &str,
-) -> ServiceStartType {
-    unsafe {
-        let name_wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
-
-        let service = match OpenServiceW(scm, PCWSTR(name_wide.as_ptr()), SERVICE_QUERY_CONFIG) {
-            Ok(handle) => handle,
-            Err(_) => return ServiceStartType::Unknown,
-        };
-
-        // First call to get necessary size
-        let mut bytes_needed = 0u32;
-        let _ = QueryServiceConfigW(service, None, 0, &mut bytes_needed);
-
-        if bytes_needed == 0 {
-            let _ = CloseServiceHandle(service);
-            return ServiceStartType::Unknown;
-        }
-
-        let mut buffer = vec![0u8; bytes_needed as usize];
-        let config_ptr = buffer.as_mut_ptr() as *mut QUERY_SERVICE_CONFIGW;
-
-        let result =
-            QueryServiceConfigW(service, Some(config_ptr), bytes_needed, &mut bytes_needed);
-
-        let _ = CloseServiceHandle(service);
-
-        if result.is_err() {
-            return ServiceStartType::Unknown;
-        }
-
-        let config = &*config_ptr;
-
-        // Map dwStartType to our enum
-        match config.dwStartType.0 {
-            0 => ServiceStartType::Boot,      // SERVICE_BOOT_START
-            1 => ServiceStartType::System,    // SERVICE_SYSTEM_START
-            2 => ServiceStartType::Automatic, // SERVICE_AUTO_START
-            3 => ServiceStartType::Manual,    // SERVICE_DEMAND_START
-            4 => ServiceStartType::Disabled,  // SERVICE_DISABLED
-            _ => ServiceStartType::Unknown,
-        }
-    }
+/// Inspects the services exposed by `services`
+pub fn inspect_services_with(services: &dyn ServiceManager) -> Result<Vec<ServiceInfo>> {
+    Ok(services
+        .list_services()?
+        .into_iter()
+        .map(|entry| ServiceInfo {
+            category: categorize_service(&entry.name),
+            name: entry.name,
+            display_name: entry.display_name,
+            status: entry.status,
+            start_type: entry.start_type,
+            pid: entry.pid,
+        })
+        .collect())
 }
 
 fn categorize_service(name: &str) -> ServiceCategory {
@@ -298,16 +177,6 @@ fn categorize_service(name: &str) -> ServiceCategory {
     ServiceCategory::Unknown
 }
 
-fn pwstr_to_string(ptr: windows::core::PWSTR) -> String {
-    if ptr.is_null() {
-        return String::new();
-    }
-    unsafe {
-        let len = (0..).take_while(|&i| *ptr.0.add(i) != 0).count();
-        String::from_utf16_lossy(std::slice::from_raw_parts(ptr.0, len))
-    }
-}
-
 /// Returns active telemetry services
 pub fn get_active_telemetry_services(services: &[ServiceInfo]) -> Vec<&ServiceInfo> {
     services
@@ -330,14 +199,8 @@ pub fn get_safe_to_disable(services: &[ServiceInfo]) -> Vec<&ServiceInfo> {
 }
 /// Returns the start type of a service by its name.
 pub fn get_service_start_type_by_name(name: &str) -> ServiceStartType {
-    unsafe {
-        let scm = match OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_ENUMERATE_SERVICE)
-        {
-            Ok(handle) => handle,
-            Err(_) => return ServiceStartType::Unknown,
-        };
-        let start_type = get_service_start_type(scm, name);
-        let _ = CloseServiceHandle(scm);
-        start_type
-    }
+    NativeServiceManager
+        .start_type(name)
+        .map(ServiceStartType::from_raw)
+        .unwrap_or(ServiceStartType::Unknown)
 }
@@ -1,389 +1,1052 @@
 //! Unit tests for pieuvre-audit
 //!
-//! Read-only/dry-run tests to validate all functionalities.
-
-use crate::{
-    appx, full_audit, hardware, is_laptop, network, network_audit, registry, security,
-    security_audit, services,
+//! Reports, rule sets and hives are built in memory, so the tests run on any
+//! OS. Offline hives are generated by a minimal regf writer.
+
+use chrono::Utc;
+use pieuvre_common::{
+    AppxCategory, AppxInfo, AuditReport, CpuInfo, HardwareInfo, MemoryInfo, RemovalRisk,
+    SecurityAudit, ServiceCategory, ServiceInfo, ServiceStartType, ServiceStatus, StorageInfo,
+    SystemInfo, TelemetryStatus,
 };
-use pieuvre_common::{RemovalRisk, ServiceCategory, ServiceStartType, ServiceStatus};
+use uuid::Uuid;
 
 // ========================================================================
-// TESTS HARDWARE
+// FIXTURES
 // ========================================================================
 
-#[test]
-fn test_probe_hardware_returns_valid_data() {
-    let result = hardware::probe_hardware();
-    assert!(result.is_ok(), "probe_hardware should succeed");
-
-    let hw = result.unwrap();
-
-    // CPU must have at least 1 core
-    assert!(
-        hw.cpu.logical_cores >= 1,
-        "Should have at least 1 logical core"
-    );
-    assert!(!hw.cpu.vendor.is_empty(), "CPU vendor should not be empty");
-    assert!(
-        !hw.cpu.model_name.is_empty(),
-        "CPU model_name should not be empty"
-    );
-
-    // RAM must be > 0
-    assert!(hw.memory.total_bytes > 0, "Total RAM should be > 0");
-    assert!(hw.memory.available_bytes > 0, "Available RAM should be > 0");
-    assert!(
-        hw.memory.available_bytes <= hw.memory.total_bytes,
-        "Available <= Total"
-    );
-}
-
-#[test]
-fn test_is_laptop_returns_bool() {
-    // Test that the function does not panic
-    let _is_laptop = hardware::is_laptop();
+fn service(name: &str, start_type: ServiceStartType, status: ServiceStatus) -> ServiceInfo {
+    ServiceInfo {
+        name: name.to_string(),
+        display_name: name.to_string(),
+        status,
+        start_type,
+        category: ServiceCategory::Unknown,
+        pid: None,
+    }
 }
 
-#[test]
-fn test_gpu_detection() {
-    let result = hardware::probe_hardware();
-    assert!(result.is_ok());
-
-    let hw = result.unwrap();
-    // GPU might be empty on some systems (VM without GPU)
-    for gpu in &hw.gpu {
-        assert!(!gpu.name.is_empty(), "GPU name should not be empty");
-        assert!(!gpu.vendor.is_empty(), "GPU vendor should not be empty");
+fn package(name: &str, version: &str, is_provisioned: bool) -> AppxInfo {
+    AppxInfo {
+        name: name.to_string(),
+        full_name: format!("{}_{}_x64__8wekyb3d8bbwe", name, version),
+        publisher: "CN=Microsoft Corporation".to_string(),
+        version: version.to_string(),
+        is_provisioned,
+        category: AppxCategory::Microsoft,
+        removal_risk: RemovalRisk::Safe,
     }
 }
 
-#[test]
-fn test_storage_detection() {
-    let result = hardware::probe_hardware();
-    assert!(result.is_ok());
-
-    let hw = result.unwrap();
-    // At least one fixed drive expected
-    assert!(
-        !hw.storage.is_empty(),
-        "Should detect at least one storage device"
-    );
-
-    for drive in &hw.storage {
-        assert!(!drive.device_id.is_empty(), "Device ID should not be empty");
+/// Rapport de référence (comparaison et scores)
+fn report() -> AuditReport {
+    AuditReport {
+        id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        system: SystemInfo {
+            os_version: "Windows 11 Pro".to_string(),
+            build_number: 22631,
+            edition: "Professional".to_string(),
+            hostname: "WKS-01".to_string(),
+        },
+        hardware: HardwareInfo {
+            cpu: CpuInfo {
+                vendor: "GenuineIntel".to_string(),
+                model_name: "Intel Core i7-13700K".to_string(),
+                logical_cores: 24,
+                physical_cores: 16,
+                ..Default::default()
+            },
+            memory: MemoryInfo {
+                total_bytes: 32 << 30,
+                available_bytes: 20 << 30,
+            },
+            storage: vec![StorageInfo {
+                device_id: "PhysicalDrive0".to_string(),
+                model: "Samsung SSD 980 PRO".to_string(),
+                size_bytes: 1 << 40,
+                is_ssd: true,
+                is_nvme: true,
+            }],
+            gpu: Vec::new(),
+        },
+        services: vec![
+            service(
+                "DiagTrack",
+                ServiceStartType::Automatic,
+                ServiceStatus::Running,
+            ),
+            service(
+                "WSearch",
+                ServiceStartType::Automatic,
+                ServiceStatus::Running,
+            ),
+            service(
+                "Spooler",
+                ServiceStartType::Automatic,
+                ServiceStatus::Running,
+            ),
+        ],
+        telemetry: TelemetryStatus {
+            diagtrack_enabled: true,
+            data_collection_level: 3,
+            advertising_id_enabled: true,
+            location_enabled: false,
+            activity_history_enabled: true,
+            cortana_enabled: false,
+            web_search_enabled: true,
+            error_reporting_enabled: true,
+        },
+        security: SecurityAudit {
+            defender_enabled: true,
+            tamper_protection: true,
+            firewall_enabled: true,
+            uac_level: 5,
+            secure_boot: true,
+            credential_guard: false,
+            bitlocker_active: false,
+        },
+        latency: None,
+        appx: vec![
+            package("Microsoft.BingNews", "4.55.0.0", false),
+            package("Microsoft.WindowsCalculator", "11.2405.0.0", false),
+            package("Microsoft.WindowsCalculator", "11.2405.0.0", true),
+        ],
+        scores: None,
     }
 }
 
 // ========================================================================
-// TESTS SERVICES
+// TESTS DIFF
 // ========================================================================
 
-#[test]
-fn test_inspect_services_returns_list() {
-    let result = services::inspect_services();
-    assert!(result.is_ok(), "inspect_services should succeed");
+mod diff_tests {
+    use super::{package, report, service};
+    use crate::diff::diff_reports;
+    use pieuvre_common::{HardwareInfo, ServiceStartType, ServiceStatus, StorageInfo};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_identical_reports_have_no_diff() {
+        let old = report();
+        let mut new = old.clone();
+        new.id = Uuid::new_v4();
+        // La mémoire disponible n'est pas une différence
+        new.hardware.memory.available_bytes = 4 << 30;
+
+        let diff = diff_reports(&old, &new);
+        assert!(diff.is_empty(), "{:?}", diff);
+        assert_eq!(diff.old_id, old.id);
+        assert_eq!(diff.new_id, new.id);
+    }
 
-    let svcs = result.unwrap();
-    assert!(!svcs.is_empty(), "Should detect services");
+    #[test]
+    fn test_changes_in_every_section() {
+        let old = report();
+        let mut new = old.clone();
+        new.system.build_number = 26100;
+        new.services[0] = service(
+            "DiagTrack",
+            ServiceStartType::Disabled,
+            ServiceStatus::Stopped,
+        );
+        new.services[2].status = ServiceStatus::Stopped;
+        new.services.push(service(
+            "NewSvc",
+            ServiceStartType::Manual,
+            ServiceStatus::Stopped,
+        ));
+        new.appx.remove(0);
+        new.appx[0] = package("Microsoft.WindowsCalculator", "11.2410.0.0", false);
+        new.appx
+            .push(package("Microsoft.Copilot", "1.0.0.0", false));
+        new.telemetry.diagtrack_enabled = false;
+        new.telemetry.data_collection_level = 0;
+        new.security.bitlocker_active = true;
+        new.hardware.memory.total_bytes = 64 << 30;
+        new.hardware.storage[0].size_bytes = 2 << 40;
+        new.hardware.storage.push(StorageInfo {
+            device_id: "PhysicalDrive1".to_string(),
+            model: "WD Black".to_string(),
+            size_bytes: 1 << 40,
+            is_ssd: true,
+            is_nvme: false,
+        });
+
+        let diff = diff_reports(&old, &new);
+
+        assert_eq!(diff.system.len(), 1);
+        assert_eq!(diff.system[0].field, "build_number");
+        assert_eq!(
+            (
+                diff.system[0].before.as_str(),
+                diff.system[0].after.as_str()
+            ),
+            ("22631", "26100")
+        );
 
-    // These services should exist on any Windows system
-    let common_services = ["EventLog", "PlugPlay", "RpcSs"];
-    for expected in common_services {
-        assert!(
-            svcs.iter().any(|s| s.name.eq_ignore_ascii_case(expected)),
-            "Should find {} service",
-            expected
+        let services: Vec<&str> = diff.services.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(services, ["DiagTrack", "Spooler"]);
+        assert_eq!(
+            diff.services[0].after_start_type,
+            ServiceStartType::Disabled
+        );
+        assert_eq!(diff.services[1].before_status, ServiceStatus::Running);
+
+        // Mise à jour de version : ni ajout ni retrait
+        let added: Vec<&str> = diff.appx_added.iter().map(|p| p.name.as_str()).collect();
+        let removed: Vec<&str> = diff.appx_removed.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(added, ["Microsoft.Copilot"]);
+        assert_eq!(removed, ["Microsoft.BingNews"]);
+
+        let telemetry: Vec<&str> = diff.telemetry.iter().map(|c| c.field.as_str()).collect();
+        assert!(telemetry.contains(&"diagtrack_enabled"));
+        assert!(telemetry.contains(&"data_collection_level"));
+        assert_eq!(telemetry.len(), 2);
+        assert_eq!(diff.security[0].field, "bitlocker_active");
+        assert_eq!(diff.security[0].after, "true");
+
+        let hardware: Vec<&str> = diff.hardware.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(
+            hardware,
+            [
+                "memory.total_bytes",
+                "storage[PhysicalDrive0].size_bytes",
+                "storage[PhysicalDrive1]"
+            ]
         );
+        assert_eq!(diff.hardware[2].after, "present");
+        assert_eq!(diff.len(), 11);
     }
-}
-
-#[test]
-fn test_service_start_type_not_all_manual() {
-    let result = services::inspect_services();
-    assert!(result.is_ok());
-
-    let svcs = result.unwrap();
-
-    let manual_count = svcs
-        .iter()
-        .filter(|s| matches!(s.start_type, ServiceStartType::Manual))
-        .count();
-
-    let total = svcs.len();
-    let manual_ratio = manual_count as f64 / total as f64;
 
-    // If > 80% are Manual, it's likely a bug
-    assert!(
-        manual_ratio < 0.8,
-        "Too many services are Manual ({}/{})",
-        manual_count,
-        total
-    );
+    #[test]
+    fn test_missing_sections_are_not_compared() {
+        let old = report();
+        let mut new = old.clone();
+        // Audit hors-ligne, module Appx désactivé
+        new.hardware = HardwareInfo::default();
+        new.appx.clear();
+
+        let diff = diff_reports(&old, &new);
+        assert!(diff.hardware.is_empty());
+        assert!(diff.appx_removed.is_empty());
+        assert!(diff.is_empty());
+    }
 }
 
-#[test]
-fn test_service_categories_diverse() {
-    let result = services::inspect_services();
-    assert!(result.is_ok());
-
-    let svcs = result.unwrap();
-
-    let has_system = svcs
-        .iter()
-        .any(|s| matches!(s.category, ServiceCategory::System));
-    assert!(has_system, "Should categorize some services as System");
-}
+// ========================================================================
+// TESTS SCORING
+// ========================================================================
 
-#[test]
-fn test_get_active_telemetry_services() {
-    let result = services::inspect_services();
-    assert!(result.is_ok());
+mod scoring_tests {
+    use super::{report, service};
+    use crate::scoring::score_report;
+    use pieuvre_common::{
+        LatencyReport, ScoringConfig, ServiceCategory, ServiceStartType, ServiceStatus,
+    };
+
+    #[test]
+    fn test_default_weights() {
+        let scores = score_report(&report(), &ScoringConfig::default());
+
+        // 10 points sur 75 : seuls la localisation et Cortana sont désactivées
+        assert_eq!(scores.privacy.score, Some(13));
+        let diagtrack = &scores.privacy.findings[0];
+        assert_eq!(diagtrack.id, "diagtrack");
+        assert_eq!(diagtrack.detail, "enabled");
+        assert_eq!(diagtrack.lost(), 20);
+        // Aucun service de télémétrie dans le rapport : constat omis
+        assert!(scores
+            .privacy
+            .findings
+            .iter()
+            .all(|f| f.id != "telemetry_services"));
+
+        // Credential Guard et BitLocker absents
+        assert_eq!(scores.security.score, Some(80));
+        let lost: Vec<&str> = scores
+            .security
+            .findings
+            .iter()
+            .filter(|f| f.lost() > 0)
+            .map(|f| f.id.as_str())
+            .collect();
+        assert_eq!(lost, ["credential_guard", "bitlocker"]);
+
+        // Un bloatware (BingNews), latence non mesurée
+        assert_eq!(scores.performance.score, Some(90));
+        assert_eq!(scores.performance.findings.len(), 1);
+        assert_eq!(scores.performance.findings[0].detail, "1 package(s)");
+    }
 
-    let svcs = result.unwrap();
-    let telemetry = services::get_active_telemetry_services(&svcs);
+    #[test]
+    fn test_services_latency_and_weights() {
+        let mut report = report();
+        let mut diagtrack = service(
+            "DiagTrack",
+            ServiceStartType::Disabled,
+            ServiceStatus::Stopped,
+        );
+        diagtrack.category = ServiceCategory::Telemetry;
+        let mut push = service(
+            "dmwappushservice",
+            ServiceStartType::Automatic,
+            ServiceStatus::Running,
+        );
+        push.category = ServiceCategory::Telemetry;
+        report.services = vec![diagtrack, push];
+        report.latency = Some(LatencyReport {
+            duration_seconds: 10,
+            dpc_max_us: 550,
+            dpc_avg_us: 20.0,
+            isr_max_us: 50,
+            isr_avg_us: 5.0,
+            top_offenders: Vec::new(),
+        });
+        let weights = ScoringConfig {
+            cortana: 0,
+            ..Default::default()
+        };
+
+        let scores = score_report(&report, &weights);
+
+        let services = scores
+            .privacy
+            .findings
+            .iter()
+            .find(|f| f.id == "telemetry_services")
+            .unwrap();
+        assert_eq!(services.detail, "1/2 disabled");
+        assert_eq!(services.earned, 8);
+        // Poids nul : constat ignoré
+        assert!(scores.privacy.findings.iter().all(|f| f.id != "cortana"));
+
+        let earned: Vec<(&str, u32)> = scores
+            .performance
+            .findings
+            .iter()
+            .map(|f| (f.id.as_str(), f.earned))
+            .collect();
+        assert_eq!(
+            earned,
+            [("bloatware", 27), ("dpc_latency", 10), ("isr_latency", 10)]
+        );
+        assert_eq!(scores.performance.score, Some(78));
+    }
 
-    for svc in telemetry {
-        assert!(matches!(svc.category, ServiceCategory::Telemetry));
-        assert!(matches!(svc.status, ServiceStatus::Running));
+    #[test]
+    fn test_missing_data_leaves_dimension_unscored() {
+        let mut report = report();
+        report.appx.clear();
+        report.telemetry.diagtrack_enabled = false;
+        report.telemetry.data_collection_level = 0;
+        report.telemetry.advertising_id_enabled = false;
+        report.telemetry.activity_history_enabled = false;
+        report.telemetry.web_search_enabled = false;
+        report.telemetry.error_reporting_enabled = false;
+
+        let scores = score_report(&report, &ScoringConfig::default());
+        assert_eq!(scores.performance.score, None);
+        assert!(scores.performance.findings.is_empty());
+        assert_eq!(scores.privacy.score, Some(100));
     }
 }
 
 // ========================================================================
-// TESTS REGISTRY
+// TESTS COMPLIANCE
 // ========================================================================
 
-#[test]
-fn test_get_telemetry_status() {
-    let result = registry::get_telemetry_status();
-    assert!(result.is_ok(), "get_telemetry_status should succeed");
-
-    let status = result.unwrap();
-    assert!(
-        status.data_collection_level <= 3,
-        "Data collection level should be 0-3, got {}",
-        status.data_collection_level
-    );
-}
+mod compliance_tests {
+    use crate::compliance::{
+        ComplianceEvaluator, ComplianceSeverity, ComplianceStatus, ComplianceSummary, RuleSet,
+        BUNDLED_RULESETS,
+    };
+    use pieuvre_common::{
+        MemoryRegistry, MemoryService, MemoryServiceManager, PieuvreError, RegistryPath,
+        RegistryValue, ServiceCall,
+    };
+
+    const RULES: &str = r#"
+    name = "test"
+
+    [[rule]]
+    id = "T-001"
+    title = "Telemetry at most Basic"
+    target = { type = "registry", key = 'HKLM\SOFTWARE\Policies\Microsoft\Windows\DataCollection', value = "AllowTelemetry" }
+    comparator = "at_most"
+    expected = 1
+    default = 3
+    severity = "High"
+    references = ["CIS 18.10.16.1"]
+
+    [[rule]]
+    id = "T-002"
+    title = "DiagTrack disabled"
+    target = { type = "service", name = "DiagTrack" }
+    expected = "Disabled"
+
+    [[rule]]
+    id = "T-003"
+    title = "Search not automatic"
+    target = { type = "service", name = "WSearch" }
+    comparator = "not_equals"
+    expected = "automatic"
+
+    [[rule]]
+    id = "T-004"
+    title = "Recall policy present"
+    target = { type = "registry", key = 'HKLM\SOFTWARE\Policies\Microsoft\Windows\WindowsAI', value = "DisableAIDataAnalysis" }
+    comparator = "exists"
+
+    [[rule]]
+    id = "T-005"
+    title = "Appraiser disabled"
+    target = { type = "task", path = '\Microsoft\Windows\Application Experience\Microsoft Compatibility Appraiser' }
+    expected = false
+    "#;
+
+    fn registry(telemetry: u32) -> MemoryRegistry {
+        MemoryRegistry::new().with_value(
+            RegistryPath::hklm(r"SOFTWARE\Policies\Microsoft\Windows\DataCollection"),
+            "AllowTelemetry",
+            RegistryValue::Dword(telemetry),
+        )
+    }
 
-#[test]
-fn test_get_defender_status() {
-    let result = registry::get_defender_status();
-    assert!(result.is_ok(), "get_defender_status should succeed");
-
-    let status = result.unwrap();
-    assert!(
-        status.sample_submission <= 3,
-        "Sample submission should be 0-3, got {}",
-        status.sample_submission
-    );
-}
+    #[test]
+    fn test_bundled_rulesets_are_valid() {
+        for (name, _) in BUNDLED_RULESETS {
+            let rules = RuleSet::bundled(name).unwrap().unwrap();
+            assert_eq!(&rules.name, name);
+            assert!(!rules.rules().is_empty());
+        }
+        assert!(RuleSet::bundled("PRIVACY").is_some());
+        assert!(RuleSet::resolve("missing-ruleset").is_err());
+    }
 
-#[test]
-fn test_get_uac_status() {
-    let result = registry::get_uac_status();
-    assert!(result.is_ok(), "get_uac_status should succeed");
-
-    let status = result.unwrap();
-    assert!(
-        status.consent_prompt_behavior <= 5,
-        "Consent prompt should be 0-5, got {}",
-        status.consent_prompt_behavior
-    );
-}
+    #[test]
+    fn test_rules_evaluated_against_backends() {
+        let rules = RuleSet::from_toml(RULES).unwrap();
+        let registry = registry(0);
+        let services = MemoryServiceManager::new()
+            .with_service(MemoryService::new("DiagTrack", 2))
+            .with_service(MemoryService::new("WSearch", 3));
+        let tasks = |path: &str| path.ends_with("Appraiser").then_some(true);
+
+        let checks = ComplianceEvaluator::new(&registry, &services)
+            .with_tasks(&tasks)
+            .evaluate_all(&rules);
+        let statuses: Vec<_> = checks.iter().map(|c| c.status.clone()).collect();
+        assert_eq!(
+            statuses,
+            [
+                ComplianceStatus::Compliant,
+                ComplianceStatus::NonCompliant,
+                ComplianceStatus::Compliant,
+                ComplianceStatus::NonCompliant,
+                ComplianceStatus::NonCompliant,
+            ]
+        );
+        assert_eq!(checks[0].expected, "<= 1");
+        assert_eq!(checks[0].actual, "0");
+        assert_eq!(checks[0].severity, ComplianceSeverity::High);
+        assert_eq!(checks[0].references, ["CIS 18.10.16.1"]);
+        assert_eq!(checks[1].actual, "Automatic");
+        assert_eq!(checks[3].actual, "not set");
+        assert_eq!(checks[4].actual, "true");
+        assert_eq!(
+            checks[0].location,
+            r"HKLM\SOFTWARE\Policies\Microsoft\Windows\DataCollection\AllowTelemetry"
+        );
+        assert_eq!(checks[1].location, "Service DiagTrack");
 
-#[test]
-fn test_get_firewall_status() {
-    let result = registry::get_firewall_status();
-    assert!(result.is_ok(), "get_firewall_status should succeed");
-}
+        let summary = ComplianceSummary::from_checks(&checks);
+        assert_eq!((summary.passed, summary.failed, summary.errors), (2, 3, 0));
+        assert!(!summary.is_compliant());
 
-#[test]
-fn test_read_dword_invalid_key() {
-    let result = registry::read_dword_value(r"SOFTWARE\NonExistent\Key\Path", "NonExistentValue");
-    assert!(result.is_err(), "Should fail for non-existent key");
-}
+        let report = ComplianceEvaluator::new(&registry, &services).report(&rules);
+        assert_eq!(report.ruleset, "test");
+        assert_eq!(report.summary().total(), 5);
+    }
 
-#[test]
-fn test_key_exists() {
-    let exists = registry::key_exists(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion");
-    assert!(exists, "Windows NT CurrentVersion key should exist");
+    #[test]
+    fn test_missing_targets_and_errors() {
+        let rules = RuleSet::from_toml(RULES).unwrap();
+        // Valeur absente : défaut Windows (3), service non installé, pas de planificateur
+        let registry = MemoryRegistry::new();
+        let services = MemoryServiceManager::new()
+            .with_service(MemoryService::new("WSearch", 2))
+            .fail_on(ServiceCall::StartType, "WSearch");
+        let checks = ComplianceEvaluator::new(&registry, &services).evaluate_all(&rules);
+
+        assert_eq!(checks[0].status, ComplianceStatus::NonCompliant);
+        assert_eq!(checks[0].actual, "not set (default 3)");
+        assert_eq!(checks[1].status, ComplianceStatus::NonCompliant);
+        assert!(matches!(checks[2].status, ComplianceStatus::Error(_)));
+        assert!(matches!(
+            checks[4].status,
+            ComplianceStatus::NotApplicable(_)
+        ));
+
+        let summary = ComplianceSummary::from_checks(&checks);
+        assert_eq!((summary.errors, summary.skipped), (1, 1));
+        assert_eq!(summary.total(), 5);
+        // Règle non applicable : ni échec ni erreur
+        assert!(ComplianceSummary::from_checks(&checks[4..]).is_compliant());
+    }
 
-    let not_exists = registry::key_exists(r"SOFTWARE\NonExistent\Key\12345");
-    assert!(!not_exists, "Non-existent key should return false");
-}
+    #[test]
+    fn test_invalid_rules_rejected() {
+        let invalid = r#"
+    name = "bad"
+
+    [[rule]]
+    id = "B-001"
+    title = "Numeric comparison on text"
+    target = { type = "registry", key = 'HKLM\SOFTWARE\Test', value = "Mode" }
+    comparator = "at_least"
+    expected = "high"
+
+    [[rule]]
+    id = "B-001"
+    title = "Unknown start type"
+    target = { type = "service", name = "DiagTrack" }
+    expected = "Off"
+
+    [[rule]]
+    id = "B-003"
+    title = "Relative task path"
+    target = { type = "task", path = 'Microsoft\Task' }
+    expected = false
+
+    [[rule]]
+    id = "B-004"
+    title = "Hive root"
+    target = { type = "registry", key = 'HKLM', value = "X" }
+    comparator = "absent"
+    "#;
+        let Err(PieuvreError::Config(msg)) = RuleSet::from_toml(invalid) else {
+            panic!("rule set should be rejected");
+        };
+        assert!(msg.contains("numeric expected value"));
+        assert!(msg.contains("duplicate id"));
+        assert!(msg.contains("is not a start type"));
+        assert!(msg.contains("absolute path"));
+        assert!(msg.contains("hive root"));
+
+        // Champ inconnu
+        assert!(
+            RuleSet::from_toml("name = \"x\"\n[[rule]]\nid = \"X\"\nseverity = \"Huge\"\n")
+                .is_err()
+        );
+    }
 
-#[test]
-fn test_is_secure_boot_enabled() {
-    let _result = registry::is_secure_boot_enabled();
+    #[test]
+    fn test_privacy_baseline_on_offline_backend() {
+        let registry = registry(1);
+        let checks = crate::compliance::check_compliance_with(&registry).unwrap();
+
+        let telemetry = checks.iter().find(|c| c.id == "PRIV-001").unwrap();
+        assert_eq!(telemetry.status, ComplianceStatus::Compliant);
+        // Services absents de la ruche : considérés désactivés
+        let diagtrack = checks.iter().find(|c| c.id == "PRIV-002").unwrap();
+        assert_eq!(diagtrack.status, ComplianceStatus::Compliant);
+        assert_eq!(diagtrack.actual, "not set (default Disabled)");
+        // Tâches planifiées : non lisibles depuis le registre, ni échec ni erreur
+        let tasks: Vec<_> = checks
+            .iter()
+            .filter(|c| c.id == "PRIV-010" || c.id == "PRIV-011")
+            .collect();
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks
+            .iter()
+            .all(|c| matches!(c.status, ComplianceStatus::NotApplicable(_))));
+        let summary = ComplianceSummary::from_checks(&checks);
+        assert_eq!((summary.errors, summary.skipped), (0, 2));
+    }
 }
 
 // ========================================================================
-// TESTS SECURITY
+// TESTS OFFLINE
 // ========================================================================
 
-#[test]
-fn test_audit_security() {
-    let result = security::audit_security();
-    assert!(result.is_ok(), "audit_security should succeed");
-
-    let audit = result.unwrap();
-    assert!(
-        audit.security_score <= 100,
-        "Security score should be <= 100, got {}",
-        audit.security_score
-    );
-}
-
-#[test]
-fn test_security_recommendations_valid() {
-    let result = security::audit_security();
-    assert!(result.is_ok());
-
-    let audit = result.unwrap();
+mod offline_tests {
+    use crate::offline::{self, OfflineHives};
+    use pieuvre_common::{
+        AuditConfig, PieuvreError, RegistryBackend, RegistryHive, RegistryPath, RegistryValue,
+        ServiceManager, ServiceStartType,
+    };
+    use std::path::{Path, PathBuf};
+
+    // ========================================================================
+    // FIXTURE HIVES
+    // ========================================================================
+
+    /// Arbitrary FILETIME (2024) for key timestamps
+    const FILETIME: u64 = 0x01DA_0000_0000_0000;
+
+    #[derive(Default)]
+    struct Key {
+        name: String,
+        values: Vec<(String, RegistryValue)>,
+        subkeys: Vec<Key>,
+    }
 
-    for rec in &audit.recommendations {
-        assert!(
-            !rec.title.is_empty(),
-            "Recommendation title should not be empty"
-        );
-        assert!(
-            !rec.category.is_empty(),
-            "Recommendation category should not be empty"
-        );
+    impl Key {
+        fn key_mut(&mut self, path: &str) -> &mut Key {
+            path.split('\\')
+                .filter(|part| !part.is_empty())
+                .fold(self, |key, part| {
+                    let index = match key.subkeys.iter().position(|k| k.name == part) {
+                        Some(index) => index,
+                        None => {
+                            key.subkeys.push(Key {
+                                name: part.to_string(),
+                                ..Default::default()
+                            });
+                            key.subkeys.len() - 1
+                        }
+                    };
+                    &mut key.subkeys[index]
+                })
+        }
     }
-}
 
-#[test]
-fn test_score_to_grade() {
-    assert_eq!(security::score_to_grade(100), "A - Excellent");
-    assert_eq!(security::score_to_grade(95), "A - Excellent");
-    assert_eq!(security::score_to_grade(85), "B - Good");
-    assert_eq!(security::score_to_grade(75), "C - Acceptable");
-    assert_eq!(security::score_to_grade(65), "D - Needs Improvement");
-    assert_eq!(security::score_to_grade(50), "F - Critical");
-}
+    /// Primary hive file with keys and DWORD / string values (no security cells)
+    #[derive(Default)]
+    struct HiveBuilder {
+        root: Key,
+    }
 
-#[test]
-fn test_count_by_severity() {
-    let recs = vec![
-        security::SecurityRecommendation {
-            severity: security::Severity::Critical,
-            category: "Test".into(),
-            title: "Test".into(),
-            description: "Test".into(),
-            remediation: "Test".into(),
-        },
-        security::SecurityRecommendation {
-            severity: security::Severity::High,
-            category: "Test".into(),
-            title: "Test".into(),
-            description: "Test".into(),
-            remediation: "Test".into(),
-        },
-        security::SecurityRecommendation {
-            severity: security::Severity::Low,
-            category: "Test".into(),
-            title: "Test".into(),
-            description: "Test".into(),
-            remediation: "Test".into(),
-        },
-    ];
+    impl HiveBuilder {
+        fn new() -> Self {
+            Self {
+                root: Key {
+                    name: "ROOT".to_string(),
+                    ..Default::default()
+                },
+            }
+        }
 
-    let (critical, high, medium, low) = security::count_by_severity(&recs);
-    assert_eq!(critical, 1);
-    assert_eq!(high, 1);
-    assert_eq!(medium, 0);
-    assert_eq!(low, 1);
-}
+        fn key(mut self, path: &str) -> Self {
+            self.root.key_mut(path);
+            self
+        }
 
-// ========================================================================
-// TESTS APPX
-// ========================================================================
+        fn dword(mut self, path: &str, name: &str, value: u32) -> Self {
+            let key = self.root.key_mut(path);
+            key.values
+                .push((name.to_string(), RegistryValue::Dword(value)));
+            self
+        }
 
-#[test]
-fn test_scan_packages() {
-    let result = appx::scan_packages();
-    assert!(result.is_ok(), "scan_packages should succeed");
-}
+        fn string(mut self, path: &str, name: &str, value: &str) -> Self {
+            let key = self.root.key_mut(path);
+            key.values
+                .push((name.to_string(), RegistryValue::String(value.to_string())));
+            self
+        }
 
-#[test]
-fn test_get_bloatware() {
-    let result = appx::scan_packages();
-    if let Ok(packages) = result {
-        let bloat = appx::get_bloatware(&packages);
+        fn raw(mut self, path: &str, name: &str, value_type: u32, data: &[u8]) -> Self {
+            let key = self.root.key_mut(path);
+            key.values.push((
+                name.to_string(),
+                RegistryValue::Raw {
+                    value_type,
+                    data: data.to_vec(),
+                },
+            ));
+            self
+        }
 
-        for pkg in bloat {
-            assert!(matches!(pkg.removal_risk, RemovalRisk::Safe));
+        fn build(&self) -> Vec<u8> {
+            // En-tête hbin, les cellules suivent
+            let mut bins = Bins {
+                data: b"hbin".to_vec(),
+            };
+            bins.data.resize(0x20, 0);
+            let root = bins.write_key(&self.root, 0, true);
+
+            let size = bins.data.len().next_multiple_of(4096);
+            let free = size - bins.data.len();
+            if free >= 8 {
+                bins.data.extend_from_slice(&(free as i32).to_le_bytes());
+            }
+            bins.data.resize(size, 0);
+            bins.data[8..12].copy_from_slice(&(size as u32).to_le_bytes());
+
+            let mut base = vec![0u8; 4096];
+            base[0..4].copy_from_slice(b"regf");
+            for (offset, value) in [
+                (0x04, 1),           // primary sequence number
+                (0x08, 1),           // secondary sequence number
+                (0x14, 1),           // major version
+                (0x18, 5),           // minor version
+                (0x20, 1),           // direct memory load
+                (0x24, root),        // root cell
+                (0x28, size as u32), // hive bins data size
+                (0x2C, 1),           // clustering factor
+            ] {
+                base[offset..offset + 4].copy_from_slice(&u32::to_le_bytes(value));
+            }
+            let checksum = base[..508]
+                .chunks(4)
+                .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .fold(0, |acc, x| acc ^ x);
+            base[508..512].copy_from_slice(&checksum.to_le_bytes());
+
+            base.extend(bins.data);
+            base
         }
     }
-}
 
-// ========================================================================
-// TESTS NETWORK
-// ========================================================================
+    struct Bins {
+        data: Vec<u8>,
+    }
 
-#[test]
-fn test_get_telemetry_domains() {
-    let domains = network::get_telemetry_domains();
-    assert!(!domains.is_empty(), "Should have telemetry domains list");
+    impl Bins {
+        /// Allocated cell, 8-byte aligned; offset from the start of the bins
+        fn alloc(&mut self, content: &[u8]) -> u32 {
+            let offset = self.data.len();
+            let size = (content.len() + 4).next_multiple_of(8);
+            self.data.extend_from_slice(&(-(size as i32)).to_le_bytes());
+            self.data.extend_from_slice(content);
+            self.data.resize(offset + size, 0);
+            offset as u32
+        }
 
-    assert!(domains.iter().any(|d: &&str| d.contains("microsoft.com")));
-    assert!(domains.iter().any(|d: &&str| d.contains("telemetry")));
-}
+        fn patch(&mut self, cell: u32, field: usize, value: u32) {
+            let at = cell as usize + 4 + field;
+            self.data[at..at + 4].copy_from_slice(&value.to_le_bytes());
+        }
 
-#[test]
-fn test_get_telemetry_ip_ranges() {
-    let ranges = network::get_telemetry_ip_ranges();
-    assert!(!ranges.is_empty(), "Should have IP ranges list");
+        fn write_key(&mut self, key: &Key, parent: u32, root: bool) -> u32 {
+            let mut nk = b"nk".to_vec();
+            nk.extend_from_slice(&u16::to_le_bytes(if root { 0x2C } else { 0x20 }));
+            nk.extend_from_slice(&FILETIME.to_le_bytes());
+            // access bits, parent, subkeys (count, volatile, list, volatile list),
+            // values (count, list), security, class, 5 maxima / work var
+            for field in [
+                0,
+                parent,
+                0,
+                0,
+                u32::MAX,
+                u32::MAX,
+                0,
+                u32::MAX,
+                u32::MAX,
+                u32::MAX,
+                0,
+                0,
+                0,
+                0,
+                0,
+            ] {
+                nk.extend_from_slice(&field.to_le_bytes());
+            }
+            nk.extend_from_slice(&(key.name.len() as u16).to_le_bytes());
+            nk.extend_from_slice(&0u16.to_le_bytes());
+            nk.extend_from_slice(key.name.as_bytes());
+            let cell = self.alloc(&nk);
+
+            if !key.values.is_empty() {
+                let offsets: Vec<u32> = key
+                    .values
+                    .iter()
+                    .map(|(name, value)| self.write_value(name, value))
+                    .collect();
+                let list = self.alloc(
+                    &offsets
+                        .iter()
+                        .flat_map(|o| o.to_le_bytes())
+                        .collect::<Vec<_>>(),
+                );
+                self.patch(cell, 0x24, offsets.len() as u32);
+                self.patch(cell, 0x28, list);
+            }
+
+            if !key.subkeys.is_empty() {
+                let offsets: Vec<u32> = key
+                    .subkeys
+                    .iter()
+                    .map(|subkey| self.write_key(subkey, cell, false))
+                    .collect();
+                let mut li = b"li".to_vec();
+                li.extend_from_slice(&(offsets.len() as u16).to_le_bytes());
+                li.extend(offsets.iter().flat_map(|o| o.to_le_bytes()));
+                let list = self.alloc(&li);
+                self.patch(cell, 0x14, offsets.len() as u32);
+                self.patch(cell, 0x1C, list);
+            }
+            cell
+        }
 
-    for range in ranges {
-        assert!(
-            range.contains('/'),
-            "IP range should be in CIDR format: {}",
-            range
-        );
+        fn write_value(&mut self, name: &str, value: &RegistryValue) -> u32 {
+            // DWORD stocké dans le champ offset (bit de poids fort de la taille)
+            let (size, data, value_type) = match value {
+                RegistryValue::Dword(v) => (0x8000_0004, *v, 4),
+                RegistryValue::String(s) => {
+                    let mut bytes: Vec<u8> =
+                        s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+                    bytes.extend_from_slice(&[0, 0]);
+                    (bytes.len() as u32, self.alloc(&bytes), 1)
+                }
+                RegistryValue::Raw { value_type, data } => {
+                    (data.len() as u32, self.alloc(data), *value_type)
+                }
+                other => panic!("fixture value not supported: {:?}", other),
+            };
+
+            let mut vk = b"vk".to_vec();
+            vk.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            for field in [size, data, value_type] {
+                vk.extend_from_slice(&field.to_le_bytes());
+            }
+            // nom ASCII
+            vk.extend_from_slice(&1u16.to_le_bytes());
+            vk.extend_from_slice(&0u16.to_le_bytes());
+            vk.extend_from_slice(name.as_bytes());
+            self.alloc(&vk)
+        }
     }
-}
 
-#[test]
-fn test_is_telemetry_domain() {
-    assert!(network::is_telemetry_domain("telemetry.microsoft.com"));
-    assert!(network::is_telemetry_domain("vortex.data.microsoft.com"));
-    assert!(!network::is_telemetry_domain("www.google.com"));
-    assert!(!network::is_telemetry_domain("github.com"));
-}
+    fn system_hive() -> HiveBuilder {
+        let services = r"ControlSet001\Services";
+        HiveBuilder::new()
+            .dword("Select", "Current", 1)
+            .string(
+                r"ControlSet001\Control\ComputerName\ComputerName",
+                "ComputerName",
+                "GOLDEN-01",
+            )
+            .dword(
+                r"ControlSet001\Services\SharedAccess\Parameters\FirewallPolicy\StandardProfile",
+                "EnableFirewall",
+                1,
+            )
+            .dword(r"ControlSet001\Control\Lsa", "LsaCfgFlags", 1)
+            .dword(&format!(r"{}\DiagTrack", services), "Type", 0x10)
+            .dword(&format!(r"{}\DiagTrack", services), "Start", 4)
+            .string(
+                &format!(r"{}\DiagTrack", services),
+                "DisplayName",
+                "Connected User Experiences and Telemetry",
+            )
+            .dword(&format!(r"{}\WSearch", services), "Type", 0x10)
+            .dword(&format!(r"{}\WSearch", services), "Start", 2)
+            // Pilote : absent de la liste des services
+            .dword(&format!(r"{}\disk", services), "Type", 1)
+            .dword(&format!(r"{}\disk", services), "Start", 0)
+    }
 
-// ========================================================================
-// TESTS LIB (FULL AUDIT)
-// ========================================================================
+    fn software_hive() -> HiveBuilder {
+        let store = r"Microsoft\Windows\CurrentVersion\Appx\AppxAllUserStore";
+        HiveBuilder::new()
+            .string(
+                r"Microsoft\Windows NT\CurrentVersion",
+                "ProductName",
+                "Windows 11 Enterprise",
+            )
+            .string(
+                r"Microsoft\Windows NT\CurrentVersion",
+                "CurrentBuildNumber",
+                "26100",
+            )
+            .string(
+                r"Microsoft\Windows NT\CurrentVersion",
+                "EditionID",
+                "Enterprise",
+            )
+            .dword(
+                r"Policies\Microsoft\Windows\DataCollection",
+                "AllowTelemetry",
+                0,
+            )
+            .dword(
+                r"Microsoft\Windows\CurrentVersion\AdvertisingInfo",
+                "Enabled",
+                1,
+            )
+            .dword(
+                r"Microsoft\Windows\CurrentVersion\Policies\System",
+                "ConsentPromptBehaviorAdmin",
+                2,
+            )
+            .key(&format!(
+                r"{}\Applications\Microsoft.BingNews_4.55.62231.0_x64__8wekyb3d8bbwe",
+                store
+            ))
+            .key(&format!(
+                r"{}\S-1-5-21-1-2-3-1001\Microsoft.WindowsCalculator_11.2405.2.0_x64__8wekyb3d8bbwe",
+                store
+            ))
+            .key(&format!(
+                r"{}\S-1-5-21-1-2-3-1001\Microsoft.BingNews_4.55.62231.0_x64__8wekyb3d8bbwe",
+                store
+            ))
+    }
 
-#[test]
-fn test_full_audit() {
-    let result = full_audit();
-    assert!(result.is_ok(), "full_audit should succeed");
+    fn ntuser_hive() -> HiveBuilder {
+        HiveBuilder::new().dword(
+            r"Software\Microsoft\Windows\CurrentVersion\AdvertisingInfo",
+            "Enabled",
+            0,
+        )
+    }
 
-    let report = result.unwrap();
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pieuvre-offline-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
 
-    assert!(!report.system.os_version.is_empty());
-    assert!(report.system.build_number > 0);
-    assert!(report.hardware.cpu.logical_cores >= 1);
-    assert!(report.hardware.memory.total_bytes > 0);
-}
+    fn write(dir: &Path, name: &str, hive: HiveBuilder) {
+        std::fs::write(dir.join(name), hive.build()).unwrap();
+    }
 
-#[test]
-fn test_security_audit_func() {
-    let result = security_audit();
-    assert!(result.is_ok(), "security_audit should succeed");
-}
+    // ========================================================================
+    // TESTS
+    // ========================================================================
+
+    #[test]
+    fn test_offline_hive_registry_reads_fixture() {
+        let registry = crate::OfflineHiveRegistry::new()
+            .mount_bytes(RegistryHive::Hklm, "SYSTEM", system_hive().build())
+            .unwrap();
+
+        // CurrentControlSet suit Select\Current
+        let path = RegistryPath::hklm(r"SYSTEM\CurrentControlSet\Services\WSearch");
+        assert_eq!(registry.read_dword(&path, "Start").unwrap(), Some(2));
+        assert_eq!(registry.read_dword(&path, "Missing").unwrap(), None);
+        assert!(registry.write_dword(&path, "Start", 4).is_err());
+
+        let services = registry
+            .list_subkeys(&RegistryPath::hklm(r"SYSTEM\CurrentControlSet\Services"))
+            .unwrap();
+        assert_eq!(services.len(), 4);
+        assert!(!registry.key_exists(&RegistryPath::hklm(r"SOFTWARE\Microsoft")));
+    }
 
-#[test]
-fn test_is_laptop_function() {
-    let _result = is_laptop();
-}
+    #[test]
+    fn test_offline_hive_unreadable_type_is_an_error() {
+        // REG_RESOURCE_LIST : données non exposées par le parseur
+        let hive = system_hive().raw(r"ControlSet001\Services\WSearch", "Resources", 8, &[1; 16]);
+        let registry = crate::OfflineHiveRegistry::new()
+            .mount_bytes(RegistryHive::Hklm, "SYSTEM", hive.build())
+            .unwrap();
+        let path = RegistryPath::hklm(r"SYSTEM\CurrentControlSet\Services\WSearch");
+
+        assert!(matches!(
+            registry.read_value(&path, "Resources"),
+            Err(PieuvreError::Unsupported(_))
+        ));
+        assert!(registry.list_values(&path).is_err());
+        assert_eq!(registry.read_dword(&path, "Start").unwrap(), Some(2));
+    }
 
-#[test]
-fn test_network_audit() {
-    let result = network_audit();
-    assert!(result.is_ok(), "network_audit should succeed");
+    #[test]
+    fn test_offline_hive_non_ascii_key_outside_mount() {
+        // U+212A (3 octets) se met en minuscule en « k » (1 octet)
+        let registry = crate::OfflineHiveRegistry::new()
+            .mount_bytes(RegistryHive::Hklm, "K", system_hive().build())
+            .unwrap();
+        let path = RegistryPath::hklm("\u{212A}\\Select");
+
+        assert_eq!(registry.read_value(&path, "Current").unwrap(), None);
+        assert_eq!(
+            registry
+                .read_dword(&RegistryPath::hklm(r"k\Select"), "Current")
+                .unwrap(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_offline_audit_from_exported_hives() {
+        let dir = temp_dir("export");
+        // Casse des noms variable selon l'outil d'export
+        write(&dir, "system", system_hive());
+        write(&dir, "SOFTWARE", software_hive());
+        write(&dir, "ntuser.dat", ntuser_hive());
+
+        let hives = OfflineHives::locate(&dir).unwrap();
+        assert!(hives.ntuser.is_some());
+        assert!(hives.usrclass.is_none());
+        let registry = hives.mount().unwrap();
+        let report = offline::audit_registry(&registry, &AuditConfig::default()).unwrap();
+
+        assert_eq!(report.system.os_version, "Windows 11 Enterprise");
+        assert_eq!(report.system.build_number, 26100);
+        assert_eq!(report.system.edition, "Enterprise");
+        assert_eq!(report.system.hostname, "GOLDEN-01");
+
+        let names: Vec<&str> = report.services.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["DiagTrack", "WSearch"]);
+        assert_eq!(report.services[0].start_type, ServiceStartType::Disabled);
+        assert_eq!(
+            report.services[0].display_name,
+            "Connected User Experiences and Telemetry"
+        );
+        assert_eq!(report.services[1].start_type, ServiceStartType::Automatic);
+
+        assert!(!report.telemetry.diagtrack_enabled);
+        assert_eq!(report.telemetry.data_collection_level, 0);
+        // NTUSER.DAT prioritaire sur la valeur machine
+        assert!(!report.telemetry.advertising_id_enabled);
+
+        assert!(report.security.firewall_enabled);
+        assert!(report.security.credential_guard);
+        assert_eq!(report.security.uac_level, 2);
+
+        let packages: Vec<(&str, &str, bool)> = report
+            .appx
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.as_str(), p.is_provisioned))
+            .collect();
+        assert_eq!(
+            packages,
+            [
+                ("Microsoft.BingNews", "4.55.62231.0", true),
+                ("Microsoft.WindowsCalculator", "11.2405.2.0", false),
+            ]
+        );
+        assert!(report.latency.is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_offline_hives_in_image_root() {
+        let dir = temp_dir("image");
+        let config_dir = dir.join("Windows").join("System32").join("config");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        write(&config_dir, "SYSTEM", system_hive());
+
+        // SOFTWARE manquant : erreur explicite
+        let err = OfflineHives::locate(&dir).unwrap_err();
+        assert!(matches!(&err, PieuvreError::Config(msg) if msg.contains("SOFTWARE")));
+
+        write(&config_dir, "SOFTWARE", software_hive());
+        let hives = OfflineHives::locate(&dir).unwrap();
+        assert_eq!(hives.system, Some(config_dir.join("SYSTEM")));
+        assert!(hives.ntuser.is_none());
+
+        // Sans NTUSER.DAT, le réglage machine s'applique
+        let registry = hives.mount().unwrap();
+        let services = offline::RegistryServiceManager::new(&registry);
+        assert_eq!(services.start_type("diagtrack").unwrap(), 4);
+        assert!(matches!(
+            services.start_type("Missing"),
+            Err(PieuvreError::ServiceNotFound(_))
+        ));
+        let telemetry = crate::security::get_telemetry_status_with(&registry, &services).unwrap();
+        assert!(telemetry.advertising_id_enabled);
+
+        // Un fichier qui n'est pas une ruche est refusé
+        std::fs::write(config_dir.join("SOFTWARE"), b"not a hive").unwrap();
+        assert!(OfflineHives::locate(&dir).unwrap().mount().is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
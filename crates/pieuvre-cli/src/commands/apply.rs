@@ -0,0 +1,54 @@
+//! Application d'une sélection de tweaks
+//!
+//! Chemin commun de `tweak apply`, `tweak apply-all` et `profile apply` : une
+//! transaction (politique `[transaction]`, snapshot étiqueté par l'issue), ou
+//! le plan de chaque étape sous `general.dry_run`.
+
+use crate::commands::interactive::executor::{run_transaction, CommandRegistry};
+use pieuvre_common::{PieuvreError, Result, TransactionOutcome};
+use pieuvre_sync::graph::Resolution;
+use std::sync::Arc;
+
+pub async fn run(name: &str, registry: Arc<CommandRegistry>, resolution: Resolution) -> Result<()> {
+    if pieuvre_common::config().general.dry_run {
+        println!("Dry-run: nothing will be applied (see `pieuvre plan` for details)");
+        for id in &resolution.order {
+            match registry.plan(id).await {
+                Ok(planned) => println!("Applying {}... {} change(s) planned", id, planned.len()),
+                Err(e) => println!("Applying {}... no preview ({})", id, e),
+            }
+        }
+        return Ok(());
+    }
+
+    // Transaction : selon [transaction], un échec annule ce qui a été appliqué
+    let undo = registry.clone();
+    let report = run_transaction(name, registry, resolution, move |_, step| {
+        match &step.error {
+            None => println!("Applying {}... OK ({} change(s))", step.name, step.changes),
+            Some(e) => println!("Applying {}... FAILED: {}", step.name, e),
+        }
+        if step.error.is_none() && !undo.undoable(&step.name) {
+            println!(
+                "  {} is not undoable: rollback cannot restore it",
+                step.name
+            );
+        }
+    })
+    .await?;
+    println!("{}", report.summary());
+    if let Some(e) = &report.rollback_error {
+        eprintln!("WARNING: {}", e);
+    }
+    match report.outcome {
+        TransactionOutcome::Success => {
+            super::status::print_pending_reboot();
+            Ok(())
+        }
+        _ => Err(PieuvreError::Internal(format!(
+            "{}: {}",
+            name,
+            report.summary()
+        ))),
+    }
+}
@@ -1,20 +1,31 @@
 //! Audit command
 //!
-//! Complete system audit with automatic report generation.
+//! Complete system audit with automatic report generation. With `--offline`,
+//! the report is read from exported hives instead of the running system.
+//! `--compliance` then evaluates a rule set and fails if a rule does not pass.
+//! `--format` renders the report in HTML, Markdown, CSV or SARIF; the JSON is
+//! still kept in `general.report_dir`. `--compare` lists what changed since
+//! an earlier JSON report. The summary ends with the privacy, performance
+//! and security scores, also read back by `status` and the TUI header.
 
+use super::report::{self, ReportData, ReportFormat};
 use chrono::Local;
-use pieuvre_common::AuditReport;
-use pieuvre_common::Result;
-use std::fs;
-use std::path::PathBuf;
-
-const OUTPUT_DIR: &str = r"C:\ProgramData\pieuvre\reports";
+use pieuvre_audit::compliance::{ComplianceCheck, ComplianceStatus};
+use pieuvre_audit::offline::{OfflineHives, RegistryServiceManager};
+use pieuvre_audit::{AuditDiff, ComplianceEvaluator, ComplianceReport, RuleSet};
+use pieuvre_common::{AuditReport, AuditScores, DimensionScore};
+use pieuvre_common::{NativeRegistry, NativeServiceManager, PieuvreError, Result};
+use std::path::{Path, PathBuf};
 
 /// Callback type for audit logging
 pub type AuditLogCallback<'a> = &'a mut dyn FnMut(&str, &str);
 
 pub fn run(
     full: bool,
+    offline: Option<PathBuf>,
+    compliance: Option<String>,
+    compare: Option<PathBuf>,
+    format: ReportFormat,
     output: Option<String>,
     mut log_cb: Option<AuditLogCallback>,
 ) -> Result<AuditReport> {
@@ -34,21 +45,48 @@ pub fn run(
         );
     }
 
-    let report = pieuvre_audit::full_audit()?;
+    // Rapport de référence lu avant l'audit : un fichier invalide échoue tôt
+    let previous = compare.as_deref().map(load_report).transpose()?;
 
-    let json = serde_json::to_string_pretty(&report)?;
+    let report = match &offline {
+        Some(dir) => pieuvre_audit::offline_audit(dir)?,
+        None => pieuvre_audit::full_audit()?,
+    };
 
-    // Determine output path
-    let output_path = if let Some(path) = output {
-        PathBuf::from(path)
-    } else {
-        // Automatic backup
-        fs::create_dir_all(OUTPUT_DIR)?;
-        let timestamp = Local::now().format("%Y%m%d_%H%M%S");
-        PathBuf::from(OUTPUT_DIR).join(format!("audit_{}.json", timestamp))
+    let compliance = match compliance {
+        Some(ruleset) => Some(evaluate_compliance(&ruleset, offline.as_deref())?),
+        None => None,
     };
 
-    fs::write(&output_path, &json)?;
+    // JSON : --output, sinon sauvegarde automatique dans general.report_dir
+    let report_dir = &pieuvre_common::config().general.report_dir;
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S");
+    let json_path = match (&output, format) {
+        (Some(path), ReportFormat::Json) => PathBuf::from(path),
+        _ => report_dir.join(format!("audit_{}.json", timestamp)),
+    };
+    report::write(&json_path, &serde_json::to_string_pretty(&report)?)?;
+    if let Some(results) = &compliance {
+        report::write(
+            &compliance_path(&json_path),
+            &serde_json::to_string_pretty(results)?,
+        )?;
+    }
+
+    let output_path = if format == ReportFormat::Json {
+        json_path.clone()
+    } else {
+        let path = output.map(PathBuf::from).unwrap_or_else(|| {
+            report_dir.join(format!("audit_{}.{}", timestamp, format.extension()))
+        });
+        let data = ReportData {
+            audit: Some(report.clone()),
+            compliance: compliance.clone(),
+            threats: Vec::new(),
+        };
+        report::write(&path, &report::render(&data, format)?)?;
+        path
+    };
 
     if let Some(ref mut cb) = log_cb {
         cb(
@@ -62,7 +100,13 @@ pub fn run(
                 report.system.os_version, report.system.build_number
             ),
         );
-        cb("INFO", &format!("CPU: {}", report.hardware.cpu.model_name));
+        match &offline {
+            Some(dir) => cb("INFO", &format!("Offline source: {}", dir.display())),
+            None => cb("INFO", &format!("CPU: {}", report.hardware.cpu.model_name)),
+        }
+        if let Some(scores) = &report.scores {
+            cb("INFO", &format!("Scores: {}", format_scores(scores)));
+        }
     } else {
         // Standard console summary
         println!("═══════════════════════════════════════════════════════════════════");
@@ -70,20 +114,215 @@ pub fn run(
         println!("═══════════════════════════════════════════════════════════════════");
         println!("  ID:         {}", report.id);
         println!("  Timestamp:  {}", report.timestamp);
+        if let Some(dir) = &offline {
+            println!("  Source:     {} (offline)", dir.display());
+            println!("  Host:       {}", report.system.hostname);
+        }
         println!(
             "  OS:         {} (Build {})",
             report.system.os_version, report.system.build_number
         );
-        println!("  CPU:        {}", report.hardware.cpu.model_name);
-        println!(
-            "  RAM:        {:.1} GB",
-            report.hardware.memory.total_bytes as f64 / 1024.0 / 1024.0 / 1024.0
-        );
+        // Pas de matériel ni de réseau pour une image hors-ligne
+        if offline.is_none() {
+            println!("  CPU:        {}", report.hardware.cpu.model_name);
+            println!(
+                "  RAM:        {:.1} GB",
+                report.hardware.memory.total_bytes as f64 / 1024.0 / 1024.0 / 1024.0
+            );
+        }
         println!("  Services:   {} analyzed", report.services.len());
         println!("  Packages:   {} Appx", report.appx.len());
+        if full && offline.is_none() && pieuvre_common::config().audit.network {
+            let network = pieuvre_audit::network::inspect_network()?;
+            println!(
+                "  Telemetry:  {} blocked / {} reachable endpoints",
+                network.blocked_count, network.reachable_count
+            );
+        }
+        if let Some(scores) = &report.scores {
+            println!("  Scores:     {}", format_scores(scores));
+        }
         println!("═══════════════════════════════════════════════════════════════════");
         println!("\n  [*] Report saved: {}", output_path.display());
+        if output_path != json_path {
+            println!("  [*] JSON kept:    {}", json_path.display());
+        }
+    }
+
+    if let Some(previous) = &previous {
+        let diff = pieuvre_audit::diff_reports(previous, &report);
+        match log_cb {
+            Some(ref mut cb) => cb(
+                "INFO",
+                &format!("{} change(s) since report {}", diff.len(), diff.old_id),
+            ),
+            None => print_diff(&diff),
+        }
+    }
+
+    if let Some(results) = &compliance {
+        check_compliance(results)?;
     }
 
     Ok(report)
 }
+
+/// Evaluates a rule set on the audited system
+fn evaluate_compliance(ruleset: &str, offline: Option<&Path>) -> Result<ComplianceReport> {
+    let rules = RuleSet::resolve(ruleset)?;
+    let results = match offline {
+        Some(dir) => {
+            let registry = OfflineHives::locate(dir)?.mount()?;
+            let services = RegistryServiceManager::new(&registry);
+            // Tâches planifiées absentes des ruches : règles non applicables
+            ComplianceEvaluator::new(&registry, &services).report(&rules)
+        }
+        None => ComplianceEvaluator::new(&NativeRegistry, &NativeServiceManager)
+            .with_tasks(&pieuvre_sync::scheduled_tasks::task_state)
+            .report(&rules),
+    };
+    Ok(results)
+}
+
+/// Prints the compliance results; an error when a rule fails
+fn check_compliance(results: &ComplianceReport) -> Result<()> {
+    println!(
+        "\n  Compliance: {} ({} rules)\n",
+        results.ruleset,
+        results.checks.len()
+    );
+    for check in &results.checks {
+        println!("  {}", format_check(check));
+    }
+
+    let summary = results.summary();
+    println!(
+        "\n  {} passed, {} failed, {} error(s), {} skipped",
+        summary.passed, summary.failed, summary.errors, summary.skipped
+    );
+    if !summary.is_compliant() {
+        return Err(PieuvreError::Internal(format!(
+            "{} of {} compliance rules did not pass ({})",
+            summary.failed + summary.errors,
+            summary.total() - summary.skipped,
+            results.ruleset
+        )));
+    }
+    Ok(())
+}
+
+/// Rapport d'audit JSON enregistré
+fn load_report(path: &Path) -> Result<AuditReport> {
+    let content = std::fs::read_to_string(path)?;
+    serde_json::from_str(&content).map_err(|e| {
+        PieuvreError::Parse(format!("{}: not an audit report ({})", path.display(), e))
+    })
+}
+
+/// Dernier rapport JSON de `general.report_dir` (horodatage dans le nom)
+pub fn latest_report() -> Option<PathBuf> {
+    std::fs::read_dir(&pieuvre_common::config().general.report_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with("audit_")
+                        && name.ends_with(".json")
+                        && !name.ends_with("_compliance.json")
+                })
+        })
+        .max()
+}
+
+/// Scores of a saved report, computed for reports older than scoring
+pub fn report_scores(path: &Path) -> Option<(AuditReport, AuditScores)> {
+    let mut report = load_report(path).ok()?;
+    let scores = match report.scores.take() {
+        Some(scores) => scores,
+        None => pieuvre_audit::score_report(&report, &pieuvre_common::config().scoring),
+    };
+    Some((report, scores))
+}
+
+/// `72` or `n/a` when nothing was scored
+pub fn score_label(dimension: &DimensionScore) -> String {
+    dimension
+        .score
+        .map_or_else(|| "n/a".to_string(), |score| score.to_string())
+}
+
+fn format_scores(scores: &AuditScores) -> String {
+    format!(
+        "privacy {} / performance {} / security {}",
+        score_label(&scores.privacy),
+        score_label(&scores.performance),
+        score_label(&scores.security)
+    )
+}
+
+/// Changes since the earlier report, one line each
+fn print_diff(diff: &AuditDiff) {
+    println!(
+        "\n  Changes since {} ({})\n",
+        &diff.old_id.to_string()[..8],
+        diff.old_timestamp.format("%Y-%m-%d %H:%M UTC")
+    );
+    let sections = [
+        ("system", &diff.system),
+        ("telemetry", &diff.telemetry),
+        ("security", &diff.security),
+        ("hardware", &diff.hardware),
+    ];
+    for (section, changes) in sections {
+        for change in changes {
+            println!(
+                "  ~ [{}] {} : {} -> {}",
+                section, change.field, change.before, change.after
+            );
+        }
+    }
+    for service in &diff.services {
+        println!(
+            "  ~ [service] {} : {:?}/{:?} -> {:?}/{:?}",
+            service.name,
+            service.before_start_type,
+            service.before_status,
+            service.after_start_type,
+            service.after_status
+        );
+    }
+    for package in &diff.appx_removed {
+        println!("  - [appx] {} {}", package.name, package.version);
+    }
+    for package in &diff.appx_added {
+        println!("  + [appx] {} {}", package.name, package.version);
+    }
+    if diff.is_empty() {
+        println!("  No differences");
+    }
+    println!("\n  {} change(s)", diff.len());
+}
+
+/// `audit_X.json` -> `audit_X_compliance.json`
+fn compliance_path(json_path: &Path) -> PathBuf {
+    let stem = json_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "audit".to_string());
+    json_path.with_file_name(format!("{}_compliance.json", stem))
+}
+
+fn format_check(check: &ComplianceCheck) -> String {
+    let line = format!("{:<9} {} [{:?}]", check.id, check.name, check.severity);
+    match &check.status {
+        ComplianceStatus::Compliant => format!("[PASS] {}", line),
+        ComplianceStatus::NonCompliant => format!(
+            "[FAIL] {}\n         expected {}, found {}",
+            line, check.expected, check.actual
+        ),
+        ComplianceStatus::Error(e) => format!("[ERR ] {}\n         {}", line, e),
+        ComplianceStatus::NotApplicable(reason) => format!("[SKIP] {}\n         {}", line, reason),
+    }
+}
@@ -0,0 +1,103 @@
+//! Catalogue déclaratif des tweaks
+//!
+//! Catalogue intégré (`catalog/builtin.toml`) fusionné avec les packs site de
+//! `tweaks.packs_dir`. Chaque entrée devient une `CatalogOperation` dans le
+//! `CommandRegistry` et un `OptItem` dans sa section.
+
+use crate::commands::interactive::types::{OptItem, RiskLevel};
+use pieuvre_sync::catalog::{TweakCatalog, TweakRisk};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Catalogue livré avec le binaire
+pub const BUILTIN_CATALOG: &str = include_str!("../../../catalog/builtin.toml");
+
+static CATALOG: OnceLock<TweakCatalog> = OnceLock::new();
+
+/// Catalogue actif (chargé au premier accès)
+pub fn catalog() -> &'static TweakCatalog {
+    CATALOG.get_or_init(|| {
+        let mut catalog =
+            TweakCatalog::from_toml(BUILTIN_CATALOG).expect("built-in tweak catalog is invalid");
+        load_packs(&mut catalog, &pieuvre_common::config().tweaks.packs_dir);
+        catalog
+    })
+}
+
+/// Fusionne les packs d'un dossier par ordre alphabétique ; un pack invalide est ignoré
+pub fn load_packs(catalog: &mut TweakCatalog, dir: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut packs: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    ext.eq_ignore_ascii_case("toml") || ext.eq_ignore_ascii_case("json")
+                })
+        })
+        .collect();
+    packs.sort();
+
+    let mut loaded = 0;
+    for path in packs {
+        match TweakCatalog::load_file(&path) {
+            Ok(pack) => {
+                tracing::info!(
+                    "Tweak pack loaded: {} ({} tweaks)",
+                    path.display(),
+                    pack.tweaks().len()
+                );
+                catalog.merge(pack);
+                loaded += 1;
+            }
+            Err(e) => tracing::warn!("Tweak pack ignored: {}", e),
+        }
+    }
+    loaded
+}
+
+/// Entrées du catalogue pour une catégorie, au format des sections
+pub fn options(category: &str) -> Vec<OptItem> {
+    catalog()
+        .tweaks()
+        .iter()
+        .filter(|t| t.category == category)
+        .map(|t| OptItem {
+            id: &t.id,
+            label: &t.label,
+            description: &t.description,
+            default: t.default,
+            risk: t.risk.into(),
+        })
+        .collect()
+}
+
+/// Catégories du catalogue sans section dédiée (packs site), dans l'ordre d'apparition
+pub fn extra_categories(known: &[&str]) -> Vec<&'static str> {
+    let mut categories: Vec<&'static str> = Vec::new();
+    for tweak in catalog().tweaks() {
+        let category = tweak.category.as_str();
+        if !known.contains(&category) && !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    categories
+}
+
+impl From<TweakRisk> for RiskLevel {
+    fn from(risk: TweakRisk) -> Self {
+        match risk {
+            TweakRisk::Safe => RiskLevel::Safe,
+            TweakRisk::Low => RiskLevel::Low,
+            TweakRisk::Medium => RiskLevel::Medium,
+            TweakRisk::High => RiskLevel::High,
+            TweakRisk::Critical => RiskLevel::Critical,
+            TweakRisk::Performance => RiskLevel::Performance,
+            TweakRisk::Conditional => RiskLevel::Conditional,
+            TweakRisk::Warning => RiskLevel::Warning,
+        }
+    }
+}
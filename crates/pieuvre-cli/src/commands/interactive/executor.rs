@@ -1,9 +1,24 @@
-use crate::commands::interactive::types::{ExecutionResult, TweakCommand};
-use anyhow::Result;
+use crate::commands::interactive::catalog::catalog;
+use crate::commands::interactive::types::{ExecutionResult, TweakCommand, TweakOutcome};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
+use pieuvre_common::{
+    ChangeRecord, PieuvreError, PlannedChange, RegistryPath, RegistryValue, TransactionOutcome,
+};
+use pieuvre_sync::catalog::CatalogOperation;
+use pieuvre_sync::graph::Resolution;
+use pieuvre_sync::operation::SyncOperation;
+use pieuvre_sync::transaction::{StepReport, Transaction, TransactionReport};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+tokio::task_local! {
+    /// Tweak en cours d'exécution, pour marquer l'origine des snapshots automatiques
+    static CURRENT_TWEAK: String;
+    /// Transaction en cours : son snapshot étiqueté remplace les snapshots par tweak
+    static IN_TRANSACTION: ();
+}
+
 /// Registre central des commandes de tweaks (SOTA v0.7.0)
 pub struct CommandRegistry {
     commands: HashMap<String, Arc<dyn TweakCommand>>,
@@ -20,123 +35,21 @@ impl CommandRegistry {
 
     fn register_all(&mut self) {
         // --- TÉLÉMÉTRIE ---
-        use pieuvre_sync::hardening::*;
-        self.register(
-            "diagtrack",
-            SyncOperationCommand::new(pieuvre_sync::operation::ServiceOperation {
-                name: SERVICE_DIAGTRACK.to_string(),
-                target_start_type: 4,
-            }),
-        );
-        self.register(
-            "dmwappush",
-            SyncOperationCommand::new(pieuvre_sync::operation::ServiceOperation {
-                name: SERVICE_WAP_PUSH.to_string(),
-                target_start_type: 4,
-            }),
-        );
-        self.register(
-            "wersvc",
-            SyncOperationCommand::new(pieuvre_sync::operation::ServiceOperation {
-                name: SERVICE_WERSVC.to_string(),
-                target_start_type: 4,
-            }),
-        );
         self.register("firewall", FirewallTelemetryBlockCommand);
         self.register("sched_tasks", ScheduledTasksTelemetryCommand);
         self.register("hosts", HostsTelemetryCommand);
         self.register("onedrive", OneDriveUninstallCommand);
 
         // --- PRIVACY ---
-        self.register(
-            "telemetry_level",
-            SyncOperationCommand::new(pieuvre_sync::operation::RegistryDwordOperation {
-                key: DATA_COLLECTION_KEY.to_string(),
-                value: "AllowTelemetry".to_string(),
-                target_data: 0,
-            }),
-        );
-        self.register(
-            "advertising_id",
-            SyncOperationCommand::new(pieuvre_sync::operation::RegistryDwordOperation {
-                key: ADVERTISING_INFO_POLICIES_KEY.to_string(),
-                value: "DisabledByGroupPolicy".to_string(),
-                target_data: 1,
-            }),
-        );
-        self.register(
-            "location",
-            SyncOperationCommand::new(pieuvre_sync::operation::RegistryDwordOperation {
-                key: format!("{}\\{}", CONSENT_STORE_KEY, "location"),
-                value: "Value".to_string(),
-                target_data: 0, // 0 for Deny in some contexts, but let's stick to what was there
-            }),
-        );
-        self.register(
-            "activity_history",
-            SyncOperationCommand::new(pieuvre_sync::operation::RegistryDwordOperation {
-                key: r"SOFTWARE\Policies\Microsoft\Windows\System".to_string(),
-                value: "EnableActivityFeed".to_string(),
-                target_data: 0,
-            }),
-        );
-        self.register(
-            "cortana",
-            SyncOperationCommand::new(pieuvre_sync::operation::RegistryDwordOperation {
-                key: WINDOWS_SEARCH_KEY.to_string(),
-                value: "AllowCortana".to_string(),
-                target_data: 0,
-            }),
-        );
-        self.register(
-            "recall",
-            SyncOperationCommand::new(pieuvre_sync::operation::RegistryDwordOperation {
-                key: WINDOWS_AI_KEY.to_string(),
-                value: "DisableAIDataAnalysis".to_string(),
-                target_data: 1,
-            }),
-        );
         self.register("context_menu", ContextMenuClassicCommand);
         self.register("edge_telemetry", EdgeTelemetryDisableCommand);
 
         // --- O&O PRIVACY ---
         self.register("oo_telemetry", OORecommendedPrivacyCommand);
-        self.register(
-            "oo_advertising",
-            SyncOperationCommand::new(pieuvre_sync::operation::RegistryDwordOperation {
-                key: ADVERTISING_INFO_POLICIES_KEY.to_string(),
-                value: "DisabledByGroupPolicy".to_string(),
-                target_data: 1,
-            }),
-        );
         self.register("oo_copilot", AppxRemoveCopilotCommand);
-        self.register(
-            "oo_recall",
-            SyncOperationCommand::new(pieuvre_sync::operation::RegistryDwordOperation {
-                key: WINDOWS_AI_KEY.to_string(),
-                value: "DisableAIDataAnalysis".to_string(),
-                target_data: 1,
-            }),
-        );
         self.register("oo_widgets", OORecommendedPrivacyCommand);
-        self.register(
-            "oo_search_highlights",
-            SyncOperationCommand::new(pieuvre_sync::operation::RegistryDwordOperation {
-                key: WINDOWS_SEARCH_KEY.to_string(),
-                value: "AllowCortana".to_string(),
-                target_data: 0,
-            }),
-        );
         self.register("oo_wudo", OORecommendedPrivacyCommand);
         self.register("oo_wifi_sense", OORecommendedPrivacyCommand);
-        self.register(
-            "oo_app_permissions",
-            SyncOperationCommand::new(pieuvre_sync::operation::RegistryDwordOperation {
-                key: format!("{}\\{}", CONSENT_STORE_KEY, "location"),
-                value: "Value".to_string(),
-                target_data: 0,
-            }),
-        );
         self.register("oo_bg_apps", OORecommendedPrivacyCommand);
 
         // --- PERFORMANCE ---
@@ -151,6 +64,8 @@ impl CommandRegistry {
         self.register("nagle", NagleDisableCommand);
         self.register("interrupts", InterruptsOptimizeCommand);
         self.register("memory", MemoryOptimizeCommand);
+        self.register("dpc_latency", DpcLatencyCommand);
+        self.register("memory_compression", MemoryCompressionDisableCommand);
 
         // --- SECURITY ---
         self.register("hvci", SecurityDisableHvciCommand);
@@ -183,6 +98,7 @@ impl CommandRegistry {
         self.register("explorer_restart", ExplorerRestartCommand);
 
         self.register("windows_update", WindowsUpdateConfigureCommand);
+        self.register("reset_defaults", ResetDefaultsCommand);
 
         // --- BLOATWARE ---
         self.register("bloat_copilot", AppxRemoveCopilotCommand);
@@ -198,51 +114,6 @@ impl CommandRegistry {
                 ],
             }),
         );
-        self.register(
-            "bloat_cortana",
-            SyncOperationCommand::new(pieuvre_sync::operation::RegistryDwordOperation {
-                key: WINDOWS_SEARCH_KEY.to_string(),
-                value: "AllowCortana".to_string(),
-                target_data: 0,
-            }),
-        );
-
-        // --- SERVICES ---
-        self.register(
-            "svc_telemetry",
-            SyncOperationCommand::new(pieuvre_sync::operation::ServiceOperation {
-                name: SERVICE_DIAGTRACK.to_string(),
-                target_start_type: 4,
-            }),
-        );
-        self.register(
-            "svc_sysmain",
-            SyncOperationCommand::new(pieuvre_sync::operation::ServiceOperation {
-                name: SERVICE_SYSMAIN.to_string(),
-                target_start_type: 4,
-            }),
-        );
-        self.register(
-            "svc_search",
-            SyncOperationCommand::new(pieuvre_sync::operation::ServiceOperation {
-                name: SERVICE_WSEARCH.to_string(),
-                target_start_type: 4,
-            }),
-        );
-        self.register(
-            "svc_update",
-            SyncOperationCommand::new(pieuvre_sync::operation::ServiceOperation {
-                name: SERVICE_UPDATE.to_string(),
-                target_start_type: 3,
-            }),
-        );
-        self.register(
-            "svc_print",
-            SyncOperationCommand::new(pieuvre_sync::operation::ServiceOperation {
-                name: "Spooler".to_string(),
-                target_start_type: 4,
-            }),
-        );
 
         // --- NETWORK ---
         self.register("net_doh", DnsDohCommand);
@@ -261,24 +132,85 @@ impl CommandRegistry {
         self.register("maint_updates_pause", WindowsUpdateConfigureCommand);
         self.register("maint_tasks", ScheduledTasksTelemetryCommand);
         self.register("maint_hibernation", DisableHibernationCommand);
+
+        // --- CATALOGUE DÉCLARATIF (intégré + packs site) ---
+        // Enregistré en dernier : une entrée du catalogue remplace une commande codée en dur
+        for tweak in catalog().tweaks() {
+            self.register(
+                &tweak.id,
+                SyncOperationCommand::new(CatalogOperation::new(tweak.clone())),
+            );
+        }
     }
 
     pub fn register(&mut self, id: &str, command: impl TweakCommand + 'static) {
         self.commands.insert(id.to_string(), Arc::new(command));
     }
 
-    pub async fn execute(&self, id: &str) -> Result<ExecutionResult> {
+    /// Exécution au sein d'une transaction : l'erreur reste typée et garde les
+    /// changements faits avant l'échec, pour le rollback. En dry-run, le plan.
+    pub async fn apply(&self, id: &str) -> pieuvre_common::Result<TweakOutcome> {
+        let cmd = self
+            .commands
+            .get(id)
+            .ok_or_else(|| PieuvreError::Config(format!("Unknown tweak: {}", id)))?;
+        if pieuvre_common::config().general.dry_run {
+            return cmd.plan().await.map(TweakOutcome::Planned).map_err(typed);
+        }
+        let result = CURRENT_TWEAK
+            .scope(id.to_string(), cmd.execute())
+            .await
+            .map_err(typed)?;
+        self.track_reboot(id, result.changes.len());
+        Ok(TweakOutcome::Applied(result.changes))
+    }
+
+    /// Changements prévus par un tweak, sans rien appliquer
+    pub async fn plan(&self, id: &str) -> Result<Vec<PlannedChange>> {
         match self.commands.get(id) {
-            Some(cmd) => {
-                tracing::debug!("Executing command: {}", id);
-                cmd.execute().await.map_err(|e| {
-                    tracing::error!("Command {} failed: {:?}", id, e);
-                    anyhow::anyhow!("Erreur lors de l'exécution de {}: {}", id, e)
-                })
-            }
-            None => {
-                tracing::warn!("Command not found in registry: {}", id);
-                anyhow::bail!("Commande non enregistrée dans le registre SOTA : {}", id)
+            Some(cmd) => cmd.plan().await,
+            None => anyhow::bail!("Commande non enregistrée dans le registre SOTA : {}", id),
+        }
+    }
+
+    /// Ordre d'exécution d'une sélection selon les relations du catalogue :
+    /// tweaks inconnus et conflits refusés, dépendances ajoutées, doublons retirés
+    pub fn resolve(&self, ids: &[String]) -> pieuvre_common::Result<Resolution> {
+        let unknown: Vec<&str> = ids
+            .iter()
+            .filter(|id| !self.contains(id))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            return Err(PieuvreError::Config(format!(
+                "Unknown tweaks: {}",
+                unknown.join(", ")
+            )));
+        }
+        catalog().graph().resolve(ids)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.commands.contains_key(id)
+    }
+
+    /// Le tweak ne prend effet qu'après un redémarrage
+    pub fn requires_reboot(&self, id: &str) -> bool {
+        self.commands
+            .get(id)
+            .is_some_and(|cmd| cmd.requires_reboot())
+    }
+
+    /// Le rollback sait restaurer l'état d'avant le tweak
+    pub fn undoable(&self, id: &str) -> bool {
+        self.commands.get(id).is_none_or(|cmd| cmd.undoable())
+    }
+
+    /// Ajoute le tweak à la file des redémarrages en attente
+    fn track_reboot(&self, id: &str, changes: usize) {
+        if self.requires_reboot(id) {
+            if let Err(e) = pieuvre_persist::reboot::record(id, changes) {
+                tracing::warn!("File de redémarrage non mise à jour pour {}: {}", id, e);
             }
         }
     }
@@ -308,14 +240,19 @@ impl<T: pieuvre_sync::operation::SyncOperation + 'static> TweakCommand for SyncO
     async fn execute(&self) -> Result<ExecutionResult> {
         let name = self.operation.name();
         tracing::info!("Applying sync operation: {}", name);
-        let changes = self.operation.apply().await.map_err(|e| {
-            anyhow::anyhow!("Échec de l'opération {}: {}", name, e)
-        })?;
-        
+        let changes = self
+            .operation
+            .apply()
+            .await
+            .with_context(|| format!("Échec de l'opération {}", name))?;
+
+        auto_snapshot(name, &changes);
+
         Ok(ExecutionResult::ok_count(
             changes.len(),
             format!("Opération {} appliquée ({} changements)", name, changes.len()),
-        ))
+        )
+        .with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
@@ -324,6 +261,136 @@ impl<T: pieuvre_sync::operation::SyncOperation + 'static> TweakCommand for SyncO
             .await
             .map_err(|e| anyhow::anyhow!(e))
     }
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        Ok(self.operation.plan().await?)
+    }
+
+    fn requires_reboot(&self) -> bool {
+        self.operation.requires_reboot()
+    }
+}
+
+/// Erreur typée d'une commande, `Internal` si elle n'en vient pas
+fn typed(error: anyhow::Error) -> PieuvreError {
+    let message = format!("{:#}", error);
+    error
+        .downcast::<PieuvreError>()
+        .unwrap_or(PieuvreError::Internal(message))
+}
+
+/// Plan de valeurs DWORD HKLM (clé, valeur, donnée cible)
+fn plan_hklm_dwords(values: &[(&str, &str, u32)]) -> Result<Vec<PlannedChange>> {
+    let registry = pieuvre_common::native_registry();
+    let mut planned = Vec::new();
+    for (key, value, data) in values {
+        planned.extend(pieuvre_sync::plan::registry_value(
+            registry.as_ref(),
+            &RegistryPath::hklm(key),
+            value,
+            Some(RegistryValue::Dword(*data)),
+        )?);
+    }
+    Ok(planned)
+}
+
+/// Dans `run_transaction` : son snapshot couvre les étapes, annulées ou non
+fn in_transaction() -> bool {
+    IN_TRANSACTION.try_with(|_| ()).is_ok()
+}
+
+/// Snapshot automatique ([snapshot] auto_create), hors transaction
+fn auto_snapshot(name: &str, changes: &[ChangeRecord]) {
+    if in_transaction() {
+        return;
+    }
+    if !changes.is_empty() && pieuvre_common::config().snapshot.auto_create {
+        let description = format!("Avant {}", name);
+        let changes = changes.to_vec();
+        let result = match CURRENT_TWEAK.try_with(|id| id.clone()) {
+            Ok(tweak) => pieuvre_persist::snapshot::create_for_tweak(&tweak, &description, changes),
+            Err(_) => pieuvre_persist::snapshot::create(&description, changes),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Snapshot automatique impossible pour {}: {}", name, e);
+            pieuvre_persist::journal::keep();
+        }
+    }
+}
+
+/// Exécute `f` hors du runtime en enregistrant ses changements.
+/// Le snapshot automatique couvre aussi les changements faits avant une erreur.
+pub async fn run_recorded<T, F>(name: &str, f: F) -> Result<(T, Vec<ChangeRecord>)>
+where
+    T: Send + 'static,
+    F: FnOnce() -> pieuvre_common::Result<T> + Send + 'static,
+{
+    let (result, changes) =
+        tokio::task::spawn_blocking(move || pieuvre_sync::changes::record(f)).await?;
+    auto_snapshot(name, &changes);
+    let value = result.map_err(|e| e.with_changes(changes.clone()))?;
+    Ok((value, changes))
+}
+
+/// Étape de transaction : un tweak du registre
+struct TweakOperation {
+    id: String,
+    registry: Arc<CommandRegistry>,
+}
+
+#[async_trait]
+impl SyncOperation for TweakOperation {
+    fn name(&self) -> &str {
+        &self.id
+    }
+
+    async fn apply(&self) -> pieuvre_common::Result<Vec<ChangeRecord>> {
+        match self.registry.apply(&self.id).await? {
+            TweakOutcome::Applied(changes) => Ok(changes),
+            TweakOutcome::Planned(_) => Err(dry_run_refused()),
+        }
+    }
+
+    async fn is_applied(&self) -> pieuvre_common::Result<bool> {
+        self.registry
+            .check_status(&self.id)
+            .await
+            .map_err(|e| PieuvreError::Internal(e.to_string()))
+    }
+}
+
+/// Applique une sélection résolue (`CommandRegistry::resolve`) en une transaction
+/// (politique `[transaction]`), dans l'ordre des dépendances, et enregistre
+/// l'issue dans un snapshot étiqueté
+pub async fn run_transaction(
+    name: &str,
+    registry: Arc<CommandRegistry>,
+    resolution: Resolution,
+    on_step: impl FnMut(usize, &StepReport) + Send + 'static,
+) -> pieuvre_common::Result<TransactionReport> {
+    // Un succès sans changement ne doit pas passer pour une application
+    if pieuvre_common::config().general.dry_run {
+        return Err(dry_run_refused());
+    }
+    let mut transaction = Transaction::new(name).on_step(on_step);
+    for id in resolution.order {
+        transaction.push(TweakOperation {
+            id,
+            registry: registry.clone(),
+        });
+    }
+    // Pas de snapshot par étape : il survivrait au rollback et ferait tourner les anciens
+    let report = IN_TRANSACTION.scope((), transaction.run()).await?;
+    if let Err(e) = pieuvre_persist::snapshot::record_transaction(&report) {
+        tracing::warn!("Snapshot de transaction impossible pour {}: {}", name, e);
+        pieuvre_persist::journal::keep();
+    }
+    Ok(report)
+}
+
+fn dry_run_refused() -> PieuvreError {
+    PieuvreError::Config(
+        "general.dry_run is set: nothing applied (see `pieuvre plan` for details)".to_string(),
+    )
 }
 
 // --- COMMANDES DE SÉCURITÉ (PERFORMANCE) ---
@@ -332,34 +399,47 @@ pub struct SecurityDisableHvciCommand;
 #[async_trait]
 impl TweakCommand for SecurityDisableHvciCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::security::disable_memory_integrity).await??;
-        Ok(ExecutionResult::ok("Memory Integrity (HVCI) disabled"))
+        let (_, changes) =
+            run_recorded("HVCI", pieuvre_sync::security::disable_memory_integrity).await?;
+        Ok(ExecutionResult::ok("Memory Integrity (HVCI) disabled").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
         Ok(!tokio::task::spawn_blocking(pieuvre_sync::security::is_memory_integrity_enabled).await?)
     }
+
+    fn requires_reboot(&self) -> bool {
+        true
+    }
 }
 
 pub struct SecurityDisableVbsCommand;
 #[async_trait]
 impl TweakCommand for SecurityDisableVbsCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::security::disable_vbs).await??;
-        Ok(ExecutionResult::ok("VBS completely disabled"))
+        let (_, changes) = run_recorded("VBS", pieuvre_sync::security::disable_vbs).await?;
+        Ok(ExecutionResult::ok("VBS completely disabled").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
         Ok(!tokio::task::spawn_blocking(pieuvre_sync::security::is_vbs_enabled).await?)
     }
+
+    fn requires_reboot(&self) -> bool {
+        true
+    }
 }
 
 pub struct SecurityDisableSpectreCommand;
 #[async_trait]
 impl TweakCommand for SecurityDisableSpectreCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::security::disable_spectre_meltdown).await??;
-        Ok(ExecutionResult::ok("Spectre/Meltdown mitigations disabled"))
+        let (_, changes) = run_recorded(
+            "Spectre/Meltdown",
+            pieuvre_sync::security::disable_spectre_meltdown,
+        )
+        .await?;
+        Ok(ExecutionResult::ok("Spectre/Meltdown mitigations disabled").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
@@ -371,26 +451,28 @@ impl TweakCommand for SecurityDisableSpectreCommand {
             Ok(v == 3)
         }).await?
     }
+
+    fn requires_reboot(&self) -> bool {
+        true
+    }
 }
 
 pub struct SecurityDisableUacCommand;
 #[async_trait]
 impl TweakCommand for SecurityDisableUacCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(|| {
-            pieuvre_sync::registry::set_dword_value(
-                r"SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\System",
-                "ConsentPromptBehaviorAdmin",
-                0,
-            )?;
-            pieuvre_sync::registry::set_dword_value(
-                r"SOFTWARE\Microsoft\Windows\CurrentVersion\Policies\System",
-                "PromptOnSecureDesktop",
-                0,
-            )
+        let (_, changes) = run_recorded("UAC", || {
+            for (key, value, data) in pieuvre_sync::security::UAC_VALUES {
+                pieuvre_sync::registry::set_dword_value(key, value, *data)?;
+            }
+            Ok(())
         })
-        .await??;
-        Ok(ExecutionResult::ok("UAC disabled (Never Notify)"))
+        .await?;
+        Ok(ExecutionResult::ok("UAC disabled (Never Notify)").with_changes(changes))
+    }
+
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        tokio::task::spawn_blocking(|| plan_hklm_dwords(pieuvre_sync::security::UAC_VALUES)).await?
     }
 
     async fn check_status(&self) -> Result<bool> {
@@ -412,14 +494,14 @@ pub struct HardeningLockCommand;
 #[async_trait]
 impl TweakCommand for HardeningLockCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        let count = tokio::task::spawn_blocking(|| {
+        let (count, changes) = run_recorded("Hardening", || {
             let mut locked = 0usize;
             for key in pieuvre_sync::hardening::CRITICAL_KEYS {
                 if pieuvre_sync::hardening::lock_registry_key(key).is_ok() {
                     locked += 1;
                 }
             }
-            locked
+            Ok(locked)
         })
         .await?;
         Ok(ExecutionResult::ok_count(
@@ -428,7 +510,8 @@ impl TweakCommand for HardeningLockCommand {
                 "{} critical registry keys locked with read-only ACLs",
                 count
             ),
-        ))
+        )
+        .with_changes(changes))
     }
 }
 
@@ -436,14 +519,14 @@ pub struct HardeningUnlockCommand;
 #[async_trait]
 impl TweakCommand for HardeningUnlockCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        let count = tokio::task::spawn_blocking(|| {
+        let (count, changes) = run_recorded("Hardening", || {
             let mut unlocked = 0usize;
             for key in pieuvre_sync::hardening::CRITICAL_KEYS {
                 if pieuvre_sync::hardening::unlock_registry_key(key).is_ok() {
                     unlocked += 1;
                 }
             }
-            unlocked
+            Ok(unlocked)
         })
         .await?;
         Ok(ExecutionResult::ok_count(
@@ -452,7 +535,8 @@ impl TweakCommand for HardeningUnlockCommand {
                 "{} critical registry keys unlocked (default ACLs restored)",
                 count
             ),
-        ))
+        )
+        .with_changes(changes))
     }
 }
 
@@ -460,8 +544,9 @@ pub struct HardeningPplCommand;
 #[async_trait]
 impl TweakCommand for HardeningPplCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::hardening::enable_ppl_protection).await??;
-        Ok(ExecutionResult::ok("PPL protection enabled"))
+        let (_, changes) =
+            run_recorded("PPL", pieuvre_sync::hardening::enable_ppl_protection).await?;
+        Ok(ExecutionResult::ok("PPL protection enabled").with_changes(changes))
     }
 }
 
@@ -471,11 +556,12 @@ pub struct OORecommendedPrivacyCommand;
 #[async_trait]
 impl TweakCommand for OORecommendedPrivacyCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::privacy_o_o::apply_all_recommended_privacy)
-            .await??;
-        Ok(ExecutionResult::ok(
-            "O&O: Recommended privacy settings applied",
-        ))
+        let (_, changes) = run_recorded(
+            "O&O Privacy",
+            pieuvre_sync::privacy_o_o::apply_all_recommended_privacy,
+        )
+        .await?;
+        Ok(ExecutionResult::ok("O&O: Recommended privacy settings applied").with_changes(changes))
     }
 }
 
@@ -483,12 +569,15 @@ pub struct FirewallTelemetryBlockCommand;
 #[async_trait]
 impl TweakCommand for FirewallTelemetryBlockCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        let r = tokio::task::spawn_blocking(pieuvre_sync::firewall::create_telemetry_block_rules)
-            .await??;
-        Ok(ExecutionResult::ok_count(
-            r.len(),
-            "Firewall telemetry rules created",
-        ))
+        let (r, changes) = run_recorded(
+            "Firewall",
+            pieuvre_sync::firewall::create_telemetry_block_rules,
+        )
+        .await?;
+        Ok(
+            ExecutionResult::ok_count(r.len(), "Firewall telemetry rules created")
+                .with_changes(changes),
+        )
     }
 }
 
@@ -496,12 +585,24 @@ pub struct ScheduledTasksTelemetryCommand;
 #[async_trait]
 impl TweakCommand for ScheduledTasksTelemetryCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        let t = tokio::task::spawn_blocking(pieuvre_sync::scheduled_tasks::disable_telemetry_tasks)
-            .await??;
-        Ok(ExecutionResult::ok_count(
-            t.len(),
-            "Telemetry scheduled tasks disabled",
-        ))
+        let (t, changes) = run_recorded(
+            "Scheduled tasks",
+            pieuvre_sync::scheduled_tasks::disable_telemetry_tasks,
+        )
+        .await?;
+        Ok(
+            ExecutionResult::ok_count(t.len(), "Telemetry scheduled tasks disabled")
+                .with_changes(changes),
+        )
+    }
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        Ok(tokio::task::spawn_blocking(|| {
+            pieuvre_sync::scheduled_tasks::get_telemetry_tasks()
+                .iter()
+                .filter_map(|task| pieuvre_sync::plan::scheduled_task(task, false))
+                .collect::<Vec<_>>()
+        })
+        .await?)
     }
 }
 
@@ -509,11 +610,18 @@ pub struct HostsTelemetryCommand;
 #[async_trait]
 impl TweakCommand for HostsTelemetryCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        let c = tokio::task::spawn_blocking(pieuvre_sync::hosts::add_telemetry_blocks).await??;
-        Ok(ExecutionResult::ok_count(
-            c as usize,
-            "Telemetry domains blocked in hosts file",
-        ))
+        let (c, changes) = run_recorded("Hosts", pieuvre_sync::hosts::add_telemetry_blocks).await?;
+        Ok(
+            ExecutionResult::ok_count(c as usize, "Telemetry domains blocked in hosts file")
+                .with_changes(changes),
+        )
+    }
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        let planned = tokio::task::spawn_blocking(|| {
+            pieuvre_sync::plan::hosts_block(&pieuvre_sync::hosts::telemetry_block_entries())
+        })
+        .await??;
+        Ok(planned.into_iter().collect())
     }
 }
 
@@ -521,8 +629,9 @@ pub struct OneDriveUninstallCommand;
 #[async_trait]
 impl TweakCommand for OneDriveUninstallCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::onedrive::uninstall_onedrive).await??;
-        Ok(ExecutionResult::ok("OneDrive uninstalled"))
+        let (_, changes) =
+            run_recorded("OneDrive", pieuvre_sync::onedrive::uninstall_onedrive).await?;
+        Ok(ExecutionResult::ok("OneDrive uninstalled").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
@@ -534,9 +643,12 @@ pub struct ContextMenuClassicCommand;
 #[async_trait]
 impl TweakCommand for ContextMenuClassicCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::context_menu::remove_context_menu_clutter)
-            .await??;
-        Ok(ExecutionResult::ok("Classic context menu enabled"))
+        let (_, changes) = run_recorded(
+            "Context menu",
+            pieuvre_sync::context_menu::remove_context_menu_clutter,
+        )
+        .await?;
+        Ok(ExecutionResult::ok("Classic context menu enabled").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
@@ -548,8 +660,8 @@ pub struct AppxRemoveCopilotCommand;
 #[async_trait]
 impl TweakCommand for AppxRemoveCopilotCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::appx::remove_copilot).await??;
-        Ok(ExecutionResult::ok("Copilot components removed"))
+        let (_, changes) = run_recorded("Copilot", pieuvre_sync::appx::remove_copilot).await?;
+        Ok(ExecutionResult::ok("Copilot components removed").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
@@ -573,7 +685,22 @@ impl TweakCommand for TimerResolutionCommand {
         let res = self.resolution;
         tokio::task::spawn_blocking(move || pieuvre_sync::timer::set_timer_resolution(res))
             .await??;
-        Ok(ExecutionResult::ok("Timer resolution optimized"))
+        Ok(ExecutionResult::ok(
+            "Timer resolution optimized until pieuvre exits (not recorded)",
+        ))
+    }
+
+    async fn check_status(&self) -> Result<bool> {
+        let res = self.resolution;
+        tokio::task::spawn_blocking(move || {
+            Ok(pieuvre_sync::timer::get_timer_resolution()?.current_100ns <= res)
+        })
+        .await?
+    }
+
+    /// NtSetTimerResolution ne vaut que pour le processus : rien à restaurer
+    fn undoable(&self) -> bool {
+        false
     }
 }
 
@@ -589,16 +716,20 @@ impl PowerPlanCommand {
 impl TweakCommand for PowerPlanCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
         let plan = self.plan;
-        tokio::task::spawn_blocking(move || pieuvre_sync::power::set_power_plan(plan)).await??;
-        Ok(ExecutionResult::ok("Power plan applied"))
+        let (_, changes) = run_recorded(plan.name(), move || {
+            pieuvre_sync::power::set_power_plan(plan)
+        })
+        .await?;
+        Ok(ExecutionResult::ok("Power plan applied").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
         let plan_guid = self.plan.guid().to_string();
         tokio::task::spawn_blocking(move || {
-            let current = pieuvre_sync::power::get_active_power_plan()?;
-            Ok(current.to_lowercase() == plan_guid.to_lowercase())
-        }).await?
+            let current = pieuvre_sync::power::get_active_scheme_guid()?;
+            Ok(current.eq_ignore_ascii_case(&plan_guid))
+        })
+        .await?
     }
 }
 
@@ -606,8 +737,12 @@ pub struct CpuThrottlingDisableCommand;
 #[async_trait]
 impl TweakCommand for CpuThrottlingDisableCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::power::disable_cpu_throttling).await??;
-        Ok(ExecutionResult::ok("CPU Throttling disabled"))
+        let (_, changes) = run_recorded(
+            "CPU Throttling",
+            pieuvre_sync::power::disable_cpu_throttling,
+        )
+        .await?;
+        Ok(ExecutionResult::ok("CPU Throttling disabled").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
@@ -625,18 +760,22 @@ pub struct MsiEnableAllCommand;
 #[async_trait]
 impl TweakCommand for MsiEnableAllCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        let devices =
-            tokio::task::spawn_blocking(pieuvre_sync::msi::list_msi_eligible_devices).await??;
-        let mut count = 0;
-        for dev in &devices {
-            if !dev.msi_enabled && pieuvre_sync::msi::enable_msi(&dev.full_path).is_ok() {
-                count += 1;
+        let (count, changes) = run_recorded("MSI", || {
+            let devices = pieuvre_sync::msi::list_msi_eligible_devices()?;
+            let mut count = 0;
+            for dev in &devices {
+                if !dev.msi_enabled && pieuvre_sync::msi::enable_msi(&dev.full_path).is_ok() {
+                    count += 1;
+                }
             }
-        }
-        Ok(ExecutionResult::ok_count(
-            count,
-            "MSI mode enabled for devices",
-        ))
+            Ok(count)
+        })
+        .await?;
+        Ok(ExecutionResult::ok_count(count, "MSI mode enabled for devices").with_changes(changes))
+    }
+
+    fn requires_reboot(&self) -> bool {
+        true
     }
 }
 
@@ -644,8 +783,8 @@ pub struct HagsDisableCommand;
 #[async_trait]
 impl TweakCommand for HagsDisableCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::game_mode::disable_hags).await??;
-        Ok(ExecutionResult::ok("HAGS disabled"))
+        let (_, changes) = run_recorded("HAGS", pieuvre_sync::game_mode::disable_hags).await?;
+        Ok(ExecutionResult::ok("HAGS disabled").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
@@ -657,12 +796,9 @@ pub struct NagleDisableCommand;
 #[async_trait]
 impl TweakCommand for NagleDisableCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        let n =
-            tokio::task::spawn_blocking(pieuvre_sync::network::disable_nagle_algorithm).await??;
-        Ok(ExecutionResult::ok_count(
-            n as usize,
-            "Nagle algorithm disabled",
-        ))
+        let (n, changes) =
+            run_recorded("Nagle", pieuvre_sync::network::disable_nagle_algorithm).await?;
+        Ok(ExecutionResult::ok_count(n as usize, "Nagle algorithm disabled").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
@@ -674,8 +810,8 @@ pub struct EdgeTelemetryDisableCommand;
 #[async_trait]
 impl TweakCommand for EdgeTelemetryDisableCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::edge::disable_edge).await??;
-        Ok(ExecutionResult::ok("Edge features disabled"))
+        let (_, changes) = run_recorded("Edge", pieuvre_sync::edge::disable_edge).await?;
+        Ok(ExecutionResult::ok("Edge features disabled").with_changes(changes))
     }
 }
 
@@ -683,11 +819,11 @@ pub struct InterruptsOptimizeCommand;
 #[async_trait]
 impl TweakCommand for InterruptsOptimizeCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(|| {
+        let (_, changes) = run_recorded("Interrupts", || {
             pieuvre_sync::interrupts::InterruptSteering::steer_high_latency_drivers(1000, 0x1)
         })
-        .await??;
-        Ok(ExecutionResult::ok("Interrupt moderation optimized"))
+        .await?;
+        Ok(ExecutionResult::ok("Interrupt moderation optimized").with_changes(changes))
     }
 }
 
@@ -695,8 +831,16 @@ pub struct MemoryOptimizeCommand;
 #[async_trait]
 impl TweakCommand for MemoryOptimizeCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::memory::enable_large_system_cache).await??;
-        Ok(ExecutionResult::ok("Large System Cache enabled"))
+        let (_, changes) = run_recorded(
+            "Large System Cache",
+            pieuvre_sync::memory::enable_large_system_cache,
+        )
+        .await?;
+        Ok(ExecutionResult::ok("Large System Cache enabled").with_changes(changes))
+    }
+
+    fn requires_reboot(&self) -> bool {
+        true
     }
 }
 
@@ -731,13 +875,11 @@ pub struct DnsDohCommand;
 #[async_trait]
 impl TweakCommand for DnsDohCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(|| {
+        let (_, changes) = run_recorded("DNS-over-HTTPS", || {
             pieuvre_sync::dns::set_doh_provider(pieuvre_sync::dns::DNSProvider::Cloudflare)
         })
-        .await??;
-        Ok(ExecutionResult::ok(
-            "DNS-over-HTTPS configured (Cloudflare)",
-        ))
+        .await?;
+        Ok(ExecutionResult::ok("DNS-over-HTTPS configured (Cloudflare)").with_changes(changes))
     }
 }
 
@@ -745,8 +887,9 @@ pub struct ExplorerOptimizeCommand;
 #[async_trait]
 impl TweakCommand for ExplorerOptimizeCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::explorer::apply_explorer_tweaks).await??;
-        Ok(ExecutionResult::ok("Explorer settings optimized"))
+        let (_, changes) =
+            run_recorded("Explorer", pieuvre_sync::explorer::apply_explorer_tweaks).await?;
+        Ok(ExecutionResult::ok("Explorer settings optimized").with_changes(changes))
     }
 }
 
@@ -763,8 +906,12 @@ pub struct WindowsUpdateConfigureCommand;
 #[async_trait]
 impl TweakCommand for WindowsUpdateConfigureCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::windows_update::pause_updates).await??;
-        Ok(ExecutionResult::ok("Windows Update paused for 35 days"))
+        let (_, changes) = run_recorded(
+            "Windows Update",
+            pieuvre_sync::windows_update::pause_updates,
+        )
+        .await?;
+        Ok(ExecutionResult::ok("Windows Update paused for 35 days").with_changes(changes))
     }
 }
 
@@ -861,16 +1008,58 @@ impl TweakCommand for SyncPersistCommand {
     }
 }
 
+/// Dynamic tick, paging executive, TSC et affinité des interruptions (bcdedit)
+pub struct DpcLatencyCommand;
+#[async_trait]
+impl TweakCommand for DpcLatencyCommand {
+    async fn execute(&self) -> Result<ExecutionResult> {
+        let (_, changes) = run_recorded(
+            "DPC Latency",
+            pieuvre_sync::dpc::apply_all_dpc_optimizations,
+        )
+        .await?;
+        Ok(ExecutionResult::ok("DPC latency optimizations applied").with_changes(changes))
+    }
+
+    fn requires_reboot(&self) -> bool {
+        true
+    }
+}
+
+pub struct MemoryCompressionDisableCommand;
+#[async_trait]
+impl TweakCommand for MemoryCompressionDisableCommand {
+    async fn execute(&self) -> Result<ExecutionResult> {
+        let (_, changes) = run_recorded(
+            "Memory compression",
+            pieuvre_sync::cpu::disable_memory_compression,
+        )
+        .await?;
+        Ok(ExecutionResult::ok("Memory compression disabled (not recorded)").with_changes(changes))
+    }
+
+    async fn check_status(&self) -> Result<bool> {
+        Ok(!tokio::task::spawn_blocking(pieuvre_sync::cpu::is_memory_compression_enabled).await?)
+    }
+
+    fn requires_reboot(&self) -> bool {
+        true
+    }
+
+    /// Disable-MMAgent : aucun état d'origine enregistré
+    fn undoable(&self) -> bool {
+        false
+    }
+}
+
 // --- COMMANDES RÉSEAU ADDITIONNELLES ---
 
 pub struct DisableIPv6Command;
 #[async_trait]
 impl TweakCommand for DisableIPv6Command {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::network::disable_ipv6).await??;
-        Ok(ExecutionResult::ok(
-            "IPv6 disabled (reboot required to take effect)",
-        ))
+        let (_, changes) = run_recorded("IPv6", pieuvre_sync::network::disable_ipv6).await?;
+        Ok(ExecutionResult::ok("IPv6 disabled").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
@@ -882,27 +1071,77 @@ impl TweakCommand for DisableIPv6Command {
             Ok(v == 0xFF)
         }).await?
     }
+
+    fn requires_reboot(&self) -> bool {
+        true
+    }
 }
 
 // --- COMMANDES MAINTENANCE ADDITIONNELLES ---
 
+/// Remise aux réglages par défaut (transaction `reset_to_defaults`) : l'état
+/// d'origine capturé au premier lancement s'il existe
+pub struct ResetDefaultsCommand;
+#[async_trait]
+impl TweakCommand for ResetDefaultsCommand {
+    async fn execute(&self) -> Result<ExecutionResult> {
+        let baseline = match pieuvre_persist::baseline::load() {
+            Ok(baseline) => baseline.map(|snapshot| snapshot.changes),
+            Err(e) => {
+                tracing::warn!("Baseline illisible, valeurs par défaut génériques: {}", e);
+                None
+            }
+        };
+        let report = pieuvre_sync::reset_to_defaults(baseline).await?;
+        // Étape d'une transaction englobante : son snapshot suffit
+        if !in_transaction() {
+            if let Err(e) = pieuvre_persist::snapshot::record_transaction(&report) {
+                tracing::warn!("Snapshot de transaction impossible pour reset: {}", e);
+                pieuvre_persist::journal::keep();
+            }
+        }
+        match report.outcome {
+            TransactionOutcome::Success => Ok(ExecutionResult::ok_count(
+                report.changes.len(),
+                format!("Réglages par défaut restaurés ({})", report.summary()),
+            )
+            .with_changes(report.changes)),
+            // Changements conservés : une transaction englobante peut les annuler
+            TransactionOutcome::Partial => Err(PieuvreError::Internal(report.summary())
+                .with_changes(report.changes)
+                .into()),
+            TransactionOutcome::RolledBack => Err(PieuvreError::Internal(report.summary()).into()),
+        }
+    }
+
+    fn requires_reboot(&self) -> bool {
+        true
+    }
+}
+
 pub struct DisableHibernationCommand;
 #[async_trait]
 impl TweakCommand for DisableHibernationCommand {
     async fn execute(&self) -> Result<ExecutionResult> {
-        tokio::task::spawn_blocking(pieuvre_sync::power::disable_hibernation).await??;
-        Ok(ExecutionResult::ok(
-            "Hibernation disabled, hiberfil.sys removed",
-        ))
+        let (_, changes) =
+            run_recorded("Hibernation", pieuvre_sync::power::disable_hibernation).await?;
+        Ok(ExecutionResult::ok("Hibernation disabled, hiberfil.sys removed").with_changes(changes))
     }
 
     async fn check_status(&self) -> Result<bool> {
         tokio::task::spawn_blocking(|| {
             let v = pieuvre_sync::registry::read_dword_value(
-                r"SYSTEM\CurrentControlSet\Control\Power",
+                pieuvre_sync::power::HIBERNATE_KEY,
                 "HibernateEnabled",
             ).unwrap_or(1);
             Ok(v == 0)
         }).await?
     }
+
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        tokio::task::spawn_blocking(|| {
+            plan_hklm_dwords(&[(pieuvre_sync::power::HIBERNATE_KEY, "HibernateEnabled", 0)])
+        })
+        .await?
+    }
 }
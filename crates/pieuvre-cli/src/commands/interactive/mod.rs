@@ -1,4 +1,5 @@
 //! Interactive mode SOTA
+pub mod catalog;
 pub mod executor;
 pub mod sections;
 pub mod tui;
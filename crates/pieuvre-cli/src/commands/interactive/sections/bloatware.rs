@@ -30,12 +30,5 @@ pub fn get_options() -> Vec<OptItem> {
             default: false,
             risk: RiskLevel::Low,
         },
-        OptItem {
-            id: "bloat_cortana",
-            label: "Désactiver Cortana",
-            description: "Désactive l'assistant vocal Cortana et son intégration à la recherche.",
-            default: true,
-            risk: RiskLevel::Safe,
-        },
     ]
 }
@@ -7,23 +7,40 @@ pub mod performance;
 pub mod privacy;
 pub mod scan;
 pub mod security;
-pub mod services;
 pub mod sync;
 pub mod system;
 pub mod telemetry;
 
+use crate::commands::interactive::catalog;
 use crate::commands::interactive::types::OptItem;
 
+/// Catégories du catalogue rattachées à une section existante
+pub const CATALOG_CATEGORIES: &[&str] = &[
+    "scan", "audit", "telemetry", "privacy", "oo_privacy", "system", "maintenance",
+    "bloatware", "services", "performance", "network", "security", "sync",
+];
+
+/// Entrées du catalogue puis commandes codées en dur ; le catalogue l'emporte sur un id commun
+fn with_catalog(category: &str, mut items: Vec<OptItem>) -> Vec<OptItem> {
+    items.retain(|item| !catalog::catalog().contains(item.id));
+    [catalog::options(category), items].concat()
+}
+
 pub fn get_all_sections() -> Vec<(&'static str, Vec<OptItem>)> {
     use crate::commands::interactive::tui::i18n::*;
-    vec![
-        (CAT_ANALYSIS, [scan::get_options(), audit::get_options()].concat()),
-        (CAT_CONFIDENTIALITY, [telemetry::get_options(), privacy::get_options(), oo_privacy::get_options()].concat()),
-        (CAT_SYSTEM, [system::get_options(), maintenance::get_options(), bloatware::get_options()].concat()),
-        (CAT_SERVICES, services::get_options()),
-        (CAT_PERFORMANCE, performance::get_options()),
-        (CAT_NETWORK, network::get_options()),
-        (CAT_SECURITY, security::get_options()),
-        (CAT_SYNC, sync::get_options()),
-    ]
+    let mut sections = vec![
+        (CAT_ANALYSIS, [with_catalog("scan", scan::get_options()), with_catalog("audit", audit::get_options())].concat()),
+        (CAT_CONFIDENTIALITY, [with_catalog("telemetry", telemetry::get_options()), with_catalog("privacy", privacy::get_options()), with_catalog("oo_privacy", oo_privacy::get_options())].concat()),
+        (CAT_SYSTEM, [with_catalog("system", system::get_options()), with_catalog("maintenance", maintenance::get_options()), with_catalog("bloatware", bloatware::get_options())].concat()),
+        (CAT_SERVICES, catalog::options("services")),
+        (CAT_PERFORMANCE, with_catalog("performance", performance::get_options())),
+        (CAT_NETWORK, with_catalog("network", network::get_options())),
+        (CAT_SECURITY, with_catalog("security", security::get_options())),
+        (CAT_SYNC, with_catalog("sync", sync::get_options())),
+    ];
+    // Packs site : une section par catégorie inconnue
+    for category in catalog::extra_categories(CATALOG_CATEGORIES) {
+        sections.push((category, catalog::options(category)));
+    }
+    sections
 }
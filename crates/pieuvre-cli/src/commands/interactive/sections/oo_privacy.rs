@@ -9,13 +9,6 @@ pub fn get_options() -> Vec<OptItem> {
             default: true,
             risk: RiskLevel::Safe,
         },
-        OptItem {
-            id: "oo_advertising",
-            label: "O&O : Désactiver l'ID de publicité",
-            description: "Désactive l'ID de publicité pour tous les utilisateurs (HKLM + HKU).",
-            default: true,
-            risk: RiskLevel::Safe,
-        },
         OptItem {
             id: "oo_copilot",
             label: "O&O : Désactiver Windows Copilot",
@@ -23,13 +16,6 @@ pub fn get_options() -> Vec<OptItem> {
             default: true,
             risk: RiskLevel::Safe,
         },
-        OptItem {
-            id: "oo_recall",
-            label: "O&O : Bloquer Windows Recall",
-            description: "Empêche l'analyse des données par l'IA et la sauvegarde des captures (Recall).",
-            default: true,
-            risk: RiskLevel::Safe,
-        },
         OptItem {
             id: "oo_widgets",
             label: "O&O : Désactiver les Widgets et Actualités",
@@ -37,13 +23,6 @@ pub fn get_options() -> Vec<OptItem> {
             default: true,
             risk: RiskLevel::Safe,
         },
-        OptItem {
-            id: "oo_search_highlights",
-            label: "O&O : Désactiver les points forts de la recherche",
-            description: "Supprime les suggestions Web et les points forts de la recherche Windows.",
-            default: true,
-            risk: RiskLevel::Safe,
-        },
         OptItem {
             id: "oo_wudo",
             label: "O&O : Désactiver WUDO (Optimisation de livraison)",
@@ -58,13 +37,6 @@ pub fn get_options() -> Vec<OptItem> {
             default: true,
             risk: RiskLevel::Safe,
         },
-        OptItem {
-            id: "oo_app_permissions",
-            label: "O&O : Verrouiller les permissions d'applications",
-            description: "Refuse globalement l'accès à la caméra, au microphone et à la localisation.",
-            default: false,
-            risk: RiskLevel::Safe,
-        },
         OptItem {
             id: "oo_bg_apps",
             label: "O&O : Désactiver les applications en arrière-plan",
@@ -5,7 +5,7 @@ pub fn get_options() -> Vec<OptItem> {
         OptItem {
             id: "timer",
             label: "Résolution Timer 0.5ms",
-            description: "Force la résolution du timer système à 0.5ms pour réduire la latence d'entrée et améliorer la fluidité.",
+            description: "Force la résolution du timer système à 0.5ms pour réduire la latence d'entrée et améliorer la fluidité. Valable tant que pieuvre tourne, non restaurable par rollback.",
             default: true,
             risk: RiskLevel::Performance,
         },
@@ -58,5 +58,19 @@ pub fn get_options() -> Vec<OptItem> {
             default: true,
             risk: RiskLevel::Performance,
         },
+        OptItem {
+            id: "dpc_latency",
+            label: "Latence DPC (Dynamic Tick)",
+            description: "Désactive le dynamic tick et la pagination de l'exécutif, synchronise le TSC. Effectif après redémarrage.",
+            default: false,
+            risk: RiskLevel::Warning,
+        },
+        OptItem {
+            id: "memory_compression",
+            label: "Désactiver Compression Mémoire",
+            description: "Désactive la compression mémoire de MMAgent (utile avec beaucoup de RAM). Effectif après redémarrage, non restaurable par rollback.",
+            default: false,
+            risk: RiskLevel::Performance,
+        },
     ]
 }
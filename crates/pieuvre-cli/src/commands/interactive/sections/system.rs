@@ -30,6 +30,13 @@ pub fn get_options() -> Vec<OptItem> {
             default: false,
             risk: RiskLevel::Warning,
         },
+        OptItem {
+            id: "reset_defaults",
+            label: "Restaurer les réglages par défaut",
+            description: "Réactive VBS/HVCI, les services de télémétrie et de recherche et le plan Équilibré. Annulé en bloc en cas d'échec.",
+            default: false,
+            risk: RiskLevel::Warning,
+        },
         OptItem {
             id: "windows_update",
             label: "Configurer Windows Update (Manuel)",
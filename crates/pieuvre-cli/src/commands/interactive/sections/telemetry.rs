@@ -2,27 +2,6 @@ use crate::commands::interactive::types::{OptItem, RiskLevel};
 
 pub fn get_options() -> Vec<OptItem> {
     vec![
-        OptItem {
-            id: "diagtrack",
-            label: "Désactiver DiagTrack (SOTA)",
-            description: "Désactive le service 'Expériences des utilisateurs connectés et télémétrie', pivot central de la collecte de données Windows.",
-            default: true,
-            risk: RiskLevel::Low,
-        },
-        OptItem {
-            id: "dmwappush",
-            label: "Désactiver WAP Push",
-            description: "Désactive 'dmwappushservice' pour neutraliser le routage furtif des données de télémétrie.",
-            default: true,
-            risk: RiskLevel::Low,
-        },
-        OptItem {
-            id: "wersvc",
-            label: "Désactiver Rapport d'erreurs (WER)",
-            description: "Neutralise Windows Error Reporting pour empêcher l'exfiltration de rapports de plantage vers Microsoft.",
-            default: true,
-            risk: RiskLevel::Low,
-        },
         OptItem {
             id: "firewall",
             label: "Hardening Pare-feu",
@@ -1,4 +1,5 @@
 use crate::commands::interactive::types::OptItem;
+use pieuvre_common::AuditScores;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Default)]
@@ -7,6 +8,12 @@ pub struct SystemMetrics {
     pub mem_used_gb: f32,
     pub mem_total_gb: f32,
     pub uptime: u64,
+    /// Changements en attente de redémarrage
+    pub pending_reboot: usize,
+    /// Snapshots disponibles (lus depuis l'index)
+    pub snapshot_count: usize,
+    /// Scores du dernier rapport d'audit
+    pub scores: Option<AuditScores>,
 }
 
 #[derive(Debug, Clone)]
@@ -42,6 +42,58 @@ impl Component for Header {
         );
         f.render_widget(title, chunks[0]);
 
+        // Snapshots et redémarrage en attente
+        let mut status = vec![
+            Span::styled(
+                format!(" {}", state.metrics.snapshot_count),
+                Style::default()
+                    .fg(Color::Rgb(200, 200, 200))
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                i18n::SNAPSHOTS,
+                Style::default().fg(Color::Rgb(100, 100, 100)),
+            ),
+        ];
+        if state.metrics.pending_reboot > 0 {
+            status.push(Span::styled(
+                format!(" {}", state.metrics.pending_reboot),
+                Style::default()
+                    .fg(Color::Rgb(200, 160, 60))
+                    .add_modifier(Modifier::BOLD),
+            ));
+            status.push(Span::styled(
+                i18n::PENDING_REBOOT,
+                Style::default().fg(Color::Rgb(150, 150, 150)),
+            ));
+        }
+        // Scores du dernier audit, même sobriété que les métriques
+        if let Some(scores) = &state.metrics.scores {
+            for (label, dimension) in [
+                (i18n::SCORE_PRIVACY, &scores.privacy),
+                (i18n::SCORE_PERFORMANCE, &scores.performance),
+                (i18n::SCORE_SECURITY, &scores.security),
+            ] {
+                status.push(Span::styled(
+                    label,
+                    Style::default().fg(Color::Rgb(100, 100, 100)),
+                ));
+                status.push(Span::styled(
+                    crate::commands::audit::score_label(dimension),
+                    Style::default()
+                        .fg(Color::Rgb(200, 200, 200))
+                        .add_modifier(Modifier::BOLD),
+                ));
+            }
+        }
+        let status = Paragraph::new(Line::from(status)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain)
+                .border_style(Style::default().fg(Color::Rgb(60, 60, 60))),
+        );
+        f.render_widget(status, chunks[1]);
+
         // Metrics (Sobriété : pas de couleurs de seuil vives)
         let cpu_color = Color::Rgb(200, 200, 200);
         let mem_color = Color::Rgb(200, 200, 200);
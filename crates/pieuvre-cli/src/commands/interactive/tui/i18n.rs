@@ -10,6 +10,11 @@ pub const MEM: &str = " MEM ";
 pub const UPTIME: &str = " UP ";
 pub const ADMIN: &str = " ADMIN ";
 pub const USER: &str = " USER ";
+pub const PENDING_REBOOT: &str = " changement(s) en attente de redémarrage ";
+pub const SNAPSHOTS: &str = " snapshot(s) ";
+pub const SCORE_PRIVACY: &str = " Confidentialité ";
+pub const SCORE_PERFORMANCE: &str = " Performance ";
+pub const SCORE_SECURITY: &str = " Sécurité ";
 
 // Sidebar
 pub const CATEGORIES: &str = " Catégories ";
@@ -54,3 +59,4 @@ pub const LOGS_TITLE: &str = " Journal d'exécution ";
 pub const LOG_RUNNING: &str = " ⚙ ";
 pub const LOG_SUCCESS: &str = " ✔ ";
 pub const LOG_ERROR: &str = " ✖ ";
+pub const NOT_UNDOABLE: &str = "appliqué, non restaurable par rollback";
@@ -2,7 +2,7 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use pieuvre_common::Result;
+use pieuvre_common::{Result, TransactionOutcome};
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
 use std::time::Duration;
@@ -49,7 +49,18 @@ pub async fn run() -> Result<()> {
     let metrics_tx = action_tx.clone();
     tokio::spawn(async move {
         let mut sys = sysinfo::System::new_all();
+        // Rapport relu seulement quand un nouvel audit apparaît
+        let mut last_report = None;
+        let mut scores = None;
         loop {
+            let latest = crate::commands::audit::latest_report();
+            if latest != last_report {
+                scores = latest
+                    .as_deref()
+                    .and_then(crate::commands::audit::report_scores)
+                    .map(|(_, scores)| scores);
+                last_report = latest;
+            }
             sys.refresh_cpu_usage();
             sys.refresh_memory();
             let metrics = SystemMetrics {
@@ -57,6 +68,13 @@ pub async fn run() -> Result<()> {
                 mem_used_gb: sys.used_memory() as f32 / 1024.0 / 1024.0 / 1024.0,
                 mem_total_gb: sys.total_memory() as f32 / 1024.0 / 1024.0 / 1024.0,
                 uptime: sysinfo::System::uptime(),
+                pending_reboot: pieuvre_persist::reboot::pending()
+                    .map(|queue| queue.change_count())
+                    .unwrap_or(0),
+                snapshot_count: pieuvre_persist::list_snapshots()
+                    .map(|entries| entries.len())
+                    .unwrap_or(0),
+                scores: scores.clone(),
             };
             let _ = metrics_tx.send(Action::UpdateMetrics(metrics));
             tokio::time::sleep(Duration::from_millis(1000)).await;
@@ -131,29 +149,59 @@ pub async fn run() -> Result<()> {
                         let log_tx = action_tx.clone();
                         let reg = registry.clone();
                         tokio::spawn(async move {
-                            let total = options_to_run.len();
-                            let mut success_count = 0;
-                            
-                            for (i, (id, label)) in options_to_run.into_iter().enumerate() {
-                                let _ = log_tx.send(Action::UpdateProgress(i + 1, total));
-                                let now = chrono::Local::now().format("%H:%M:%S").to_string();
-                                let _ = log_tx.send(Action::AddLog(format!("[{}] {} Application de : {}...", now, i18n::LOG_RUNNING, label)));
-                                match reg.execute(&id).await {
-                                    Ok(res) => {
-                                        success_count += 1;
-                                        let now = chrono::Local::now().format("%H:%M:%S").to_string();
-                                        let _ = log_tx.send(Action::AddLog(format!("[{}] {} {}: {}", now, i18n::LOG_SUCCESS, label, res.message)));
-                                    }
-                                    Err(e) => {
-                                        let now = chrono::Local::now().format("%H:%M:%S").to_string();
-                                        let _ = log_tx.send(Action::AddLog(format!("[{}] {} {}: {}", now, i18n::LOG_ERROR, label, e)));
-                                    }
+                            let labels: std::collections::HashMap<String, String> = options_to_run.iter().cloned().collect();
+                            let ids: Vec<String> = options_to_run.into_iter().map(|(id, _)| id).collect();
+
+                            // Ordre des dépendances, commandes partagées dédupliquées, conflits refusés
+                            let now = chrono::Local::now().format("%H:%M:%S").to_string();
+                            let resolution = match reg.resolve(&ids) {
+                                Ok(resolution) => resolution,
+                                Err(e) => {
+                                    let _ = log_tx.send(Action::AddLog(format!("[{}] {} Sélection refusée : {}", now, i18n::LOG_ERROR, e)));
+                                    return;
+                                }
+                            };
+                            for note in resolution.notes() {
+                                let _ = log_tx.send(Action::AddLog(format!("[{}] {} {}", now, i18n::LOG_RUNNING, note)));
+                            }
+                            let total = resolution.order.len();
+                            let _ = log_tx.send(Action::AddLog(format!("[{}] {} Application de {} élément(s)...", now, i18n::LOG_RUNNING, total)));
+
+                            // general.dry_run : plan de chaque étape, aucune transaction
+                            if pieuvre_common::config().general.dry_run {
+                                for id in &resolution.order {
+                                    let label = labels.get(id).unwrap_or(id);
+                                    let line = match reg.plan(id).await {
+                                        Ok(planned) => format!("[{}] {} Dry-run {}: {} changement(s) prévu(s)", now, i18n::LOG_RUNNING, label, planned.len()),
+                                        Err(e) => format!("[{}] {} Dry-run {}: {}", now, i18n::LOG_ERROR, label, e),
+                                    };
+                                    let _ = log_tx.send(Action::AddLog(line));
                                 }
+                                return;
                             }
-                            
+
+                            // Transaction : selon [transaction], un échec annule ce qui a été appliqué
+                            let step_tx = log_tx.clone();
+                            let undo = reg.clone();
+                            let report = crate::commands::interactive::executor::run_transaction("TUI", reg, resolution, move |i, step| {
+                                let _ = step_tx.send(Action::UpdateProgress(i + 1, total));
+                                let label = labels.get(&step.name).unwrap_or(&step.name);
+                                let now = chrono::Local::now().format("%H:%M:%S").to_string();
+                                let line = match &step.error {
+                                    None if !undo.undoable(&step.name) => format!("[{}] {} {}: {}", now, i18n::LOG_SUCCESS, label, i18n::NOT_UNDOABLE),
+                                    None => format!("[{}] {} {}: {} changement(s)", now, i18n::LOG_SUCCESS, label, step.changes),
+                                    Some(e) => format!("[{}] {} {}: {}", now, i18n::LOG_ERROR, label, e),
+                                };
+                                let _ = step_tx.send(Action::AddLog(line));
+                            }).await;
+
                             let _ = log_tx.send(Action::UpdateProgress(0, 0));
                             let now = chrono::Local::now().format("%H:%M:%S").to_string();
-                            let final_msg = format!("[{}] {} Terminé : {}/{} appliqués avec succès.", now, i18n::LOG_SUCCESS, success_count, total);
+                            let final_msg = match report {
+                                Ok(report) if report.outcome == TransactionOutcome::Success => format!("[{}] {} Terminé : {}", now, i18n::LOG_SUCCESS, report.summary()),
+                                Ok(report) => format!("[{}] {} Terminé : {}", now, i18n::LOG_ERROR, report.summary()),
+                                Err(e) => format!("[{}] {} Transaction interrompue : {}", now, i18n::LOG_ERROR, e),
+                            };
                             let _ = log_tx.send(Action::AddLog(final_msg));
                             let _ = log_tx.send(Action::RefreshStatus);
                         });
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use pieuvre_common::{ChangeRecord, PieuvreError, PlannedChange};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -26,6 +27,8 @@ pub struct OptItem {
 pub struct ExecutionResult {
     pub _affected_count: usize,
     pub message: String,
+    /// État d'origine de ce que la commande a modifié (vide si rien à restaurer)
+    pub changes: Vec<ChangeRecord>,
 }
 
 impl ExecutionResult {
@@ -33,14 +36,29 @@ impl ExecutionResult {
         Self {
             _affected_count: 1,
             message: msg.into(),
+            changes: Vec::new(),
         }
     }
     pub fn ok_count(count: usize, msg: impl Into<String>) -> Self {
         Self {
             _affected_count: count,
             message: msg.into(),
+            changes: Vec::new(),
         }
     }
+    pub fn with_changes(mut self, changes: Vec<ChangeRecord>) -> Self {
+        self.changes = changes;
+        self
+    }
+}
+
+/// Issue de `CommandRegistry::apply`
+#[derive(Debug)]
+pub enum TweakOutcome {
+    /// État d'origine de ce qui a été modifié
+    Applied(Vec<ChangeRecord>),
+    /// `general.dry_run` : changements prévus, rien n'a été appliqué
+    Planned(Vec<PlannedChange>),
 }
 
 #[async_trait]
@@ -49,4 +67,16 @@ pub trait TweakCommand: Send + Sync {
     async fn check_status(&self) -> Result<bool> {
         Ok(false) // Par défaut, on ne sait pas si c'est appliqué
     }
+    /// Changements prévus, sans rien appliquer (dry-run)
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        Err(PieuvreError::Unsupported("no dry-run preview for this tweak".into()).into())
+    }
+    /// Effet effectif au prochain démarrage (file des redémarrages en attente)
+    fn requires_reboot(&self) -> bool {
+        false
+    }
+    /// Effet restaurable par rollback (`false` : rien n'est enregistré dans le snapshot)
+    fn undoable(&self) -> bool {
+        true
+    }
 }
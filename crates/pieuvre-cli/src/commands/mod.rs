@@ -1,8 +1,13 @@
 //! Commandes CLI
 
+pub mod apply;
 pub mod audit;
 pub mod completions;
 pub mod interactive;
+pub mod plan;
+pub mod profile;
+pub mod recover;
+pub mod report;
 pub mod rollback;
 
 pub mod status;
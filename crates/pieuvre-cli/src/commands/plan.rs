@@ -0,0 +1,202 @@
+//! Commande plan
+//!
+//! Dry-run : changements qu'appliqueraient des tweaks ou un profil (état actuel ->
+//! état cible), affichés ou exportés en JSON. Rien n'est modifié.
+
+use crate::commands::interactive::executor::CommandRegistry;
+use crate::commands::profile::{load_profiles, registry_for, resolve, steps};
+use pieuvre_common::{PieuvreError, PlannedChange, Result, ServiceStartType};
+use serde::Serialize;
+
+/// Plan complet, tel qu'exporté en JSON
+#[derive(Debug, Serialize)]
+pub struct Plan {
+    /// Profil planifié (`None` : liste de tweaks)
+    pub profile: Option<String>,
+    /// Dépendances ajoutées et doublons retirés par la résolution
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    pub steps: Vec<StepPlan>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepPlan {
+    pub id: String,
+    pub changes: Vec<PlannedChange>,
+    /// Raison pour laquelle l'étape n'a pas pu être prévisualisée
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unavailable: Option<String>,
+    /// `false` : le rollback ne pourra pas restaurer l'état d'avant l'étape
+    pub undoable: bool,
+}
+
+impl Plan {
+    pub fn change_count(&self) -> usize {
+        self.steps.iter().map(|s| s.changes.len()).sum()
+    }
+}
+
+/// `targets` : ids de tweaks, ou un nom de profil (profil par défaut si vide).
+/// `output` : fichier JSON, `-` pour la sortie standard.
+pub async fn run(targets: Vec<String>, output: Option<String>) -> Result<()> {
+    let plan = build(targets).await?;
+    match output.as_deref() {
+        Some("-") => println!("{}", serde_json::to_string_pretty(&plan)?),
+        Some(path) => {
+            std::fs::write(path, serde_json::to_string_pretty(&plan)?)?;
+            println!(
+                "Plan exported: {} ({} change(s))",
+                path,
+                plan.change_count()
+            );
+        }
+        None => print(&plan),
+    }
+    Ok(())
+}
+
+async fn build(targets: Vec<String>) -> Result<Plan> {
+    let builtin = CommandRegistry::new();
+    let profile_name = match targets.as_slice() {
+        [] => Some(None),
+        [name] if !builtin.contains(name) => Some(Some(name.as_str())),
+        _ => None,
+    };
+
+    let (profile, registry, steps) = match profile_name {
+        Some(name) => {
+            let set = load_profiles()?;
+            let profile = resolve(&set, name).map_err(|e| match name {
+                Some(name) => PieuvreError::Config(format!(
+                    "'{}' is neither a tweak nor a profile ({})",
+                    name, e
+                )),
+                None => e,
+            })?;
+            (
+                Some(profile.name.clone()),
+                registry_for(&profile),
+                steps(&profile),
+            )
+        }
+        None => (None, builtin, targets),
+    };
+    let resolution = registry.resolve(&steps)?;
+
+    let mut plan = Plan {
+        profile,
+        notes: resolution.notes(),
+        steps: Vec::with_capacity(resolution.order.len()),
+    };
+    for id in resolution.order {
+        let undoable = registry.undoable(&id);
+        let step = match registry.plan(&id).await {
+            Ok(changes) => StepPlan {
+                id,
+                changes,
+                unavailable: None,
+                undoable,
+            },
+            Err(e) => StepPlan {
+                id,
+                changes: Vec::new(),
+                unavailable: Some(e.to_string()),
+                undoable,
+            },
+        };
+        plan.steps.push(step);
+    }
+    Ok(plan)
+}
+
+fn print(plan: &Plan) {
+    match &plan.profile {
+        Some(name) => println!("Plan for profile '{}' ({} steps)", name, plan.steps.len()),
+        None => println!("Plan for {} tweak(s)", plan.steps.len()),
+    }
+    for note in &plan.notes {
+        println!("  note: {}", note);
+    }
+
+    let mut unavailable = 0;
+    for step in &plan.steps {
+        if !step.undoable {
+            println!("  {}: not undoable, rollback cannot restore it", step.id);
+        }
+        if let Some(reason) = &step.unavailable {
+            println!("  {}: no preview ({})", step.id, reason);
+            unavailable += 1;
+        } else if step.changes.is_empty() {
+            println!("  {}: already applied", step.id);
+        } else {
+            println!("  {}", step.id);
+            for change in &step.changes {
+                println!("    ~ {}", describe(change));
+            }
+        }
+    }
+
+    println!(
+        "\n{} change(s) planned, {} step(s) without preview. Nothing was applied.",
+        plan.change_count(),
+        unavailable
+    );
+}
+
+/// Ligne lisible d'un changement prévu
+pub fn describe(change: &PlannedChange) -> String {
+    match change {
+        PlannedChange::Registry {
+            hive,
+            key,
+            value_name,
+            current,
+            target,
+        } => format!(
+            r"{}\{}\{}: {} -> {}",
+            hive.short_name(),
+            key,
+            value_name,
+            current
+                .as_ref()
+                .map_or_else(|| "(absent)".to_string(), |v| v.to_string()),
+            target
+                .as_ref()
+                .map_or_else(|| "(deleted)".to_string(), |v| v.to_string())
+        ),
+        PlannedChange::Service {
+            name,
+            current_start_type,
+            target_start_type,
+        } => format!(
+            "service {}: {} -> {:?}",
+            name,
+            current_start_type.map_or_else(
+                || "(not found)".to_string(),
+                |t| format!("{:?}", ServiceStartType::from_raw(t))
+            ),
+            ServiceStartType::from_raw(*target_start_type)
+        ),
+        PlannedChange::ScheduledTask {
+            path,
+            current_enabled,
+            target_enabled,
+        } => format!(
+            "task {}: {} -> {}",
+            path,
+            enabled_label(*current_enabled),
+            enabled_label(*target_enabled)
+        ),
+        PlannedChange::HostsBlock { domains } => {
+            format!("hosts: {} domain(s) redirected to 0.0.0.0", domains.len())
+        }
+    }
+}
+
+fn enabled_label(enabled: bool) -> &'static str {
+    if enabled {
+        "enabled"
+    } else {
+        "disabled"
+    }
+}
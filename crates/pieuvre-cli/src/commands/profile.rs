@@ -0,0 +1,227 @@
+//! Commande profile
+//!
+//! Profils intégrés (`config/profiles`), puis profils personnalisés de
+//! `profile.profiles_dir` et `%APPDATA%\pieuvre\profiles` (un nom existant est remplacé).
+
+use crate::commands::interactive::executor::{
+    CommandRegistry, PowerPlanCommand, TimerResolutionCommand,
+};
+use pieuvre_common::{config, PieuvreError, Result};
+use pieuvre_sync::profile::{ProfileSet, ResolvedProfile};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Profils livrés avec le binaire
+pub const BUILTIN_PROFILES: &[&str] = &[
+    include_str!("../../../../config/profiles/base.toml"),
+    include_str!("../../../../config/profiles/gaming.toml"),
+    include_str!("../../../../config/profiles/privacy.toml"),
+    include_str!("../../../../config/profiles/workstation.toml"),
+];
+
+/// Étape ajoutée par le paramètre `power_plan`
+const POWER_PLAN_STEP: &str = "power_plan";
+/// Étape pilotée par le paramètre `timer_resolution`
+const TIMER_STEP: &str = "timer";
+
+/// Profils intégrés fusionnés avec les fichiers machine puis utilisateur
+pub fn load_profiles() -> Result<ProfileSet> {
+    let mut set = ProfileSet::default();
+    for content in BUILTIN_PROFILES {
+        set.merge(ProfileSet::from_toml(content)?);
+    }
+    load_profile_dir(&mut set, &config().profile.profiles_dir);
+    if let Some(dir) = user_profiles_dir() {
+        load_profile_dir(&mut set, &dir);
+    }
+    Ok(set)
+}
+
+/// `%APPDATA%\pieuvre\profiles`
+pub fn user_profiles_dir() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("pieuvre").join("profiles"))
+}
+
+/// Fusionne les `*.toml` d'un dossier par ordre alphabétique ; un fichier invalide est ignoré
+pub fn load_profile_dir(set: &mut ProfileSet, dir: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+        })
+        .collect();
+    files.sort();
+
+    let mut loaded = 0;
+    for path in files {
+        match ProfileSet::load_file(&path) {
+            Ok(profiles) => {
+                tracing::info!("Profile file loaded: {}", path.display());
+                set.merge(profiles);
+                loaded += 1;
+            }
+            Err(e) => tracing::warn!("Profile file ignored: {}", e),
+        }
+    }
+    loaded
+}
+
+/// Étapes d'application : tweaks du profil, puis étapes issues des paramètres
+pub fn steps(profile: &ResolvedProfile) -> Vec<String> {
+    let mut steps = profile.tweaks.clone();
+    if profile.params.timer_resolution.is_some() && !steps.iter().any(|s| s == TIMER_STEP) {
+        steps.push(TIMER_STEP.to_string());
+    }
+    if profile.params.power_plan.is_some() {
+        steps.push(POWER_PLAN_STEP.to_string());
+    }
+    steps
+}
+
+/// Registre de commandes avec les paramètres du profil
+pub fn registry_for(profile: &ResolvedProfile) -> CommandRegistry {
+    let mut registry = CommandRegistry::new();
+    if let Some(resolution) = profile.params.timer_resolution {
+        registry.register(TIMER_STEP, TimerResolutionCommand::new(resolution));
+    }
+    if let Some(plan) = profile.params.power_plan {
+        registry.register(POWER_PLAN_STEP, PowerPlanCommand::new(plan));
+    }
+    registry
+}
+
+/// Profil demandé, ou `profile.default_profile`
+pub fn resolve(set: &ProfileSet, name: Option<&str>) -> Result<ResolvedProfile> {
+    set.resolve(name.unwrap_or(&config().profile.default_profile))
+}
+
+pub fn list() -> Result<()> {
+    let set = load_profiles()?;
+    let default = &config().profile.default_profile;
+
+    println!("Available profiles:\n");
+    for profile in set.profiles() {
+        let marker = if profile.name.eq_ignore_ascii_case(default) {
+            " (default)"
+        } else {
+            ""
+        };
+        let parent = profile
+            .extends
+            .as_deref()
+            .map(|p| format!(" [extends {}]", p))
+            .unwrap_or_default();
+        println!("  {:<15}{}{}", profile.name, marker, parent);
+        if !profile.description.is_empty() {
+            println!("    {}", profile.description);
+        }
+    }
+    Ok(())
+}
+
+pub fn show(name: Option<String>) -> Result<()> {
+    let set = load_profiles()?;
+    let profile = resolve(&set, name.as_deref())?;
+    let registry = registry_for(&profile);
+
+    println!("Profile: {}", profile.name);
+    if !profile.description.is_empty() {
+        println!("  {}", profile.description);
+    }
+    println!("Inheritance: {}", profile.chain.join(" -> "));
+    if let Some(res) = profile.params.timer_resolution {
+        println!("Timer resolution: {:.2} ms", res as f64 / 10000.0);
+    }
+    if let Some(plan) = profile.params.power_plan {
+        println!("Power plan: {}", plan.name());
+    }
+
+    println!("\nSteps:");
+    for step in steps(&profile) {
+        let status = if registry.contains(&step) {
+            ""
+        } else {
+            "  (unknown tweak)"
+        };
+        println!("  - {}{}", step, status);
+    }
+    Ok(())
+}
+
+pub async fn apply(name: Option<String>) -> Result<()> {
+    let set = load_profiles()?;
+    let profile = resolve(&set, name.as_deref())?;
+    let registry = registry_for(&profile);
+    let steps = steps(&profile);
+
+    let unknown: Vec<&str> = steps
+        .iter()
+        .filter(|s| !registry.contains(s))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(PieuvreError::Config(format!(
+            "Profile '{}' references unknown tweaks: {}",
+            profile.name,
+            unknown.join(", ")
+        )));
+    }
+    let resolution = registry.resolve(&steps)?;
+
+    println!(
+        "Applying profile '{}' ({} steps)...",
+        profile.name,
+        resolution.order.len()
+    );
+    for note in resolution.notes() {
+        println!("  note: {}", note);
+    }
+    let name = format!("Profile '{}'", profile.name);
+    super::apply::run(&name, Arc::new(registry), resolution).await?;
+    if !config().general.dry_run {
+        println!("Profile '{}' applied", profile.name);
+    }
+    Ok(())
+}
+
+/// Compare deux profils, ou un profil avec l'état actuel du système
+pub async fn diff(name: String, other: Option<String>) -> Result<()> {
+    let set = load_profiles()?;
+    let profile = set.resolve(&name)?;
+
+    if let Some(other) = other {
+        let other = set.resolve(&other)?;
+        let diff = profile.diff(&other);
+        println!("{} -> {}", profile.name, other.name);
+        if diff.is_empty() {
+            println!("  No differences");
+        }
+        for tweak in &diff.added {
+            println!("  + {}", tweak);
+        }
+        for tweak in &diff.removed {
+            println!("  - {}", tweak);
+        }
+        for (param, left, right) in &diff.params {
+            println!("  ~ {}: {} -> {}", param, left, right);
+        }
+        return Ok(());
+    }
+
+    let registry = registry_for(&profile);
+    let mut pending = 0;
+    println!("{} vs current system:", profile.name);
+    for step in steps(&profile) {
+        let applied = registry.check_status(&step).await.unwrap_or(false);
+        if !applied {
+            pending += 1;
+        }
+        println!("  [{}] {}", if applied { "x" } else { " " }, step);
+    }
+    println!("\n{} step(s) to apply", pending);
+    Ok(())
+}
@@ -0,0 +1,113 @@
+//! Commande recover : journal d'une session interrompue (crash, BSOD)
+
+use pieuvre_common::{PieuvreError, Result};
+use pieuvre_persist::journal::{self, PendingJournal};
+use std::io::IsTerminal;
+
+pub fn run(finish: bool, rollback: bool, discard: bool) -> Result<()> {
+    let Some(pending) = journal::pending()? else {
+        println!("No interrupted session to recover");
+        return Ok(());
+    };
+
+    if finish {
+        finish_journal(&pending)
+    } else if rollback {
+        rollback_journal(&pending)
+    } else if discard {
+        journal::discard(&pending)?;
+        println!("Journal discarded, nothing was restored");
+        Ok(())
+    } else {
+        describe(&pending);
+        for change in &pending.changes {
+            for view in pieuvre_persist::inspect::inspect_change(change) {
+                println!("  [{}] {} : {}", view.kind, view.target, view.stored);
+            }
+        }
+        println!("\nUsage: pieuvre recover --finish | --rollback | --discard");
+        Ok(())
+    }
+}
+
+/// Au démarrage : propose de terminer ou d'annuler une session interrompue
+pub fn check_pending() {
+    let pending = match journal::pending() {
+        Ok(Some(pending)) => pending,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::warn!("Journal illisible: {}", e);
+            return;
+        }
+    };
+
+    describe(&pending);
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        println!("Run `pieuvre recover --finish` or `pieuvre recover --rollback`\n");
+        return;
+    }
+
+    let choice = dialoguer::Select::new()
+        .with_prompt("What should be done with these changes?")
+        .items(&[
+            "Save them as a snapshot (keep the changes)",
+            "Roll them back (restore the original values)",
+            "Decide later",
+        ])
+        .default(0)
+        .interact();
+    let result = match choice {
+        Ok(0) => finish_journal(&pending),
+        Ok(1) => rollback_journal(&pending),
+        Ok(_) => {
+            println!("Journal kept, see `pieuvre recover`\n");
+            Ok(())
+        }
+        Err(e) => Err(PieuvreError::Internal(e.to_string())),
+    };
+    if let Err(e) = result {
+        eprintln!("WARNING: Recovery failed: {}", e);
+    }
+}
+
+fn describe(pending: &PendingJournal) {
+    println!(
+        "An interrupted session was found ({}): {} change(s) journaled{}",
+        pending
+            .started
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "unknown date".to_string()),
+        pending.changes.len(),
+        if pending.skipped > 0 {
+            format!(", {} unreadable line(s)", pending.skipped)
+        } else {
+            String::new()
+        }
+    );
+}
+
+fn finish_journal(pending: &PendingJournal) -> Result<()> {
+    match journal::finish(pending)? {
+        Some(snapshot) => println!(
+            "Snapshot {} created from the journal",
+            &snapshot.id.to_string()[..8]
+        ),
+        None => println!("Journal was empty, removed"),
+    }
+    Ok(())
+}
+
+fn rollback_journal(pending: &PendingJournal) -> Result<()> {
+    let summary = journal::rollback(pending)?;
+    println!(
+        "Restored {} of {} change(s)",
+        summary.restored, summary.selected
+    );
+    if summary.failed > 0 {
+        return Err(PieuvreError::Internal(format!(
+            "{} restoration(s) failed, journal kept (see logs)",
+            summary.failed
+        )));
+    }
+    Ok(())
+}
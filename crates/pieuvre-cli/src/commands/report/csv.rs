@@ -0,0 +1,113 @@
+//! Rendu CSV : une ligne par élément, colonne `section` pour filtrer (RFC 4180)
+
+use super::{audit_flags, status_label, ReportData};
+
+const HEADER: [&str; 8] = [
+    "section", "id", "name", "status", "severity", "expected", "actual", "details",
+];
+
+pub fn render(data: &ReportData) -> String {
+    let mut rows: Vec<[String; 8]> = Vec::new();
+
+    if let Some(audit) = &data.audit {
+        let system = [
+            ("hostname", audit.system.hostname.clone()),
+            ("os_version", audit.system.os_version.clone()),
+            ("build_number", audit.system.build_number.to_string()),
+            ("edition", audit.system.edition.clone()),
+            ("cpu", audit.hardware.cpu.model_name.clone()),
+        ];
+        for (id, value) in system {
+            rows.push(row(["system", id, "", "", "", "", &value, ""]));
+        }
+        for (section, name, value) in audit_flags(audit) {
+            rows.push(row([section, name, "", "", "", "", &value, ""]));
+        }
+        for s in &audit.services {
+            rows.push(row([
+                "service",
+                &s.name,
+                &s.display_name,
+                &format!("{:?}", s.status),
+                "",
+                "",
+                &format!("{:?}", s.start_type),
+                &format!("{:?}", s.category),
+            ]));
+        }
+        for p in &audit.appx {
+            rows.push(row([
+                "appx",
+                &p.full_name,
+                &p.name,
+                if p.is_provisioned {
+                    "Provisioned"
+                } else {
+                    "Installed"
+                },
+                &format!("{:?}", p.removal_risk),
+                "",
+                &p.version,
+                &format!("{:?}", p.category),
+            ]));
+        }
+    }
+
+    if let Some(compliance) = &data.compliance {
+        for c in &compliance.checks {
+            let details = match &c.status {
+                pieuvre_audit::ComplianceStatus::Error(e)
+                | pieuvre_audit::ComplianceStatus::NotApplicable(e) => e.clone(),
+                _ => c.location.clone(),
+            };
+            rows.push(row([
+                "compliance",
+                &c.id,
+                &c.name,
+                status_label(&c.status),
+                &format!("{:?}", c.severity),
+                &c.expected,
+                &c.actual,
+                &details,
+            ]));
+        }
+    }
+
+    for t in &data.threats {
+        rows.push(row([
+            "threat",
+            &t.source,
+            &t.name,
+            "",
+            &format!("{:?}", t.severity),
+            "",
+            &t.location,
+            &t.description,
+        ]));
+    }
+
+    let mut out = line(&HEADER.map(String::from));
+    for r in &rows {
+        out.push_str(&line(r));
+    }
+    out
+}
+
+/// Colonnes dans l'ordre de `HEADER`
+fn row(fields: [&str; 8]) -> [String; 8] {
+    fields.map(String::from)
+}
+
+fn line(fields: &[String; 8]) -> String {
+    let fields: Vec<String> = fields.iter().map(|f| field(f)).collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+/// Guillemets si le champ contient un séparateur, un guillemet ou un retour à la ligne
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
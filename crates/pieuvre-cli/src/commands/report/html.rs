@@ -0,0 +1,194 @@
+//! Rendu HTML : page unique, CSS intégré, aucune ressource externe
+
+use super::{audit_flags, status_label, yes_no, ReportData};
+use std::fmt::Write;
+
+const STYLE: &str = "\
+body{font-family:Segoe UI,Arial,sans-serif;margin:2em;color:#1f2328;background:#fff}\
+h1{font-size:1.5em;border-bottom:2px solid #6e40c9;padding-bottom:.3em}\
+h2{font-size:1.2em;margin-top:1.8em}\
+table{border-collapse:collapse;width:100%;font-size:.9em}\
+th,td{border:1px solid #d0d7de;padding:4px 8px;text-align:left;vertical-align:top}\
+th{background:#f6f8fa}\
+tr:nth-child(even) td{background:#fbfbfc}\
+.pass{color:#1a7f37;font-weight:600}.fail{color:#cf222e;font-weight:600}.error{color:#9a6700;font-weight:600}.skip{color:#57606a;font-weight:600}\
+.summary{font-weight:600}.muted{color:#656d76}";
+
+pub fn render(data: &ReportData) -> String {
+    let title = escape(&data.title());
+    let mut body = format!("<h1>{}</h1>\n", title);
+
+    if let Some(audit) = &data.audit {
+        body.push_str("<h2>System</h2>\n");
+        table(
+            &mut body,
+            &["Field", "Value"],
+            [
+                ("Hostname", audit.system.hostname.clone()),
+                ("OS", audit.system.os_version.clone()),
+                ("Build", audit.system.build_number.to_string()),
+                ("Edition", audit.system.edition.clone()),
+                ("CPU", audit.hardware.cpu.model_name.clone()),
+                ("Report ID", audit.id.to_string()),
+            ]
+            .into_iter()
+            .map(|(field, value)| vec![escape(field), escape(&value)])
+            .collect(),
+        );
+
+        body.push_str("<h2>Telemetry &amp; Security</h2>\n");
+        table(
+            &mut body,
+            &["Section", "Setting", "Value"],
+            audit_flags(audit)
+                .into_iter()
+                .map(|(section, name, value)| vec![escape(section), escape(name), escape(&value)])
+                .collect(),
+        );
+
+        let _ = writeln!(body, "<h2>Services ({})</h2>", audit.services.len());
+        table(
+            &mut body,
+            &["Name", "Display name", "Status", "Start type", "Category"],
+            audit
+                .services
+                .iter()
+                .map(|s| {
+                    vec![
+                        escape(&s.name),
+                        escape(&s.display_name),
+                        escape(&format!("{:?}", s.status)),
+                        escape(&format!("{:?}", s.start_type)),
+                        escape(&format!("{:?}", s.category)),
+                    ]
+                })
+                .collect(),
+        );
+
+        let _ = writeln!(body, "<h2>Appx packages ({})</h2>", audit.appx.len());
+        table(
+            &mut body,
+            &["Name", "Version", "Provisioned", "Category", "Removal risk"],
+            audit
+                .appx
+                .iter()
+                .map(|p| {
+                    vec![
+                        escape(&p.name),
+                        escape(&p.version),
+                        escape(yes_no(p.is_provisioned)),
+                        escape(&format!("{:?}", p.category)),
+                        escape(&format!("{:?}", p.removal_risk)),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    if let Some(compliance) = &data.compliance {
+        let summary = compliance.summary();
+        let _ = writeln!(
+            body,
+            "<h2>Compliance: {}</h2>\n<p class=\"summary\">{} passed, {} failed, {} error(s), {} skipped</p>",
+            escape(&compliance.ruleset),
+            summary.passed,
+            summary.failed,
+            summary.errors,
+            summary.skipped
+        );
+        table(
+            &mut body,
+            &["Status", "ID", "Rule", "Severity", "Expected", "Actual"],
+            compliance
+                .checks
+                .iter()
+                .map(|c| {
+                    let label = status_label(&c.status);
+                    let mut rule = escape(&c.name);
+                    if !c.rationale.is_empty() {
+                        let _ = write!(
+                            rule,
+                            "<br><span class=\"muted\">{}</span>",
+                            escape(&c.rationale)
+                        );
+                    }
+                    let actual = match &c.status {
+                        pieuvre_audit::ComplianceStatus::Error(e)
+                        | pieuvre_audit::ComplianceStatus::NotApplicable(e) => escape(e),
+                        _ => escape(&c.actual),
+                    };
+                    vec![
+                        format!("<span class=\"{}\">{}</span>", label.to_lowercase(), label),
+                        escape(&c.id),
+                        rule,
+                        escape(&format!("{:?}", c.severity)),
+                        escape(&c.expected),
+                        actual,
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    if !data.threats.is_empty() {
+        let _ = writeln!(body, "<h2>Threats ({})</h2>", data.threats.len());
+        table(
+            &mut body,
+            &["Severity", "Name", "Source", "Location", "Description"],
+            data.threats
+                .iter()
+                .map(|t| {
+                    vec![
+                        escape(&format!("{:?}", t.severity)),
+                        escape(&t.name),
+                        escape(&t.source),
+                        escape(&t.location),
+                        escape(&t.description),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        title, STYLE, body
+    )
+}
+
+/// `rows` : cellules déjà échappées
+fn table(out: &mut String, headers: &[&str], rows: Vec<Vec<String>>) {
+    if rows.is_empty() {
+        out.push_str("<p class=\"muted\">None</p>\n");
+        return;
+    }
+    out.push_str("<table>\n<tr>");
+    for header in headers {
+        let _ = write!(out, "<th>{}</th>", escape(header));
+    }
+    out.push_str("</tr>\n");
+    for row in rows {
+        out.push_str("<tr>");
+        for cell in row {
+            let _ = write!(out, "<td>{}</td>", cell);
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,145 @@
+//! Rendu Markdown (tables GitHub)
+
+use super::{audit_flags, status_label, ReportData};
+use std::fmt::Write;
+
+pub fn render(data: &ReportData) -> String {
+    let mut out = format!("# {}\n", data.title());
+
+    if let Some(audit) = &data.audit {
+        out.push_str("\n## System\n\n");
+        table(
+            &mut out,
+            &["Field", "Value"],
+            vec![
+                vec!["Hostname".into(), audit.system.hostname.clone()],
+                vec!["OS".into(), audit.system.os_version.clone()],
+                vec!["Build".into(), audit.system.build_number.to_string()],
+                vec!["Edition".into(), audit.system.edition.clone()],
+                vec!["CPU".into(), audit.hardware.cpu.model_name.clone()],
+                vec!["Report ID".into(), audit.id.to_string()],
+            ],
+        );
+
+        out.push_str("\n## Telemetry & Security\n\n");
+        table(
+            &mut out,
+            &["Section", "Setting", "Value"],
+            audit_flags(audit)
+                .into_iter()
+                .map(|(section, name, value)| vec![section.into(), name.into(), value])
+                .collect(),
+        );
+
+        let _ = write!(out, "\n## Services ({})\n\n", audit.services.len());
+        table(
+            &mut out,
+            &["Name", "Display name", "Status", "Start type", "Category"],
+            audit
+                .services
+                .iter()
+                .map(|s| {
+                    vec![
+                        s.name.clone(),
+                        s.display_name.clone(),
+                        format!("{:?}", s.status),
+                        format!("{:?}", s.start_type),
+                        format!("{:?}", s.category),
+                    ]
+                })
+                .collect(),
+        );
+
+        let _ = write!(out, "\n## Appx packages ({})\n\n", audit.appx.len());
+        table(
+            &mut out,
+            &["Name", "Version", "Provisioned", "Category", "Removal risk"],
+            audit
+                .appx
+                .iter()
+                .map(|p| {
+                    vec![
+                        p.name.clone(),
+                        p.version.clone(),
+                        super::yes_no(p.is_provisioned).into(),
+                        format!("{:?}", p.category),
+                        format!("{:?}", p.removal_risk),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    if let Some(compliance) = &data.compliance {
+        let summary = compliance.summary();
+        let _ = write!(
+            out,
+            "\n## Compliance: {}\n\n**{} passed, {} failed, {} error(s), {} skipped**\n\n",
+            compliance.ruleset, summary.passed, summary.failed, summary.errors, summary.skipped
+        );
+        table(
+            &mut out,
+            &["Status", "ID", "Rule", "Severity", "Expected", "Actual"],
+            compliance
+                .checks
+                .iter()
+                .map(|c| {
+                    let actual = match &c.status {
+                        pieuvre_audit::ComplianceStatus::Error(e)
+                        | pieuvre_audit::ComplianceStatus::NotApplicable(e) => e.clone(),
+                        _ => c.actual.clone(),
+                    };
+                    vec![
+                        status_label(&c.status).into(),
+                        c.id.clone(),
+                        c.name.clone(),
+                        format!("{:?}", c.severity),
+                        c.expected.clone(),
+                        actual,
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    if !data.threats.is_empty() {
+        let _ = write!(out, "\n## Threats ({})\n\n", data.threats.len());
+        table(
+            &mut out,
+            &["Severity", "Name", "Source", "Location", "Description"],
+            data.threats
+                .iter()
+                .map(|t| {
+                    vec![
+                        format!("{:?}", t.severity),
+                        t.name.clone(),
+                        t.source.clone(),
+                        t.location.clone(),
+                        t.description.clone(),
+                    ]
+                })
+                .collect(),
+        );
+    }
+    out
+}
+
+fn table(out: &mut String, headers: &[&str], rows: Vec<Vec<String>>) {
+    if rows.is_empty() {
+        out.push_str("_None_\n");
+        return;
+    }
+    let _ = writeln!(out, "| {} |", headers.join(" | "));
+    let _ = writeln!(out, "|{}", "---|".repeat(headers.len()));
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|cell| escape(cell)).collect();
+        let _ = writeln!(out, "| {} |", cells.join(" | "));
+    }
+}
+
+/// `|` et retours à la ligne casseraient la table
+fn escape(cell: &str) -> String {
+    cell.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
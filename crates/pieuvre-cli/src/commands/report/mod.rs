@@ -0,0 +1,227 @@
+//! Commande report
+//!
+//! Rendu des rapports : audit, résultats de conformité et menaces du scanner,
+//! en HTML autonome, Markdown, CSV ou SARIF 2.1.0. `report render` relit des
+//! rapports JSON enregistrés.
+
+mod csv;
+mod html;
+mod markdown;
+mod sarif;
+
+use clap::ValueEnum;
+use pieuvre_audit::ComplianceReport;
+use pieuvre_common::{AuditReport, PieuvreError, Result};
+use pieuvre_scan::engine::Threat;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Format de sortie d'un rapport
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Html,
+    #[value(alias = "md")]
+    Markdown,
+    Csv,
+    Sarif,
+}
+
+impl ReportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Sarif => "sarif",
+        }
+    }
+}
+
+/// Contenu d'un rapport ; chaque partie est optionnelle
+#[derive(Debug, Clone, Default)]
+pub struct ReportData {
+    pub audit: Option<AuditReport>,
+    pub compliance: Option<ComplianceReport>,
+    pub threats: Vec<Threat>,
+}
+
+/// Fichier JSON enregistré, reconnu à sa forme
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredReport {
+    Audit(Box<AuditReport>),
+    Compliance(ComplianceReport),
+    Threats(Vec<Threat>),
+}
+
+impl ReportData {
+    /// Rapport d'audit, résultats de conformité ou liste de menaces, dans n'importe quel ordre
+    pub fn load(paths: &[PathBuf]) -> Result<Self> {
+        let mut data = Self::default();
+        for path in paths {
+            let content = std::fs::read_to_string(path)?;
+            let stored: StoredReport = serde_json::from_str(&content).map_err(|_| {
+                PieuvreError::Parse(format!(
+                    "{}: not an audit report, compliance results or threat list",
+                    path.display()
+                ))
+            })?;
+            match stored {
+                StoredReport::Audit(report) => data.audit = Some(*report),
+                StoredReport::Compliance(results) => data.compliance = Some(results),
+                StoredReport::Threats(threats) => data.threats.extend(threats),
+            }
+        }
+        Ok(data)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.audit.is_none() && self.compliance.is_none() && self.threats.is_empty()
+    }
+
+    /// Titre : machine et date de l'audit, si présent
+    fn title(&self) -> String {
+        match &self.audit {
+            Some(audit) => format!(
+                "pieuvre report - {} - {}",
+                audit.system.hostname,
+                audit.timestamp.format("%Y-%m-%d %H:%M UTC")
+            ),
+            None => "pieuvre report".to_string(),
+        }
+    }
+}
+
+/// Rapport complet dans `format`
+pub fn render(data: &ReportData, format: ReportFormat) -> Result<String> {
+    Ok(match format {
+        ReportFormat::Json => render_json(data)?,
+        ReportFormat::Html => html::render(data),
+        ReportFormat::Markdown => markdown::render(data),
+        ReportFormat::Csv => csv::render(data),
+        ReportFormat::Sarif => serde_json::to_string_pretty(&sarif::render(data))?,
+    })
+}
+
+/// Une seule partie : son JSON tel quel ; sinon un objet les regroupant
+fn render_json(data: &ReportData) -> Result<String> {
+    let json = match (&data.audit, &data.compliance, data.threats.is_empty()) {
+        (Some(audit), None, true) => serde_json::to_string_pretty(audit)?,
+        (None, Some(compliance), true) => serde_json::to_string_pretty(compliance)?,
+        (None, None, false) => serde_json::to_string_pretty(&data.threats)?,
+        _ => serde_json::to_string_pretty(&serde_json::json!({
+            "audit": data.audit,
+            "compliance": data.compliance,
+            "threats": data.threats,
+        }))?,
+    };
+    Ok(json)
+}
+
+/// `pieuvre report render` : sortie dans `output`, sinon à côté du premier fichier
+pub fn run_render(
+    inputs: Vec<PathBuf>,
+    format: ReportFormat,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let data = ReportData::load(&inputs)?;
+    if data.is_empty() {
+        return Err(PieuvreError::Config("Nothing to render".to_string()));
+    }
+
+    let output = output.unwrap_or_else(|| inputs[0].with_extension(format.extension()));
+    if inputs.contains(&output) {
+        return Err(PieuvreError::Config(format!(
+            "{} would overwrite an input; use --output",
+            output.display()
+        )));
+    }
+    write(&output, &render(&data, format)?)?;
+    println!("[*] Report rendered: {}", output.display());
+    Ok(())
+}
+
+pub fn write(path: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+/// `true` / `false` lisible pour les drapeaux de télémétrie et de sécurité
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// Drapeaux de télémétrie et de sécurité : (section, nom, valeur)
+fn audit_flags(audit: &AuditReport) -> Vec<(&'static str, &'static str, String)> {
+    let t = &audit.telemetry;
+    let s = &audit.security;
+    vec![
+        (
+            "telemetry",
+            "DiagTrack enabled",
+            yes_no(t.diagtrack_enabled).into(),
+        ),
+        (
+            "telemetry",
+            "Data collection level",
+            t.data_collection_level.to_string(),
+        ),
+        (
+            "telemetry",
+            "Advertising ID",
+            yes_no(t.advertising_id_enabled).into(),
+        ),
+        ("telemetry", "Location", yes_no(t.location_enabled).into()),
+        (
+            "telemetry",
+            "Activity history",
+            yes_no(t.activity_history_enabled).into(),
+        ),
+        ("telemetry", "Cortana", yes_no(t.cortana_enabled).into()),
+        (
+            "telemetry",
+            "Web search",
+            yes_no(t.web_search_enabled).into(),
+        ),
+        (
+            "telemetry",
+            "Error reporting",
+            yes_no(t.error_reporting_enabled).into(),
+        ),
+        ("security", "Defender", yes_no(s.defender_enabled).into()),
+        (
+            "security",
+            "Tamper protection",
+            yes_no(s.tamper_protection).into(),
+        ),
+        ("security", "Firewall", yes_no(s.firewall_enabled).into()),
+        ("security", "UAC level", s.uac_level.to_string()),
+        ("security", "Secure Boot", yes_no(s.secure_boot).into()),
+        (
+            "security",
+            "Credential Guard",
+            yes_no(s.credential_guard).into(),
+        ),
+        ("security", "BitLocker", yes_no(s.bitlocker_active).into()),
+    ]
+}
+
+/// Libellé d'un statut de conformité
+fn status_label(status: &pieuvre_audit::ComplianceStatus) -> &'static str {
+    match status {
+        pieuvre_audit::ComplianceStatus::Compliant => "PASS",
+        pieuvre_audit::ComplianceStatus::NonCompliant => "FAIL",
+        pieuvre_audit::ComplianceStatus::Error(_) => "ERROR",
+        pieuvre_audit::ComplianceStatus::NotApplicable(_) => "SKIP",
+    }
+}
@@ -0,0 +1,200 @@
+//! Rendu SARIF 2.1.0
+//!
+//! Chaque règle de conformité évaluée devient un `result` (`kind` pass, fail
+//! ou review), chaque menace un `result` en échec. L'audit lui-même n'est pas
+//! un constat : il est joint aux propriétés du run.
+
+use super::ReportData;
+use pieuvre_audit::compliance::ComplianceSeverity;
+use pieuvre_audit::{ComplianceCheck, ComplianceStatus};
+use pieuvre_scan::engine::{Threat, ThreatSeverity};
+use serde_json::{json, Value};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/pieuvre/pieuvre";
+
+pub fn render(data: &ReportData) -> Value {
+    let mut rules: Vec<Value> = Vec::new();
+    let mut results: Vec<Value> = Vec::new();
+
+    if let Some(compliance) = &data.compliance {
+        for check in &compliance.checks {
+            rules.push(compliance_rule(check, &compliance.ruleset));
+            results.push(compliance_result(check, rules.len() - 1));
+        }
+    }
+
+    for threat in &data.threats {
+        let id = threat_rule_id(threat);
+        let index = match rules.iter().position(|rule| rule["id"] == id.as_str()) {
+            Some(index) => index,
+            None => {
+                rules.push(json!({
+                    "id": id,
+                    "name": threat.name,
+                    "shortDescription": { "text": threat.name },
+                    "properties": { "source": threat.source },
+                }));
+                rules.len() - 1
+            }
+        };
+        results.push(threat_result(threat, &id, index));
+    }
+
+    let mut run = json!({
+        "tool": {
+            "driver": {
+                "name": "pieuvre",
+                "version": env!("CARGO_PKG_VERSION"),
+                "informationUri": INFORMATION_URI,
+                "rules": rules,
+            }
+        },
+        "results": results,
+    });
+    if let Some(audit) = &data.audit {
+        run["properties"] = json!({
+            "audit": {
+                "id": audit.id,
+                "timestamp": audit.timestamp,
+                "system": audit.system,
+                "telemetry": audit.telemetry,
+                "security": audit.security,
+            }
+        });
+    }
+
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [run],
+    })
+}
+
+fn compliance_rule(check: &ComplianceCheck, ruleset: &str) -> Value {
+    let mut rule = json!({
+        "id": check.id,
+        "shortDescription": { "text": check.name },
+        "defaultConfiguration": { "level": compliance_level(check.severity) },
+        "properties": {
+            "ruleset": ruleset,
+            "severity": format!("{:?}", check.severity),
+            "references": check.references,
+        },
+    });
+    if !check.rationale.is_empty() {
+        rule["fullDescription"] = json!({ "text": check.rationale });
+    }
+    if let Some(uri) = check.references.iter().find(|r| r.starts_with("https://")) {
+        rule["helpUri"] = json!(uri);
+    }
+    rule
+}
+
+fn compliance_result(check: &ComplianceCheck, rule_index: usize) -> Value {
+    // Hors échec, SARIF impose level "none"
+    let (kind, level, text) = match &check.status {
+        ComplianceStatus::Compliant => {
+            ("pass", "none", format!("{}: {}", check.name, check.actual))
+        }
+        ComplianceStatus::NonCompliant => (
+            "fail",
+            compliance_level(check.severity),
+            format!(
+                "{}: expected {}, found {}",
+                check.name, check.expected, check.actual
+            ),
+        ),
+        ComplianceStatus::Error(e) => ("review", "none", format!("{}: {}", check.name, e)),
+        ComplianceStatus::NotApplicable(reason) => (
+            "notApplicable",
+            "none",
+            format!("{}: {}", check.name, reason),
+        ),
+    };
+
+    let mut result = json!({
+        "ruleId": check.id,
+        "ruleIndex": rule_index,
+        "kind": kind,
+        "level": level,
+        "message": { "text": text },
+    });
+    if !check.location.is_empty() {
+        result["locations"] = json!([{
+            "logicalLocations": [{ "fullyQualifiedName": check.location }]
+        }]);
+    }
+    result
+}
+
+fn threat_result(threat: &Threat, rule_id: &str, rule_index: usize) -> Value {
+    let level = match threat.severity {
+        ThreatSeverity::Critical | ThreatSeverity::High => "error",
+        ThreatSeverity::Medium => "warning",
+        ThreatSeverity::Low => "note",
+    };
+    // Fichier : URI file:///, clé de registre ou autre : emplacement logique
+    let location = match file_uri(&threat.location) {
+        Some(uri) => json!({ "physicalLocation": { "artifactLocation": { "uri": uri } } }),
+        None => json!({ "logicalLocations": [{ "fullyQualifiedName": threat.location }] }),
+    };
+    json!({
+        "ruleId": rule_id,
+        "ruleIndex": rule_index,
+        "kind": "fail",
+        "level": level,
+        "message": { "text": format!("{}: {}", threat.name, threat.description) },
+        "locations": [location],
+        "properties": { "severity": format!("{:?}", threat.severity) },
+    })
+}
+
+fn compliance_level(severity: ComplianceSeverity) -> &'static str {
+    match severity {
+        ComplianceSeverity::Critical | ComplianceSeverity::High => "error",
+        ComplianceSeverity::Medium => "warning",
+        ComplianceSeverity::Low | ComplianceSeverity::Info => "note",
+    }
+}
+
+/// `threat/<nom-en-minuscules>` : une règle par type de détection
+fn threat_rule_id(threat: &Threat) -> String {
+    let slug: String = threat
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("threat/{}", slug)
+}
+
+/// `C:\dir\file` -> `file:///C:/dir/file` (espaces et caractères réservés encodés)
+fn file_uri(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let is_drive_path = bytes.len() > 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+    if !is_drive_path {
+        return None;
+    }
+
+    let mut uri = String::from("file:///");
+    for c in path.chars() {
+        match c {
+            '\\' => uri.push('/'),
+            ' ' => uri.push_str("%20"),
+            '%' => uri.push_str("%25"),
+            '#' => uri.push_str("%23"),
+            '?' => uri.push_str("%3F"),
+            c => uri.push(c),
+        }
+    }
+    Some(uri)
+}
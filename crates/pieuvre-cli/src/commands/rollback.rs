@@ -1,8 +1,49 @@
 //! Commande rollback
 
-use pieuvre_common::Result;
+use pieuvre_common::{PieuvreError, Result, TransactionOutcome};
+use pieuvre_persist::baseline::BASELINE_TAG;
+use pieuvre_persist::format::FORMAT_VERSION;
+use pieuvre_persist::index::IndexEntry;
+use pieuvre_persist::inspect::{self, ChangeView};
+use pieuvre_persist::restore::{ChangeKind, RestoreFilter};
+use std::io::IsTerminal;
+
+/// Sélection d'une restauration partielle (--index, --kind, --key, --tweak)
+pub fn restore_filter(
+    indices: Vec<usize>,
+    kinds: Vec<String>,
+    key_prefixes: Vec<String>,
+    tweaks: Vec<String>,
+) -> Result<RestoreFilter> {
+    Ok(RestoreFilter {
+        indices,
+        kinds: kinds
+            .iter()
+            .map(|kind| kind.parse::<ChangeKind>())
+            .collect::<Result<_>>()?,
+        key_prefixes,
+        tweaks,
+    })
+}
+
+pub fn run(
+    list: bool,
+    last: bool,
+    baseline: bool,
+    id: Option<String>,
+    check: bool,
+    reindex: bool,
+    filter: RestoreFilter,
+) -> Result<()> {
+    if check {
+        return check_snapshots();
+    }
+    if reindex {
+        let count = pieuvre_persist::snapshot::rebuild_index()?;
+        println!("Snapshot index rebuilt ({} snapshots)", count);
+        return Ok(());
+    }
 
-pub fn run(list: bool, last: bool, id: Option<String>) -> Result<()> {
     let snapshots = pieuvre_persist::list_snapshots()?;
 
     if list {
@@ -12,10 +53,12 @@ pub fn run(list: bool, last: bool, id: Option<String>) -> Result<()> {
             println!("Available snapshots:\n");
             for s in &snapshots {
                 println!(
-                    "  {} - {} ({} changes)",
-                    &s.id.to_string()[..8],
+                    "  {} - {} ({} changes){}{}",
+                    s.short_id(),
                     s.timestamp.format("%Y-%m-%d %H:%M"),
-                    s.changes.len()
+                    s.change_count,
+                    s.outcome.map(outcome_tag).unwrap_or_default(),
+                    tags(s)
                 );
                 println!("    {}", s.description);
             }
@@ -23,11 +66,26 @@ pub fn run(list: bool, last: bool, id: Option<String>) -> Result<()> {
         return Ok(());
     }
 
+    if baseline {
+        let summary = pieuvre_persist::baseline::restore(&filter)?;
+        println!(
+            "Restored {} of {} baseline change(s)",
+            summary.restored, summary.selected
+        );
+        if summary.failed > 0 {
+            println!("  {} restoration(s) failed, see logs", summary.failed);
+        }
+        return Ok(());
+    }
+
     if last {
-        if let Some(snapshot) = snapshots.first() {
-            println!("Restoring last snapshot: {}", &snapshot.id.to_string()[..8]);
-            pieuvre_persist::restore_snapshot(&snapshot.id.to_string())?;
-            println!("Restoration completed");
+        // La baseline n'est pas une session : `--baseline` pour la restaurer
+        if let Some(snapshot) = snapshots
+            .iter()
+            .find(|s| !s.tags.iter().any(|tag| tag == BASELINE_TAG))
+        {
+            println!("Restoring last snapshot: {}", snapshot.short_id());
+            restore(&snapshot.id, &filter)?;
         } else {
             println!("No snapshots available");
         }
@@ -36,11 +94,274 @@ pub fn run(list: bool, last: bool, id: Option<String>) -> Result<()> {
 
     if let Some(snapshot_id) = id {
         println!("Restoring snapshot: {}", snapshot_id);
-        pieuvre_persist::restore_snapshot(&snapshot_id)?;
+        restore(&snapshot_id, &filter)?;
+        return Ok(());
+    }
+
+    println!("Usage: pieuvre rollback --list | --last | --baseline | --id <ID> | --show <ID> | --diff <A> <B> | --check | --reindex");
+    println!("       --pin <ID> [--label <TEXT>] | --unpin <ID> | --adopt-unsigned [--yes]");
+    println!(
+        "       --last / --baseline / --id <ID> [--index <N>] [--kind <KIND>] [--key <PREFIX>] [--tweak <ID>]"
+    );
+    Ok(())
+}
+
+/// `[pinned, label...]` après un snapshot de la liste
+fn tags(entry: &IndexEntry) -> String {
+    let mut tags: Vec<&str> = entry.tags.iter().map(String::as_str).collect();
+    if entry.pinned {
+        tags.insert(0, "pinned");
+    }
+    if tags.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", tags.join(", "))
+    }
+}
+
+/// Épingle un snapshot : la rotation le conserve
+pub fn pin(id: &str, label: Option<&str>) -> Result<()> {
+    let entry = pieuvre_persist::snapshot::pin(id, label)?;
+    println!("Snapshot {} pinned{}", entry.short_id(), tags(&entry));
+    Ok(())
+}
+
+pub fn unpin(id: &str) -> Result<()> {
+    let entry = pieuvre_persist::snapshot::unpin(id)?;
+    println!("Snapshot {} returned to rotation", entry.short_id());
+    Ok(())
+}
+
+/// Premier lancement : capture l'état d'origine de tout ce que pieuvre peut modifier
+pub fn capture_baseline() {
+    // Session interrompue non résolue : le système n'est plus dans son état d'origine
+    if matches!(pieuvre_persist::journal::pending(), Ok(Some(_))) {
+        return;
+    }
+    let capture = || {
+        let mut targets = pieuvre_sync::baseline::BaselineTargets::builtin();
+        targets.merge(pieuvre_sync::baseline::BaselineTargets::from_catalog(
+            crate::commands::interactive::catalog::catalog(),
+        ));
+        pieuvre_sync::baseline::capture(&targets)
+    };
+    match pieuvre_persist::baseline::capture_if_missing(capture) {
+        Ok(Some(snapshot)) => println!(
+            "Baseline captured: {} original value(s) saved ({})\n",
+            snapshot.changes.len(),
+            &snapshot.id.to_string()[..8]
+        ),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Baseline non capturée: {}", e),
+    }
+}
+
+fn restore(id: &str, filter: &RestoreFilter) -> Result<()> {
+    if filter.is_empty() {
+        pieuvre_persist::restore_snapshot(id)?;
         println!("Restoration completed");
         return Ok(());
     }
 
-    println!("Usage: pieuvre rollback --list | --last | --id <ID>");
+    let summary = pieuvre_persist::snapshot::restore_selected(id, filter)?;
+    if summary.selected == 0 {
+        println!("No change matches the selection");
+        return Ok(());
+    }
+    println!(
+        "Restored {} of {} selected change(s)",
+        summary.restored, summary.selected
+    );
+    if summary.failed > 0 {
+        println!("  {} restoration(s) failed, see logs", summary.failed);
+    }
+    Ok(())
+}
+
+/// Contenu d'un snapshot : valeur stockée et valeur actuelle de chaque changement
+pub fn show(id: &str, json: bool) -> Result<()> {
+    let view = inspect::show(id)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&view)?);
+        return Ok(());
+    }
+
+    println!(
+        "Snapshot {} - {}{}",
+        &view.id[..8],
+        view.timestamp.format("%Y-%m-%d %H:%M"),
+        view.outcome.map(outcome_tag).unwrap_or_default()
+    );
+    println!("  {}\n", view.description);
+    for change in &view.changes {
+        print_change(change);
+    }
+
+    let pending = view.changes.iter().filter(|c| !c.is_current()).count();
+    println!(
+        "\n{} item(s), {} would change on restore",
+        view.changes.len(),
+        pending
+    );
     Ok(())
 }
+
+fn print_change(change: &ChangeView) {
+    // `=` : déjà à la valeur stockée, `*` : modifié par une restauration
+    let marker = if change.is_current() { "=" } else { "*" };
+    let tweak = change
+        .tweak
+        .as_deref()
+        .map(|t| format!(" ({})", t))
+        .unwrap_or_default();
+    println!(
+        "  {} #{} [{}] {}{}",
+        marker, change.index, change.kind, change.target, tweak
+    );
+    println!("      stored : {}", change.stored);
+    println!(
+        "      current: {}",
+        change.current.as_deref().unwrap_or("(unreadable)")
+    );
+}
+
+/// Compare les valeurs stockées de deux snapshots
+pub fn diff(a: &str, b: &str, json: bool) -> Result<()> {
+    let diff = inspect::diff(a, b)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+
+    println!("Diff {} -> {}\n", &diff.a[..8], &diff.b[..8]);
+    for item in &diff.changed {
+        println!(
+            "  ~ [{}] {} : {} -> {}",
+            item.kind, item.target, item.before, item.after
+        );
+    }
+    for view in &diff.removed {
+        println!("  - [{}] {} : {}", view.kind, view.target, view.stored);
+    }
+    for view in &diff.added {
+        println!("  + [{}] {} : {}", view.kind, view.target, view.stored);
+    }
+    if diff.is_empty() {
+        println!("  No differences");
+    }
+    println!(
+        "\n{} changed, {} only in {}, {} only in {}, {} identical",
+        diff.changed.len(),
+        diff.removed.len(),
+        &diff.a[..8],
+        diff.added.len(),
+        &diff.b[..8],
+        diff.unchanged
+    );
+    Ok(())
+}
+
+/// Valide chaque snapshot stocké (checksum, format, restaurabilité)
+fn check_snapshots() -> Result<()> {
+    let checks = pieuvre_persist::snapshot::check_all()?;
+    if checks.is_empty() {
+        println!("No snapshots available");
+        return Ok(());
+    }
+
+    println!("Checking {} snapshot(s):\n", checks.len());
+    for check in &checks {
+        let short_id = check.id.get(..8).unwrap_or(&check.id);
+        let status = if check.is_ok() { "OK  " } else { "FAIL" };
+        let legacy = match check.format_version {
+            Some(v) if check.is_legacy() => {
+                format!(" [format v{}, migrated to v{}]", v, FORMAT_VERSION)
+            }
+            _ => String::new(),
+        };
+        println!(
+            "  [{}] {} {}{}",
+            status,
+            short_id,
+            check.description.as_deref().unwrap_or("-"),
+            legacy
+        );
+        for problem in &check.problems {
+            println!("         - {}", problem);
+        }
+    }
+
+    let failed = checks.iter().filter(|c| !c.is_ok()).count();
+    if failed > 0 {
+        return Err(PieuvreError::Internal(format!(
+            "{} of {} snapshots failed verification",
+            failed,
+            checks.len()
+        )));
+    }
+    println!("\nAll snapshots are valid");
+    Ok(())
+}
+
+/// Signe les snapshots non signés (antérieurs à la clé machine), après
+/// confirmation de l'administrateur
+pub fn adopt_unsigned(yes: bool) -> Result<()> {
+    let stores: Vec<_> = pieuvre_persist::store::configured_directories()
+        .into_iter()
+        .map(|store| store.unsigned().map(|snapshots| (store, snapshots)))
+        .collect::<Result<_>>()?;
+    let count: usize = stores.iter().map(|(_, snapshots)| snapshots.len()).sum();
+    if count == 0 {
+        println!("No unsigned snapshot to adopt");
+        return Ok(());
+    }
+
+    println!("Unsigned snapshots (checksum intact):\n");
+    for (store, snapshots) in &stores {
+        println!("  {}", store.dir().display());
+        for s in snapshots {
+            println!(
+                "    {} - {} ({} changes) {}",
+                &s.id.to_string()[..8],
+                s.timestamp.format("%Y-%m-%d %H:%M"),
+                s.changes.len(),
+                s.description
+            );
+        }
+    }
+    println!("\nOnly adopt snapshots this machine wrote: once signed, they can be restored.");
+
+    if !yes {
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            return Err(PieuvreError::Config(
+                "Confirmation required: run `pieuvre rollback --adopt-unsigned --yes`".into(),
+            ));
+        }
+        let confirmed = dialoguer::Confirm::new()
+            .with_prompt(format!("Sign these {} snapshot(s)?", count))
+            .default(false)
+            .interact()
+            .map_err(|e| PieuvreError::Internal(e.to_string()))?;
+        if !confirmed {
+            println!("Nothing signed");
+            return Ok(());
+        }
+    }
+
+    let mut signed = 0;
+    for (store, snapshots) in &stores {
+        let ids: Vec<String> = snapshots.iter().map(|s| s.id.to_string()).collect();
+        signed += store.adopt(&ids)?;
+    }
+    println!("{} snapshot(s) signed", signed);
+    Ok(())
+}
+
+/// Étiquette d'un snapshot de transaction
+fn outcome_tag(outcome: TransactionOutcome) -> &'static str {
+    match outcome {
+        TransactionOutcome::Success => " [success]",
+        TransactionOutcome::RolledBack => " [rolled-back]",
+        TransactionOutcome::Partial => " [partial]",
+    }
+}
@@ -2,8 +2,9 @@
 //!
 //! Real-time dashboard displaying the state of the optimization sections.
 
+use super::audit;
 use console::style;
-use pieuvre_common::Result;
+use pieuvre_common::{DimensionScore, Result};
 use pieuvre_sync::timer;
 
 pub fn run(live: bool) -> Result<()> {
@@ -84,7 +85,7 @@ fn render_dashboard() -> Result<()> {
     println!("    GPU MSI Mode:      {}", msi_status);
 
     match pieuvre_audit::registry::read_dword_value(
-        windows::Win32::System::Registry::HKEY_LOCAL_MACHINE,
+        pieuvre_common::RegistryHive::Hklm,
         r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Multimedia\SystemProfile",
         "SystemResponsiveness",
     ) {
@@ -237,6 +238,30 @@ fn render_dashboard() -> Result<()> {
         }
         Err(_) => println!("    Snapshots:         0"),
     }
+    match pieuvre_persist::reboot::pending() {
+        Ok(queue) if !queue.is_empty() => println!(
+            "    Pending Reboot:    {}",
+            style(format!("{} change(s)", queue.change_count())).yellow()
+        ),
+        Ok(_) => println!("    Pending Reboot:    {}", style("None").green()),
+        Err(_) => println!("    Pending Reboot:    {}", style("Unknown").dim()),
+    }
+    println!();
+
+    // 7. AUDIT SCORES (dernier rapport de general.report_dir)
+    println!("  {}", style("AUDIT SCORES").bold().underlined());
+    match audit::latest_report().and_then(|path| audit::report_scores(&path)) {
+        Some((report, scores)) => {
+            println!(
+                "    Last Audit:        {}",
+                style(report.timestamp.format("%d/%m %H:%M")).dim()
+            );
+            print_score("Privacy:", &scores.privacy);
+            print_score("Performance:", &scores.performance);
+            print_score("Security:", &scores.security);
+        }
+        None => println!("    {}", style("No audit report (run pieuvre audit)").dim()),
+    }
 
     println!();
     println!(
@@ -253,3 +278,45 @@ fn render_dashboard() -> Result<()> {
 
     Ok(())
 }
+
+/// Score line, then the findings that lost points (largest loss first)
+fn print_score(label: &str, dimension: &DimensionScore) {
+    let color = match dimension.score {
+        Some(score) if score >= 80 => 10, // Green
+        Some(score) if score >= 50 => 11, // Yellow
+        Some(_) => 9,                     // Red
+        None => 8,
+    };
+    let value = match dimension.score {
+        Some(score) => format!("{}/100", score),
+        None => "n/a (not audited)".to_string(),
+    };
+    println!("    {:<19}{}", label, style(value).color256(color));
+
+    let mut lost: Vec<_> = dimension.findings.iter().filter(|f| f.lost() > 0).collect();
+    lost.sort_by_key(|f| std::cmp::Reverse(f.lost()));
+    for finding in lost.iter().take(3) {
+        println!(
+            "      {} {} ({})",
+            style(format!("-{:<3}", finding.lost())).red(),
+            finding.title,
+            style(&finding.detail).dim()
+        );
+    }
+}
+
+/// "N changes pending reboot", après une application
+pub fn print_pending_reboot() {
+    match pieuvre_persist::reboot::pending() {
+        Ok(queue) if !queue.is_empty() => {
+            let tweaks: Vec<&str> = queue.entries.iter().map(|e| e.tweak.as_str()).collect();
+            println!(
+                "{} change(s) pending reboot ({})",
+                queue.change_count(),
+                tweaks.join(", ")
+            );
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Pending reboot queue unreadable: {}", e),
+    }
+}
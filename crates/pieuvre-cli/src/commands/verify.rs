@@ -14,6 +14,10 @@ pub fn run(repair: bool) -> Result<()> {
     let mut issues = Vec::new();
     let mut ok_count = 0;
 
+    // Tweaks appliqués mais pas encore actifs : pas de dérive avant le redémarrage
+    let reboot_queue = pieuvre_persist::reboot::pending().unwrap_or_default();
+    let mut pending = Vec::new();
+
     // Verification Timer
     print!("[*] Timer Resolution... ");
     match pieuvre_sync::timer::get_timer_resolution() {
@@ -82,6 +86,9 @@ pub fn run(repair: bool) -> Result<()> {
     if pieuvre_sync::msi::is_msi_enabled_on_gpu() {
         println!("OK");
         ok_count += 1;
+    } else if reboot_queue.is_pending("msi") {
+        println!("PENDING (reboot required)");
+        pending.push("MSI");
     } else {
         println!("WARN (not active)");
         issues.push(("MSI", "MSI Mode not active on GPU".to_string()));
@@ -108,7 +115,7 @@ pub fn run(repair: bool) -> Result<()> {
     // Verification Registry Win32PrioritySeparation
     print!("[*] Scheduler... ");
     match pieuvre_audit::registry::read_dword_value(
-        windows::Win32::System::Registry::HKEY_LOCAL_MACHINE,
+        pieuvre_common::RegistryHive::Hklm,
         r"SYSTEM\CurrentControlSet\Control\PriorityControl",
         "Win32PrioritySeparation",
     ) {
@@ -133,7 +140,7 @@ pub fn run(repair: bool) -> Result<()> {
     // Verification MMCSS SystemResponsiveness
     print!("[*] MMCSS Gaming... ");
     match pieuvre_audit::registry::read_dword_value(
-        windows::Win32::System::Registry::HKEY_LOCAL_MACHINE,
+        pieuvre_common::RegistryHive::Hklm,
         r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Multimedia\SystemProfile",
         "SystemResponsiveness",
     ) {
@@ -158,7 +165,7 @@ pub fn run(repair: bool) -> Result<()> {
     // Verification Network Throttling
     print!("[*] Network Throttling... ");
     match pieuvre_audit::registry::read_dword_value(
-        windows::Win32::System::Registry::HKEY_LOCAL_MACHINE,
+        pieuvre_common::RegistryHive::Hklm,
         r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\Multimedia\SystemProfile",
         "NetworkThrottlingIndex",
     ) {
@@ -188,6 +195,15 @@ pub fn run(repair: bool) -> Result<()> {
     println!();
     println!("  Verifications OK: {}", ok_count);
     println!("  Issues:           {}", issues.len());
+    if !reboot_queue.is_empty() {
+        println!(
+            "  Pending reboot:   {} change(s)",
+            reboot_queue.change_count()
+        );
+        if !pending.is_empty() {
+            println!("    Not checked until reboot: {}", pending.join(", "));
+        }
+    }
 
     if !issues.is_empty() {
         println!();
@@ -4,7 +4,9 @@
 
 mod commands;
 
-use pieuvre_common::Result;
+use commands::report::ReportFormat;
+use pieuvre_common::{ConfigLoader, PieuvreConfig, PieuvreError, Result};
+use std::path::PathBuf;
 
 #[cfg(test)]
 mod tests;
@@ -26,6 +28,26 @@ struct Cli {
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
 
+    /// Configuration file (applied over the machine and user files)
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Snapshot directory (overrides general.snapshot_dir)
+    #[arg(long, global = true, value_name = "DIR")]
+    snapshot_dir: Option<String>,
+
+    /// Report directory (overrides general.report_dir)
+    #[arg(long, global = true, value_name = "DIR")]
+    report_dir: Option<String>,
+
+    /// Override a configuration key, e.g. --set snapshot.max_count=20
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    overrides: Vec<String>,
+
+    /// Compute changes without applying them (sets general.dry_run)
+    #[arg(long, global = true)]
+    dry_run: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -38,9 +60,25 @@ enum Commands {
         #[arg(long)]
         full: bool,
 
-        /// JSON output file
+        /// Output file (JSON unless --format is given)
         #[arg(short, long)]
         output: Option<String>,
+
+        /// Report format (json, html, markdown, csv, sarif)
+        #[arg(long, value_enum, default_value_t = ReportFormat::Json)]
+        format: ReportFormat,
+
+        /// Audit an offline image: folder with the SYSTEM, SOFTWARE and NTUSER.DAT hives
+        #[arg(long, value_name = "DIR")]
+        offline: Option<PathBuf>,
+
+        /// Compliance rule set to check (privacy, performance or a .toml/.json file)
+        #[arg(long, value_name = "RULESET")]
+        compliance: Option<String>,
+
+        /// Show what changed since an earlier JSON audit report
+        #[arg(long, value_name = "OLD")]
+        compare: Option<PathBuf>,
     },
 
     /// Display current status
@@ -60,9 +98,69 @@ enum Commands {
         #[arg(long)]
         last: bool,
 
+        /// Restore the baseline captured on the first run (original state)
+        #[arg(long, conflicts_with_all = ["last", "id"])]
+        baseline: bool,
+
         /// Snapshot ID to restore
         #[arg(long)]
         id: Option<String>,
+
+        /// Validate stored snapshots (checksum, format, restorability)
+        #[arg(long)]
+        check: bool,
+
+        /// Rebuild the snapshot metadata index
+        #[arg(long)]
+        reindex: bool,
+
+        /// Pin a snapshot: kept by rotation, cannot be deleted
+        #[arg(long, value_name = "ID", conflicts_with = "unpin")]
+        pin: Option<String>,
+
+        /// Label added to the pinned snapshot
+        #[arg(long, value_name = "TEXT", requires = "pin")]
+        label: Option<String>,
+
+        /// Return a pinned snapshot to rotation
+        #[arg(long, value_name = "ID")]
+        unpin: Option<String>,
+
+        /// Show a snapshot: stored and current value of each change
+        #[arg(long, value_name = "ID")]
+        show: Option<String>,
+
+        /// Compare two snapshots
+        #[arg(long, num_args = 2, value_names = ["A", "B"])]
+        diff: Option<Vec<String>>,
+
+        /// Sign the unsigned snapshots written before the signing key, after confirmation
+        #[arg(long)]
+        adopt_unsigned: bool,
+
+        /// Do not ask for confirmation (--adopt-unsigned)
+        #[arg(long, requires = "adopt_unsigned")]
+        yes: bool,
+
+        /// JSON output for --show and --diff
+        #[arg(long)]
+        json: bool,
+
+        /// Restore only change number N (as shown by --show), repeatable
+        #[arg(long = "index", value_name = "N")]
+        indices: Vec<usize>,
+
+        /// Restore only one kind of change (registry, service, firewall, appx...), repeatable
+        #[arg(long = "kind", value_name = "KIND")]
+        kinds: Vec<String>,
+
+        /// Restore only registry changes under a key prefix, repeatable
+        #[arg(long = "key", value_name = "PREFIX")]
+        keys: Vec<String>,
+
+        /// Restore only the changes made by a tweak, repeatable
+        #[arg(long = "tweak", value_name = "ID")]
+        tweaks: Vec<String>,
     },
 
     /// Verify optimization integrity
@@ -81,6 +179,43 @@ enum Commands {
         action: TweakAction,
     },
 
+    /// Show the changes tweaks or a profile would make, without applying them
+    Plan {
+        /// Tweak IDs, or a profile name (default profile if omitted)
+        targets: Vec<String>,
+
+        /// Export the plan as JSON to this file ("-" for stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Optimization profiles (gaming, privacy, workstation...)
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+
+    /// Recover a session interrupted by a crash (write-ahead journal)
+    Recover {
+        /// Save the journaled changes as a snapshot
+        #[arg(long, conflicts_with_all = ["rollback", "discard"])]
+        finish: bool,
+
+        /// Restore the original values of the journaled changes
+        #[arg(long, conflicts_with = "discard")]
+        rollback: bool,
+
+        /// Drop the journal without restoring anything
+        #[arg(long)]
+        discard: bool,
+    },
+
+    /// Render saved reports (audit, compliance results, threats)
+    Report {
+        #[command(subcommand)]
+        action: ReportAction,
+    },
+
     /// Generate shell completion scripts
     Completions {
         /// Target shell (bash, zsh, fish, powershell, elvish)
@@ -93,22 +228,107 @@ enum Commands {
 pub enum TweakAction {
     /// List all available tweaks by category
     List,
-    /// Apply a specific tweak by its ID
+    /// Apply tweaks by ID, in dependency order (conflicting selections are refused)
     Apply {
-        /// The ID of the tweak to apply (e.g., 'diagtrack', 'timer')
-        id: String,
+        /// IDs of the tweaks to apply (e.g., 'diagtrack', 'timer')
+        #[arg(required = true)]
+        ids: Vec<String>,
     },
-    /// Apply all recommended optimizations
+    /// Apply all recommended optimizations (see `profile apply` for targeted sets)
     ApplyAll,
 }
 
+#[derive(Subcommand)]
+pub enum ProfileAction {
+    /// List built-in and custom profiles
+    List,
+    /// Show the tweaks and parameters of a profile (default profile if omitted)
+    Show {
+        /// Profile name
+        name: Option<String>,
+    },
+    /// Apply a profile (default profile if omitted)
+    Apply {
+        /// Profile name
+        name: Option<String>,
+    },
+    /// Compare a profile with another one, or with the current system
+    Diff {
+        /// Profile name
+        name: String,
+        /// Profile to compare with (current system if omitted)
+        other: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ReportAction {
+    /// Render JSON reports as HTML, Markdown, CSV or SARIF
+    Render {
+        /// Audit report, compliance results and/or threat list (JSON)
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Html)]
+        format: ReportFormat,
+
+        /// Output file (next to the first input if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+impl Cli {
+    /// La commande peut modifier le système (TUI, tweaks, profil, réparation) :
+    /// les commandes de lecture ne capturent pas la baseline
+    fn applies_changes(&self) -> bool {
+        matches!(
+            &self.command,
+            None | Some(Commands::Interactive)
+                | Some(Commands::Tweak {
+                    action: TweakAction::Apply { .. } | TweakAction::ApplyAll,
+                })
+                | Some(Commands::Profile {
+                    action: ProfileAction::Apply { .. },
+                })
+                | Some(Commands::Verify { repair: true })
+        )
+    }
+}
+
+/// Configuration en couches : défauts, fichiers, `PIEUVRE_*`, puis flags CLI
+fn load_config(cli: &Cli) -> Result<PieuvreConfig> {
+    let mut loader = ConfigLoader::new();
+    if let Some(path) = &cli.config {
+        loader = loader.explicit_file(path);
+    }
+    if let Some(dir) = &cli.snapshot_dir {
+        loader = loader.set("general.snapshot_dir", dir.as_str());
+    }
+    if let Some(dir) = &cli.report_dir {
+        loader = loader.set("general.report_dir", dir.as_str());
+    }
+    if cli.dry_run {
+        loader = loader.set("general.dry_run", "true");
+    }
+    for pair in &cli.overrides {
+        let (key, value) = pair.split_once('=').ok_or_else(|| {
+            PieuvreError::Config(format!("Invalid --set '{}': expected KEY=VALUE", pair))
+        })?;
+        loader = loader.set(key.trim(), value.trim());
+    }
+    loader.load()
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    let config = load_config(&cli)?;
 
-    // Logging configuration
+    // Logging configuration (-v overrides general.log_level)
     let filter = match cli.verbose {
-        0 => "warn",
+        0 => config.general.log_level.as_str(),
         1 => "info",
         2 => "debug",
         _ => "trace",
@@ -119,6 +339,8 @@ async fn main() -> Result<()> {
         .with(EnvFilter::new(filter))
         .init();
 
+    pieuvre_common::init_config(config)?;
+
     // Check for Administrator privileges
     if !commands::interactive::tui::is_elevated() {
         eprintln!("ERREUR : Privilèges Administrateur requis.");
@@ -126,14 +348,66 @@ async fn main() -> Result<()> {
         std::process::exit(1);
     }
 
+    // Session interrompue (crash, BSOD) : terminer ou annuler avant d'aller plus loin
+    if !matches!(cli.command, Some(Commands::Recover { .. })) {
+        commands::recover::check_pending();
+    }
+    // Premier lancement : état d'origine, restauré par reset et `rollback --baseline`.
+    // Sous dry-run rien n'est modifié, l'état lu ne serait pas plus « d'origine » ensuite.
+    if cli.applies_changes() && !pieuvre_common::config().general.dry_run {
+        commands::rollback::capture_baseline();
+    }
+    // Journal write-ahead des changements, supprimé à la sortie normale
+    let _journal = pieuvre_persist::journal::install();
+
     match cli.command {
         // Launch Interactive Mode by default
         None => commands::interactive::tui::run().await,
-        Some(Commands::Audit { full, output }) => {
-            commands::audit::run(full, output, None).map(|_| ())
-        }
+        Some(Commands::Audit {
+            full,
+            output,
+            format,
+            offline,
+            compliance,
+            compare,
+        }) => commands::audit::run(full, offline, compliance, compare, format, output, None)
+            .map(|_| ()),
         Some(Commands::Status { live }) => commands::status::run(live),
-        Some(Commands::Rollback { list, last, id }) => commands::rollback::run(list, last, id),
+        Some(Commands::Rollback {
+            adopt_unsigned: true,
+            yes,
+            ..
+        }) => commands::rollback::adopt_unsigned(yes),
+        Some(Commands::Rollback {
+            list,
+            last,
+            baseline,
+            id,
+            check,
+            reindex,
+            pin,
+            label,
+            unpin,
+            show,
+            diff,
+            json,
+            indices,
+            kinds,
+            keys,
+            tweaks,
+            ..
+        }) => match (show, diff, pin, unpin) {
+            (Some(id), ..) => commands::rollback::show(&id, json),
+            (None, Some(ids), ..) => commands::rollback::diff(&ids[0], &ids[1], json),
+            (None, None, Some(id), _) => commands::rollback::pin(&id, label.as_deref()),
+            (None, None, None, Some(id)) => commands::rollback::unpin(&id),
+            (None, None, None, None) => commands::rollback::restore_filter(
+                indices, kinds, keys, tweaks,
+            )
+            .and_then(|filter| {
+                commands::rollback::run(list, last, baseline, id, check, reindex, filter)
+            }),
+        },
         Some(Commands::Verify { repair }) => commands::verify::run(repair),
 
         Some(Commands::Interactive) => commands::interactive::tui::run().await,
@@ -148,36 +422,51 @@ async fn main() -> Result<()> {
                 }
                 Ok(())
             }
-            TweakAction::Apply { id } => {
-                let registry = commands::interactive::executor::CommandRegistry::new();
-                match registry.execute(&id).await {
-                    Ok(res) => {
-                        println!("SUCCESS: {}", res.message);
-                        Ok(())
-                    }
-                    Err(e) => {
-                        eprintln!("ERROR: Failed to apply tweak '{}': {}", id, e);
-                        Err(pieuvre_common::PieuvreError::Internal(e.to_string()))
-                    }
+            TweakAction::Apply { ids } => {
+                let registry =
+                    std::sync::Arc::new(commands::interactive::executor::CommandRegistry::new());
+                let resolution = registry.resolve(&ids)?;
+                for note in resolution.notes() {
+                    println!("NOTE: {}", note);
                 }
+                commands::apply::run("Apply", registry, resolution).await
             }
             TweakAction::ApplyAll => {
                 println!("Applying all recommended optimizations...");
-                let registry = commands::interactive::executor::CommandRegistry::new();
-                for (_, items) in commands::interactive::sections::get_all_sections() {
-                    for item in items {
-                        if item.default {
-                            print!("Applying {}... ", item.id);
-                            match registry.execute(item.id).await {
-                                Ok(_) => println!("OK"),
-                                Err(e) => println!("FAILED: {}", e),
-                            }
-                        }
-                    }
+                let registry =
+                    std::sync::Arc::new(commands::interactive::executor::CommandRegistry::new());
+                let ids: Vec<String> = commands::interactive::sections::get_all_sections()
+                    .into_iter()
+                    .flat_map(|(_, items)| items)
+                    .filter(|item| item.default)
+                    .map(|item| item.id.to_string())
+                    .collect();
+                let resolution = registry.resolve(&ids)?;
+                for note in resolution.notes() {
+                    println!("NOTE: {}", note);
                 }
-                Ok(())
+                commands::apply::run("Apply all", registry, resolution).await
             }
         },
+        Some(Commands::Plan { targets, output }) => commands::plan::run(targets, output).await,
+        Some(Commands::Profile { action }) => match action {
+            ProfileAction::List => commands::profile::list(),
+            ProfileAction::Show { name } => commands::profile::show(name),
+            ProfileAction::Apply { name } => commands::profile::apply(name).await,
+            ProfileAction::Diff { name, other } => commands::profile::diff(name, other).await,
+        },
+        Some(Commands::Recover {
+            finish,
+            rollback,
+            discard,
+        }) => commands::recover::run(finish, rollback, discard),
+        Some(Commands::Report { action }) => match action {
+            ReportAction::Render {
+                inputs,
+                format,
+                output,
+            } => commands::report::run_render(inputs, format, output),
+        },
         Some(Commands::Completions { shell }) => commands::completions::run(shell),
     }
 }
@@ -36,9 +36,9 @@ fn test_interactive_module_exists() {
 #[test]
 fn test_command_registry_registration() {
     use crate::commands::interactive::executor::CommandRegistry;
-    let _registry = CommandRegistry::new();
+    let registry = CommandRegistry::new();
 
-    // Vérifier que les commandes critiques sont enregistrées
+    // Vérifier que les commandes critiques sont enregistrées (codées en dur et catalogue)
     let critical_commands = [
         "diagtrack",
         "telemetry_level",
@@ -47,13 +47,266 @@ fn test_command_registry_registration() {
         "scan_yara",
     ];
 
-    for _id in critical_commands {
-        // On ne peut pas facilement exécuter sans effets de bord,
-        // mais on peut vérifier si elles existent dans le registre (via execute qui ne panique pas sur "not found")
-        // En fait, execute renvoie une erreur si non trouvé.
+    for id in critical_commands {
+        assert!(registry.contains(id), "Command {} should be registered", id);
     }
 }
 
+#[test]
+fn test_every_section_option_has_a_command() {
+    use crate::commands::interactive::executor::CommandRegistry;
+    use crate::commands::interactive::sections::get_all_sections;
+    let registry = CommandRegistry::new();
+
+    for (_, options) in get_all_sections() {
+        for item in options {
+            assert!(
+                registry.contains(item.id),
+                "Option {} has no command",
+                item.id
+            );
+        }
+    }
+}
+
+#[test]
+fn test_reboot_metadata() {
+    use crate::commands::interactive::executor::{CommandRegistry, SyncOperationCommand};
+    use pieuvre_sync::catalog::{CatalogOperation, TweakCatalog};
+
+    let mut registry = CommandRegistry::new();
+    for id in [
+        "net_ipv6",
+        "vbs",
+        "hvci",
+        "msi",
+        "dpc_latency",
+        "memory_compression",
+    ] {
+        assert!(
+            registry.requires_reboot(id),
+            "{} should require a reboot",
+            id
+        );
+    }
+    assert!(!registry.requires_reboot("diagtrack"));
+    assert!(!registry.requires_reboot("unknown"));
+
+    let catalog = TweakCatalog::from_toml(
+        r#"
+[[tweak]]
+id = "site_reboot"
+label = "Reboot"
+category = "site"
+risk = "Low"
+reboot = true
+actions = [{ type = "registry_set", key = 'HKLM\SOFTWARE\Pieuvre', value = "Flag", data = 1 }]
+"#,
+    )
+    .unwrap();
+    let tweak = catalog.get("site_reboot").unwrap().clone();
+    registry.register(
+        "site_reboot",
+        SyncOperationCommand::new(CatalogOperation::new(tweak)),
+    );
+    assert!(registry.requires_reboot("site_reboot"));
+}
+
+#[test]
+fn test_undoable_metadata() {
+    use crate::commands::interactive::executor::CommandRegistry;
+
+    let registry = CommandRegistry::new();
+    // Effet sans ChangeRecord : signalé plutôt qu'un succès restaurable
+    assert!(!registry.undoable("timer"));
+    assert!(!registry.undoable("memory_compression"));
+    assert!(registry.undoable("maint_hibernation"));
+    assert!(registry.undoable("diagtrack"));
+}
+
+#[tokio::test]
+async fn test_registry_apply_keeps_partial_changes() {
+    use crate::commands::interactive::executor::{CommandRegistry, SyncOperationCommand};
+    use pieuvre_common::{MemoryRegistry, MemoryServiceManager, PieuvreError, RegistryPath};
+    use pieuvre_sync::catalog::{CatalogOperation, TweakCatalog};
+    use std::sync::Arc;
+
+    // Service absent : l'échec survient après l'écriture de registre
+    let catalog = TweakCatalog::from_toml(
+        r#"
+[[tweak]]
+id = "test_partial"
+label = "Partiel"
+category = "site"
+risk = "Low"
+actions = [
+    { type = "registry_set", key = 'HKLM\SOFTWARE\Pieuvre', value = "Flag", data = 1 },
+    { type = "service", name = "Missing", start_type = "Disabled" },
+]
+"#,
+    )
+    .unwrap();
+    let reg = Arc::new(MemoryRegistry::new());
+    let mut registry = CommandRegistry::new();
+    registry.register(
+        "test_partial",
+        SyncOperationCommand::new(
+            CatalogOperation::new(catalog.get("test_partial").unwrap().clone())
+                .with_backend(reg.clone())
+                .with_services(Arc::new(MemoryServiceManager::new())),
+        ),
+    );
+
+    let (error, changes) = registry
+        .apply("test_partial")
+        .await
+        .unwrap_err()
+        .split_changes();
+    assert!(matches!(error, PieuvreError::ServiceNotFound(_)));
+    assert_eq!(changes.len(), 1);
+    assert_eq!(
+        reg.read_dword(&RegistryPath::hklm(r"SOFTWARE\Pieuvre"), "Flag")
+            .unwrap(),
+        Some(1)
+    );
+}
+
+// ============================================================================
+// TESTS CATALOGUE DE TWEAKS
+// ============================================================================
+
+#[test]
+fn test_builtin_catalog_is_valid() {
+    use crate::commands::interactive::catalog::BUILTIN_CATALOG;
+    use crate::commands::interactive::sections::CATALOG_CATEGORIES;
+    use pieuvre_sync::catalog::TweakCatalog;
+
+    let catalog = TweakCatalog::from_toml(BUILTIN_CATALOG).expect("built-in catalog");
+    assert!(!catalog.tweaks().is_empty());
+    for tweak in catalog.tweaks() {
+        assert!(
+            CATALOG_CATEGORIES.contains(&tweak.category.as_str()),
+            "Unknown category {} for {}",
+            tweak.category,
+            tweak.id
+        );
+    }
+}
+
+#[test]
+fn test_load_packs_skips_invalid_files() {
+    use crate::commands::interactive::catalog::{load_packs, BUILTIN_CATALOG};
+    use pieuvre_sync::catalog::TweakCatalog;
+
+    let dir = std::env::temp_dir().join(format!("pieuvre-packs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("10-site.toml"),
+        r#"
+[[tweak]]
+id = "site_wsearch"
+label = "Site: WSearch manuel"
+category = "site"
+risk = "Low"
+actions = [{ type = "service", name = "WSearch", start_type = "Manual" }]
+"#,
+    )
+    .unwrap();
+    std::fs::write(dir.join("20-broken.toml"), "[[tweak]]\nid = \"Bad Id\"\n").unwrap();
+    std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+    let mut catalog = TweakCatalog::from_toml(BUILTIN_CATALOG).unwrap();
+    let builtin_len = catalog.tweaks().len();
+    assert_eq!(load_packs(&mut catalog, &dir), 1);
+    assert_eq!(catalog.tweaks().len(), builtin_len + 1);
+    assert!(catalog.contains("site_wsearch"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_builtin_relations_resolve() {
+    use crate::commands::interactive::catalog::BUILTIN_CATALOG;
+    use crate::commands::interactive::executor::CommandRegistry;
+    use crate::commands::interactive::sections::get_all_sections;
+    use pieuvre_sync::catalog::TweakCatalog;
+
+    let registry = CommandRegistry::new();
+    let catalog = TweakCatalog::from_toml(BUILTIN_CATALOG).unwrap();
+    for relation in catalog.relations() {
+        let ids = std::iter::once(&relation.tweak)
+            .chain(&relation.requires)
+            .chain(&relation.conflicts)
+            .chain(&relation.implies);
+        for id in ids {
+            assert!(registry.contains(id), "Relation on unknown tweak {}", id);
+        }
+    }
+
+    // La sélection par défaut est cohérente ; les commandes partagées ne s'exécutent qu'une fois
+    let defaults: Vec<String> = get_all_sections()
+        .into_iter()
+        .flat_map(|(_, items)| items)
+        .filter(|item| item.default)
+        .map(|item| item.id.to_string())
+        .collect();
+    let resolution = registry.resolve(&defaults).unwrap();
+    assert!(resolution.order.len() < defaults.len());
+    let ids = ["oo_telemetry".to_string(), "oo_widgets".to_string()];
+    assert_eq!(registry.resolve(&ids).unwrap().order, ["oo_telemetry"]);
+
+    let ids = ["hardening_lock".to_string()];
+    let resolution = registry.resolve(&ids).unwrap();
+    assert_eq!(resolution.order, ["telemetry_level", "hardening_lock"]);
+    assert_eq!(
+        resolution.notes(),
+        ["telemetry_level added (required by hardening_lock)"]
+    );
+
+    let ids = ["hardening_lock".to_string(), "hardening_unlock".to_string()];
+    let err = registry.resolve(&ids).unwrap_err().to_string();
+    assert!(err.contains("hardening_lock / hardening_unlock"), "{}", err);
+}
+
+// ============================================================================
+// TESTS PROFILS
+// ============================================================================
+
+#[test]
+fn test_builtin_profiles_resolve_to_known_tweaks() {
+    use crate::commands::profile::{load_profile_dir, registry_for, steps, BUILTIN_PROFILES};
+    use pieuvre_common::PieuvreConfig;
+    use pieuvre_sync::profile::ProfileSet;
+
+    let mut set = ProfileSet::default();
+    for content in BUILTIN_PROFILES {
+        set.merge(ProfileSet::from_toml(content).unwrap());
+    }
+    assert_eq!(
+        load_profile_dir(&mut set, std::path::Path::new("missing")),
+        0
+    );
+
+    // Les profils annoncés par la configuration par défaut existent
+    for name in PieuvreConfig::default().profile.available {
+        let profile = set.resolve(&name).unwrap();
+        assert_eq!(profile.chain.first().map(String::as_str), Some("base"));
+
+        let registry = registry_for(&profile);
+        for step in steps(&profile) {
+            assert!(registry.contains(&step), "{}: unknown step {}", name, step);
+        }
+        assert!(
+            registry.resolve(&steps(&profile)).is_ok(),
+            "{}: conflicting tweaks",
+            name
+        );
+    }
+
+    let gaming = set.resolve("gaming").unwrap();
+    assert!(steps(&gaming).ends_with(&["timer".to_string(), "power_plan".to_string()]));
+}
+
 // test_interactive_opt_item_safe removed as OptItem is now internal to sections.rs
 
 // ============================================================================
@@ -76,6 +329,304 @@ fn test_verbose_levels() {
     }
 }
 
+// ============================================================================
+// TESTS CONFIGURATION FLAGS
+// ============================================================================
+
+#[test]
+fn test_config_flags_override_layers() {
+    use clap::Parser;
+
+    let cli = crate::Cli::try_parse_from([
+        "pieuvre",
+        "--snapshot-dir",
+        r"D:\Pieuvre\Snapshots",
+        "status",
+        "--set",
+        "snapshot.max_count=3",
+    ])
+    .unwrap();
+    let config = crate::load_config(&cli).unwrap();
+    assert_eq!(
+        config.general.snapshot_dir,
+        std::path::PathBuf::from(r"D:\Pieuvre\Snapshots")
+    );
+    assert_eq!(config.snapshot.max_count, 3);
+
+    let cli = crate::Cli::try_parse_from(["pieuvre", "--set", "snapshot.max_count"]).unwrap();
+    assert!(crate::load_config(&cli).is_err());
+}
+
+#[test]
+fn test_plan_command_and_dry_run_flag() {
+    use clap::Parser;
+
+    let cli =
+        crate::Cli::try_parse_from(["pieuvre", "plan", "diagtrack", "hosts", "-o", "plan.json"])
+            .unwrap();
+    match &cli.command {
+        Some(crate::Commands::Plan { targets, output }) => {
+            assert_eq!(targets, &["diagtrack", "hosts"]);
+            assert_eq!(output.as_deref(), Some("plan.json"));
+        }
+        _ => panic!("expected plan command"),
+    }
+    assert!(!crate::load_config(&cli).unwrap().general.dry_run);
+
+    let cli =
+        crate::Cli::try_parse_from(["pieuvre", "tweak", "apply", "timer", "--dry-run"]).unwrap();
+    assert!(crate::load_config(&cli).unwrap().general.dry_run);
+}
+
+#[test]
+fn test_baseline_captured_only_before_changes() {
+    let applies = |args: &[&str]| crate::Cli::try_parse_from(args).unwrap().applies_changes();
+    assert!(applies(&["pieuvre"]));
+    assert!(applies(&["pieuvre", "tweak", "apply", "timer"]));
+    assert!(applies(&["pieuvre", "profile", "apply", "gaming"]));
+    assert!(applies(&["pieuvre", "verify", "--repair"]));
+
+    assert!(!applies(&["pieuvre", "audit"]));
+    assert!(!applies(&["pieuvre", "verify"]));
+    assert!(!applies(&["pieuvre", "plan", "timer"]));
+    assert!(!applies(&["pieuvre", "rollback", "--list"]));
+    assert!(!applies(&["pieuvre", "rollback", "--adopt-unsigned"]));
+}
+
+#[test]
+fn test_rollback_inspection_flags() {
+    use clap::Parser;
+
+    let cli = crate::Cli::try_parse_from(["pieuvre", "rollback", "--diff", "7be4b13b", "1a2b3c4d"])
+        .unwrap();
+    match &cli.command {
+        Some(crate::Commands::Rollback { diff, json, .. }) => {
+            assert_eq!(
+                diff.as_deref(),
+                Some(&["7be4b13b".to_string(), "1a2b3c4d".to_string()][..])
+            );
+            assert!(!json);
+        }
+        _ => panic!("expected rollback command"),
+    }
+
+    let cli = crate::Cli::try_parse_from(["pieuvre", "rollback", "--show", "7be4b13b", "--json"])
+        .unwrap();
+    assert!(matches!(
+        &cli.command,
+        Some(crate::Commands::Rollback { show: Some(id), json: true, .. }) if id == "7be4b13b"
+    ));
+    assert!(crate::Cli::try_parse_from(["pieuvre", "rollback", "--diff", "7be4b13b"]).is_err());
+}
+
+#[test]
+fn test_rollback_selective_restore_flags() {
+    use clap::Parser;
+
+    let cli = crate::Cli::try_parse_from([
+        "pieuvre",
+        "rollback",
+        "--last",
+        "--index",
+        "3",
+        "--index",
+        "1",
+        "--kind",
+        "service",
+        "--key",
+        r"HKLM\SOFTWARE\Policies",
+        "--tweak",
+        "telemetry",
+    ])
+    .unwrap();
+    let Some(crate::Commands::Rollback {
+        last,
+        indices,
+        kinds,
+        keys,
+        tweaks,
+        ..
+    }) = cli.command
+    else {
+        panic!("expected rollback command");
+    };
+    assert!(last);
+    let filter = crate::commands::rollback::restore_filter(indices, kinds, keys, tweaks).unwrap();
+    assert_eq!(filter.indices, [3, 1]);
+    assert_eq!(
+        filter.kinds,
+        [pieuvre_persist::restore::ChangeKind::Service]
+    );
+    assert_eq!(filter.key_prefixes, [r"HKLM\SOFTWARE\Policies"]);
+    assert_eq!(filter.tweaks, ["telemetry"]);
+
+    assert!(
+        crate::commands::rollback::restore_filter(vec![], vec!["disk".into()], vec![], vec![])
+            .is_err()
+    );
+}
+
+#[test]
+fn test_rollback_baseline_flag() {
+    use clap::Parser;
+
+    let cli =
+        crate::Cli::try_parse_from(["pieuvre", "rollback", "--baseline", "--kind", "service"])
+            .unwrap();
+    assert!(matches!(
+        cli.command,
+        Some(crate::Commands::Rollback {
+            baseline: true,
+            last: false,
+            ..
+        })
+    ));
+    assert!(crate::Cli::try_parse_from(["pieuvre", "rollback", "--baseline", "--last"]).is_err());
+}
+
+#[test]
+fn test_audit_flags() {
+    use clap::Parser;
+
+    let cli = crate::Cli::try_parse_from(["pieuvre", "audit", "--offline", r"D:\image"]).unwrap();
+    match cli.command {
+        Some(crate::Commands::Audit { offline, full, .. }) => {
+            assert_eq!(offline, Some(std::path::PathBuf::from(r"D:\image")));
+            assert!(!full);
+        }
+        _ => panic!("expected audit command"),
+    }
+    assert!(crate::Cli::try_parse_from(["pieuvre", "audit", "--offline"]).is_err());
+
+    let cli = crate::Cli::try_parse_from(["pieuvre", "audit", "--compliance", "privacy"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Some(crate::Commands::Audit { compliance: Some(ref name), offline: None, .. }) if name == "privacy"
+    ));
+
+    let cli = crate::Cli::try_parse_from(["pieuvre", "audit", "--format", "md"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Some(crate::Commands::Audit {
+            format: crate::ReportFormat::Markdown,
+            ..
+        })
+    ));
+    assert!(crate::Cli::try_parse_from(["pieuvre", "audit", "--format", "pdf"]).is_err());
+
+    let cli =
+        crate::Cli::try_parse_from(["pieuvre", "audit", "--compare", "audit_old.json"]).unwrap();
+    let Some(crate::Commands::Audit { compare, .. }) = cli.command else {
+        panic!("expected audit command");
+    };
+    assert_eq!(compare, Some(std::path::PathBuf::from("audit_old.json")));
+}
+
+#[test]
+fn test_report_render_flags() {
+    use clap::Parser;
+
+    let cli = crate::Cli::try_parse_from([
+        "pieuvre",
+        "report",
+        "render",
+        "audit.json",
+        "threats.json",
+        "--format",
+        "sarif",
+    ])
+    .unwrap();
+    let Some(crate::Commands::Report {
+        action:
+            crate::ReportAction::Render {
+                inputs,
+                format,
+                output,
+            },
+    }) = cli.command
+    else {
+        panic!("expected report render command");
+    };
+    assert_eq!(inputs.len(), 2);
+    assert_eq!(format, crate::ReportFormat::Sarif);
+    assert!(output.is_none());
+    assert!(crate::Cli::try_parse_from(["pieuvre", "report", "render"]).is_err());
+}
+
+#[test]
+fn test_rollback_pin_flags() {
+    use clap::Parser;
+
+    let cli = crate::Cli::try_parse_from([
+        "pieuvre",
+        "rollback",
+        "--pin",
+        "1a2b3c4d",
+        "--label",
+        "before upgrade",
+    ])
+    .unwrap();
+    let Some(crate::Commands::Rollback {
+        pin, label, unpin, ..
+    }) = cli.command
+    else {
+        panic!("expected rollback command");
+    };
+    assert_eq!(pin.as_deref(), Some("1a2b3c4d"));
+    assert_eq!(label.as_deref(), Some("before upgrade"));
+    assert!(unpin.is_none());
+
+    // Un label sans --pin, ou --pin avec --unpin, est refusé
+    assert!(crate::Cli::try_parse_from(["pieuvre", "rollback", "--label", "x"]).is_err());
+    assert!(
+        crate::Cli::try_parse_from(["pieuvre", "rollback", "--pin", "a", "--unpin", "b"]).is_err()
+    );
+}
+
+#[test]
+fn test_recover_flags() {
+    use clap::Parser;
+
+    let cli = crate::Cli::try_parse_from(["pieuvre", "recover", "--rollback"]).unwrap();
+    assert!(matches!(
+        cli.command,
+        Some(crate::Commands::Recover {
+            finish: false,
+            rollback: true,
+            discard: false,
+        })
+    ));
+    assert!(crate::Cli::try_parse_from(["pieuvre", "recover", "--finish", "--discard"]).is_err());
+}
+
+#[test]
+fn test_plan_describe_changes() {
+    use crate::commands::plan::describe;
+    use pieuvre_common::{PlannedChange, RegistryHive, RegistryValue};
+
+    let change = PlannedChange::Registry {
+        hive: RegistryHive::Hklm,
+        key: r"SOFTWARE\Pieuvre".to_string(),
+        value_name: "Level".to_string(),
+        current: None,
+        target: Some(RegistryValue::Dword(1)),
+    };
+    assert_eq!(
+        describe(&change),
+        r"HKLM\SOFTWARE\Pieuvre\Level: (absent) -> 1 (0x00000001)"
+    );
+
+    let change = PlannedChange::Service {
+        name: "DiagTrack".to_string(),
+        current_start_type: Some(2),
+        target_start_type: 4,
+    };
+    assert_eq!(
+        describe(&change),
+        "service DiagTrack: Automatic -> Disabled"
+    );
+}
+
 // ============================================================================
 // TESTS OUTPUT FORMAT
 // ============================================================================
@@ -92,6 +643,157 @@ fn test_output_none_valid() {
     assert!(output.is_none());
 }
 
+fn sample_report_data() -> crate::commands::report::ReportData {
+    use pieuvre_audit::compliance::ComplianceSeverity;
+    use pieuvre_audit::{ComplianceCheck, ComplianceReport, ComplianceStatus};
+    use pieuvre_scan::engine::{Threat, ThreatSeverity};
+
+    let check = |id: &str, status, severity| ComplianceCheck {
+        id: id.to_string(),
+        name: format!("Rule {}, \"quoted\"", id),
+        status,
+        expected: "0".to_string(),
+        actual: "1".to_string(),
+        severity,
+        rationale: "<b>why</b>".to_string(),
+        references: vec!["https://learn.microsoft.com/windows".to_string()],
+        location: r"HKLM\SOFTWARE\Policies\Test\Value".to_string(),
+    };
+    crate::commands::report::ReportData {
+        audit: None,
+        compliance: Some(ComplianceReport {
+            ruleset: "privacy".to_string(),
+            checks: vec![
+                check("T-1", ComplianceStatus::Compliant, ComplianceSeverity::High),
+                check(
+                    "T-2",
+                    ComplianceStatus::NonCompliant,
+                    ComplianceSeverity::High,
+                ),
+                check(
+                    "T-3",
+                    ComplianceStatus::Error("access denied".to_string()),
+                    ComplianceSeverity::Low,
+                ),
+                check(
+                    "T-4",
+                    ComplianceStatus::NotApplicable("no task scheduler".to_string()),
+                    ComplianceSeverity::Low,
+                ),
+            ],
+        }),
+        threats: vec![Threat {
+            name: "Suspicious LNK".to_string(),
+            description: "Shortcut runs a script\nfrom a temp folder".to_string(),
+            severity: ThreatSeverity::Medium,
+            source: "LNK".to_string(),
+            location: r"C:\Users\Public\My Files\run.lnk".to_string(),
+        }],
+    }
+}
+
+#[test]
+fn test_report_csv_and_markdown_escaping() {
+    use crate::commands::report::{render, ReportFormat};
+
+    let data = sample_report_data();
+    let csv = render(&data, ReportFormat::Csv).unwrap();
+    let lines: Vec<&str> = csv.split_terminator("\r\n").collect();
+    assert_eq!(
+        lines[0],
+        "section,id,name,status,severity,expected,actual,details"
+    );
+    assert!(lines[2].starts_with(r#"compliance,T-2,"Rule T-2, ""quoted""",FAIL,High,0,1,"#));
+    assert!(csv.contains("\"Shortcut runs a script\nfrom a temp folder\""));
+
+    let markdown = render(&data, ReportFormat::Markdown).unwrap();
+    assert!(markdown.contains("## Compliance: privacy"));
+    assert!(markdown.contains("**1 passed, 1 failed, 1 error(s), 1 skipped**"));
+    assert!(markdown.contains("| Shortcut runs a script from a temp folder |"));
+}
+
+#[test]
+fn test_report_html_is_escaped() {
+    use crate::commands::report::{render, ReportFormat};
+
+    let html = render(&sample_report_data(), ReportFormat::Html).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("&lt;b&gt;why&lt;/b&gt;"));
+    assert!(!html.contains("<b>why</b>"));
+    assert!(html.contains("Rule T-2, &quot;quoted&quot;"));
+    assert!(html.contains("<span class=\"fail\">FAIL</span>"));
+    assert!(html.contains("<span class=\"skip\">SKIP</span>"));
+}
+
+#[test]
+fn test_report_sarif_results() {
+    use crate::commands::report::{render, ReportFormat};
+
+    let sarif = render(&sample_report_data(), ReportFormat::Sarif).unwrap();
+    let sarif: serde_json::Value = serde_json::from_str(&sarif).unwrap();
+    assert_eq!(sarif["version"], "2.1.0");
+
+    let run = &sarif["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "pieuvre");
+    assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 5);
+    assert_eq!(
+        run["tool"]["driver"]["rules"][1]["helpUri"],
+        "https://learn.microsoft.com/windows"
+    );
+
+    let results = run["results"].as_array().unwrap();
+    let kinds: Vec<(&str, &str)> = results
+        .iter()
+        .map(|r| (r["kind"].as_str().unwrap(), r["level"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            ("pass", "none"),
+            ("fail", "error"),
+            ("review", "none"),
+            ("notApplicable", "none"),
+            ("fail", "warning")
+        ]
+    );
+    assert_eq!(
+        results[1]["locations"][0]["logicalLocations"][0]["fullyQualifiedName"],
+        r"HKLM\SOFTWARE\Policies\Test\Value"
+    );
+    assert_eq!(results[4]["ruleId"], "threat/suspicious-lnk");
+    assert_eq!(
+        results[4]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+        "file:///C:/Users/Public/My%20Files/run.lnk"
+    );
+}
+
+#[test]
+fn test_report_load_detects_inputs() {
+    use crate::commands::report::ReportData;
+
+    let dir = std::env::temp_dir().join(format!("pieuvre_report_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let data = sample_report_data();
+    let compliance = dir.join("compliance.json");
+    let threats = dir.join("threats.json");
+    let other = dir.join("other.json");
+    std::fs::write(
+        &compliance,
+        serde_json::to_string(&data.compliance).unwrap(),
+    )
+    .unwrap();
+    std::fs::write(&threats, serde_json::to_string(&data.threats).unwrap()).unwrap();
+    std::fs::write(&other, r#"{"name": "not a report"}"#).unwrap();
+
+    let loaded = ReportData::load(&[threats, compliance]).unwrap();
+    assert!(loaded.audit.is_none());
+    assert_eq!(loaded.compliance.unwrap().checks.len(), 4);
+    assert_eq!(loaded.threats.len(), 1);
+    assert!(ReportData::load(&[other]).is_err());
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
 // ============================================================================
 // TESTS EDGE CASES
 // ============================================================================
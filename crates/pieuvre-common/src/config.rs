@@ -1,27 +1,606 @@
 //! Configuration pieuvre
 //!
-//! Gestion des fichiers de configuration TOML et profils.
+//! Chargement en couches, chaque couche surchargeant la précédente :
+//! valeurs intégrées (voir `config/default.toml`), fichier machine, fichier
+//! utilisateur, variables `PIEUVRE_*`, puis options CLI.
 
+use crate::error::{PieuvreError, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Préfixe des variables d'environnement (`PIEUVRE_SNAPSHOT_MAX_COUNT`)
+pub const ENV_PREFIX: &str = "PIEUVRE_";
+
+const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+const MAX_SNAPSHOT_COUNT: usize = 1000;
+
+static CONFIG: OnceLock<PieuvreConfig> = OnceLock::new();
 
 /// Configuration globale pieuvre
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct PieuvreConfig {
+    pub general: GeneralConfig,
+    pub profile: ProfileConfig,
+    pub audit: AuditConfig,
+    pub snapshot: SnapshotConfig,
+    pub scan: ScanConfig,
+    pub tweaks: TweaksConfig,
+    pub transaction: TransactionConfig,
+    pub scoring: ScoringConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeneralConfig {
+    /// Niveau de log (trace, debug, info, warn, error)
+    pub log_level: String,
     /// Chemin vers le dossier de snapshots
     pub snapshot_dir: PathBuf,
-    /// Niveau de log
-    pub log_level: String,
+    /// Dossier des rapports d'audit
+    pub report_dir: PathBuf,
     /// Mode dry-run par défaut
     pub dry_run: bool,
 }
 
-impl Default for PieuvreConfig {
+impl Default for GeneralConfig {
     fn default() -> Self {
         Self {
+            log_level: "warn".into(),
             snapshot_dir: PathBuf::from(r"C:\ProgramData\pieuvre\snapshots"),
-            log_level: "info".into(),
+            report_dir: PathBuf::from(r"C:\ProgramData\pieuvre\reports"),
             dry_run: false,
         }
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileConfig {
+    /// Profil par défaut
+    pub default_profile: String,
+    /// Profils disponibles
+    pub available: Vec<String>,
+    /// Dossier des profils personnalisés (*.toml)
+    pub profiles_dir: PathBuf,
+}
+
+impl Default for ProfileConfig {
+    fn default() -> Self {
+        Self {
+            default_profile: "workstation".into(),
+            available: vec!["gaming".into(), "privacy".into(), "workstation".into()],
+            profiles_dir: PathBuf::from(r"C:\ProgramData\pieuvre\profiles"),
+        }
+    }
+}
+
+/// Modules inclus dans l'audit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    pub services: bool,
+    pub hardware: bool,
+    pub appx: bool,
+    pub network: bool,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            services: true,
+            hardware: true,
+            appx: true,
+            network: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnapshotConfig {
+    /// Créer un snapshot automatiquement lors des modifications
+    pub auto_create: bool,
+    /// Nombre max de snapshots à conserver
+    pub max_count: usize,
+    /// Âge max d'un snapshot en jours (0 : illimité)
+    pub max_age_days: u32,
+    /// Taille totale max des snapshots stockés en Mo (0 : illimitée)
+    pub max_total_size_mb: u64,
+    /// Garder au moins un snapshot par jour sur les N derniers jours
+    pub keep_daily: usize,
+    /// Garder au moins un snapshot par semaine sur les N dernières semaines
+    pub keep_weekly: usize,
+    /// Compression zstd des snapshots
+    pub compress: bool,
+    /// Copie miroir des snapshots (partage réseau), dans un sous-dossier par machine.
+    /// Vide : désactivée
+    pub mirror_dir: PathBuf,
+    /// Fichier domaines télémétrie externe (relatif au dossier de config)
+    pub telemetry_domains_file: PathBuf,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            auto_create: true,
+            max_count: 10,
+            max_age_days: 0,
+            max_total_size_mb: 0,
+            keep_daily: 0,
+            keep_weekly: 0,
+            compress: true,
+            mirror_dir: PathBuf::new(),
+            telemetry_domains_file: PathBuf::from("telemetry-domains.txt"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanConfig {
+    /// Dossier de quarantaine des fichiers remédiés
+    pub quarantine_dir: PathBuf,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            quarantine_dir: PathBuf::from(r"C:\Pieuvre\Quarantine"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TweaksConfig {
+    /// Dossier des packs de tweaks site (*.toml, *.json)
+    pub packs_dir: PathBuf,
+}
+
+impl Default for TweaksConfig {
+    fn default() -> Self {
+        Self {
+            packs_dir: PathBuf::from(r"C:\ProgramData\pieuvre\tweaks"),
+        }
+    }
+}
+
+/// Politique d'échec des applications transactionnelles
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransactionConfig {
+    /// Échecs qui déclenchent le rollback
+    pub rollback_on: RollbackPolicy,
+    /// Étapes critiques (ids de tweaks ou noms d'opérations), pour `rollback_on = "critical"`
+    pub critical: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RollbackPolicy {
+    /// Rollback au premier échec
+    #[default]
+    AnyFailure,
+    /// Rollback seulement si une étape critique échoue ; les autres échecs sont ignorés
+    Critical,
+}
+
+/// Poids des constats dans les scores d'audit (0 : constat ignoré)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoringConfig {
+    // Confidentialité
+    pub diagtrack: u32,
+    pub data_collection: u32,
+    pub advertising_id: u32,
+    pub location: u32,
+    pub activity_history: u32,
+    pub cortana: u32,
+    pub web_search: u32,
+    pub error_reporting: u32,
+    /// Services de catégorie télémétrie désactivés
+    pub telemetry_services: u32,
+    // Performance
+    pub bloatware: u32,
+    pub dpc_latency: u32,
+    pub isr_latency: u32,
+    // Sécurité
+    pub defender: u32,
+    pub tamper_protection: u32,
+    pub firewall: u32,
+    pub uac: u32,
+    pub secure_boot: u32,
+    pub credential_guard: u32,
+    pub bitlocker: u32,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            diagtrack: 20,
+            data_collection: 15,
+            advertising_id: 10,
+            location: 5,
+            activity_history: 10,
+            cortana: 5,
+            web_search: 5,
+            error_reporting: 5,
+            telemetry_services: 15,
+            bloatware: 30,
+            dpc_latency: 20,
+            isr_latency: 10,
+            defender: 25,
+            tamper_protection: 15,
+            firewall: 20,
+            uac: 10,
+            secure_boot: 10,
+            credential_guard: 10,
+            bitlocker: 10,
+        }
+    }
+}
+
+impl ScoringConfig {
+    /// Somme des poids par dimension : confidentialité, performance, sécurité
+    pub fn totals(&self) -> [(&'static str, u32); 3] {
+        [
+            (
+                "privacy",
+                self.diagtrack
+                    + self.data_collection
+                    + self.advertising_id
+                    + self.location
+                    + self.activity_history
+                    + self.cortana
+                    + self.web_search
+                    + self.error_reporting
+                    + self.telemetry_services,
+            ),
+            (
+                "performance",
+                self.bloatware + self.dpc_latency + self.isr_latency,
+            ),
+            (
+                "security",
+                self.defender
+                    + self.tamper_protection
+                    + self.firewall
+                    + self.uac
+                    + self.secure_boot
+                    + self.credential_guard
+                    + self.bitlocker,
+            ),
+        ]
+    }
+}
+
+impl TransactionConfig {
+    /// Un échec de cette étape doit-il annuler la transaction ?
+    pub fn is_fatal(&self, step: &str) -> bool {
+        match self.rollback_on {
+            RollbackPolicy::AnyFailure => true,
+            RollbackPolicy::Critical => self.critical.iter().any(|c| c == step),
+        }
+    }
+}
+
+impl PieuvreConfig {
+    /// Parses a TOML document; missing keys keep their built-in default
+    pub fn from_toml(content: &str) -> Result<Self> {
+        toml::from_str(content).map_err(|e| PieuvreError::Config(e.to_string()))
+    }
+
+    /// Checks value ranges and cross-field constraints, reporting every problem at once
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+
+        if !LOG_LEVELS.contains(&self.general.log_level.to_lowercase().as_str()) {
+            errors.push(format!(
+                "general.log_level: `{}` is not one of {}",
+                self.general.log_level,
+                LOG_LEVELS.join(", ")
+            ));
+        }
+        for (key, path) in [
+            ("general.snapshot_dir", &self.general.snapshot_dir),
+            ("general.report_dir", &self.general.report_dir),
+            ("scan.quarantine_dir", &self.scan.quarantine_dir),
+            ("tweaks.packs_dir", &self.tweaks.packs_dir),
+            ("profile.profiles_dir", &self.profile.profiles_dir),
+            (
+                "snapshot.telemetry_domains_file",
+                &self.snapshot.telemetry_domains_file,
+            ),
+        ] {
+            if path.as_os_str().is_empty() {
+                errors.push(format!("{}: path must not be empty", key));
+            }
+        }
+        if !(1..=MAX_SNAPSHOT_COUNT).contains(&self.snapshot.max_count) {
+            errors.push(format!(
+                "snapshot.max_count: {} is out of range (1..={})",
+                self.snapshot.max_count, MAX_SNAPSHOT_COUNT
+            ));
+        }
+        for (key, keep) in [
+            ("snapshot.keep_daily", self.snapshot.keep_daily),
+            ("snapshot.keep_weekly", self.snapshot.keep_weekly),
+        ] {
+            if keep > MAX_SNAPSHOT_COUNT {
+                errors.push(format!(
+                    "{}: {} is out of range (0..={})",
+                    key, keep, MAX_SNAPSHOT_COUNT
+                ));
+            }
+        }
+        for (dimension, total) in self.scoring.totals() {
+            if total == 0 {
+                errors.push(format!("scoring: every {} weight is 0", dimension));
+            }
+        }
+        if !self
+            .profile
+            .available
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(&self.profile.default_profile))
+        {
+            errors.push(format!(
+                "profile.default_profile: `{}` is not listed in profile.available [{}]",
+                self.profile.default_profile,
+                self.profile.available.join(", ")
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PieuvreError::Config(format!(
+                "Invalid configuration:\n  - {}",
+                errors.join("\n  - ")
+            )))
+        }
+    }
+
+    /// Chemin du fichier de domaines télémétrie, résolu depuis `config_dir` s'il est relatif
+    pub fn telemetry_domains_path(&self, config_dir: &Path) -> PathBuf {
+        if self.snapshot.telemetry_domains_file.is_absolute() {
+            self.snapshot.telemetry_domains_file.clone()
+        } else {
+            config_dir.join(&self.snapshot.telemetry_domains_file)
+        }
+    }
+}
+
+/// Origine d'une couche de configuration (pour les messages d'erreur)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    File(PathBuf),
+    Env(String),
+    Cli(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::File(path) => write!(f, "{}", path.display()),
+            ConfigSource::Env(var) => write!(f, "environment variable {}", var),
+            ConfigSource::Cli(flag) => write!(f, "command line ({})", flag),
+        }
+    }
+}
+
+/// Chargeur de configuration en couches
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    machine_file: Option<PathBuf>,
+    user_file: Option<PathBuf>,
+    explicit_file: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    overrides: Vec<(String, String)>,
+}
+
+impl ConfigLoader {
+    /// Standard layers: `%ProgramData%\pieuvre\config.toml`,
+    /// `%APPDATA%\pieuvre\config.toml` and the process environment
+    pub fn new() -> Self {
+        Self {
+            machine_file: Some(machine_config_dir().join("config.toml")),
+            user_file: std::env::var_os("APPDATA")
+                .map(|dir| PathBuf::from(dir).join("pieuvre").join("config.toml")),
+            env: std::env::vars().collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Built-in defaults only (no file, no environment)
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Machine-wide file, ignored if missing
+    pub fn machine_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.machine_file = Some(path.into());
+        self
+    }
+
+    /// Per-user file, ignored if missing
+    pub fn user_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.user_file = Some(path.into());
+        self
+    }
+
+    /// File given on the command line (`--config`), must exist
+    pub fn explicit_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.explicit_file = Some(path.into());
+        self
+    }
+
+    /// Replaces the environment layer (only `PIEUVRE_*` variables are read)
+    pub fn env_vars<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.env = vars
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        self
+    }
+
+    /// CLI override of a dotted key (`snapshot.max_count`)
+    pub fn set(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.overrides.push((key.to_string(), value.into()));
+        self
+    }
+
+    pub fn load(&self) -> Result<PieuvreConfig> {
+        let mut merged = toml::Value::try_from(PieuvreConfig::default())
+            .map_err(|e| PieuvreError::Internal(e.to_string()))?;
+
+        for path in [&self.machine_file, &self.user_file].into_iter().flatten() {
+            if path.exists() {
+                merge(&mut merged, read_layer(path)?);
+            }
+        }
+        if let Some(path) = &self.explicit_file {
+            if !path.exists() {
+                return Err(PieuvreError::Config(format!(
+                    "Configuration file not found: {}",
+                    path.display()
+                )));
+            }
+            merge(&mut merged, read_layer(path)?);
+        }
+
+        for (var, raw) in &self.env {
+            let Some(name) = var.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let name = name.to_lowercase();
+            let source = ConfigSource::Env(var.clone());
+            let (section, key) = name
+                .split_once('_')
+                .ok_or_else(|| unknown_key(&name, &source))?;
+            apply_override(&mut merged, section, key, raw, &source)?;
+        }
+
+        for (dotted, raw) in &self.overrides {
+            let source = ConfigSource::Cli(dotted.clone());
+            let (section, key) = dotted
+                .split_once('.')
+                .ok_or_else(|| unknown_key(dotted, &source))?;
+            apply_override(&mut merged, section, key, raw, &source)?;
+        }
+
+        let config: PieuvreConfig = merged
+            .try_into()
+            .map_err(|e: toml::de::Error| PieuvreError::Config(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Dossier de configuration machine (`%ProgramData%\pieuvre`)
+pub fn machine_config_dir() -> PathBuf {
+    std::env::var_os("ProgramData")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"))
+        .join("pieuvre")
+}
+
+/// Installe la configuration du processus ; à appeler une fois au démarrage
+pub fn init_config(config: PieuvreConfig) -> Result<()> {
+    CONFIG
+        .set(config)
+        .map_err(|_| PieuvreError::Internal("Configuration already initialized".into()))
+}
+
+/// Configuration active. Sans `init_config`, charge les couches standard
+/// au premier accès (valeurs intégrées si elles sont invalides).
+pub fn config() -> &'static PieuvreConfig {
+    CONFIG.get_or_init(|| {
+        ConfigLoader::new().load().unwrap_or_else(|e| {
+            tracing::warn!("Configuration ignored, using defaults: {}", e);
+            PieuvreConfig::default()
+        })
+    })
+}
+
+fn read_layer(path: &Path) -> Result<toml::Value> {
+    let content = std::fs::read_to_string(path)?;
+    let source = ConfigSource::File(path.to_path_buf());
+    let layer: toml::Value =
+        toml::from_str(&content).map_err(|e| PieuvreError::Config(format!("{}: {}", source, e)))?;
+    // Schéma vérifié couche par couche pour attribuer les erreurs au bon fichier
+    PieuvreConfig::deserialize(layer.clone())
+        .map_err(|e| PieuvreError::Config(format!("{}: {}", source, e)))?;
+    Ok(layer)
+}
+
+fn merge(base: &mut toml::Value, layer: toml::Value) {
+    match (base, layer) {
+        (toml::Value::Table(base), toml::Value::Table(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+fn unknown_key(key: &str, source: &ConfigSource) -> PieuvreError {
+    PieuvreError::Config(format!("{}: unknown configuration key `{}`", source, key))
+}
+
+/// Applies a raw string value, converted to the type of the existing key
+fn apply_override(
+    merged: &mut toml::Value,
+    section: &str,
+    key: &str,
+    raw: &str,
+    source: &ConfigSource,
+) -> Result<()> {
+    let dotted = format!("{}.{}", section, key);
+    let slot = merged
+        .get_mut(section)
+        .and_then(|s| s.get_mut(key))
+        .ok_or_else(|| unknown_key(&dotted, source))?;
+
+    let invalid = |expected: &str| {
+        PieuvreError::Config(format!(
+            "{}: `{}` expects {}, got `{}`",
+            source, dotted, expected, raw
+        ))
+    };
+    *slot = match slot {
+        toml::Value::Boolean(_) => match raw.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => toml::Value::Boolean(true),
+            "false" | "0" | "no" | "off" => toml::Value::Boolean(false),
+            _ => return Err(invalid("a boolean")),
+        },
+        toml::Value::Integer(_) => toml::Value::Integer(
+            raw.trim()
+                .parse::<i64>()
+                .map_err(|_| invalid("an integer"))?,
+        ),
+        toml::Value::Array(_) => toml::Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| toml::Value::String(s.to_string()))
+                .collect(),
+        ),
+        _ => toml::Value::String(raw.to_string()),
+    };
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use crate::types::ChangeRecord;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -55,6 +56,46 @@ pub enum PieuvreError {
 
     #[error("Scan error: {0}")]
     Scan(String),
+
+    /// Échec survenu après des modifications, conservées pour le rollback
+    #[error("{error} ({} change(s) applied before the failure)", .changes.len())]
+    Partial {
+        error: Box<PieuvreError>,
+        changes: Vec<ChangeRecord>,
+    },
+}
+
+impl PieuvreError {
+    /// Attache les changements appliqués avant l'erreur (inchangée s'il n'y en a pas)
+    pub fn with_changes(self, mut changes: Vec<ChangeRecord>) -> Self {
+        if changes.is_empty() {
+            return self;
+        }
+        match self {
+            PieuvreError::Partial {
+                error,
+                changes: mut earlier,
+            } => {
+                earlier.append(&mut changes);
+                PieuvreError::Partial {
+                    error,
+                    changes: earlier,
+                }
+            }
+            error => PieuvreError::Partial {
+                error: Box::new(error),
+                changes,
+            },
+        }
+    }
+
+    /// Sépare l'erreur des changements appliqués avant elle
+    pub fn split_changes(self) -> (PieuvreError, Vec<ChangeRecord>) {
+        match self {
+            PieuvreError::Partial { error, changes } => (*error, changes),
+            error => (error, Vec::new()),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, PieuvreError>;
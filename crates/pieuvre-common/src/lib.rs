@@ -6,11 +6,18 @@
 mod config;
 mod error;
 mod privilege;
+mod registry_backend;
+mod service_manager;
 mod types;
 pub mod wmi_utils;
 
+#[cfg(test)]
+mod tests;
+
 pub use config::*;
 pub use error::*;
 pub use privilege::*;
+pub use registry_backend::*;
+pub use service_manager::*;
 pub use types::*;
 pub use wmi_utils::*;
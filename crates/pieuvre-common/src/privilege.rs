@@ -17,7 +17,8 @@ impl PrivilegeManager {
         Ok(())
     }
 
-    fn enable_privilege(privilege_name: &str) -> Result<()> {
+    /// Active un privilège nommé (ex: `SeTakeOwnershipPrivilege`) sur le token du processus.
+    pub fn enable_privilege(privilege_name: &str) -> Result<()> {
         unsafe {
             let mut token: HANDLE = HANDLE::default();
             OpenProcessToken(
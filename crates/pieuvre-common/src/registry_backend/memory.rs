@@ -0,0 +1,149 @@
+//! In-memory registry backend (tests and simulations)
+
+use super::{RegistryBackend, RegistryPath};
+use crate::{PieuvreError, RegistryValue, Result};
+use std::collections::BTreeMap;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+#[derive(Debug, Clone)]
+struct MemoryKey {
+    /// Key name as created (original case)
+    name: String,
+    /// Values indexed by lowercase name
+    values: BTreeMap<String, (String, RegistryValue)>,
+}
+
+/// Case-insensitive registry held in memory.
+///
+/// Hive roots always exist; creating a key creates its ancestors.
+#[derive(Debug, Default)]
+pub struct MemoryRegistry {
+    keys: RwLock<BTreeMap<String, MemoryKey>>,
+}
+
+fn normalize(path: &RegistryPath) -> String {
+    path.to_string().to_lowercase()
+}
+
+impl MemoryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder used to seed fixtures
+    pub fn with_value(self, path: RegistryPath, name: &str, value: RegistryValue) -> Self {
+        self.insert(&path, name, &value);
+        self
+    }
+
+    fn insert(&self, path: &RegistryPath, name: &str, value: &RegistryValue) {
+        let mut keys = self.write();
+        Self::ensure_key(&mut keys, path);
+        if let Some(key) = keys.get_mut(&normalize(path)) {
+            key.values
+                .insert(name.to_lowercase(), (name.to_string(), value.clone()));
+        }
+    }
+
+    fn ensure_key(keys: &mut BTreeMap<String, MemoryKey>, path: &RegistryPath) {
+        let mut current = Some(path.clone());
+        while let Some(p) = current {
+            if p.is_root() {
+                break;
+            }
+            let name = p.key.rsplit('\\').next().unwrap_or_default().to_string();
+            keys.entry(normalize(&p)).or_insert_with(|| MemoryKey {
+                name,
+                values: BTreeMap::new(),
+            });
+            current = p.parent();
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, BTreeMap<String, MemoryKey>> {
+        self.keys.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, BTreeMap<String, MemoryKey>> {
+        self.keys.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl RegistryBackend for MemoryRegistry {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn key_exists(&self, path: &RegistryPath) -> bool {
+        path.is_root() || self.read().contains_key(&normalize(path))
+    }
+
+    fn read_value(&self, path: &RegistryPath, name: &str) -> Result<Option<RegistryValue>> {
+        Ok(self
+            .read()
+            .get(&normalize(path))
+            .and_then(|k| k.values.get(&name.to_lowercase()))
+            .map(|(_, v)| v.clone()))
+    }
+
+    fn write_value(&self, path: &RegistryPath, name: &str, value: &RegistryValue) -> Result<()> {
+        if path.is_root() {
+            return Err(PieuvreError::Registry(format!(
+                "Cannot write value {} at hive root {}",
+                name, path
+            )));
+        }
+        self.insert(path, name, value);
+        Ok(())
+    }
+
+    fn delete_value(&self, path: &RegistryPath, name: &str) -> Result<()> {
+        if let Some(key) = self.write().get_mut(&normalize(path)) {
+            key.values.remove(&name.to_lowercase());
+        }
+        Ok(())
+    }
+
+    fn create_key(&self, path: &RegistryPath) -> Result<()> {
+        Self::ensure_key(&mut self.write(), path);
+        Ok(())
+    }
+
+    fn delete_key(&self, path: &RegistryPath) -> Result<()> {
+        if path.is_root() {
+            return Err(PieuvreError::Registry(format!(
+                "Cannot delete hive root {}",
+                path
+            )));
+        }
+        let norm = normalize(path);
+        let prefix = format!("{}\\", norm);
+        self.write()
+            .retain(|k, _| k != &norm && !k.starts_with(&prefix));
+        Ok(())
+    }
+
+    fn list_subkeys(&self, path: &RegistryPath) -> Result<Vec<String>> {
+        if !self.key_exists(path) {
+            return Err(PieuvreError::Registry(format!("Cannot open key {}", path)));
+        }
+        let prefix = format!("{}\\", normalize(path));
+        Ok(self
+            .read()
+            .iter()
+            .filter(|(k, _)| k.starts_with(&prefix) && !k[prefix.len()..].contains('\\'))
+            .map(|(_, key)| key.name.clone())
+            .collect())
+    }
+
+    fn list_values(&self, path: &RegistryPath) -> Result<Vec<(String, RegistryValue)>> {
+        if path.is_root() {
+            return Ok(Vec::new());
+        }
+        let keys = self.read();
+        let key = keys
+            .get(&normalize(path))
+            .ok_or_else(|| PieuvreError::Registry(format!("Cannot open key {}", path)))?;
+        Ok(key.values.values().cloned().collect())
+    }
+}
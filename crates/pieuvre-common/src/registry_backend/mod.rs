@@ -0,0 +1,326 @@
+//! Registry backends
+//!
+//! Abstraction over the Windows registry so that tweak, audit and scan logic
+//! can run against the live system, an in-memory fixture or offline hive files.
+
+mod memory;
+mod native;
+
+pub use memory::MemoryRegistry;
+pub use native::NativeRegistry;
+
+use crate::{PieuvreError, RegistryHive, RegistryTree, RegistryValue, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// Shared handle to a registry backend
+pub type SharedRegistry = Arc<dyn RegistryBackend>;
+
+/// Returns the live Win32 registry backend
+pub fn native_registry() -> SharedRegistry {
+    Arc::new(NativeRegistry)
+}
+
+impl RegistryHive {
+    /// Short root name (`HKLM`, `HKU`, `HKCU`)
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            RegistryHive::Hklm => "HKLM",
+            RegistryHive::Hku => "HKU",
+            RegistryHive::Hkcu => "HKCU",
+        }
+    }
+
+    /// Parses a root name, short (`HKLM`) or long (`HKEY_LOCAL_MACHINE`)
+    pub fn from_root_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "HKLM" | "HKEY_LOCAL_MACHINE" => Some(RegistryHive::Hklm),
+            "HKU" | "HKEY_USERS" => Some(RegistryHive::Hku),
+            "HKCU" | "HKEY_CURRENT_USER" => Some(RegistryHive::Hkcu),
+            _ => None,
+        }
+    }
+}
+
+// REG_* type codes (winnt.h)
+const REG_NONE: u32 = 0;
+const REG_SZ: u32 = 1;
+const REG_EXPAND_SZ: u32 = 2;
+const REG_BINARY: u32 = 3;
+const REG_DWORD: u32 = 4;
+const REG_MULTI_SZ: u32 = 7;
+const REG_QWORD: u32 = 11;
+
+impl RegistryValue {
+    /// REG_* type code
+    pub fn value_type(&self) -> u32 {
+        match self {
+            RegistryValue::None(_) => REG_NONE,
+            RegistryValue::String(_) => REG_SZ,
+            RegistryValue::ExpandString(_) => REG_EXPAND_SZ,
+            RegistryValue::Binary(_) => REG_BINARY,
+            RegistryValue::Dword(_) => REG_DWORD,
+            RegistryValue::MultiString(_) => REG_MULTI_SZ,
+            RegistryValue::Qword(_) => REG_QWORD,
+            RegistryValue::Raw { value_type, .. } => *value_type,
+        }
+    }
+
+    /// Decodes raw registry data; malformed DWORD/QWORD data is kept as `Raw`
+    pub fn from_raw(value_type: u32, data: Vec<u8>) -> Self {
+        match value_type {
+            REG_NONE => RegistryValue::None(data),
+            REG_SZ => RegistryValue::String(decode_utf16(&data)),
+            REG_EXPAND_SZ => RegistryValue::ExpandString(decode_utf16(&data)),
+            REG_BINARY => RegistryValue::Binary(data),
+            REG_DWORD if data.len() >= 4 => {
+                RegistryValue::Dword(u32::from_le_bytes([data[0], data[1], data[2], data[3]]))
+            }
+            REG_QWORD if data.len() >= 8 => {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(&data[..8]);
+                RegistryValue::Qword(u64::from_le_bytes(bytes))
+            }
+            REG_MULTI_SZ => {
+                let text = decode_utf16_all(&data);
+                let list = text.split('\0').take_while(|s| !s.is_empty());
+                RegistryValue::MultiString(list.map(str::to_string).collect())
+            }
+            _ => RegistryValue::Raw { value_type, data },
+        }
+    }
+
+    /// Raw registry data (strings are NUL-terminated UTF-16LE)
+    pub fn to_raw(&self) -> (u32, Vec<u8>) {
+        let data = match self {
+            RegistryValue::Dword(v) => v.to_le_bytes().to_vec(),
+            RegistryValue::Qword(v) => v.to_le_bytes().to_vec(),
+            RegistryValue::String(s) | RegistryValue::ExpandString(s) => encode_utf16(s),
+            RegistryValue::MultiString(list) => {
+                let mut data: Vec<u8> = list.iter().flat_map(|s| encode_utf16(s)).collect();
+                data.extend_from_slice(&[0, 0]);
+                data
+            }
+            RegistryValue::Binary(data)
+            | RegistryValue::None(data)
+            | RegistryValue::Raw { data, .. } => data.clone(),
+        };
+        (self.value_type(), data)
+    }
+}
+
+impl fmt::Display for RegistryValue {
+    /// Short human-readable form; binary data is truncated to 16 bytes
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryValue::Dword(v) => write!(f, "{} (0x{:08x})", v, v),
+            RegistryValue::Qword(v) => write!(f, "{} (0x{:016x})", v, v),
+            RegistryValue::String(s) | RegistryValue::ExpandString(s) => write!(f, "\"{}\"", s),
+            RegistryValue::MultiString(list) => write!(f, "{:?}", list),
+            RegistryValue::Binary(data)
+            | RegistryValue::None(data)
+            | RegistryValue::Raw { data, .. } => {
+                if data.is_empty() {
+                    return write!(f, "(empty)");
+                }
+                for (i, byte) in data.iter().take(16).enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{:02x}", byte)?;
+                }
+                if data.len() > 16 {
+                    write!(f, " ... ({} bytes)", data.len())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn decode_utf16_all(data: &[u8]) -> String {
+    let chars: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16_lossy(&chars)
+}
+
+/// UTF-16LE string up to the first NUL
+fn decode_utf16(data: &[u8]) -> String {
+    let text = decode_utf16_all(data);
+    match text.split_once('\0') {
+        Some((text, _)) => text.to_string(),
+        None => text,
+    }
+}
+
+fn encode_utf16(s: &str) -> Vec<u8> {
+    s.encode_utf16()
+        .chain(std::iter::once(0))
+        .flat_map(|c| c.to_le_bytes())
+        .collect()
+}
+
+/// Hive-qualified registry key path
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RegistryPath {
+    pub hive: RegistryHive,
+    /// Subkey relative to the hive root, without leading/trailing backslash
+    pub key: String,
+}
+
+impl RegistryPath {
+    pub fn new(hive: RegistryHive, key: impl AsRef<str>) -> Self {
+        Self {
+            hive,
+            key: key.as_ref().trim_matches('\\').to_string(),
+        }
+    }
+
+    pub fn hklm(key: impl AsRef<str>) -> Self {
+        Self::new(RegistryHive::Hklm, key)
+    }
+
+    pub fn hku(key: impl AsRef<str>) -> Self {
+        Self::new(RegistryHive::Hku, key)
+    }
+
+    pub fn hkcu(key: impl AsRef<str>) -> Self {
+        Self::new(RegistryHive::Hkcu, key)
+    }
+
+    /// Parses `HKLM\SOFTWARE\...` style paths
+    pub fn parse(path: &str) -> Result<Self> {
+        let path = path.trim_matches('\\');
+        let (root, key) = path.split_once('\\').unwrap_or((path, ""));
+        let hive = RegistryHive::from_root_name(root)
+            .ok_or_else(|| PieuvreError::Parse(format!("Unknown registry root: {}", root)))?;
+        Ok(Self::new(hive, key))
+    }
+
+    /// Path of a child key
+    pub fn join(&self, child: &str) -> Self {
+        let child = child.trim_matches('\\');
+        if self.key.is_empty() {
+            Self::new(self.hive, child)
+        } else if child.is_empty() {
+            self.clone()
+        } else {
+            Self::new(self.hive, format!("{}\\{}", self.key, child))
+        }
+    }
+
+    /// Parent key, `None` for the hive root
+    pub fn parent(&self) -> Option<Self> {
+        if self.key.is_empty() {
+            return None;
+        }
+        Some(match self.key.rsplit_once('\\') {
+            Some((parent, _)) => Self::new(self.hive, parent),
+            None => Self::new(self.hive, ""),
+        })
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.key.is_empty()
+    }
+}
+
+impl fmt::Display for RegistryPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.key.is_empty() {
+            write!(f, "{}", self.hive.short_name())
+        } else {
+            write!(f, "{}\\{}", self.hive.short_name(), self.key)
+        }
+    }
+}
+
+/// Registry access used by every pieuvre crate.
+///
+/// Missing keys or values are reported as `Ok(None)` by readers and ignored by
+/// deleters; only enumeration of a missing key is an error.
+pub trait RegistryBackend: Send + Sync {
+    /// Backend name (for logging)
+    fn name(&self) -> &str;
+
+    /// True if writes are rejected (offline hives)
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    fn key_exists(&self, path: &RegistryPath) -> bool;
+
+    fn read_value(&self, path: &RegistryPath, name: &str) -> Result<Option<RegistryValue>>;
+
+    /// Writes a value, creating the key if needed
+    fn write_value(&self, path: &RegistryPath, name: &str, value: &RegistryValue) -> Result<()>;
+
+    fn delete_value(&self, path: &RegistryPath, name: &str) -> Result<()>;
+
+    fn create_key(&self, path: &RegistryPath) -> Result<()>;
+
+    /// Deletes a key and all its subkeys
+    fn delete_key(&self, path: &RegistryPath) -> Result<()>;
+
+    fn list_subkeys(&self, path: &RegistryPath) -> Result<Vec<String>>;
+
+    fn list_values(&self, path: &RegistryPath) -> Result<Vec<(String, RegistryValue)>>;
+
+    fn read_dword(&self, path: &RegistryPath, name: &str) -> Result<Option<u32>> {
+        match self.read_value(path, name)? {
+            Some(RegistryValue::Dword(v)) => Ok(Some(v)),
+            Some(_) => Err(PieuvreError::Parse(format!(
+                "Value {} in {} is not a DWORD",
+                name, path
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    fn read_string(&self, path: &RegistryPath, name: &str) -> Result<Option<String>> {
+        match self.read_value(path, name)? {
+            Some(RegistryValue::String(s) | RegistryValue::ExpandString(s)) => Ok(Some(s)),
+            Some(_) => Err(PieuvreError::Parse(format!(
+                "Value {} in {} is not a string",
+                name, path
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    fn write_dword(&self, path: &RegistryPath, name: &str, value: u32) -> Result<()> {
+        self.write_value(path, name, &RegistryValue::Dword(value))
+    }
+
+    fn write_string(&self, path: &RegistryPath, name: &str, value: &str) -> Result<()> {
+        self.write_value(path, name, &RegistryValue::String(value.to_string()))
+    }
+
+    /// Copies a key, its values and all its subkeys
+    fn export_tree(&self, path: &RegistryPath) -> Result<RegistryTree> {
+        let mut tree = RegistryTree {
+            values: self.list_values(path)?,
+            subkeys: Vec::new(),
+        };
+        for name in self.list_subkeys(path)? {
+            let subtree = self.export_tree(&path.join(&name))?;
+            tree.subkeys.push((name, subtree));
+        }
+        Ok(tree)
+    }
+
+    /// Recreates a tree produced by `export_tree`; existing values are overwritten
+    fn import_tree(&self, path: &RegistryPath, tree: &RegistryTree) -> Result<()> {
+        self.create_key(path)?;
+        for (name, value) in &tree.values {
+            self.write_value(path, name, value)?;
+        }
+        for (name, subtree) in &tree.subkeys {
+            self.import_tree(&path.join(name), subtree)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+//! Live Win32 registry backend (64-bit view)
+
+use super::{RegistryBackend, RegistryPath};
+use crate::{PieuvreError, RegistryHive, RegistryValue, Result};
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, WIN32_ERROR};
+use windows::Win32::System::Registry::{
+    RegCloseKey, RegCreateKeyExW, RegDeleteTreeW, RegDeleteValueW, RegEnumKeyExW, RegEnumValueW,
+    RegOpenKeyExW, RegQueryValueExW, RegSetValueExW, HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE,
+    HKEY_USERS, KEY_READ, KEY_SET_VALUE, KEY_WOW64_64KEY, KEY_WRITE, REG_OPTION_NON_VOLATILE,
+    REG_SAM_FLAGS, REG_VALUE_TYPE,
+};
+
+/// Backend over the live registry via Win32 APIs
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NativeRegistry;
+
+/// Open key handle, closed on drop
+struct KeyHandle(HKEY);
+
+impl Drop for KeyHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = RegCloseKey(self.0);
+        }
+    }
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+impl NativeRegistry {
+    /// Predefined root handle of a hive
+    pub fn root(hive: RegistryHive) -> HKEY {
+        match hive {
+            RegistryHive::Hklm => HKEY_LOCAL_MACHINE,
+            RegistryHive::Hku => HKEY_USERS,
+            RegistryHive::Hkcu => HKEY_CURRENT_USER,
+        }
+    }
+
+    fn open(
+        path: &RegistryPath,
+        access: REG_SAM_FLAGS,
+    ) -> std::result::Result<KeyHandle, WIN32_ERROR> {
+        let subkey = wide(&path.key);
+        let mut hkey = HKEY::default();
+        let res = unsafe {
+            RegOpenKeyExW(
+                Self::root(path.hive),
+                PCWSTR(subkey.as_ptr()),
+                Some(0),
+                access | KEY_WOW64_64KEY,
+                &mut hkey,
+            )
+        };
+        if res.is_ok() {
+            Ok(KeyHandle(hkey))
+        } else {
+            Err(res)
+        }
+    }
+
+    fn create(path: &RegistryPath) -> Result<KeyHandle> {
+        let subkey = wide(&path.key);
+        let mut hkey = HKEY::default();
+        unsafe {
+            RegCreateKeyExW(
+                Self::root(path.hive),
+                PCWSTR(subkey.as_ptr()),
+                Some(0),
+                None,
+                REG_OPTION_NON_VOLATILE,
+                KEY_WRITE | KEY_WOW64_64KEY,
+                None,
+                &mut hkey,
+                None,
+            )
+            .ok()
+            .map_err(|e| {
+                PieuvreError::Registry(format!("Cannot create/open key {}: {}", path, e))
+            })?;
+        }
+        Ok(KeyHandle(hkey))
+    }
+}
+
+impl RegistryBackend for NativeRegistry {
+    fn name(&self) -> &str {
+        "native"
+    }
+
+    fn key_exists(&self, path: &RegistryPath) -> bool {
+        Self::open(path, KEY_READ).is_ok()
+    }
+
+    fn read_value(&self, path: &RegistryPath, name: &str) -> Result<Option<RegistryValue>> {
+        let key = match Self::open(path, KEY_READ) {
+            Ok(key) => key,
+            Err(e) if e == ERROR_FILE_NOT_FOUND => return Ok(None),
+            Err(e) => {
+                return Err(PieuvreError::Registry(format!(
+                    "Cannot open key {}: {}",
+                    path,
+                    e.to_hresult().message()
+                )))
+            }
+        };
+        let name_wide = wide(name);
+        let mut value_type = REG_VALUE_TYPE::default();
+        let mut size = 0u32;
+
+        unsafe {
+            let res = RegQueryValueExW(
+                key.0,
+                PCWSTR(name_wide.as_ptr()),
+                None,
+                Some(&mut value_type),
+                None,
+                Some(&mut size),
+            );
+            if res == ERROR_FILE_NOT_FOUND {
+                return Ok(None);
+            }
+            res.ok().map_err(|e| {
+                PieuvreError::Registry(format!("Cannot read value {} in {}: {}", name, path, e))
+            })?;
+
+            let mut data = vec![0u8; size as usize];
+            RegQueryValueExW(
+                key.0,
+                PCWSTR(name_wide.as_ptr()),
+                None,
+                Some(&mut value_type),
+                Some(data.as_mut_ptr()),
+                Some(&mut size),
+            )
+            .ok()
+            .map_err(|e| {
+                PieuvreError::Registry(format!("Cannot read value {} in {}: {}", name, path, e))
+            })?;
+            data.truncate(size as usize);
+            Ok(Some(RegistryValue::from_raw(value_type.0, data)))
+        }
+    }
+
+    fn write_value(&self, path: &RegistryPath, name: &str, value: &RegistryValue) -> Result<()> {
+        let key = Self::create(path)?;
+        let name_wide = wide(name);
+        let (value_type, data) = value.to_raw();
+        unsafe {
+            RegSetValueExW(
+                key.0,
+                PCWSTR(name_wide.as_ptr()),
+                Some(0),
+                REG_VALUE_TYPE(value_type),
+                Some(&data),
+            )
+            .ok()
+            .map_err(|e| {
+                PieuvreError::Registry(format!("Cannot set value {} in {}: {}", name, path, e))
+            })
+        }
+    }
+
+    fn delete_value(&self, path: &RegistryPath, name: &str) -> Result<()> {
+        let key = match Self::open(path, KEY_SET_VALUE) {
+            Ok(key) => key,
+            Err(e) if e == ERROR_FILE_NOT_FOUND => return Ok(()),
+            Err(e) => {
+                return Err(PieuvreError::Registry(format!(
+                    "Cannot open key {}: {}",
+                    path,
+                    e.to_hresult().message()
+                )))
+            }
+        };
+        let name_wide = wide(name);
+        let res = unsafe { RegDeleteValueW(key.0, PCWSTR(name_wide.as_ptr())) };
+        if res.is_err() && res != ERROR_FILE_NOT_FOUND {
+            return Err(PieuvreError::Registry(format!(
+                "Cannot delete value {} in {}: {}",
+                name,
+                path,
+                res.to_hresult().message()
+            )));
+        }
+        Ok(())
+    }
+
+    fn create_key(&self, path: &RegistryPath) -> Result<()> {
+        Self::create(path).map(|_| ())
+    }
+
+    fn delete_key(&self, path: &RegistryPath) -> Result<()> {
+        if path.is_root() {
+            return Err(PieuvreError::Registry(format!(
+                "Cannot delete hive root {}",
+                path
+            )));
+        }
+        let subkey = wide(&path.key);
+        let res = unsafe { RegDeleteTreeW(Self::root(path.hive), PCWSTR(subkey.as_ptr())) };
+        if res.is_err() && res != ERROR_FILE_NOT_FOUND {
+            return Err(PieuvreError::Registry(format!(
+                "Failed to delete key {}: {}",
+                path,
+                res.to_hresult().message()
+            )));
+        }
+        Ok(())
+    }
+
+    fn list_subkeys(&self, path: &RegistryPath) -> Result<Vec<String>> {
+        let key = Self::open(path, KEY_READ).map_err(|e| {
+            PieuvreError::Registry(format!(
+                "Cannot open key {}: {}",
+                path,
+                e.to_hresult().message()
+            ))
+        })?;
+
+        let mut subkeys = Vec::new();
+        let mut name_buffer = vec![0u16; 256];
+        let mut index = 0;
+        loop {
+            let mut name_len = name_buffer.len() as u32;
+            let res = unsafe {
+                RegEnumKeyExW(
+                    key.0,
+                    index,
+                    Some(PWSTR(name_buffer.as_mut_ptr())),
+                    &mut name_len,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+            };
+            if res.is_err() {
+                break;
+            }
+            subkeys.push(String::from_utf16_lossy(&name_buffer[..name_len as usize]));
+            index += 1;
+        }
+        Ok(subkeys)
+    }
+
+    fn list_values(&self, path: &RegistryPath) -> Result<Vec<(String, RegistryValue)>> {
+        let key = Self::open(path, KEY_READ).map_err(|e| {
+            PieuvreError::Registry(format!(
+                "Cannot open key {}: {}",
+                path,
+                e.to_hresult().message()
+            ))
+        })?;
+
+        // Value names are limited to 16383 characters
+        let mut names = Vec::new();
+        let mut name_buffer = vec![0u16; 16384];
+        let mut index = 0;
+        loop {
+            let mut name_len = name_buffer.len() as u32;
+            let res = unsafe {
+                RegEnumValueW(
+                    key.0,
+                    index,
+                    Some(PWSTR(name_buffer.as_mut_ptr())),
+                    &mut name_len,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+            };
+            if res.is_err() {
+                break;
+            }
+            names.push(String::from_utf16_lossy(&name_buffer[..name_len as usize]));
+            index += 1;
+        }
+        drop(key);
+
+        let mut values = Vec::with_capacity(names.len());
+        for name in names {
+            if let Some(value) = self.read_value(path, &name)? {
+                values.push((name, value));
+            }
+        }
+        Ok(values)
+    }
+}
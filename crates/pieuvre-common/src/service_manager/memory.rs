@@ -0,0 +1,228 @@
+//! Scriptable in-memory service manager (tests and simulations)
+
+use super::{ServiceEntry, ServiceManager, ServiceTrigger};
+use crate::{PieuvreError, Result, ServiceStartType, ServiceStatus};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Default DACL given to fixture services
+const DEFAULT_SDDL: &str = "D:(A;;CCLCSWRPWPDTLOCRRC;;;SY)(A;;CCDCLCSWRPWPDTLOCRSDRCWDWO;;;BA)";
+
+/// Service Control Manager call, as recorded by [`MemoryServiceManager`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ServiceCall {
+    StartType,
+    SetStartType,
+    Status,
+    SecurityDescriptor,
+    SetSecurityDescriptor,
+    Dependencies,
+    Triggers,
+}
+
+/// Service held by a [`MemoryServiceManager`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryService {
+    pub name: String,
+    pub display_name: String,
+    pub status: ServiceStatus,
+    pub start_type: u32,
+    pub pid: Option<u32>,
+    pub sddl: String,
+    pub dependencies: Vec<String>,
+    pub triggers: Vec<ServiceTrigger>,
+}
+
+impl MemoryService {
+    /// Stopped service with a default DACL
+    pub fn new(name: &str, start_type: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            display_name: name.to_string(),
+            status: ServiceStatus::Stopped,
+            start_type,
+            pid: None,
+            sddl: DEFAULT_SDDL.to_string(),
+            dependencies: Vec::new(),
+            triggers: Vec::new(),
+        }
+    }
+
+    pub fn display_name(mut self, display_name: &str) -> Self {
+        self.display_name = display_name.to_string();
+        self
+    }
+
+    pub fn running(mut self, pid: u32) -> Self {
+        self.status = ServiceStatus::Running;
+        self.pid = Some(pid);
+        self
+    }
+
+    pub fn sddl(mut self, sddl: &str) -> Self {
+        self.sddl = sddl.to_string();
+        self
+    }
+
+    pub fn depends_on(mut self, dependency: &str) -> Self {
+        self.dependencies.push(dependency.to_string());
+        self
+    }
+
+    pub fn trigger(mut self, trigger: ServiceTrigger) -> Self {
+        self.triggers.push(trigger);
+        self
+    }
+}
+
+/// Case-insensitive SCM held in memory.
+///
+/// Failures can be scripted per call and per service with [`fail_on`], and
+/// every call is recorded so tests can assert what was touched.
+///
+/// [`fail_on`]: MemoryServiceManager::fail_on
+#[derive(Debug, Default)]
+pub struct MemoryServiceManager {
+    services: RwLock<BTreeMap<String, MemoryService>>,
+    failures: RwLock<HashSet<(ServiceCall, String)>>,
+    calls: Mutex<Vec<(ServiceCall, String)>>,
+}
+
+impl MemoryServiceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder used to seed fixtures
+    pub fn with_service(self, service: MemoryService) -> Self {
+        self.insert(service);
+        self
+    }
+
+    /// Makes every `call` on `service` fail with a permission error
+    pub fn fail_on(self, call: ServiceCall, service: &str) -> Self {
+        self.failures
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert((call, service.to_lowercase()));
+        self
+    }
+
+    /// Adds or replaces a service (also used to simulate external drift)
+    pub fn insert(&self, service: MemoryService) {
+        self.write().insert(service.name.to_lowercase(), service);
+    }
+
+    /// Current state of a service
+    pub fn service(&self, name: &str) -> Option<MemoryService> {
+        self.read().get(&name.to_lowercase()).cloned()
+    }
+
+    /// Calls made so far, in order
+    pub fn calls(&self) -> Vec<(ServiceCall, String)> {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Records `call` then runs `f` on the service, honoring scripted failures
+    fn call<T>(
+        &self,
+        call: ServiceCall,
+        name: &str,
+        f: impl FnOnce(&mut MemoryService) -> T,
+    ) -> Result<T> {
+        self.calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((call, name.to_string()));
+
+        let key = name.to_lowercase();
+        let mut services = self.write();
+        let service = services
+            .get_mut(&key)
+            .ok_or_else(|| PieuvreError::ServiceNotFound(name.to_string()))?;
+        if self
+            .failures
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&(call, key))
+        {
+            return Err(PieuvreError::Permission(format!(
+                "Scripted failure: {:?} on {}",
+                call, name
+            )));
+        }
+        Ok(f(service))
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, BTreeMap<String, MemoryService>> {
+        self.services.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, BTreeMap<String, MemoryService>> {
+        self.services.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl ServiceManager for MemoryServiceManager {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    fn exists(&self, service: &str) -> bool {
+        self.read().contains_key(&service.to_lowercase())
+    }
+
+    fn list_services(&self) -> Result<Vec<ServiceEntry>> {
+        Ok(self
+            .read()
+            .values()
+            .map(|s| ServiceEntry {
+                name: s.name.clone(),
+                display_name: s.display_name.clone(),
+                status: s.status,
+                start_type: ServiceStartType::from_raw(s.start_type),
+                pid: s.pid,
+            })
+            .collect())
+    }
+
+    fn start_type(&self, service: &str) -> Result<u32> {
+        self.call(ServiceCall::StartType, service, |s| s.start_type)
+    }
+
+    fn set_start_type(&self, service: &str, start_type: u32) -> Result<()> {
+        self.call(ServiceCall::SetStartType, service, |s| {
+            s.start_type = start_type
+        })
+    }
+
+    fn status(&self, service: &str) -> Result<ServiceStatus> {
+        self.call(ServiceCall::Status, service, |s| s.status)
+    }
+
+    fn security_descriptor(&self, service: &str) -> Result<String> {
+        self.call(ServiceCall::SecurityDescriptor, service, |s| s.sddl.clone())
+    }
+
+    fn set_security_descriptor(&self, service: &str, sddl: &str) -> Result<()> {
+        if !sddl.starts_with("D:") {
+            return Err(PieuvreError::Internal(format!(
+                "SDDL conversion failed: {}",
+                sddl
+            )));
+        }
+        self.call(ServiceCall::SetSecurityDescriptor, service, |s| {
+            s.sddl = sddl.to_string()
+        })
+    }
+
+    fn dependencies(&self, service: &str) -> Result<Vec<String>> {
+        self.call(ServiceCall::Dependencies, service, |s| {
+            s.dependencies.clone()
+        })
+    }
+
+    fn triggers(&self, service: &str) -> Result<Vec<ServiceTrigger>> {
+        self.call(ServiceCall::Triggers, service, |s| s.triggers.clone())
+    }
+}
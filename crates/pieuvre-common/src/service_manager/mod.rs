@@ -0,0 +1,119 @@
+//! Service managers
+//!
+//! Abstraction over the Service Control Manager so that service tweaks,
+//! rollback, audit and Sentinel drift repair can run against the live SCM or
+//! a scripted in-memory fixture.
+
+mod memory;
+mod native;
+
+pub use memory::{MemoryService, MemoryServiceManager, ServiceCall};
+pub use native::NativeServiceManager;
+
+use crate::{Result, ServiceStartType, ServiceStatus};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Shared handle to a service manager
+pub type SharedServiceManager = Arc<dyn ServiceManager>;
+
+/// Returns the live SCM backend
+pub fn native_service_manager() -> SharedServiceManager {
+    Arc::new(NativeServiceManager)
+}
+
+impl ServiceStartType {
+    /// Maps a raw `dwStartType` (0..=4)
+    pub fn from_raw(start_type: u32) -> Self {
+        match start_type {
+            0 => ServiceStartType::Boot,
+            1 => ServiceStartType::System,
+            2 => ServiceStartType::Automatic,
+            3 => ServiceStartType::Manual,
+            4 => ServiceStartType::Disabled,
+            _ => ServiceStartType::Unknown,
+        }
+    }
+
+    /// Raw `dwStartType`, `None` for `Unknown`
+    pub fn to_raw(&self) -> Option<u32> {
+        match self {
+            ServiceStartType::Boot => Some(0),
+            ServiceStartType::System => Some(1),
+            ServiceStartType::Automatic => Some(2),
+            ServiceStartType::Manual => Some(3),
+            ServiceStartType::Disabled => Some(4),
+            ServiceStartType::Unknown => None,
+        }
+    }
+}
+
+impl ServiceStatus {
+    /// Maps a raw `dwCurrentState` (1..=7)
+    pub fn from_raw(state: u32) -> Self {
+        match state {
+            1 => ServiceStatus::Stopped,
+            2 => ServiceStatus::StartPending,
+            3 => ServiceStatus::StopPending,
+            4 => ServiceStatus::Running,
+            5 => ServiceStatus::ContinuePending,
+            6 => ServiceStatus::PausePending,
+            7 => ServiceStatus::Paused,
+            _ => ServiceStatus::Unknown,
+        }
+    }
+}
+
+/// Service as enumerated by a manager
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceEntry {
+    pub name: String,
+    pub display_name: String,
+    pub status: ServiceStatus,
+    pub start_type: ServiceStartType,
+    /// Service process PID if running
+    pub pid: Option<u32>,
+}
+
+/// Trigger that can start or stop a service (`SERVICE_TRIGGER`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceTrigger {
+    /// `SERVICE_TRIGGER_TYPE_*` (1 = device arrival, 3 = IP address, 20 = custom...)
+    pub trigger_type: u32,
+    /// 1 = start the service, 2 = stop it
+    pub action: u32,
+    /// Trigger subtype GUID, if any
+    pub subtype: Option<String>,
+}
+
+/// Service Control Manager access used by every pieuvre crate.
+///
+/// Every per-service call fails with `PieuvreError::ServiceNotFound` when the
+/// service does not exist.
+pub trait ServiceManager: Send + Sync {
+    /// Backend name (for logging)
+    fn name(&self) -> &str;
+
+    fn exists(&self, service: &str) -> bool;
+
+    /// Enumerates Win32 services
+    fn list_services(&self) -> Result<Vec<ServiceEntry>>;
+
+    /// Raw start type (2 = Auto, 3 = Manual, 4 = Disabled)
+    fn start_type(&self, service: &str) -> Result<u32>;
+
+    fn set_start_type(&self, service: &str, start_type: u32) -> Result<()>;
+
+    fn status(&self, service: &str) -> Result<ServiceStatus>;
+
+    /// DACL of the service object, as SDDL
+    fn security_descriptor(&self, service: &str) -> Result<String>;
+
+    /// Replaces the DACL with the (protected) DACL of `sddl`
+    fn set_security_descriptor(&self, service: &str, sddl: &str) -> Result<()>;
+
+    /// Services and load-order groups (`+` prefixed) this service depends on
+    fn dependencies(&self, service: &str) -> Result<Vec<String>>;
+
+    fn triggers(&self, service: &str) -> Result<Vec<ServiceTrigger>>;
+}
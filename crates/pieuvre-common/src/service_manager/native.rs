@@ -0,0 +1,402 @@
+//! Live Service Control Manager backend
+
+use super::{ServiceEntry, ServiceManager, ServiceTrigger};
+use crate::{PieuvreError, PrivilegeManager, Result, ServiceStartType, ServiceStatus};
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{LocalFree, HLOCAL};
+use windows::Win32::Security::Authorization::{
+    ConvertSecurityDescriptorToStringSecurityDescriptorW,
+    ConvertStringSecurityDescriptorToSecurityDescriptorW, GetNamedSecurityInfoW,
+    SetNamedSecurityInfoW, SE_SERVICE,
+};
+use windows::Win32::Security::{
+    GetSecurityDescriptorDacl, DACL_SECURITY_INFORMATION, PROTECTED_DACL_SECURITY_INFORMATION,
+    PSECURITY_DESCRIPTOR,
+};
+use windows::Win32::System::Services::{
+    ChangeServiceConfigW, CloseServiceHandle, EnumServicesStatusExW, OpenSCManagerW, OpenServiceW,
+    QueryServiceConfig2W, QueryServiceConfigW, QueryServiceStatusEx, ENUM_SERVICE_STATUS_PROCESSW,
+    ENUM_SERVICE_TYPE, QUERY_SERVICE_CONFIGW, SC_ENUM_PROCESS_INFO, SC_HANDLE, SC_MANAGER_CONNECT,
+    SC_MANAGER_ENUMERATE_SERVICE, SC_STATUS_PROCESS_INFO, SERVICE_CHANGE_CONFIG,
+    SERVICE_CONFIG_TRIGGER_INFO, SERVICE_ERROR, SERVICE_QUERY_CONFIG, SERVICE_QUERY_STATUS,
+    SERVICE_START_TYPE, SERVICE_STATE_ALL, SERVICE_STATUS_PROCESS, SERVICE_TRIGGER_INFO,
+    SERVICE_WIN32,
+};
+
+/// Constantes pour ChangeServiceConfigW
+const SERVICE_NO_CHANGE_TYPE: ENUM_SERVICE_TYPE = ENUM_SERVICE_TYPE(0xFFFFFFFF);
+const SERVICE_NO_CHANGE_ERROR: SERVICE_ERROR = SERVICE_ERROR(0xFFFFFFFF);
+
+const SDDL_REVISION_1: u32 = 1;
+
+/// Backend over the live SCM via Win32 APIs
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NativeServiceManager;
+
+/// Open SCM or service handle, closed on drop
+struct ScHandle(SC_HANDLE);
+
+impl Drop for ScHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseServiceHandle(self.0);
+        }
+    }
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn pwstr_to_string(ptr: PWSTR) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe {
+        let len = (0..).take_while(|&i| *ptr.0.add(i) != 0).count();
+        String::from_utf16_lossy(std::slice::from_raw_parts(ptr.0, len))
+    }
+}
+
+/// Zeroed buffer aligned for the Win32 structures written into it
+fn aligned_buffer(bytes: u32) -> Vec<u64> {
+    vec![0u64; (bytes as usize).div_ceil(8)]
+}
+
+fn as_bytes_mut(buffer: &mut [u64]) -> &mut [u8] {
+    unsafe { std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len() * 8) }
+}
+
+impl NativeServiceManager {
+    fn open_scm(access: u32) -> Result<ScHandle> {
+        unsafe {
+            OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), access)
+                .map(ScHandle)
+                .map_err(|e| PieuvreError::Permission(e.to_string()))
+        }
+    }
+
+    fn open_service(scm: &ScHandle, name: &str, access: u32) -> Result<ScHandle> {
+        let name_wide = wide(name);
+        unsafe {
+            OpenServiceW(scm.0, PCWSTR(name_wide.as_ptr()), access)
+                .map(ScHandle)
+                .map_err(|_| PieuvreError::ServiceNotFound(name.to_string()))
+        }
+    }
+
+    fn open(name: &str, access: u32) -> Result<ScHandle> {
+        let scm = Self::open_scm(SC_MANAGER_CONNECT)?;
+        Self::open_service(&scm, name, access)
+    }
+
+    /// Runs `f` on the service configuration (two-call QueryServiceConfigW)
+    fn with_config<T>(
+        service: &ScHandle,
+        name: &str,
+        f: impl FnOnce(&QUERY_SERVICE_CONFIGW) -> T,
+    ) -> Result<T> {
+        unsafe {
+            let mut bytes_needed = 0u32;
+            let _ = QueryServiceConfigW(service.0, None, 0, &mut bytes_needed);
+            if bytes_needed == 0 {
+                return Err(PieuvreError::ServiceNotFound(name.to_string()));
+            }
+
+            let mut buffer = aligned_buffer(bytes_needed);
+            let config_ptr = buffer.as_mut_ptr() as *mut QUERY_SERVICE_CONFIGW;
+            QueryServiceConfigW(service.0, Some(config_ptr), bytes_needed, &mut bytes_needed)
+                .map_err(|e| {
+                    PieuvreError::System(format!("QueryServiceConfigW failed for {}: {}", name, e))
+                })?;
+            Ok(f(&*config_ptr))
+        }
+    }
+
+    fn enumerate(scm: &ScHandle) -> Result<Vec<ServiceEntry>> {
+        unsafe {
+            let mut bytes_needed = 0u32;
+            let mut services_returned = 0u32;
+            let mut resume_handle = 0u32;
+
+            // First call to get size
+            let _ = EnumServicesStatusExW(
+                scm.0,
+                SC_ENUM_PROCESS_INFO,
+                SERVICE_WIN32,
+                SERVICE_STATE_ALL,
+                None,
+                &mut bytes_needed,
+                &mut services_returned,
+                Some(&mut resume_handle),
+                None,
+            );
+            if bytes_needed == 0 {
+                return Ok(Vec::new());
+            }
+
+            let mut buffer = aligned_buffer(bytes_needed);
+            EnumServicesStatusExW(
+                scm.0,
+                SC_ENUM_PROCESS_INFO,
+                SERVICE_WIN32,
+                SERVICE_STATE_ALL,
+                Some(&mut as_bytes_mut(&mut buffer)[..bytes_needed as usize]),
+                &mut bytes_needed,
+                &mut services_returned,
+                Some(&mut resume_handle),
+                None,
+            )
+            .map_err(|e| PieuvreError::System(format!("EnumServicesStatusExW failed: {}", e)))?;
+
+            let entries = std::slice::from_raw_parts(
+                buffer.as_ptr() as *const ENUM_SERVICE_STATUS_PROCESSW,
+                services_returned as usize,
+            );
+
+            Ok(entries
+                .iter()
+                .map(|entry| {
+                    let name = pwstr_to_string(entry.lpServiceName);
+                    let status =
+                        ServiceStatus::from_raw(entry.ServiceStatusProcess.dwCurrentState.0);
+                    let start_type = Self::open_service(scm, &name, SERVICE_QUERY_CONFIG)
+                        .and_then(|service| Self::with_config(&service, &name, |c| c.dwStartType.0))
+                        .map(ServiceStartType::from_raw)
+                        .unwrap_or(ServiceStartType::Unknown);
+                    let pid = if status == ServiceStatus::Running {
+                        Some(entry.ServiceStatusProcess.dwProcessId)
+                    } else {
+                        None
+                    };
+                    ServiceEntry {
+                        display_name: pwstr_to_string(entry.lpDisplayName),
+                        name,
+                        status,
+                        start_type,
+                        pid,
+                    }
+                })
+                .collect())
+        }
+    }
+}
+
+impl ServiceManager for NativeServiceManager {
+    fn name(&self) -> &str {
+        "native"
+    }
+
+    fn exists(&self, service: &str) -> bool {
+        Self::open(service, SERVICE_QUERY_CONFIG).is_ok()
+    }
+
+    fn list_services(&self) -> Result<Vec<ServiceEntry>> {
+        let scm = Self::open_scm(SC_MANAGER_CONNECT | SC_MANAGER_ENUMERATE_SERVICE)?;
+        Self::enumerate(&scm)
+    }
+
+    fn start_type(&self, service: &str) -> Result<u32> {
+        let handle = Self::open(service, SERVICE_QUERY_CONFIG)?;
+        Self::with_config(&handle, service, |c| c.dwStartType.0)
+    }
+
+    fn set_start_type(&self, service: &str, start_type: u32) -> Result<()> {
+        let handle = Self::open(service, SERVICE_CHANGE_CONFIG)?;
+        unsafe {
+            ChangeServiceConfigW(
+                handle.0,
+                SERVICE_NO_CHANGE_TYPE,
+                SERVICE_START_TYPE(start_type),
+                SERVICE_NO_CHANGE_ERROR,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                None,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                PCWSTR::null(),
+                PCWSTR::null(),
+            )
+            .map_err(|e| PieuvreError::Registry(e.to_string()))?;
+        }
+        tracing::info!("Service {} start_type -> {}", service, start_type);
+        Ok(())
+    }
+
+    fn status(&self, service: &str) -> Result<ServiceStatus> {
+        let handle = Self::open(service, SERVICE_QUERY_STATUS)?;
+        let mut status = SERVICE_STATUS_PROCESS::default();
+        let mut bytes_needed = 0u32;
+        unsafe {
+            let buffer = std::slice::from_raw_parts_mut(
+                &mut status as *mut SERVICE_STATUS_PROCESS as *mut u8,
+                std::mem::size_of::<SERVICE_STATUS_PROCESS>(),
+            );
+            QueryServiceStatusEx(
+                handle.0,
+                SC_STATUS_PROCESS_INFO,
+                Some(buffer),
+                &mut bytes_needed,
+            )
+            .map_err(|e| {
+                PieuvreError::System(format!(
+                    "QueryServiceStatusEx failed for {}: {}",
+                    service, e
+                ))
+            })?;
+        }
+        Ok(ServiceStatus::from_raw(status.dwCurrentState.0))
+    }
+
+    fn security_descriptor(&self, service: &str) -> Result<String> {
+        if !self.exists(service) {
+            return Err(PieuvreError::ServiceNotFound(service.to_string()));
+        }
+        let path = wide(&format!("SERVICE\\{}", service));
+        unsafe {
+            let mut sd = PSECURITY_DESCRIPTOR::default();
+            GetNamedSecurityInfoW(
+                PCWSTR(path.as_ptr()),
+                SE_SERVICE,
+                DACL_SECURITY_INFORMATION,
+                None,
+                None,
+                None,
+                None,
+                &mut sd,
+            )
+            .ok()
+            .map_err(|e| {
+                PieuvreError::Permission(format!("Cannot read DACL of {}: {}", service, e))
+            })?;
+
+            let mut sddl = PWSTR::null();
+            let result = ConvertSecurityDescriptorToStringSecurityDescriptorW(
+                sd,
+                SDDL_REVISION_1,
+                DACL_SECURITY_INFORMATION,
+                &mut sddl,
+                None,
+            );
+            let _ = LocalFree(Some(HLOCAL(sd.0 as *mut _)));
+            result.map_err(|e| PieuvreError::Internal(format!("SDDL conversion failed: {}", e)))?;
+
+            let text = pwstr_to_string(sddl);
+            let _ = LocalFree(Some(HLOCAL(sddl.0 as *mut _)));
+            Ok(text)
+        }
+    }
+
+    fn set_security_descriptor(&self, service: &str, sddl: &str) -> Result<()> {
+        if !self.exists(service) {
+            return Err(PieuvreError::ServiceNotFound(service.to_string()));
+        }
+        let _ = PrivilegeManager::enable_privilege("SeTakeOwnershipPrivilege");
+        let _ = PrivilegeManager::enable_privilege("SeRestorePrivilege");
+
+        let sddl_wide = wide(sddl);
+        unsafe {
+            let mut sd = PSECURITY_DESCRIPTOR::default();
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                PCWSTR(sddl_wide.as_ptr()),
+                SDDL_REVISION_1,
+                &mut sd,
+                None,
+            )
+            .map_err(|e| PieuvreError::Internal(format!("SDDL conversion failed: {}", e)))?;
+
+            let mut dacl = std::ptr::null_mut();
+            let mut dacl_present = 0i32;
+            let mut dacl_defaulted = 0i32;
+            let _ = GetSecurityDescriptorDacl(
+                sd,
+                &mut dacl_present as *mut i32 as *mut _,
+                &mut dacl,
+                &mut dacl_defaulted as *mut i32 as *mut _,
+            );
+
+            // SetNamedSecurityInfoW avec préfixe SERVICE\\ pour plus de fiabilité
+            let path = wide(&format!("SERVICE\\{}", service));
+            let result = SetNamedSecurityInfoW(
+                PCWSTR(path.as_ptr()),
+                SE_SERVICE,
+                DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+                None,
+                None,
+                Some(dacl),
+                None,
+            )
+            .ok();
+
+            let _ = LocalFree(Some(HLOCAL(sd.0 as *mut _)));
+            result.map_err(|e| {
+                PieuvreError::Internal(format!("Failed to set DACL of {}: {:?}", service, e))
+            })
+        }
+    }
+
+    fn dependencies(&self, service: &str) -> Result<Vec<String>> {
+        let handle = Self::open(service, SERVICE_QUERY_CONFIG)?;
+        Self::with_config(&handle, service, |c| {
+            // Liste de chaînes terminée par un double NUL
+            let mut dependencies = Vec::new();
+            let mut ptr = c.lpDependencies;
+            while !ptr.is_null() {
+                let dependency = pwstr_to_string(ptr);
+                if dependency.is_empty() {
+                    break;
+                }
+                // SAFETY: on saute la chaîne et son NUL, la liste se termine par un NUL supplémentaire
+                ptr = PWSTR(unsafe { ptr.0.add(dependency.encode_utf16().count() + 1) });
+                dependencies.push(dependency);
+            }
+            dependencies
+        })
+    }
+
+    fn triggers(&self, service: &str) -> Result<Vec<ServiceTrigger>> {
+        let handle = Self::open(service, SERVICE_QUERY_CONFIG)?;
+        unsafe {
+            let mut bytes_needed = 0u32;
+            let _ = QueryServiceConfig2W(
+                handle.0,
+                SERVICE_CONFIG_TRIGGER_INFO,
+                None,
+                &mut bytes_needed,
+            );
+            if bytes_needed == 0 {
+                return Ok(Vec::new());
+            }
+
+            let mut buffer = aligned_buffer(bytes_needed);
+            QueryServiceConfig2W(
+                handle.0,
+                SERVICE_CONFIG_TRIGGER_INFO,
+                Some(&mut as_bytes_mut(&mut buffer)[..bytes_needed as usize]),
+                &mut bytes_needed,
+            )
+            .map_err(|e| {
+                PieuvreError::System(format!(
+                    "QueryServiceConfig2W failed for {}: {}",
+                    service, e
+                ))
+            })?;
+
+            let info = &*(buffer.as_ptr() as *const SERVICE_TRIGGER_INFO);
+            if info.pTriggers.is_null() {
+                return Ok(Vec::new());
+            }
+            Ok(
+                std::slice::from_raw_parts(info.pTriggers, info.cTriggers as usize)
+                    .iter()
+                    .map(|t| ServiceTrigger {
+                        trigger_type: t.dwTriggerType.0,
+                        action: t.dwAction.0,
+                        subtype: (!t.pTriggerSubtype.is_null())
+                            .then(|| format!("{:?}", *t.pTriggerSubtype)),
+                    })
+                    .collect(),
+            )
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Tests unitaires pieuvre-common
+
+use crate::{ConfigLoader, PieuvreConfig, PieuvreError};
+use std::io::Write;
+
+// ============================================================================
+// TESTS CONFIGURATION
+// ============================================================================
+
+fn write_toml(dir: &std::path::Path, name: &str, content: &str) -> std::path::PathBuf {
+    let path = dir.join(name);
+    let mut file = std::fs::File::create(&path).unwrap();
+    file.write_all(content.as_bytes()).unwrap();
+    path
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("pieuvre-config-{}-{}", name, uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_default_toml_matches_builtin_defaults() {
+    let shipped = PieuvreConfig::from_toml(include_str!("../../../config/default.toml")).unwrap();
+    assert_eq!(shipped, PieuvreConfig::default());
+    assert!(shipped.validate().is_ok());
+}
+
+#[test]
+fn test_config_layer_precedence() {
+    let dir = temp_dir("layers");
+    let machine = write_toml(
+        &dir,
+        "machine.toml",
+        "[snapshot]\nmax_count = 20\ncompress = false\n[general]\nlog_level = \"info\"\n",
+    );
+    let user = write_toml(&dir, "user.toml", "[snapshot]\nmax_count = 30\n");
+
+    let config = ConfigLoader::empty()
+        .machine_file(&machine)
+        .user_file(&user)
+        .env_vars([
+            ("PIEUVRE_GENERAL_LOG_LEVEL", "debug"),
+            ("PIEUVRE_AUDIT_APPX", "false"),
+            ("PIEUVRE_SCORING_DPC_LATENCY", "40"),
+            ("OTHER_VAR", "ignored"),
+        ])
+        .set("snapshot.max_count", "5")
+        .load()
+        .unwrap();
+
+    assert_eq!(config.snapshot.max_count, 5); // CLI > user > machine
+    assert!(!config.snapshot.compress); // machine
+    assert_eq!(config.general.log_level, "debug"); // env > machine
+    assert!(!config.audit.appx);
+    assert!(config.audit.services); // défaut intégré
+    assert_eq!(config.scoring.dpc_latency, 40);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_config_validation_errors() {
+    let dir = temp_dir("invalid");
+    let typo = write_toml(&dir, "typo.toml", "[snapshot]\nmax_cout = 3\n");
+    match ConfigLoader::empty().user_file(&typo).load() {
+        Err(PieuvreError::Config(msg)) => {
+            assert!(
+                msg.contains("typo.toml") && msg.contains("max_cout"),
+                "{}",
+                msg
+            )
+        }
+        other => panic!("Expected config error, got {:?}", other),
+    }
+
+    match ConfigLoader::empty()
+        .set("snapshot.max_count", "0")
+        .set("snapshot.keep_weekly", "5000")
+        .set("general.log_level", "loud")
+        .set("scoring.bloatware", "0")
+        .set("scoring.dpc_latency", "0")
+        .set("scoring.isr_latency", "0")
+        .load()
+    {
+        Err(PieuvreError::Config(msg)) => {
+            assert!(msg.contains("snapshot.max_count") && msg.contains("general.log_level"));
+            assert!(msg.contains("snapshot.keep_weekly"), "{}", msg);
+            assert!(msg.contains("every performance weight is 0"), "{}", msg);
+        }
+        other => panic!("Expected config error, got {:?}", other),
+    }
+
+    assert!(ConfigLoader::empty()
+        .env_vars([("PIEUVRE_SNAPSHOT_MAX_COUNT", "many")])
+        .load()
+        .is_err());
+    assert!(ConfigLoader::empty()
+        .set("scan.unknown", "x")
+        .load()
+        .is_err());
+    assert!(ConfigLoader::empty()
+        .explicit_file(dir.join("missing.toml"))
+        .load()
+        .is_err());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+// ============================================================================
+// TESTS VALEURS DE REGISTRE
+// ============================================================================
+
+#[test]
+fn test_registry_value_raw_round_trip() {
+    use crate::RegistryValue;
+
+    let values = [
+        RegistryValue::Dword(0xDEAD_BEEF),
+        RegistryValue::Qword(u64::MAX - 1),
+        RegistryValue::String("Pieuvre".into()),
+        RegistryValue::ExpandString(r"%SystemRoot%\System32".into()),
+        RegistryValue::MultiString(vec!["a".into(), "bc".into()]),
+        RegistryValue::MultiString(Vec::new()),
+        RegistryValue::Binary(vec![1, 2, 3]),
+        RegistryValue::None(Vec::new()),
+        RegistryValue::Raw {
+            value_type: 8,
+            data: vec![9, 9],
+        },
+    ];
+    for value in values {
+        let (value_type, data) = value.to_raw();
+        assert_eq!(RegistryValue::from_raw(value_type, data), value);
+    }
+
+    // Données tronquées : conservées telles quelles
+    assert_eq!(
+        RegistryValue::from_raw(11, vec![1, 2]),
+        RegistryValue::Raw {
+            value_type: 11,
+            data: vec![1, 2]
+        }
+    );
+    // REG_SZ sans terminateur
+    assert_eq!(
+        RegistryValue::from_raw(1, vec![b'o', 0, b'k', 0]),
+        RegistryValue::String("ok".into())
+    );
+}
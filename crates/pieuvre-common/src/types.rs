@@ -16,6 +16,9 @@ pub struct AuditReport {
     pub security: SecurityAudit,
     pub latency: Option<LatencyReport>,
     pub appx: Vec<AppxInfo>,
+    /// Scores de confidentialité, performance et sécurité (absents des anciens rapports)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scores: Option<AuditScores>,
 }
 
 /// System information
@@ -28,7 +31,7 @@ pub struct SystemInfo {
 }
 
 /// Hardware information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct HardwareInfo {
     pub cpu: CpuInfo,
     pub memory: MemoryInfo,
@@ -36,7 +39,7 @@ pub struct HardwareInfo {
     pub gpu: Vec<GpuInfo>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CpuInfo {
     pub vendor: String,
     pub model_name: String,
@@ -47,7 +50,7 @@ pub struct CpuInfo {
     pub e_cores: Vec<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MemoryInfo {
     pub total_bytes: u64,
     pub available_bytes: u64,
@@ -192,6 +195,50 @@ pub enum RemovalRisk {
     Critical,
 }
 
+/// Weighted scores of an audit, one per dimension
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditScores {
+    pub privacy: DimensionScore,
+    pub performance: DimensionScore,
+    pub security: DimensionScore,
+}
+
+/// Score (0-100) and the findings it is made of
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DimensionScore {
+    /// `None` : aucun constat applicable (module désactivé, audit hors-ligne)
+    pub score: Option<u8>,
+    pub findings: Vec<ScoreFinding>,
+}
+
+impl DimensionScore {
+    /// Points obtenus sur le total des poids, ramenés à 100
+    pub fn from_findings(findings: Vec<ScoreFinding>) -> Self {
+        let total: u32 = findings.iter().map(|f| f.weight).sum();
+        let earned: u32 = findings.iter().map(|f| f.earned).sum();
+        let score = (total > 0).then(|| ((earned * 100 + total / 2) / total) as u8);
+        Self { score, findings }
+    }
+}
+
+/// One weighted check contributing to a score
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreFinding {
+    pub id: String,
+    pub title: String,
+    /// Observed state ("enabled", "level 3", "4 package(s)")
+    pub detail: String,
+    pub weight: u32,
+    /// Points obtained, `0..=weight`
+    pub earned: u32,
+}
+
+impl ScoreFinding {
+    pub fn lost(&self) -> u32 {
+        self.weight.saturating_sub(self.earned)
+    }
+}
+
 /// Rollback snapshot
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Snapshot {
@@ -199,23 +246,79 @@ pub struct Snapshot {
     pub timestamp: DateTime<Utc>,
     pub description: String,
     pub changes: Vec<ChangeRecord>,
+    /// Issue de la transaction qui a produit le snapshot (absent : snapshot simple)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<TransactionOutcome>,
+    /// Tweak à l'origine de chaque groupe de changements (vide : inconnu)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub origins: Vec<ChangeOrigin>,
+}
+
+impl Snapshot {
+    /// Tweak qui a produit le changement d'index `index`
+    pub fn origin_of(&self, index: usize) -> Option<&str> {
+        self.origins
+            .iter()
+            .find(|o| (o.first..o.first + o.count).contains(&index))
+            .map(|o| o.tweak.as_str())
+    }
+}
+
+/// Changements consécutifs d'un snapshot produits par un même tweak
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeOrigin {
+    pub tweak: String,
+    /// Index du premier changement
+    pub first: usize,
+    pub count: usize,
 }
 
+/// Issue d'une application transactionnelle
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransactionOutcome {
+    /// Toutes les étapes ont réussi
+    Success,
+    /// Un échec a déclenché le rollback de ce qui avait été appliqué
+    RolledBack,
+    /// Des étapes ont échoué sans rollback (ou le rollback est incomplet)
+    Partial,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RegistryHive {
     Hklm,
     Hku,
     Hkcu,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Valeur de registre, un variant par type REG_*
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RegistryValue {
     Dword(u32),
     String(String),
     Binary(Vec<u8>),
+    Qword(u64),
+    /// REG_EXPAND_SZ, variables d'environnement non développées
+    ExpandString(String),
+    MultiString(Vec<String>),
+    /// REG_NONE, données brutes éventuelles
+    None(Vec<u8>),
+    /// Autres types (REG_LINK, REG_RESOURCE_LIST...) conservés tels quels
+    Raw {
+        value_type: u32,
+        data: Vec<u8>,
+    },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Copie d'une clé de registre et de ses sous-clés
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryTree {
+    pub values: Vec<(String, RegistryValue)>,
+    pub subkeys: Vec<(String, RegistryTree)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ChangeRecord {
     Registry {
         hive: RegistryHive,
@@ -223,6 +326,17 @@ pub enum ChangeRecord {
         value_name: String,
         original_value: Option<RegistryValue>,
     },
+    /// Clé créée par pieuvre (restauration : suppression)
+    RegistryKeyCreated {
+        hive: RegistryHive,
+        key: String,
+    },
+    /// Clé supprimée par pieuvre, avec sa sous-arborescence
+    RegistryKeyDeleted {
+        hive: RegistryHive,
+        key: String,
+        tree: RegistryTree,
+    },
     Service {
         name: String,
         original_start_type: u32,
@@ -233,4 +347,80 @@ pub enum ChangeRecord {
     AppX {
         package_full_name: String,
     },
+    /// Plan d'alimentation actif et index de paramètres modifiés
+    PowerScheme {
+        /// GUID du plan actif avant modification (`None` : plan inchangé)
+        original_scheme: Option<String>,
+        settings: Vec<PowerSettingRecord>,
+    },
+    /// Valeur `MSISupported` d'un device PCI (clé HKLM complète)
+    MsiDevice {
+        device_path: String,
+        original_msi_supported: Option<u32>,
+    },
+    ScheduledTask {
+        path: String,
+        original_enabled: bool,
+    },
+    /// Domaines ajoutés au bloc pieuvre du fichier hosts
+    HostsBlock {
+        domains: Vec<String>,
+    },
+    /// Élément BCD de l'entrée courante (`None` : élément absent)
+    Bcd {
+        element: String,
+        original_value: Option<String>,
+    },
+    /// DACL d'origine (SDDL) d'une clé ou d'un service
+    Dacl {
+        target: DaclTarget,
+        original_sddl: String,
+    },
+}
+
+/// Changement prévu par un dry-run : état actuel puis état cible
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlannedChange {
+    /// `None` : valeur absente (avant) ou supprimée (après)
+    Registry {
+        hive: RegistryHive,
+        key: String,
+        value_name: String,
+        current: Option<RegistryValue>,
+        target: Option<RegistryValue>,
+    },
+    /// `current_start_type: None` : service introuvable
+    Service {
+        name: String,
+        current_start_type: Option<u32>,
+        target_start_type: u32,
+    },
+    ScheduledTask {
+        path: String,
+        current_enabled: bool,
+        target_enabled: bool,
+    },
+    /// Domaines pas encore redirigés par le fichier hosts
+    HostsBlock { domains: Vec<String> },
+}
+
+/// Index AC/DC d'origine d'un paramètre d'alimentation (`None` : côté non modifié)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerSettingRecord {
+    pub scheme: String,
+    pub subgroup: String,
+    pub setting: String,
+    pub original_ac: Option<u32>,
+    pub original_dc: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DaclTarget {
+    /// Clé HKLM
+    RegistryKey {
+        key: String,
+    },
+    Service {
+        name: String,
+    },
 }
@@ -0,0 +1,101 @@
+//! Pristine Baseline Snapshot
+//!
+//! Original state of everything pieuvre can touch, captured once on the first
+//! run. The snapshot is pinned: rotation keeps it and it cannot be deleted.
+//! `reset_to_defaults` writes it back, `rollback --baseline` restores it.
+
+use crate::index::IndexEntry;
+use crate::restore::{RestoreFilter, RestoreSummary};
+use crate::snapshot;
+use crate::store::{configured_store, SnapshotStore};
+use pieuvre_common::{ChangeRecord, PieuvreError, Result, Snapshot};
+
+pub const BASELINE_TAG: &str = "baseline";
+
+/// Index entry of the baseline, if captured; the oldest one if several
+/// (a baseline signed by `--adopt-unsigned` after a later capture)
+pub fn find() -> Result<Option<IndexEntry>> {
+    find_in(configured_store().as_ref())
+}
+
+pub fn find_in(store: &dyn SnapshotStore) -> Result<Option<IndexEntry>> {
+    Ok(snapshot::list_in(store)?
+        .into_iter()
+        .filter(|entry| entry.tags.iter().any(|tag| tag == BASELINE_TAG))
+        .min_by_key(|entry| entry.timestamp))
+}
+
+/// Captures the baseline unless one exists; `capture` reads the system and is
+/// only called on the first run. Refused while some snapshots are unreadable
+/// (unsigned, corrupt): the system was modified, and one may be the baseline.
+pub fn capture_if_missing(capture: impl FnOnce() -> Vec<ChangeRecord>) -> Result<Option<Snapshot>> {
+    capture_if_missing_in(configured_store().as_ref(), capture)
+}
+
+pub fn capture_if_missing_in(
+    store: &dyn SnapshotStore,
+    capture: impl FnOnce() -> Vec<ChangeRecord>,
+) -> Result<Option<Snapshot>> {
+    if find_in(store)?.is_some() {
+        return Ok(None);
+    }
+    let unreadable = store
+        .ids()?
+        .iter()
+        .filter(|id| store.load_envelope(id).is_err())
+        .count();
+    if unreadable > 0 {
+        return Err(PieuvreError::Security(format!(
+            "{} unreadable snapshot(s) in {}: baseline not captured",
+            unreadable,
+            store.location()
+        )));
+    }
+
+    // Des snapshots existent déjà : le système a pu être modifié avant
+    let description = if snapshot::list_in(store)?.is_empty() {
+        "Pristine baseline".to_string()
+    } else {
+        "Baseline (captured after earlier changes)".to_string()
+    };
+    let changes = capture();
+    let snapshot =
+        snapshot::create_pinned_in(store, &description, changes, vec![BASELINE_TAG.to_string()])?;
+    tracing::info!(
+        id = %snapshot.id,
+        changes = snapshot.changes.len(),
+        "Baseline captured"
+    );
+    Ok(Some(snapshot))
+}
+
+/// Captured original state, `None` before the first capture
+pub fn load() -> Result<Option<Snapshot>> {
+    load_in(configured_store().as_ref())
+}
+
+pub fn load_in(store: &dyn SnapshotStore) -> Result<Option<Snapshot>> {
+    match find_in(store)? {
+        Some(entry) => store.load(&entry.id).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Restores the baseline changes selected by `filter` (all when empty)
+pub fn restore(filter: &RestoreFilter) -> Result<RestoreSummary> {
+    restore_in(
+        configured_store().as_ref(),
+        filter,
+        &pieuvre_sync::rollback::restore_change,
+    )
+}
+
+pub fn restore_in(
+    store: &dyn SnapshotStore,
+    filter: &RestoreFilter,
+    restore_change: &dyn Fn(&ChangeRecord) -> Result<()>,
+) -> Result<RestoreSummary> {
+    let entry = find_in(store)?
+        .ok_or_else(|| PieuvreError::SnapshotNotFound("no baseline captured".to_string()))?;
+    snapshot::restore_selected_in(store, &entry.id, filter, restore_change)
+}
@@ -0,0 +1,153 @@
+//! Snapshot File Format
+//!
+//! Snapshots are stored in a versioned envelope (format version, pieuvre
+//! version, host, configuration in effect). Older formats are migrated on read,
+//! one version at a time, before deserialization.
+
+use pieuvre_common::{config, native_registry, PieuvreError, RegistryPath, Result, Snapshot};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Current envelope version. v1: bare `Snapshot` JSON (before versioning)
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Machine that created the snapshot
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub hostname: String,
+    pub os: String,
+    /// Windows build (`CurrentBuildNumber`), empty if unknown
+    #[serde(default)]
+    pub os_build: String,
+}
+
+impl HostInfo {
+    pub fn current() -> Self {
+        let os_build = native_registry()
+            .read_string(
+                &RegistryPath::hklm(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion"),
+                "CurrentBuildNumber",
+            )
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        Self {
+            hostname: std::env::var("COMPUTERNAME").unwrap_or_else(|_| "Unknown".into()),
+            os: std::env::consts::OS.to_string(),
+            os_build,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotEnvelope {
+    pub format_version: u32,
+    /// Version of pieuvre that wrote the snapshot ("unknown" for migrated v1)
+    pub pieuvre_version: String,
+    pub host: HostInfo,
+    /// Configuration in effect, kept as JSON so it stays readable when
+    /// `PieuvreConfig` changes (`null` for migrated v1)
+    #[serde(default)]
+    pub config: Value,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Kept by rotation, cannot be deleted
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    pub snapshot: Snapshot,
+}
+
+impl SnapshotEnvelope {
+    /// Envelope for a snapshot taken now, on this machine
+    pub fn new(snapshot: Snapshot) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            pieuvre_version: env!("CARGO_PKG_VERSION").to_string(),
+            host: HostInfo::current(),
+            config: serde_json::to_value(config()).unwrap_or(Value::Null),
+            tags: Vec::new(),
+            pinned: false,
+            snapshot,
+        }
+    }
+}
+
+/// Envelope read from storage, with the format version found on disk
+#[derive(Debug, Clone)]
+pub struct StoredSnapshot {
+    pub envelope: SnapshotEnvelope,
+    /// Before migration (`< FORMAT_VERSION`: migrated on read)
+    pub stored_version: u32,
+    /// SHA256 of the stored bytes
+    pub checksum: String,
+}
+
+pub fn encode(envelope: &SnapshotEnvelope) -> Result<Vec<u8>> {
+    serde_json::to_vec_pretty(envelope).map_err(|e| PieuvreError::Parse(e.to_string()))
+}
+
+/// SHA256 (hex) of an encoded envelope
+pub fn checksum(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Parses an envelope of any supported version, migrating it to the current one
+pub fn decode(data: &[u8]) -> Result<StoredSnapshot> {
+    let mut value: Value = serde_json::from_slice(data)
+        .map_err(|e| PieuvreError::Parse(format!("Invalid snapshot JSON: {}", e)))?;
+
+    let stored_version = version_of(&value)?;
+    if stored_version > FORMAT_VERSION {
+        return Err(PieuvreError::Parse(format!(
+            "Unsupported snapshot format v{} (this pieuvre reads up to v{})",
+            stored_version, FORMAT_VERSION
+        )));
+    }
+    for migrate in &MIGRATIONS[stored_version as usize - 1..] {
+        value = migrate(value)?;
+    }
+
+    let envelope: SnapshotEnvelope = serde_json::from_value(value).map_err(|e| {
+        PieuvreError::Parse(format!(
+            "Invalid snapshot schema (v{}): {}",
+            stored_version, e
+        ))
+    })?;
+    Ok(StoredSnapshot {
+        envelope,
+        stored_version,
+        checksum: checksum(data),
+    })
+}
+
+fn version_of(value: &Value) -> Result<u32> {
+    match value.get("format_version") {
+        // Avant l'enveloppe : le snapshot était écrit tel quel
+        None => Ok(1),
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v >= 1)
+            .ok_or_else(|| PieuvreError::Parse(format!("Invalid format_version: {}", v))),
+    }
+}
+
+type Migration = fn(Value) -> Result<Value>;
+
+/// `MIGRATIONS[i]` upgrades version `i + 1` to `i + 2`
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2];
+
+/// v1 -> v2 : bare snapshot wrapped in an envelope
+fn migrate_v1_to_v2(snapshot: Value) -> Result<Value> {
+    Ok(serde_json::json!({
+        "format_version": 2,
+        "pieuvre_version": "unknown",
+        "host": HostInfo::default(),
+        "config": Value::Null,
+        "snapshot": snapshot,
+    }))
+}
@@ -0,0 +1,92 @@
+//! Snapshot Metadata Index
+//!
+//! One entry per snapshot (date, description, change count...) so that listings
+//! never decompress the snapshots themselves. The index is kept up to date on
+//! create, delete and rotation, and reconciled with the store when read.
+
+use crate::format::{SnapshotEnvelope, StoredSnapshot};
+use chrono::{DateTime, Utc};
+use pieuvre_common::TransactionOutcome;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub description: String,
+    pub change_count: usize,
+    #[serde(default)]
+    pub outcome: Option<TransactionOutcome>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Kept by rotation
+    #[serde(default)]
+    pub pinned: bool,
+    /// SHA256 of the stored envelope
+    pub checksum: String,
+    /// Stored size in bytes (0: unknown)
+    #[serde(default)]
+    pub size: u64,
+}
+
+impl IndexEntry {
+    pub fn new(envelope: &SnapshotEnvelope, checksum: String) -> Self {
+        let snapshot = &envelope.snapshot;
+        Self {
+            id: snapshot.id.to_string(),
+            timestamp: snapshot.timestamp,
+            description: snapshot.description.clone(),
+            change_count: snapshot.changes.len(),
+            outcome: snapshot.outcome,
+            tags: envelope.tags.clone(),
+            pinned: envelope.pinned,
+            checksum,
+            size: 0,
+        }
+    }
+
+    pub fn from_stored(stored: &StoredSnapshot) -> Self {
+        Self::new(&stored.envelope, stored.checksum.clone())
+    }
+
+    pub fn short_id(&self) -> &str {
+        self.id.get(..8).unwrap_or(&self.id)
+    }
+}
+
+/// Entries sorted most recent first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotIndex {
+    pub entries: Vec<IndexEntry>,
+}
+
+impl SnapshotIndex {
+    /// Adds or replaces the entry of a snapshot
+    pub fn upsert(&mut self, entry: IndexEntry) {
+        self.entries.retain(|e| e.id != entry.id);
+        let position = self
+            .entries
+            .iter()
+            .position(|e| e.timestamp < entry.timestamp)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(position, entry);
+    }
+
+    pub fn remove(&mut self, id: &str) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.id != id);
+        self.entries.len() != before
+    }
+
+    pub fn get(&self, id: &str) -> Option<&IndexEntry> {
+        self.entries.iter().find(|e| e.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
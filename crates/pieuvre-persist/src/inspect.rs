@@ -0,0 +1,464 @@
+//! Snapshot Inspection
+//!
+//! What a restore would do: each recorded change with the stored original
+//! state next to the live state, and the comparison of two snapshots. Used by
+//! `pieuvre rollback --show / --diff`; the views are plain data for the TUI.
+
+use crate::snapshot;
+use crate::store::{configured_store, SnapshotStore};
+use chrono::{DateTime, Utc};
+use pieuvre_common::{
+    native_registry, native_service_manager, ChangeRecord, DaclTarget, RegistryBackend,
+    RegistryPath, RegistryValue, Result, ServiceManager, ServiceStartType, Snapshot,
+    TransactionOutcome,
+};
+use serde::Serialize;
+
+const ABSENT: &str = "(absent)";
+const PRESENT: &str = "present";
+
+/// One restorable item of a change (a `PowerScheme` record gives one per setting)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangeView {
+    /// Number of the change in its snapshot, from 1 (`rollback --index`), 0 outside one
+    pub index: usize,
+    /// Tweak that produced the change, if recorded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tweak: Option<String>,
+    /// `registry`, `service`, `power`...
+    pub kind: &'static str,
+    pub target: String,
+    /// State a restore brings back
+    pub stored: String,
+    /// Live state (`None`: not read, or unreadable on this machine)
+    pub current: Option<String>,
+}
+
+impl ChangeView {
+    fn new(kind: &'static str, target: impl Into<String>, stored: impl Into<String>) -> Self {
+        Self {
+            index: 0,
+            tweak: None,
+            kind,
+            target: target.into(),
+            stored: stored.into(),
+            current: None,
+        }
+    }
+
+    /// Restoring would change nothing
+    pub fn is_current(&self) -> bool {
+        self.current.as_deref() == Some(self.stored.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotView {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub description: String,
+    pub outcome: Option<TransactionOutcome>,
+    pub changes: Vec<ChangeView>,
+}
+
+/// Snapshot found by ID or ID prefix, with the live state of each change
+pub fn show(id: &str) -> Result<SnapshotView> {
+    show_in(
+        configured_store().as_ref(),
+        id,
+        native_registry().as_ref(),
+        native_service_manager().as_ref(),
+    )
+}
+
+pub fn show_in(
+    store: &dyn SnapshotStore,
+    id: &str,
+    registry: &dyn RegistryBackend,
+    services: &dyn ServiceManager,
+) -> Result<SnapshotView> {
+    let snapshot = store.load(&snapshot::resolve_id_in(store, id)?)?;
+    let mut changes = Vec::new();
+    for (i, change) in snapshot.changes.iter().enumerate() {
+        let tweak = snapshot.origin_of(i).map(str::to_string);
+        changes.extend(
+            inspect_change_with(change, registry, services)
+                .into_iter()
+                .map(|view| ChangeView {
+                    index: i + 1,
+                    tweak: tweak.clone(),
+                    ..view
+                }),
+        );
+    }
+    Ok(SnapshotView {
+        id: snapshot.id.to_string(),
+        timestamp: snapshot.timestamp,
+        description: snapshot.description,
+        outcome: snapshot.outcome,
+        changes,
+    })
+}
+
+/// Stored state only, without touching the system
+pub fn inspect_change(record: &ChangeRecord) -> Vec<ChangeView> {
+    views(record, None)
+}
+
+/// Stored state next to the live state read through the given backends
+pub fn inspect_change_with(
+    record: &ChangeRecord,
+    registry: &dyn RegistryBackend,
+    services: &dyn ServiceManager,
+) -> Vec<ChangeView> {
+    views(record, Some((registry, services)))
+}
+
+type Live<'a> = Option<(&'a dyn RegistryBackend, &'a dyn ServiceManager)>;
+
+fn views(record: &ChangeRecord, live: Live) -> Vec<ChangeView> {
+    let reading = live.is_some();
+    let registry = live.map(|(registry, _)| registry);
+    let services = live.map(|(_, services)| services);
+
+    match record {
+        ChangeRecord::Registry {
+            hive,
+            key,
+            value_name,
+            original_value,
+        } => {
+            let path = RegistryPath::new(*hive, key);
+            let mut view = ChangeView::new(
+                "registry",
+                format!("{} : {}", path, value_name_label(value_name)),
+                value_label(original_value.as_ref()),
+            );
+            view.current = registry.and_then(|r| {
+                r.read_value(&path, value_name)
+                    .ok()
+                    .map(|v| value_label(v.as_ref()))
+            });
+            vec![view]
+        }
+        ChangeRecord::RegistryKeyCreated { hive, key } => {
+            let path = RegistryPath::new(*hive, key);
+            let mut view = ChangeView::new("registry-key", path.to_string(), ABSENT);
+            view.current = registry.map(|r| presence(r.key_exists(&path)));
+            vec![view]
+        }
+        ChangeRecord::RegistryKeyDeleted { hive, key, tree } => {
+            let path = RegistryPath::new(*hive, key);
+            let mut view = ChangeView::new(
+                "registry-key",
+                path.to_string(),
+                format!(
+                    "{} ({} values, {} subkeys)",
+                    PRESENT,
+                    tree.values.len(),
+                    tree.subkeys.len()
+                ),
+            );
+            view.current = registry.map(|r| {
+                if !r.key_exists(&path) {
+                    return ABSENT.to_string();
+                }
+                match r.export_tree(&path) {
+                    Ok(current) if current == *tree => view.stored.clone(),
+                    Ok(current) => format!(
+                        "{} ({} values, {} subkeys)",
+                        PRESENT,
+                        current.values.len(),
+                        current.subkeys.len()
+                    ),
+                    Err(_) => PRESENT.to_string(),
+                }
+            });
+            vec![view]
+        }
+        ChangeRecord::Service {
+            name,
+            original_start_type,
+        } => {
+            let mut view = ChangeView::new("service", name, start_type_label(*original_start_type));
+            view.current = services.map(|s| match s.start_type(name) {
+                Ok(start_type) => start_type_label(start_type),
+                Err(_) => ABSENT.to_string(),
+            });
+            vec![view]
+        }
+        ChangeRecord::FirewallRule { name } => {
+            let mut view = ChangeView::new("firewall", name, ABSENT);
+            if reading {
+                view.current = pieuvre_sync::firewall::list_pieuvre_rules()
+                    .ok()
+                    .map(|rules| presence(rules.iter().any(|rule| rule.name == *name)));
+            }
+            vec![view]
+        }
+        ChangeRecord::AppX { package_full_name } => {
+            let mut view = ChangeView::new("appx", package_full_name, "installed");
+            if reading {
+                let name = package_full_name.split('_').next().unwrap_or_default();
+                view.current = Some(
+                    if pieuvre_sync::appx::is_package_installed(name) {
+                        "installed"
+                    } else {
+                        "removed"
+                    }
+                    .to_string(),
+                );
+            }
+            vec![view]
+        }
+        ChangeRecord::PowerScheme {
+            original_scheme,
+            settings,
+        } => {
+            let mut views: Vec<ChangeView> = settings
+                .iter()
+                .map(|setting| {
+                    let mut view = ChangeView::new(
+                        "power",
+                        format!(
+                            "{}\\{}\\{}",
+                            setting.scheme, setting.subgroup, setting.setting
+                        ),
+                        ac_dc_label(setting.original_ac, setting.original_dc),
+                    );
+                    if reading {
+                        let (ac, dc) = pieuvre_sync::power::read_setting(
+                            &setting.scheme,
+                            &setting.subgroup,
+                            &setting.setting,
+                        );
+                        // Seuls les index modifiés sont restaurés
+                        view.current = Some(ac_dc_label(
+                            ac.filter(|_| setting.original_ac.is_some()),
+                            dc.filter(|_| setting.original_dc.is_some()),
+                        ));
+                    }
+                    view
+                })
+                .collect();
+            if let Some(scheme) = original_scheme {
+                let mut view = ChangeView::new("power", "active scheme", scheme);
+                if reading {
+                    view.current = pieuvre_sync::power::get_active_scheme_guid().ok();
+                }
+                views.insert(0, view);
+            }
+            views
+        }
+        ChangeRecord::MsiDevice {
+            device_path,
+            original_msi_supported,
+        } => {
+            let path = RegistryPath::hklm(device_path);
+            let mut view =
+                ChangeView::new("msi", device_path, dword_label(*original_msi_supported));
+            view.current =
+                registry.and_then(|r| r.read_dword(&path, "MSISupported").ok().map(dword_label));
+            vec![view]
+        }
+        ChangeRecord::ScheduledTask {
+            path,
+            original_enabled,
+        } => {
+            let mut view = ChangeView::new("task", path, enabled_label(*original_enabled));
+            if reading {
+                view.current = Some(
+                    pieuvre_sync::scheduled_tasks::task_state(path)
+                        .map(enabled_label)
+                        .unwrap_or_else(|| ABSENT.to_string()),
+                );
+            }
+            vec![view]
+        }
+        ChangeRecord::HostsBlock { domains } => domains
+            .iter()
+            .map(|domain| {
+                let mut view = ChangeView::new("hosts", domain, "not blocked");
+                if reading {
+                    view.current =
+                        pieuvre_sync::hosts::pending_domains(std::slice::from_ref(domain))
+                            .ok()
+                            .map(|pending| {
+                                if pending.is_empty() {
+                                    "blocked"
+                                } else {
+                                    "not blocked"
+                                }
+                                .to_string()
+                            });
+                }
+                view
+            })
+            .collect(),
+        ChangeRecord::Bcd {
+            element,
+            original_value,
+        } => {
+            let mut view =
+                ChangeView::new("bcd", element, original_value.as_deref().unwrap_or(ABSENT));
+            if reading {
+                view.current = pieuvre_sync::bcd::read_element(element)
+                    .ok()
+                    .map(|value| value.unwrap_or_else(|| ABSENT.to_string()));
+            }
+            vec![view]
+        }
+        ChangeRecord::Dacl {
+            target,
+            original_sddl,
+        } => {
+            let (label, current) = match target {
+                DaclTarget::RegistryKey { key } => (
+                    format!("HKLM\\{}", key),
+                    reading
+                        .then(|| pieuvre_sync::hardening::registry_key_sddl(key).ok())
+                        .flatten(),
+                ),
+                DaclTarget::Service { name } => (
+                    format!("service {}", name),
+                    services.and_then(|s| s.security_descriptor(name).ok()),
+                ),
+            };
+            let mut view = ChangeView::new("dacl", label, original_sddl);
+            view.current = current;
+            vec![view]
+        }
+    }
+}
+
+fn value_name_label(name: &str) -> &str {
+    if name.is_empty() {
+        "(default)"
+    } else {
+        name
+    }
+}
+
+fn value_label(value: Option<&RegistryValue>) -> String {
+    value.map_or_else(|| ABSENT.to_string(), |v| v.to_string())
+}
+
+fn dword_label(value: Option<u32>) -> String {
+    value.map_or_else(|| ABSENT.to_string(), |v| v.to_string())
+}
+
+fn start_type_label(start_type: u32) -> String {
+    format!(
+        "{:?} ({})",
+        ServiceStartType::from_raw(start_type),
+        start_type
+    )
+}
+
+fn enabled_label(enabled: bool) -> String {
+    if enabled { "enabled" } else { "disabled" }.to_string()
+}
+
+fn presence(exists: bool) -> String {
+    if exists { PRESENT } else { ABSENT }.to_string()
+}
+
+fn ac_dc_label(ac: Option<u32>, dc: Option<u32>) -> String {
+    let index = |v: Option<u32>| v.map_or_else(|| "-".to_string(), |v| v.to_string());
+    format!("AC {} / DC {}", index(ac), index(dc))
+}
+
+// ============================================
+// COMPARAISON
+// ============================================
+
+/// Item present in both snapshots with different stored states
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangedItem {
+    pub kind: &'static str,
+    pub target: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Comparison of the stored states of two snapshots (`a` then `b`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SnapshotDiff {
+    pub a: String,
+    pub b: String,
+    /// Only in `a`
+    pub removed: Vec<ChangeView>,
+    /// Only in `b`
+    pub added: Vec<ChangeView>,
+    pub changed: Vec<ChangedItem>,
+    pub unchanged: usize,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares two snapshots of the configured store, found by ID or ID prefix
+pub fn diff(a: &str, b: &str) -> Result<SnapshotDiff> {
+    diff_in(configured_store().as_ref(), a, b)
+}
+
+pub fn diff_in(store: &dyn SnapshotStore, a: &str, b: &str) -> Result<SnapshotDiff> {
+    let a = store.load(&snapshot::resolve_id_in(store, a)?)?;
+    let b = store.load(&snapshot::resolve_id_in(store, b)?)?;
+    Ok(diff_snapshots(&a, &b))
+}
+
+/// Items are matched on kind and target; when a target appears several times,
+/// the first recorded state wins (restored last, it is the one left in place)
+pub fn diff_snapshots(a: &Snapshot, b: &Snapshot) -> SnapshotDiff {
+    let items_a = stored_items(a);
+    let items_b = stored_items(b);
+
+    let mut diff = SnapshotDiff {
+        a: a.id.to_string(),
+        b: b.id.to_string(),
+        ..Default::default()
+    };
+    for view in &items_a {
+        match items_b
+            .iter()
+            .find(|other| other.kind == view.kind && other.target == view.target)
+        {
+            None => diff.removed.push(view.clone()),
+            Some(other) if other.stored != view.stored => diff.changed.push(ChangedItem {
+                kind: view.kind,
+                target: view.target.clone(),
+                before: view.stored.clone(),
+                after: other.stored.clone(),
+            }),
+            Some(_) => diff.unchanged += 1,
+        }
+    }
+    diff.added = items_b
+        .into_iter()
+        .filter(|view| {
+            !items_a
+                .iter()
+                .any(|other| other.kind == view.kind && other.target == view.target)
+        })
+        .collect();
+    diff
+}
+
+/// Stored items of a snapshot, one per target
+fn stored_items(snapshot: &Snapshot) -> Vec<ChangeView> {
+    let mut items: Vec<ChangeView> = Vec::new();
+    // Restauration en ordre inverse : la première occurrence enregistrée l'emporte
+    for view in snapshot.changes.iter().flat_map(inspect_change) {
+        if !items
+            .iter()
+            .any(|item| item.kind == view.kind && item.target == view.target)
+        {
+            items.push(view);
+        }
+    }
+    items
+}
@@ -0,0 +1,287 @@
+//! Write-Ahead Journal
+//!
+//! Every `ChangeRecord` is appended (and fsynced) to `journal.wal` before the
+//! change is made, so original values survive a crash or a blue screen in the
+//! middle of an apply. The journal is removed when pieuvre exits normally, unless
+//! a snapshot could not be written (`keep`); one found at startup is unfinished
+//! and can be turned into a snapshot or rolled back.
+
+use crate::restore::RestoreSummary;
+use chrono::{DateTime, Utc};
+use pieuvre_common::{ChangeRecord, PieuvreError, Result, Snapshot};
+use pieuvre_sync::changes::ChangeJournal;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// One line of the journal (JSON Lines)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// First line, written with the first change
+    Begin {
+        started: DateTime<Utc>,
+        pid: u32,
+        pieuvre_version: String,
+    },
+    Change {
+        at: DateTime<Utc>,
+        change: ChangeRecord,
+    },
+}
+
+/// Append-only journal file, created on the first change
+pub struct Journal {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+    /// An unfinished journal was already there, or a snapshot was not written:
+    /// appended to and never removed
+    keep: AtomicBool,
+}
+
+impl Journal {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            keep: AtomicBool::new(path.exists()),
+            path,
+            file: Mutex::new(None),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn open(&self) -> Result<File> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        Self::write(
+            &mut file,
+            &JournalEntry::Begin {
+                started: Utc::now(),
+                pid: std::process::id(),
+                pieuvre_version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+        )?;
+        Ok(file)
+    }
+
+    /// Writes `entry` and waits until it is on disk
+    fn write(file: &mut File, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry).map_err(|e| PieuvreError::Parse(e.to_string()))?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// The journaled changes are not saved elsewhere: the file outlives the session
+    pub fn keep(&self) {
+        self.keep.store(true, Ordering::SeqCst);
+    }
+
+    /// Normal end of the session: the journal file is removed
+    pub fn close(&self) -> Result<()> {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        *file = None;
+        if self.keep.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        remove(&self.path)
+    }
+}
+
+impl ChangeJournal for Journal {
+    fn append(&self, change: &ChangeRecord) -> Result<()> {
+        let mut guard = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = match guard.take() {
+            Some(file) => file,
+            None => self.open()?,
+        };
+        let result = Self::write(
+            &mut file,
+            &JournalEntry::Change {
+                at: Utc::now(),
+                change: change.clone(),
+            },
+        );
+        *guard = Some(file);
+        result
+    }
+}
+
+/// Journal left by a session that did not end normally
+#[derive(Debug, Clone)]
+pub struct PendingJournal {
+    pub path: PathBuf,
+    /// Start of the (first) interrupted session
+    pub started: Option<DateTime<Utc>>,
+    /// Changes in journal order, one per target (first original state kept)
+    pub changes: Vec<ChangeRecord>,
+    /// Unreadable lines, usually a write cut by the crash
+    pub skipped: usize,
+}
+
+/// Installs the journal for the whole process: changes recorded by
+/// `pieuvre_sync::changes` are written to it before being made
+pub struct JournalGuard {
+    journal: Arc<Journal>,
+}
+
+/// Journal installed for the process, marked by `keep`
+static INSTALLED: Mutex<Option<Arc<Journal>>> = Mutex::new(None);
+
+impl Drop for JournalGuard {
+    fn drop(&mut self) {
+        pieuvre_sync::changes::set_journal(None);
+        *INSTALLED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        // Panique : le journal reste pour la récupération au prochain démarrage
+        if !std::thread::panicking() {
+            if let Err(e) = self.journal.close() {
+                tracing::warn!(error = %e, "Journal not removed");
+            }
+        }
+    }
+}
+
+/// Starts journaling to `journal.wal`; the journal is removed when the guard
+/// is dropped, unless an unfinished one was already there or `keep` was called
+pub fn install() -> JournalGuard {
+    install_at(get_journal_path())
+}
+
+pub fn install_at(path: impl Into<PathBuf>) -> JournalGuard {
+    let journal = Arc::new(Journal::new(path));
+    pieuvre_sync::changes::set_journal(Some(journal.clone()));
+    *INSTALLED.lock().unwrap_or_else(|e| e.into_inner()) = Some(journal.clone());
+    JournalGuard { journal }
+}
+
+/// A snapshot of journaled changes could not be written: the installed journal
+/// is kept for `pieuvre recover` instead of being removed at exit
+pub fn keep() {
+    if let Some(journal) = INSTALLED.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        journal.keep();
+    }
+}
+
+/// Unfinished journal, if any
+pub fn pending() -> Result<Option<PendingJournal>> {
+    pending_at(&get_journal_path())
+}
+
+pub fn pending_at(path: &Path) -> Result<Option<PendingJournal>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(path)?;
+
+    let mut started = None;
+    let mut changes = Vec::new();
+    let mut skipped = 0;
+    for line in data.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str::<JournalEntry>(line) {
+            Ok(JournalEntry::Begin { started: at, .. }) => {
+                started.get_or_insert(at);
+            }
+            Ok(JournalEntry::Change { change, .. }) => changes.push(change),
+            Err(e) => {
+                tracing::warn!(error = %e, "Unreadable journal line skipped");
+                skipped += 1;
+            }
+        }
+    }
+
+    Ok(Some(PendingJournal {
+        path: path.to_path_buf(),
+        started,
+        changes: pieuvre_sync::changes::merged(changes),
+        skipped,
+    }))
+}
+
+/// Saves the journaled changes as a snapshot, then removes the journal
+pub fn finish(pending: &PendingJournal) -> Result<Option<Snapshot>> {
+    finish_in(crate::store::configured_store().as_ref(), pending)
+}
+
+pub fn finish_in(
+    store: &dyn crate::store::SnapshotStore,
+    pending: &PendingJournal,
+) -> Result<Option<Snapshot>> {
+    let snapshot = if pending.changes.is_empty() {
+        None
+    } else {
+        let started = pending
+            .started
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "unknown date".to_string());
+        let description = format!("Recovered from interrupted session ({})", started);
+        Some(crate::snapshot::create_in(
+            store,
+            &description,
+            pending.changes.clone(),
+        )?)
+    };
+    remove(&pending.path)?;
+    Ok(snapshot)
+}
+
+/// Restores the journaled changes (last first), then removes the journal.
+/// The journal is kept if a restoration failed.
+pub fn rollback(pending: &PendingJournal) -> Result<RestoreSummary> {
+    rollback_with(pending, &pieuvre_sync::rollback::restore_change)
+}
+
+pub fn rollback_with(
+    pending: &PendingJournal,
+    restore_change: &dyn Fn(&ChangeRecord) -> Result<()>,
+) -> Result<RestoreSummary> {
+    let mut summary = RestoreSummary {
+        selected: pending.changes.len(),
+        ..Default::default()
+    };
+    for change in pending.changes.iter().rev() {
+        match restore_change(change) {
+            Ok(()) => summary.restored += 1,
+            Err(e) => {
+                tracing::warn!(change = ?change, error = %e, "Restoration failed");
+                summary.failed += 1;
+            }
+        }
+    }
+    if summary.failed == 0 {
+        remove(&pending.path)?;
+    }
+    Ok(summary)
+}
+
+/// Drops the journal without restoring anything
+pub fn discard(pending: &PendingJournal) -> Result<()> {
+    remove(&pending.path)
+}
+
+fn remove(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// `journal.wal`, next to the snapshot directory
+pub fn get_journal_path() -> PathBuf {
+    let dir = crate::snapshot::get_snapshot_dir();
+    dir.parent()
+        .map(|parent| parent.to_path_buf())
+        .unwrap_or(dir)
+        .join("journal.wal")
+}
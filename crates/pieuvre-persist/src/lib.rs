@@ -1,17 +1,27 @@
 //! pieuvre Persistence Engine
 //!
-//! Gestion des snapshots et rollback.
+//! Gestion des snapshots (stockage configurable) et rollback.
 
+pub mod baseline;
+pub mod format;
+pub mod index;
+pub mod inspect;
+pub mod journal;
+pub mod reboot;
+pub mod restore;
+pub mod retention;
+pub mod signing;
 pub mod snapshot;
+pub mod store;
 
 #[cfg(test)]
 mod tests;
 
 use pieuvre_common::Result;
 
-/// Liste tous les snapshots disponibles
-pub fn list_snapshots() -> Result<Vec<pieuvre_common::Snapshot>> {
-    snapshot::list_all()
+/// Liste les snapshots disponibles (index de métadonnées, plus récent d'abord)
+pub fn list_snapshots() -> Result<Vec<index::IndexEntry>> {
+    snapshot::list()
 }
 
 /// Restaure un snapshot par ID
@@ -0,0 +1,118 @@
+//! Pending Reboot Queue
+//!
+//! Tweaks whose effect waits for the next boot (IPv6, VBS/HVCI, dynamic tick,
+//! MSI mode, memory compression...). An entry is dropped once the machine has
+//! booted after it was applied.
+
+use chrono::{DateTime, Duration, Utc};
+use pieuvre_common::{PieuvreError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// A tweak applied but not yet active
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingReboot {
+    pub tweak: String,
+    pub applied_at: DateTime<Utc>,
+    /// Changes recorded by the apply
+    pub changes: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RebootQueue {
+    pub entries: Vec<PendingReboot>,
+}
+
+impl RebootQueue {
+    /// Adds a tweak; re-applying it only refreshes its entry
+    pub fn push(&mut self, tweak: &str, changes: usize, applied_at: DateTime<Utc>) {
+        self.entries.retain(|e| e.tweak != tweak);
+        self.entries.push(PendingReboot {
+            tweak: tweak.to_string(),
+            applied_at,
+            changes,
+        });
+    }
+
+    /// Drops entries applied before `boot_time`; returns how many were removed
+    pub fn prune(&mut self, boot_time: DateTime<Utc>) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|e| e.applied_at > boot_time);
+        before - self.entries.len()
+    }
+
+    pub fn is_pending(&self, tweak: &str) -> bool {
+        self.entries.iter().any(|e| e.tweak == tweak)
+    }
+
+    /// Changes waiting for a reboot; a tweak without recorded changes
+    /// (MMAgent, bcdedit failure...) still counts as one
+    pub fn change_count(&self) -> usize {
+        self.entries.iter().map(|e| e.changes.max(1)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Records a tweak waiting for a reboot
+pub fn record(tweak: &str, changes: usize) -> Result<()> {
+    let mut queue = pending()?;
+    queue.push(tweak, changes, Utc::now());
+    save(&queue)
+}
+
+/// Tweaks still waiting for a reboot (the queue is pruned against the last boot)
+pub fn pending() -> Result<RebootQueue> {
+    let mut queue = load()?;
+    if let Some(boot_time) = last_boot_time() {
+        if queue.prune(boot_time) > 0 {
+            tracing::info!(
+                remaining = queue.entries.len(),
+                "Reboot detected, queue pruned"
+            );
+            save(&queue)?;
+        }
+    }
+    Ok(queue)
+}
+
+/// Boot time derived from the uptime (one second early, so an apply made right
+/// after boot stays pending)
+pub fn last_boot_time() -> Option<DateTime<Utc>> {
+    // SAFETY: GetTickCount64 has no preconditions
+    let uptime_ms = unsafe { windows::Win32::System::SystemInformation::GetTickCount64() };
+    let uptime = Duration::milliseconds(i64::try_from(uptime_ms).ok()?);
+    Utc::now().checked_sub_signed(uptime + Duration::seconds(1))
+}
+
+fn load() -> Result<RebootQueue> {
+    let path = get_queue_path();
+    if !path.exists() {
+        return Ok(RebootQueue::default());
+    }
+    let data = fs::read(&path)?;
+    serde_json::from_slice(&data).map_err(|e| PieuvreError::Parse(e.to_string()))
+}
+
+fn save(queue: &RebootQueue) -> Result<()> {
+    let path = get_queue_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json =
+        serde_json::to_string_pretty(queue).map_err(|e| PieuvreError::Parse(e.to_string()))?;
+    fs::write(&path, json)?;
+    Ok(())
+}
+
+/// `pending_reboot.json`, next to the snapshot directory
+pub fn get_queue_path() -> PathBuf {
+    let dir = crate::snapshot::get_snapshot_dir();
+    dir.parent()
+        .map(|parent| parent.to_path_buf())
+        .unwrap_or(dir)
+        .join("pending_reboot.json")
+}
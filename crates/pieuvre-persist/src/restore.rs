@@ -0,0 +1,190 @@
+//! Selective Restore
+//!
+//! Which changes of a snapshot to restore: by index, kind, registry key prefix
+//! or originating tweak. Criteria of different types must all match; several
+//! values of the same criterion are alternatives. An empty filter selects
+//! everything.
+
+use pieuvre_common::{ChangeRecord, DaclTarget, PieuvreError, RegistryPath, Result, Snapshot};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Values and keys
+    Registry,
+    Service,
+    Firewall,
+    AppX,
+    Power,
+    Msi,
+    Task,
+    Hosts,
+    Bcd,
+    Dacl,
+}
+
+impl ChangeKind {
+    pub const ALL: [ChangeKind; 10] = [
+        ChangeKind::Registry,
+        ChangeKind::Service,
+        ChangeKind::Firewall,
+        ChangeKind::AppX,
+        ChangeKind::Power,
+        ChangeKind::Msi,
+        ChangeKind::Task,
+        ChangeKind::Hosts,
+        ChangeKind::Bcd,
+        ChangeKind::Dacl,
+    ];
+
+    pub fn of(record: &ChangeRecord) -> Self {
+        match record {
+            ChangeRecord::Registry { .. }
+            | ChangeRecord::RegistryKeyCreated { .. }
+            | ChangeRecord::RegistryKeyDeleted { .. } => ChangeKind::Registry,
+            ChangeRecord::Service { .. } => ChangeKind::Service,
+            ChangeRecord::FirewallRule { .. } => ChangeKind::Firewall,
+            ChangeRecord::AppX { .. } => ChangeKind::AppX,
+            ChangeRecord::PowerScheme { .. } => ChangeKind::Power,
+            ChangeRecord::MsiDevice { .. } => ChangeKind::Msi,
+            ChangeRecord::ScheduledTask { .. } => ChangeKind::Task,
+            ChangeRecord::HostsBlock { .. } => ChangeKind::Hosts,
+            ChangeRecord::Bcd { .. } => ChangeKind::Bcd,
+            ChangeRecord::Dacl { .. } => ChangeKind::Dacl,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeKind::Registry => "registry",
+            ChangeKind::Service => "service",
+            ChangeKind::Firewall => "firewall",
+            ChangeKind::AppX => "appx",
+            ChangeKind::Power => "power",
+            ChangeKind::Msi => "msi",
+            ChangeKind::Task => "task",
+            ChangeKind::Hosts => "hosts",
+            ChangeKind::Bcd => "bcd",
+            ChangeKind::Dacl => "dacl",
+        }
+    }
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ChangeKind {
+    type Err = PieuvreError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ChangeKind::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<&str> = ChangeKind::ALL.iter().map(|k| k.name()).collect();
+                PieuvreError::Config(format!(
+                    "Unknown change kind '{}' (expected: {})",
+                    s,
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RestoreFilter {
+    /// Change numbers, from 1 (as shown by `rollback --show`)
+    pub indices: Vec<usize>,
+    pub kinds: Vec<ChangeKind>,
+    /// Registry key prefixes, with or without hive (`HKLM\SOFTWARE\Policies`),
+    /// case-insensitive
+    pub key_prefixes: Vec<String>,
+    /// Tweaks that produced the changes (`Snapshot::origins`)
+    pub tweaks: Vec<String>,
+}
+
+impl RestoreFilter {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+            && self.kinds.is_empty()
+            && self.key_prefixes.is_empty()
+            && self.tweaks.is_empty()
+    }
+
+    /// Positions (from 0) of the selected changes of `snapshot`
+    pub fn select(&self, snapshot: &Snapshot) -> Result<Vec<usize>> {
+        let count = snapshot.changes.len();
+        if let Some(index) = self.indices.iter().find(|i| **i == 0 || **i > count) {
+            return Err(PieuvreError::Config(format!(
+                "Change #{} does not exist (snapshot has {} changes)",
+                index, count
+            )));
+        }
+        if !self.tweaks.is_empty() && snapshot.origins.is_empty() {
+            return Err(PieuvreError::Config(format!(
+                "Snapshot {} does not record which tweak produced each change",
+                snapshot.id
+            )));
+        }
+
+        Ok((0..count)
+            .filter(|index| self.matches(snapshot, *index))
+            .collect())
+    }
+
+    fn matches(&self, snapshot: &Snapshot, index: usize) -> bool {
+        let record = &snapshot.changes[index];
+        (self.indices.is_empty() || self.indices.contains(&(index + 1)))
+            && (self.kinds.is_empty() || self.kinds.contains(&ChangeKind::of(record)))
+            && (self.key_prefixes.is_empty()
+                || registry_path(record).is_some_and(|path| {
+                    self.key_prefixes
+                        .iter()
+                        .any(|prefix| key_matches(&path, prefix))
+                }))
+            && (self.tweaks.is_empty()
+                || snapshot
+                    .origin_of(index)
+                    .is_some_and(|tweak| self.tweaks.iter().any(|t| t == tweak)))
+    }
+}
+
+/// Registry key touched by a change, if any
+fn registry_path(record: &ChangeRecord) -> Option<RegistryPath> {
+    match record {
+        ChangeRecord::Registry { hive, key, .. }
+        | ChangeRecord::RegistryKeyCreated { hive, key }
+        | ChangeRecord::RegistryKeyDeleted { hive, key, .. } => Some(RegistryPath::new(*hive, key)),
+        ChangeRecord::MsiDevice { device_path, .. } => Some(RegistryPath::hklm(device_path)),
+        ChangeRecord::Dacl {
+            target: DaclTarget::RegistryKey { key },
+            ..
+        } => Some(RegistryPath::hklm(key)),
+        _ => None,
+    }
+}
+
+/// Whole key components only: `SOFTWARE\Pol` does not match `SOFTWARE\Policies`
+fn key_matches(path: &RegistryPath, prefix: &str) -> bool {
+    // Avec racine (HKLM\..., HKEY_LOCAL_MACHINE\...) la ruche doit aussi correspondre
+    let prefix = match RegistryPath::parse(prefix) {
+        Ok(parsed) if parsed.hive != path.hive => return false,
+        Ok(parsed) => parsed.key,
+        Err(_) => prefix.trim_matches('\\').to_string(),
+    };
+    let key = path.key.to_ascii_lowercase();
+    let prefix = prefix.to_ascii_lowercase();
+    prefix.is_empty() || key == prefix || key.starts_with(&format!("{}\\", prefix))
+}
+
+/// Result of a (possibly partial) restore
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RestoreSummary {
+    pub selected: usize,
+    pub restored: usize,
+    pub failed: usize,
+}
@@ -0,0 +1,106 @@
+//! Snapshot Retention
+//!
+//! Which snapshots rotation deletes: beyond `max_count`, older than `max_age`
+//! or over `max_total_size`. The newest snapshot of each of the last
+//! `keep_daily` days (and `keep_weekly` ISO weeks) that have snapshots is kept
+//! whatever the limits. Pinned snapshots are neither counted nor deleted.
+
+use crate::index::IndexEntry;
+use chrono::{DateTime, Datelike, Duration, Local, Utc};
+use pieuvre_common::SnapshotConfig;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_count: Option<usize>,
+    pub max_age: Option<Duration>,
+    /// Bytes, as stored (after compression)
+    pub max_total_size: Option<u64>,
+    pub keep_daily: usize,
+    pub keep_weekly: usize,
+}
+
+impl RetentionPolicy {
+    /// `[snapshot]` section; 0 disables the age and size limits
+    pub fn from_config(config: &SnapshotConfig) -> Self {
+        Self {
+            max_count: Some(config.max_count),
+            max_age: (config.max_age_days > 0).then(|| Duration::days(config.max_age_days.into())),
+            max_total_size: (config.max_total_size_mb > 0)
+                .then(|| config.max_total_size_mb.saturating_mul(1024 * 1024)),
+            keep_daily: config.keep_daily,
+            keep_weekly: config.keep_weekly,
+        }
+    }
+
+    /// Nothing is ever deleted
+    pub fn is_unlimited(&self) -> bool {
+        self.max_count.is_none() && self.max_age.is_none() && self.max_total_size.is_none()
+    }
+
+    /// IDs of the snapshots to delete; `entries` most recent first
+    pub fn expired(&self, entries: &[IndexEntry], now: DateTime<Utc>) -> Vec<String> {
+        let candidates: Vec<&IndexEntry> = entries.iter().filter(|e| !e.pinned).collect();
+        let kept = self.kept(&candidates);
+
+        // Les snapshots gardés par jour/semaine occupent le budget en premier
+        let mut count = kept.len();
+        let mut size: u64 = candidates
+            .iter()
+            .filter(|e| kept.contains(e.id.as_str()))
+            .map(|e| e.size)
+            .sum();
+
+        let mut expired = Vec::new();
+        for entry in candidates.iter().filter(|e| !kept.contains(e.id.as_str())) {
+            let over = self.max_count.is_some_and(|max| count >= max)
+                || self.max_age.is_some_and(|age| now - entry.timestamp > age)
+                || self
+                    .max_total_size
+                    .is_some_and(|max| size.saturating_add(entry.size) > max);
+            if over {
+                expired.push(entry.id.clone());
+            } else {
+                count += 1;
+                size += entry.size;
+            }
+        }
+        expired
+    }
+
+    /// Newest snapshot of each of the last `keep_daily` days and `keep_weekly` weeks
+    fn kept<'a>(&self, candidates: &[&'a IndexEntry]) -> HashSet<&'a str> {
+        let mut kept = HashSet::new();
+        let day = |e: &IndexEntry| e.timestamp.with_timezone(&Local).date_naive();
+        keep_newest_per(candidates, self.keep_daily, day, &mut kept);
+        keep_newest_per(
+            candidates,
+            self.keep_weekly,
+            |e| {
+                let week = day(e).iso_week();
+                (week.year(), week.week())
+            },
+            &mut kept,
+        );
+        kept
+    }
+}
+
+fn keep_newest_per<'a, K: PartialEq>(
+    candidates: &[&'a IndexEntry],
+    periods: usize,
+    period_of: impl Fn(&IndexEntry) -> K,
+    kept: &mut HashSet<&'a str>,
+) {
+    let mut seen: Vec<K> = Vec::new();
+    for entry in candidates {
+        if seen.len() >= periods {
+            break;
+        }
+        let period = period_of(entry);
+        if !seen.contains(&period) {
+            seen.push(period);
+            kept.insert(entry.id.as_str());
+        }
+    }
+}
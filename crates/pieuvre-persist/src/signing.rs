@@ -0,0 +1,377 @@
+//! Snapshot Signatures
+//!
+//! The `<id>.sha256` sidecar catches corruption, not edits: whoever changes a
+//! snapshot can rewrite its checksum. Each snapshot is also signed with
+//! HMAC-SHA256 under a machine-local key (`<id>.sig`). The key lives in
+//! `snapshot.key`, next to the snapshot directory, encrypted with DPAPI for the
+//! local machine. DPAPI machine scope lets any local account encrypt a key, so
+//! the file is created with its SYSTEM/Administrators-only DACL in one step and
+//! an existing key is refused unless its owner and DACL still match.
+
+use hmac::{Hmac, Mac};
+use pieuvre_common::{PieuvreError, Result};
+use sha2::Sha256;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+const KEY_LEN: usize = 32;
+
+/// Propriétaire Administrateurs ; SYSTEM et Administrateurs uniquement, sans héritage
+const KEY_FILE_SDDL: &str = "O:BAD:P(A;;FA;;;SY)(A;;FA;;;BA)";
+
+/// SYSTEM et Administrateurs, sous forme d'alias ou de SID
+const KEY_FILE_TRUSTEES: [&str; 4] = ["SY", "BA", "S-1-5-18", "S-1-5-32-544"];
+
+static MACHINE_KEY: OnceLock<std::result::Result<Arc<SigningKey>, String>> = OnceLock::new();
+
+/// Secret key signing the snapshots of this machine
+#[derive(Clone)]
+pub struct SigningKey([u8; KEY_LEN]);
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SigningKey(..)")
+    }
+}
+
+impl SigningKey {
+    pub fn from_bytes(bytes: [u8; KEY_LEN]) -> Self {
+        Self(bytes)
+    }
+
+    /// Hex HMAC-SHA256 of a snapshot: its ID, then its uncompressed JSON
+    pub fn sign(&self, id: &str, data: &[u8]) -> String {
+        hex::encode(self.mac(id, data).finalize().into_bytes())
+    }
+
+    /// Checks a signature written by [`SigningKey::sign`] (constant time)
+    pub fn verify(&self, id: &str, data: &[u8], signature: &str) -> Result<()> {
+        let valid = hex::decode(signature.trim())
+            .is_ok_and(|bytes| self.mac(id, data).verify_slice(&bytes).is_ok());
+        if !valid {
+            return Err(PieuvreError::Security(format!(
+                "Snapshot {} has an invalid signature; it may have been tampered with",
+                id
+            )));
+        }
+        Ok(())
+    }
+
+    fn mac(&self, id: &str, data: &[u8]) -> Hmac<Sha256> {
+        // HMAC accepte une clé de n'importe quelle longueur
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.0).expect("HMAC key");
+        mac.update(id.as_bytes());
+        mac.update(&[0]);
+        mac.update(data);
+        mac
+    }
+}
+
+/// Key of this machine, loaded once per process and created on first use.
+/// Snapshots already stored stay unsigned, hence refused, until an
+/// administrator adopts them (`pieuvre rollback --adopt-unsigned`).
+pub fn machine_key() -> Result<Arc<SigningKey>> {
+    MACHINE_KEY
+        .get_or_init(|| {
+            let path = get_key_path();
+            let (key, created) = load_or_create(&path).map_err(|e| e.to_string())?;
+            if created {
+                tracing::info!(path = %path.display(), "Snapshot signing key created");
+            }
+            Ok(Arc::new(key))
+        })
+        .clone()
+        .map_err(|e| PieuvreError::Security(format!("Snapshot signing key unavailable: {}", e)))
+}
+
+/// `snapshot.key`, next to the snapshot directory
+pub fn get_key_path() -> PathBuf {
+    let dir = crate::snapshot::get_snapshot_dir();
+    dir.parent()
+        .map(|parent| parent.to_path_buf())
+        .unwrap_or(dir)
+        .join("snapshot.key")
+}
+
+/// Reads the key at `path`, or creates it; `true` when created
+pub fn load_or_create(path: &Path) -> Result<(SigningKey, bool)> {
+    if !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let bytes = random_key()?;
+        // Déjà créé entre-temps par un autre processus : relu et vérifié comme tel
+        if create_restricted(path, &protect(&bytes)?)? {
+            return Ok((SigningKey(bytes), true));
+        }
+    }
+
+    let sddl = file_sddl(path)?;
+    if !is_restricted_sddl(&sddl) {
+        return Err(PieuvreError::Security(format!(
+            "{} is not owned by and restricted to SYSTEM/Administrators ({}); refusing it",
+            path.display(),
+            sddl
+        )));
+    }
+    let bytes = unprotect(&fs::read(path)?)?;
+    let bytes: [u8; KEY_LEN] = bytes
+        .try_into()
+        .map_err(|_| PieuvreError::Security(format!("{} is not a signing key", path.display())))?;
+    Ok((SigningKey(bytes), false))
+}
+
+/// `true` when the owner and every allowed account of `sddl` are SYSTEM or
+/// Administrators, and the DACL inherits nothing from its directory
+pub fn is_restricted_sddl(sddl: &str) -> bool {
+    let trusted = |sid: &str| {
+        KEY_FILE_TRUSTEES
+            .iter()
+            .any(|trustee| trustee.eq_ignore_ascii_case(sid))
+    };
+
+    let Some(rest) = sddl.strip_prefix("O:") else {
+        return false;
+    };
+    // Le propriétaire s'arrête à la section suivante (`G:`, `D:`, `S:`)
+    let owner_end = rest.find(':').map_or(rest.len(), |i| i.saturating_sub(1));
+    if !trusted(&rest[..owner_end]) {
+        return false;
+    }
+
+    let Some(dacl) = rest.find("D:").map(|i| &rest[i + 2..]) else {
+        return false;
+    };
+    let dacl = dacl.find("S:").map_or(dacl, |i| &dacl[..i]);
+    let Some(aces_start) = dacl.find('(') else {
+        return false;
+    };
+    if !dacl[..aces_start].contains('P') {
+        return false;
+    }
+
+    dacl[aces_start..]
+        .split(')')
+        .filter(|ace| !ace.is_empty())
+        .all(|ace| {
+            let fields: Vec<&str> = ace.trim_start_matches('(').split(';').collect();
+            match fields.as_slice() {
+                // Refus : sans danger quel que soit le compte
+                ["D" | "OD" | "XD", ..] => true,
+                [_, _, _, _, _, sid] => trusted(sid),
+                _ => false,
+            }
+        })
+}
+
+fn random_key() -> Result<[u8; KEY_LEN]> {
+    use windows::Win32::Security::Cryptography::{
+        BCryptGenRandom, BCRYPT_USE_SYSTEM_PREFERRED_RNG,
+    };
+
+    let mut bytes = [0u8; KEY_LEN];
+    unsafe { BCryptGenRandom(None, &mut bytes, BCRYPT_USE_SYSTEM_PREFERRED_RNG) }
+        .ok()
+        .map_err(|e| PieuvreError::Security(format!("Random key generation failed: {}", e)))?;
+    Ok(bytes)
+}
+
+/// DPAPI, machine scope: only this machine can decrypt the key
+fn protect(data: &[u8]) -> Result<Vec<u8>> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Security::Cryptography::{
+        CryptProtectData, CRYPTPROTECT_LOCAL_MACHINE, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
+    };
+
+    let input = CRYPT_INTEGER_BLOB {
+        cbData: data.len() as u32,
+        pbData: data.as_ptr() as *mut u8,
+    };
+    let mut output = CRYPT_INTEGER_BLOB::default();
+    unsafe {
+        CryptProtectData(
+            &input,
+            PCWSTR::null(),
+            None,
+            None,
+            None,
+            CRYPTPROTECT_LOCAL_MACHINE | CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+        .map_err(|e| PieuvreError::Security(format!("DPAPI encryption failed: {}", e)))?;
+        Ok(take_blob(output))
+    }
+}
+
+fn unprotect(data: &[u8]) -> Result<Vec<u8>> {
+    use windows::Win32::Security::Cryptography::{
+        CryptUnprotectData, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
+    };
+
+    let input = CRYPT_INTEGER_BLOB {
+        cbData: data.len() as u32,
+        pbData: data.as_ptr() as *mut u8,
+    };
+    let mut output = CRYPT_INTEGER_BLOB::default();
+    unsafe {
+        CryptUnprotectData(
+            &input,
+            None,
+            None,
+            None,
+            None,
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+        .map_err(|e| PieuvreError::Security(format!("DPAPI decryption failed: {}", e)))?;
+        Ok(take_blob(output))
+    }
+}
+
+/// Copies a blob allocated by DPAPI and frees it
+unsafe fn take_blob(blob: windows::Win32::Security::Cryptography::CRYPT_INTEGER_BLOB) -> Vec<u8> {
+    use windows::Win32::Foundation::{LocalFree, HLOCAL};
+
+    let bytes = std::slice::from_raw_parts(blob.pbData, blob.cbData as usize).to_vec();
+    let _ = LocalFree(Some(HLOCAL(blob.pbData as *mut _)));
+    bytes
+}
+
+/// Creates the key file with `KEY_FILE_SDDL` in one call (`CREATE_NEW`), so it
+/// never exists with inherited access; `false` when it already exists
+fn create_restricted(path: &Path, data: &[u8]) -> Result<bool> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{CloseHandle, LocalFree, ERROR_FILE_EXISTS, HLOCAL};
+    use windows::Win32::Security::Authorization::ConvertStringSecurityDescriptorToSecurityDescriptorW;
+    use windows::Win32::Security::{PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES};
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, FlushFileBuffers, WriteFile, CREATE_NEW, FILE_ATTRIBUTE_NORMAL,
+        FILE_GENERIC_WRITE, FILE_SHARE_NONE,
+    };
+
+    let path_wide: Vec<u16> = path
+        .to_string_lossy()
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let sddl_wide: Vec<u16> = KEY_FILE_SDDL
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
+    unsafe {
+        let mut sd = PSECURITY_DESCRIPTOR::default();
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            PCWSTR(sddl_wide.as_ptr()),
+            1,
+            &mut sd,
+            None,
+        )
+        .map_err(|e| PieuvreError::Security(format!("SDDL conversion failed: {}", e)))?;
+
+        let attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+            lpSecurityDescriptor: sd.0,
+            bInheritHandle: false.into(),
+        };
+        let handle = CreateFileW(
+            PCWSTR(path_wide.as_ptr()),
+            FILE_GENERIC_WRITE.0,
+            FILE_SHARE_NONE,
+            Some(&attributes),
+            CREATE_NEW,
+            FILE_ATTRIBUTE_NORMAL,
+            None,
+        );
+        let _ = LocalFree(Some(HLOCAL(sd.0 as *mut _)));
+
+        let handle = match handle {
+            Ok(handle) => handle,
+            Err(e) if e.code() == ERROR_FILE_EXISTS.to_hresult() => return Ok(false),
+            Err(e) => {
+                return Err(PieuvreError::Security(format!(
+                    "Cannot create {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        let written =
+            WriteFile(handle, Some(data), None, None).and_then(|()| FlushFileBuffers(handle));
+        let _ = CloseHandle(handle);
+
+        if let Err(e) = written {
+            // Une clé tronquée serait refusée à chaque lancement
+            let _ = fs::remove_file(path);
+            return Err(PieuvreError::Security(format!(
+                "Cannot write {}: {}",
+                path.display(),
+                e
+            )));
+        }
+    }
+    Ok(true)
+}
+
+/// Owner and DACL of the key file, as SDDL
+fn file_sddl(path: &Path) -> Result<String> {
+    use windows::core::{PCWSTR, PWSTR};
+    use windows::Win32::Foundation::{LocalFree, HLOCAL};
+    use windows::Win32::Security::Authorization::{
+        ConvertSecurityDescriptorToStringSecurityDescriptorW, GetNamedSecurityInfoW,
+        SDDL_REVISION_1, SE_FILE_OBJECT,
+    };
+    use windows::Win32::Security::{
+        DACL_SECURITY_INFORMATION, OWNER_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR,
+    };
+
+    let path_wide: Vec<u16> = path
+        .to_string_lossy()
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let information = OWNER_SECURITY_INFORMATION | DACL_SECURITY_INFORMATION;
+
+    unsafe {
+        let mut sd = PSECURITY_DESCRIPTOR::default();
+        let result = GetNamedSecurityInfoW(
+            PCWSTR(path_wide.as_ptr()),
+            SE_FILE_OBJECT,
+            information,
+            None,
+            None,
+            None,
+            None,
+            &mut sd,
+        );
+        if result.is_err() {
+            return Err(PieuvreError::Security(format!(
+                "Cannot read the security of {}: {:?}",
+                path.display(),
+                result
+            )));
+        }
+
+        let mut sddl = PWSTR::null();
+        let converted = ConvertSecurityDescriptorToStringSecurityDescriptorW(
+            sd,
+            SDDL_REVISION_1,
+            information,
+            &mut sddl,
+            None,
+        );
+        let text = converted
+            .ok()
+            .and_then(|_| sddl.to_string().ok())
+            .ok_or_else(|| {
+                PieuvreError::Security(format!("SDDL conversion failed for {}", path.display()))
+            });
+
+        if !sddl.is_null() {
+            let _ = LocalFree(Some(HLOCAL(sddl.0 as *mut _)));
+        }
+        let _ = LocalFree(Some(HLOCAL(sd.0 as *mut _)));
+        text
+    }
+}
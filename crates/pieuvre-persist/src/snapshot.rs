@@ -1,437 +1,532 @@
 //! Snapshot Management
 //!
-//! Backup and restoration of system modifications.
-//! zstd compression and SHA256 checksum validation.
-
+//! Backup and restoration of system modifications, through a `SnapshotStore`
+//! (the configured directory unless a store is passed explicitly). Listings
+//! read the metadata index, not the snapshots.
+
+use crate::baseline::BASELINE_TAG;
+use crate::format::{self, SnapshotEnvelope, FORMAT_VERSION};
+use crate::index::{IndexEntry, SnapshotIndex};
+use crate::restore::{RestoreFilter, RestoreSummary};
+use crate::store::{configured_store, SnapshotStore};
 use chrono::Utc;
-use pieuvre_common::{ChangeRecord, PieuvreError, RegistryHive, RegistryValue, Result, Snapshot};
-use sha2::{Digest, Sha256};
-use std::fs;
-use std::path::{Path, PathBuf};
+use pieuvre_common::{
+    config, ChangeOrigin, ChangeRecord, PieuvreError, Result, Snapshot, TransactionOutcome,
+};
+use pieuvre_sync::transaction::TransactionReport;
+use std::path::PathBuf;
 use uuid::Uuid;
 
-const SNAPSHOT_DIR: &str = r"C:\ProgramData\pieuvre\snapshots";
-const DEFAULT_MAX_SNAPSHOTS: usize = 10;
-
 // ============================================
 // SNAPSHOT CREATION
 // ============================================
 
-/// Creates a new snapshot with compression and checksum
+/// Creates a new snapshot in the configured store
 pub fn create(description: &str, changes: Vec<ChangeRecord>) -> Result<Snapshot> {
-    let snapshot = Snapshot {
-        id: Uuid::new_v4(),
-        timestamp: Utc::now(),
-        description: description.to_string(),
-        changes,
-    };
+    create_in(configured_store().as_ref(), description, changes)
+}
 
-    let dir = PathBuf::from(SNAPSHOT_DIR);
-    fs::create_dir_all(&dir)?;
+pub fn create_in(
+    store: &dyn SnapshotStore,
+    description: &str,
+    changes: Vec<ChangeRecord>,
+) -> Result<Snapshot> {
+    save(
+        store,
+        new_snapshot(description.to_string(), changes, Vec::new()),
+    )
+}
 
-    // Serialize to JSON
-    let json =
-        serde_json::to_string_pretty(&snapshot).map_err(|e| PieuvreError::Parse(e.to_string()))?;
+/// Creates a snapshot of the changes made by one tweak (selective restore by tweak)
+pub fn create_for_tweak(
+    tweak: &str,
+    description: &str,
+    changes: Vec<ChangeRecord>,
+) -> Result<Snapshot> {
+    create_for_tweak_in(configured_store().as_ref(), tweak, description, changes)
+}
 
-    // Save with compression
-    save_compressed(&dir, &snapshot.id.to_string(), json.as_bytes())?;
+pub fn create_for_tweak_in(
+    store: &dyn SnapshotStore,
+    tweak: &str,
+    description: &str,
+    changes: Vec<ChangeRecord>,
+) -> Result<Snapshot> {
+    let origins = vec![ChangeOrigin {
+        tweak: tweak.to_string(),
+        first: 0,
+        count: changes.len(),
+    }];
+    save(
+        store,
+        new_snapshot(description.to_string(), changes, origins),
+    )
+}
 
-    // Automatic rotation
-    rotate_snapshots(&dir)?;
+fn new_snapshot(
+    description: String,
+    changes: Vec<ChangeRecord>,
+    origins: Vec<ChangeOrigin>,
+) -> Snapshot {
+    Snapshot {
+        id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        description,
+        changes,
+        outcome: None,
+        origins,
+    }
+}
 
-    tracing::info!(id = %snapshot.id, description = %snapshot.description, "Snapshot created");
-    Ok(snapshot)
+/// Persists the changes of a transaction, tagged with its outcome.
+/// Nothing is saved when the transaction changed nothing.
+pub fn record_transaction(report: &TransactionReport) -> Result<Option<Snapshot>> {
+    record_transaction_in(configured_store().as_ref(), report)
 }
 
-/// Saves data with zstd compression and SHA256 checksum
-fn save_compressed(dir: &Path, id: &str, data: &[u8]) -> Result<()> {
-    // Calculate SHA256 checksum
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    let checksum = hasher.finalize();
-    let checksum_hex = hex_encode(&checksum);
-
-    // Compress with zstd (level 3 = good size/speed ratio)
-    let compressed =
-        zstd::encode_all(data, 3).map_err(|e| PieuvreError::Io(std::io::Error::other(e)))?;
-
-    // Save compressed file
-    let path = dir.join(format!("{}.json.zst", id));
-    fs::write(&path, &compressed)?;
-
-    // Save checksum
-    let checksum_path = dir.join(format!("{}.sha256", id));
-    fs::write(&checksum_path, &checksum_hex)?;
-
-    tracing::debug!(
-        id = id,
-        original_size = data.len(),
-        compressed_size = compressed.len(),
-        ratio = format!("{:.1}x", data.len() as f64 / compressed.len() as f64),
-        "Snapshot compressed"
-    );
+pub fn record_transaction_in(
+    store: &dyn SnapshotStore,
+    report: &TransactionReport,
+) -> Result<Option<Snapshot>> {
+    if report.changes.is_empty() {
+        return Ok(None);
+    }
+    let description = match report.outcome {
+        TransactionOutcome::Success => report.name.clone(),
+        _ => format!("{} ({})", report.name, report.summary()),
+    };
 
-    Ok(())
+    // Les changements des étapes se suivent dans l'ordre d'exécution
+    let mut origins = Vec::new();
+    let mut first = 0;
+    for step in report.steps.iter().filter(|step| step.changes > 0) {
+        origins.push(ChangeOrigin {
+            tweak: step.name.clone(),
+            first,
+            count: step.changes,
+        });
+        first += step.changes;
+    }
+
+    let snapshot = Snapshot {
+        outcome: Some(report.outcome),
+        ..new_snapshot(description, report.changes.clone(), origins)
+    };
+    save(store, snapshot).map(Some)
 }
 
-/// Loads a snapshot with decompression and checksum validation
-fn load_compressed(dir: &Path, id: &str) -> Result<Snapshot> {
-    // Look for compressed or uncompressed file (backward compatibility)
-    let zst_path = dir.join(format!("{}.json.zst", id));
-    let json_path = dir.join(format!("{}.json", id));
-
-    let data = if zst_path.exists() {
-        // Compressed file
-        let compressed = fs::read(&zst_path)?;
-        zstd::decode_all(compressed.as_slice())
-            .map_err(|e| PieuvreError::Parse(format!("Decompression failed: {}", e)))?
-    } else if json_path.exists() {
-        // Uncompressed JSON file (legacy)
-        fs::read(&json_path)?
-    } else {
-        return Err(PieuvreError::SnapshotNotFound(id.to_string()));
+/// Creates a tagged snapshot; a pinned one escapes rotation and deletion
+pub(crate) fn create_pinned_in(
+    store: &dyn SnapshotStore,
+    description: &str,
+    changes: Vec<ChangeRecord>,
+    tags: Vec<String>,
+) -> Result<Snapshot> {
+    let envelope = SnapshotEnvelope {
+        tags,
+        pinned: true,
+        ..SnapshotEnvelope::new(new_snapshot(description.to_string(), changes, Vec::new()))
     };
+    save_envelope(store, envelope)
+}
 
-    // Validate checksum if present
-    let checksum_path = dir.join(format!("{}.sha256", id));
-    if checksum_path.exists() {
-        let expected = fs::read_to_string(&checksum_path)?;
-
-        let mut hasher = Sha256::new();
-        hasher.update(&data);
-        let actual = hex_encode(&hasher.finalize());
-
-        if expected.trim() != actual {
-            return Err(PieuvreError::Parse(format!(
-                "Checksum mismatch: expected {}, got {}",
-                expected.trim(),
-                actual
-            )));
-        }
-        tracing::debug!(id = id, "Checksum validated");
-    }
+fn save(store: &dyn SnapshotStore, snapshot: Snapshot) -> Result<Snapshot> {
+    save_envelope(store, SnapshotEnvelope::new(snapshot))
+}
 
-    let snapshot: Snapshot =
-        serde_json::from_slice(&data).map_err(|e| PieuvreError::Parse(e.to_string()))?;
+fn save_envelope(store: &dyn SnapshotStore, envelope: SnapshotEnvelope) -> Result<Snapshot> {
+    // Index chargé avant l'écriture : le nouveau snapshot n'a pas à être relu
+    let mut index = load_index(store)?;
+    store.save_envelope(&envelope)?;
+    index.upsert(index_entry(store, &envelope)?);
+    store.write_index(&index)?;
+    let snapshot = envelope.snapshot;
+
+    // Automatic rotation
+    rotate_snapshots(store)?;
 
+    tracing::info!(
+        id = %snapshot.id,
+        description = %snapshot.description,
+        store = %store.location(),
+        "Snapshot created"
+    );
     Ok(snapshot)
 }
 
-/// Encodes bytes to hex string
-fn hex_encode(bytes: &[u8]) -> String {
-    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+/// Index entry of an envelope just written to `store`
+fn index_entry(store: &dyn SnapshotStore, envelope: &SnapshotEnvelope) -> Result<IndexEntry> {
+    let encoded = format::encode(envelope)?;
+    let mut entry = IndexEntry::new(envelope, format::checksum(&encoded));
+    entry.size = store.stored_size(&entry.id).unwrap_or(encoded.len() as u64);
+    Ok(entry)
 }
 
 // ============================================
 // ROTATION AUTOMATIQUE
 // ============================================
 
-/// Automatic snapshot rotation (keeps N most recent)
-fn rotate_snapshots(dir: &Path) -> Result<()> {
-    let mut snapshots = list_all_internal(dir)?;
-
-    if snapshots.len() <= DEFAULT_MAX_SNAPSHOTS {
+/// Automatic snapshot rotation, following the retention policy of the store
+/// (pinned snapshots are never deleted)
+fn rotate_snapshots(store: &dyn SnapshotStore) -> Result<()> {
+    let policy = store.retention();
+    if policy.is_unlimited() {
+        return Ok(());
+    }
+    // Sorted by date (most recent first)
+    let mut index = load_index(store)?;
+    let expired = policy.expired(&index.entries, Utc::now());
+    if expired.is_empty() {
         return Ok(());
     }
 
-    // Sort by date (most recent first)
-    snapshots.sort_by(|a, b| b.1.cmp(&a.1));
-
-    // Remove oldest
-    let to_remove = snapshots.len() - DEFAULT_MAX_SNAPSHOTS;
-    for (id, _) in snapshots.iter().skip(DEFAULT_MAX_SNAPSHOTS) {
-        let _ = delete_by_id(dir, id);
-        tracing::debug!(id = id, "Snapshot deleted (rotation)");
+    for id in &expired {
+        let _ = store.delete(id);
+        index.remove(id);
+        tracing::debug!(id = %id, "Snapshot deleted (rotation)");
     }
+    store.write_index(&index)?;
 
-    tracing::info!(
-        removed = to_remove,
-        max = DEFAULT_MAX_SNAPSHOTS,
-        "Snapshot rotation"
-    );
+    tracing::info!(removed = expired.len(), "Snapshot rotation");
 
     Ok(())
 }
 
-/// Internal list returning (id, timestamp)
-fn list_all_internal(dir: &Path) -> Result<Vec<(String, chrono::DateTime<Utc>)>> {
-    if !dir.exists() {
-        return Ok(Vec::new());
-    }
+// ============================================
+// ÉPINGLAGE
+// ============================================
 
-    let mut snapshots = Vec::new();
+/// Pins a snapshot: rotation keeps it and it cannot be deleted. `label` is
+/// added to its tags.
+pub fn pin(id: &str, label: Option<&str>) -> Result<IndexEntry> {
+    pin_in(configured_store().as_ref(), id, label)
+}
 
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-        // Supporter .json et .json.zst
-        let id = if filename.ends_with(".json.zst") {
-            filename.trim_end_matches(".json.zst")
-        } else if filename.ends_with(".json") && !filename.ends_with(".sha256") {
-            filename.trim_end_matches(".json")
-        } else {
-            continue;
-        };
-
-        // Try to load to get timestamp
-        if let Ok(snapshot) = load_compressed(dir, id) {
-            snapshots.push((id.to_string(), snapshot.timestamp));
+pub fn pin_in(store: &dyn SnapshotStore, id: &str, label: Option<&str>) -> Result<IndexEntry> {
+    update_envelope(store, id, |envelope| {
+        envelope.pinned = true;
+        if let Some(label) = label.map(str::trim).filter(|l| !l.is_empty()) {
+            if !envelope.tags.iter().any(|tag| tag == label) {
+                envelope.tags.push(label.to_string());
+            }
         }
-    }
-
-    Ok(snapshots)
+        Ok(())
+    })
 }
 
-/// Deletes a snapshot by ID
-fn delete_by_id(dir: &Path, id: &str) -> Result<()> {
-    let zst_path = dir.join(format!("{}.json.zst", id));
-    let json_path = dir.join(format!("{}.json", id));
-    let checksum_path = dir.join(format!("{}.sha256", id));
+/// Returns a snapshot to rotation (labels are kept); the baseline stays pinned
+pub fn unpin(id: &str) -> Result<IndexEntry> {
+    unpin_in(configured_store().as_ref(), id)
+}
 
-    if zst_path.exists() {
-        fs::remove_file(&zst_path)?;
-    }
-    if json_path.exists() {
-        fs::remove_file(&json_path)?;
-    }
-    if checksum_path.exists() {
-        fs::remove_file(&checksum_path)?;
-    }
+pub fn unpin_in(store: &dyn SnapshotStore, id: &str) -> Result<IndexEntry> {
+    update_envelope(store, id, |envelope| {
+        if envelope.tags.iter().any(|tag| tag == BASELINE_TAG) {
+            return Err(PieuvreError::Config(
+                "The baseline snapshot cannot be unpinned".to_string(),
+            ));
+        }
+        envelope.pinned = false;
+        Ok(())
+    })
+}
 
-    Ok(())
+/// Rewrites the envelope of a snapshot (ID or ID prefix) and its index entry
+fn update_envelope(
+    store: &dyn SnapshotStore,
+    id: &str,
+    update: impl FnOnce(&mut SnapshotEnvelope) -> Result<()>,
+) -> Result<IndexEntry> {
+    let id = resolve_id_in(store, id)?;
+    let mut index = load_index(store)?;
+    let mut envelope = store.load_envelope(&id)?.envelope;
+    update(&mut envelope)?;
+
+    store.save_envelope(&envelope)?;
+    let entry = index_entry(store, &envelope)?;
+    index.upsert(entry.clone());
+    store.write_index(&index)?;
+    Ok(entry)
 }
 
 // ============================================
 // API PUBLIQUE
 // ============================================
 
-/// Lists all snapshots
-pub fn list_all() -> Result<Vec<Snapshot>> {
-    let dir = PathBuf::from(SNAPSHOT_DIR);
+/// Snapshot metadata from the index, most recent first
+pub fn list() -> Result<Vec<IndexEntry>> {
+    list_in(configured_store().as_ref())
+}
+
+pub fn list_in(store: &dyn SnapshotStore) -> Result<Vec<IndexEntry>> {
+    Ok(load_index(store)?.entries)
+}
 
-    if !dir.exists() {
-        return Ok(Vec::new());
+/// Rebuilds the index by reading every snapshot; returns the indexed count
+pub fn rebuild_index() -> Result<usize> {
+    rebuild_index_in(configured_store().as_ref())
+}
+
+pub fn rebuild_index_in(store: &dyn SnapshotStore) -> Result<usize> {
+    let mut index = SnapshotIndex::default();
+    for id in store.ids()? {
+        index_snapshot(store, &mut index, &id);
     }
+    store.write_index(&index)?;
+    tracing::info!(count = index.len(), "Snapshot index rebuilt");
+    Ok(index.len())
+}
 
-    let mut snapshots = Vec::new();
+/// Index of `store`, reconciled with the stored IDs: missing or corrupt index
+/// entries are rebuilt from the snapshots, entries of deleted snapshots dropped
+fn load_index(store: &dyn SnapshotStore) -> Result<SnapshotIndex> {
+    let ids = store.ids()?;
+    let mut index = store.read_index()?.unwrap_or_default();
+
+    let mut changed = false;
+    for entry in index.entries.clone() {
+        if !ids.contains(&entry.id) {
+            changed |= index.remove(&entry.id);
+        } else if entry.size == 0 {
+            // Index écrit avant le suivi des tailles
+            if let Some(size) = store.stored_size(&entry.id) {
+                index.upsert(IndexEntry { size, ..entry });
+                changed = true;
+            }
+        }
+    }
+    for id in &ids {
+        if index.get(id).is_none() {
+            changed |= index_snapshot(store, &mut index, id);
+        }
+    }
+
+    if changed {
+        store.write_index(&index)?;
+    }
+    Ok(index)
+}
+
+/// Adds a snapshot read from the store; unreadable ones stay out of the index
+fn index_snapshot(store: &dyn SnapshotStore, index: &mut SnapshotIndex, id: &str) -> bool {
+    match store.load_envelope(id) {
+        Ok(stored) => {
+            let mut entry = IndexEntry::from_stored(&stored);
+            entry.size = store.stored_size(id).unwrap_or(0);
+            index.upsert(entry);
+            true
+        }
+        Err(e) => {
+            tracing::warn!(id = %id, error = %e, "Unreadable snapshot not indexed");
+            false
+        }
+    }
+}
+
+/// Loads every snapshot (slow: prefer [`list`] for listings)
+pub fn list_all() -> Result<Vec<Snapshot>> {
+    list_all_in(configured_store().as_ref())
+}
 
-    for entry in fs::read_dir(&dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-        // Supporter .json et .json.zst
-        let id = if filename.ends_with(".json.zst") {
-            filename.trim_end_matches(".json.zst")
-        } else if filename.ends_with(".json") && !filename.contains(".sha256") {
-            filename.trim_end_matches(".json")
-        } else {
-            continue;
-        };
-
-        if let Ok(snapshot) = load_compressed(&dir, id) {
-            snapshots.push(snapshot);
+/// Lists the snapshots of a store, most recent first (unreadable ones are skipped)
+pub fn list_all_in(store: &dyn SnapshotStore) -> Result<Vec<Snapshot>> {
+    let mut snapshots = Vec::new();
+    for id in store.ids()? {
+        match store.load(&id) {
+            Ok(snapshot) => snapshots.push(snapshot),
+            Err(e) => tracing::warn!(id = %id, error = %e, "Unreadable snapshot skipped"),
         }
     }
 
     // Sort by date descending
-    snapshots.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
 
     Ok(snapshots)
 }
 
 /// Loads a snapshot by ID
 pub fn load(id: &str) -> Result<Snapshot> {
-    let dir = PathBuf::from(SNAPSHOT_DIR);
-    load_compressed(&dir, id)
+    configured_store().load(id)
+}
+
+/// Full ID of the snapshot starting with `id`
+pub fn resolve_id_in(store: &dyn SnapshotStore, id: &str) -> Result<String> {
+    store
+        .ids()?
+        .into_iter()
+        .find(|snapshot_id| snapshot_id.starts_with(id))
+        .ok_or_else(|| PieuvreError::SnapshotNotFound(id.to_string()))
 }
 
 /// Restores a snapshot (applies original values)
 pub fn restore(id: &str) -> Result<()> {
-    let dir = PathBuf::from(SNAPSHOT_DIR);
-
-    // Search for snapshot starting with provided ID
-    let mut found_id: Option<String> = None;
-
-    if dir.exists() {
-        for entry in fs::read_dir(&dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-            if filename.starts_with(id) {
-                let snapshot_id = if filename.ends_with(".json.zst") {
-                    filename.trim_end_matches(".json.zst")
-                } else if filename.ends_with(".json") {
-                    filename.trim_end_matches(".json")
-                } else {
-                    continue;
-                };
-                found_id = Some(snapshot_id.to_string());
-                break;
-            }
-        }
-    }
+    restore_selected(id, &RestoreFilter::default()).map(|_| ())
+}
 
-    let snapshot_id = found_id.ok_or_else(|| PieuvreError::SnapshotNotFound(id.to_string()))?;
-    let snapshot = load_compressed(&dir, &snapshot_id)?;
-
-    tracing::info!(id = %snapshot.id, "Restoring snapshot");
-
-    let mut restored = 0;
-    let mut errors = 0;
-
-    for change in &snapshot.changes {
-        match change {
-            ChangeRecord::Registry {
-                hive,
-                key,
-                value_name,
-                original_value,
-            } => {
-                tracing::debug!(hive = ?hive, key = key, value_name = value_name, "Restoring registry");
-
-                let result = match original_value {
-                    Some(RegistryValue::Dword(val)) => match hive {
-                        RegistryHive::Hklm => {
-                            pieuvre_sync::registry::set_dword_value(key, value_name, *val)
-                        }
-                        RegistryHive::Hku => pieuvre_sync::registry::set_dword_value_in_hive(
-                            windows::Win32::System::Registry::HKEY_USERS,
-                            key,
-                            value_name,
-                            *val,
-                        ),
-                        RegistryHive::Hkcu => pieuvre_sync::registry::set_dword_value_in_hive(
-                            windows::Win32::System::Registry::HKEY_CURRENT_USER,
-                            key,
-                            value_name,
-                            *val,
-                        ),
-                    },
-                    Some(RegistryValue::String(val)) => match hive {
-                        RegistryHive::Hklm => {
-                            pieuvre_sync::registry::set_string_value(key, value_name, val)
-                        }
-                        RegistryHive::Hku => pieuvre_sync::registry::set_string_value_in_hive(
-                            windows::Win32::System::Registry::HKEY_USERS,
-                            key,
-                            value_name,
-                            val,
-                        ),
-                        RegistryHive::Hkcu => pieuvre_sync::registry::set_string_value_in_hive(
-                            windows::Win32::System::Registry::HKEY_CURRENT_USER,
-                            key,
-                            value_name,
-                            val,
-                        ),
-                    },
-                    Some(RegistryValue::Binary(_)) => {
-                        tracing::warn!("Binary restoration not yet implemented");
-                        Ok(())
-                    }
-                    None => {
-                        // Si la valeur n'existait pas, on tente de la supprimer
-                        match hive {
-                            RegistryHive::Hklm => {
-                                pieuvre_sync::registry::delete_value(key, value_name)
-                            }
-                            _ => Ok(()), // Suppression multi-ruche non critique pour le rollback simple
-                        }
-                    }
-                };
-
-                match result {
-                    Ok(_) => {
-                        tracing::info!(key = key, value_name = value_name, "Registry restored");
-                        restored += 1;
-                    }
-                    Err(e) => {
-                        tracing::warn!(key = key, error = %e, "Registry restoration failed");
-                        errors += 1;
-                    }
-                }
-            }
-            ChangeRecord::Service {
-                name,
-                original_start_type,
-            } => {
-                tracing::debug!(
-                    service = name,
-                    start_type = original_start_type,
-                    "Restoring service"
-                );
-
-                let result =
-                    pieuvre_sync::services::set_service_start_type(name, *original_start_type);
-
-                match result {
-                    Ok(_) => {
-                        tracing::info!(
-                            service = name,
-                            start_type = original_start_type,
-                            "Service restored"
-                        );
-                        restored += 1;
-                    }
-                    Err(e) => {
-                        tracing::warn!(service = name, error = %e, "Service restoration failed");
-                        errors += 1;
-                    }
-                }
-            }
-            ChangeRecord::FirewallRule { name } => {
-                tracing::debug!(rule = name, "Removing firewall rule");
-
-                if let Err(e) = pieuvre_sync::firewall::remove_pieuvre_rules() {
-                    tracing::warn!(rule = name, error = %e, "Rule removal failed");
-                    errors += 1;
-                } else {
-                    restored += 1;
-                }
+/// Restores a snapshot of `store`, found by ID or ID prefix
+pub fn restore_in(store: &dyn SnapshotStore, id: &str) -> Result<()> {
+    restore_selected_in(
+        store,
+        id,
+        &RestoreFilter::default(),
+        &pieuvre_sync::rollback::restore_change,
+    )
+    .map(|_| ())
+}
+
+/// Restores the changes of a snapshot selected by `filter` (all when empty)
+pub fn restore_selected(id: &str, filter: &RestoreFilter) -> Result<RestoreSummary> {
+    restore_selected_in(
+        configured_store().as_ref(),
+        id,
+        filter,
+        &pieuvre_sync::rollback::restore_change,
+    )
+}
+
+/// `restore_change` restores one change (live system, or backends in tests).
+/// Failures are counted, not fatal.
+pub fn restore_selected_in(
+    store: &dyn SnapshotStore,
+    id: &str,
+    filter: &RestoreFilter,
+    restore_change: &dyn Fn(&ChangeRecord) -> Result<()>,
+) -> Result<RestoreSummary> {
+    let snapshot = store.load(&resolve_id_in(store, id)?)?;
+    let selected = filter.select(&snapshot)?;
+
+    tracing::info!(
+        id = %snapshot.id,
+        selected = selected.len(),
+        total = snapshot.changes.len(),
+        "Restoring snapshot"
+    );
+
+    let mut summary = RestoreSummary {
+        selected: selected.len(),
+        ..Default::default()
+    };
+
+    // Ordre inverse : le dernier changement appliqué est restauré en premier
+    for change in selected.iter().rev().map(|i| &snapshot.changes[*i]) {
+        match restore_change(change) {
+            Ok(()) => {
+                tracing::debug!(change = ?change, "Change restored");
+                summary.restored += 1;
             }
-            ChangeRecord::AppX { package_full_name } => {
-                tracing::debug!(package = package_full_name, "Restoring AppX (no-op)");
-                restored += 1;
+            Err(e) => {
+                tracing::warn!(change = ?change, error = %e, "Restoration failed");
+                summary.failed += 1;
             }
         }
     }
 
     tracing::info!(
-        restored = restored,
-        errors = errors,
+        restored = summary.restored,
+        errors = summary.failed,
         "Restoration completed"
     );
 
-    if errors > 0 {
-        tracing::warn!(errors = errors, "Some restorations failed");
+    if summary.failed > 0 {
+        tracing::warn!(errors = summary.failed, "Some restorations failed");
     }
 
-    Ok(())
+    Ok(summary)
 }
 
-/// Deletes a snapshot
-pub fn delete(id: &str) -> Result<()> {
-    let dir = PathBuf::from(SNAPSHOT_DIR);
+// ============================================
+// VERIFICATION
+// ============================================
+
+/// Result of checking one stored snapshot
+#[derive(Debug, Clone)]
+pub struct SnapshotCheck {
+    pub id: String,
+    /// Format found on disk (`None` when unreadable)
+    pub format_version: Option<u32>,
+    pub description: Option<String>,
+    pub problems: Vec<String>,
+}
+
+impl SnapshotCheck {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Written in an older format, migrated when read
+    pub fn is_legacy(&self) -> bool {
+        self.format_version.is_some_and(|v| v < FORMAT_VERSION)
+    }
+}
+
+/// Checks every snapshot of the configured store
+pub fn check_all() -> Result<Vec<SnapshotCheck>> {
+    check_all_in(configured_store().as_ref())
+}
+
+/// Checks checksum, format and restorability of each snapshot, without
+/// touching the system
+pub fn check_all_in(store: &dyn SnapshotStore) -> Result<Vec<SnapshotCheck>> {
+    let mut ids = store.ids()?;
+    ids.sort();
+
+    Ok(ids.into_iter().map(|id| check_in(store, id)).collect())
+}
+
+fn check_in(store: &dyn SnapshotStore, id: String) -> SnapshotCheck {
+    let stored = match store.load_envelope(&id) {
+        Ok(stored) => stored,
+        // Checksum, decompression, JSON, format non supporté ou schéma
+        Err(e) => {
+            return SnapshotCheck {
+                id,
+                format_version: None,
+                description: None,
+                problems: vec![e.to_string()],
+            }
+        }
+    };
 
-    // Search for corresponding file
-    let zst_path = dir.join(format!("{}.json.zst", id));
-    let json_path = dir.join(format!("{}.json", id));
-    let exists = zst_path.exists() || json_path.exists();
+    let snapshot = &stored.envelope.snapshot;
+    let mut problems = Vec::new();
+    if snapshot.id.to_string() != id {
+        problems.push(format!("ID mismatch: file contains {}", snapshot.id));
+    }
+    for (i, change) in snapshot.changes.iter().enumerate() {
+        if let Err(e) = pieuvre_sync::rollback::validate_change(change) {
+            problems.push(format!("change #{}: {}", i + 1, e));
+        }
+    }
 
-    if !exists {
-        return Err(PieuvreError::SnapshotNotFound(id.to_string()));
+    SnapshotCheck {
+        id,
+        format_version: Some(stored.stored_version),
+        description: Some(snapshot.description.clone()),
+        problems,
     }
+}
 
-    delete_by_id(&dir, id)?;
+/// Deletes a snapshot
+pub fn delete(id: &str) -> Result<()> {
+    delete_in(configured_store().as_ref(), id)
+}
 
+/// Pinned snapshots (the baseline) are refused
+pub fn delete_in(store: &dyn SnapshotStore, id: &str) -> Result<()> {
+    if let Some(entry) = load_index(store)?.get(id).filter(|entry| entry.pinned) {
+        return Err(PieuvreError::Config(format!(
+            "Snapshot {} is pinned and cannot be deleted (unpin it first)",
+            entry.short_id()
+        )));
+    }
+    store.delete(id)?;
+    // L'entrée disparaît à la réconciliation de l'index
+    load_index(store)?;
     tracing::info!(id = id, "Snapshot deleted");
     Ok(())
 }
 
-/// Returns the snapshot directory path
+/// Returns the snapshot directory path (`general.snapshot_dir`)
 pub fn get_snapshot_dir() -> PathBuf {
-    PathBuf::from(SNAPSHOT_DIR)
+    config().general.snapshot_dir.clone()
 }
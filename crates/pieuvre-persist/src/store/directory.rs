@@ -0,0 +1,372 @@
+//! Directory snapshot store
+//!
+//! One `<id>.json.zst` (or `<id>.json`) file per snapshot, with a `<id>.sha256`
+//! checksum of the uncompressed JSON and, when signing, a `<id>.sig` HMAC (see
+//! [`crate::signing`]). The JSON is a versioned envelope (see
+//! [`crate::format`]); older formats are migrated when read. The metadata
+//! index is `index.json`, replaced atomically and signed like the snapshots
+//! (`index.json.sig`); an index whose signature does not match is rebuilt from
+//! the signed snapshots.
+
+use super::SnapshotStore;
+use crate::format::{self, SnapshotEnvelope, StoredSnapshot};
+use crate::index::SnapshotIndex;
+use crate::retention::RetentionPolicy;
+use crate::signing::{self, SigningKey};
+use pieuvre_common::{config, PieuvreError, Result, Snapshot};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const INDEX_FILE: &str = "index.json";
+
+#[derive(Debug, Clone)]
+enum Signing {
+    Off,
+    Key(Arc<SigningKey>),
+    /// Clé machine illisible : rien n'est lu ni écrit sans signature
+    Unavailable(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct DirectoryStore {
+    dir: PathBuf,
+    compress: bool,
+    retention: RetentionPolicy,
+    signing: Signing,
+}
+
+impl DirectoryStore {
+    /// Compressed, unsigned store without rotation
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            compress: true,
+            retention: RetentionPolicy::default(),
+            signing: Signing::Off,
+        }
+    }
+
+    /// `general.snapshot_dir` with the `[snapshot]` settings, signed with the
+    /// machine key
+    pub fn from_config() -> Self {
+        let config = config();
+        Self::new(&config.general.snapshot_dir)
+            .with_compression(config.snapshot.compress)
+            .with_retention(RetentionPolicy::from_config(&config.snapshot))
+            .with_machine_key()
+    }
+
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    pub fn with_max_count(mut self, max_count: usize) -> Self {
+        self.retention.max_count = Some(max_count);
+        self
+    }
+
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Signs new snapshots and refuses unsigned ones
+    pub fn with_signing_key(mut self, key: Arc<SigningKey>) -> Self {
+        self.signing = Signing::Key(key);
+        self
+    }
+
+    /// Signs with [`signing::machine_key`]; when the key cannot be read, the
+    /// store refuses every read and write
+    pub fn with_machine_key(mut self) -> Self {
+        self.signing = match signing::machine_key() {
+            Ok(key) => Signing::Key(key),
+            Err(e) => {
+                tracing::error!(error = %e, "Snapshots cannot be signed");
+                Signing::Unavailable(e.to_string())
+            }
+        };
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Unsigned snapshots whose checksum is intact. Nothing tells a snapshot
+    /// written before the key existed from one dropped in since: signing them
+    /// is left to the administrator ([`DirectoryStore::adopt`])
+    pub fn unsigned(&self) -> Result<Vec<Snapshot>> {
+        let mut unsigned = Vec::new();
+        for id in self.ids()? {
+            if self.dir.join(format!("{}.sig", id)).exists() {
+                continue;
+            }
+            match self
+                .read_checked(&id)
+                .and_then(|data| format::decode(&data))
+            {
+                Ok(stored) => unsigned.push(stored.envelope.snapshot),
+                Err(e) => tracing::warn!(id = %id, error = %e, "Unsigned snapshot unreadable"),
+            }
+        }
+        Ok(unsigned)
+    }
+
+    /// Signs the given unsigned snapshots, as listed by
+    /// [`DirectoryStore::unsigned`]; returns how many were signed
+    pub fn adopt(&self, ids: &[String]) -> Result<usize> {
+        let key = match &self.signing {
+            Signing::Key(key) => key,
+            Signing::Off => {
+                return Err(PieuvreError::Security(format!(
+                    "{}: no signing key",
+                    self.dir.display()
+                )))
+            }
+            Signing::Unavailable(reason) => return Err(PieuvreError::Security(reason.clone())),
+        };
+
+        let mut signed = 0;
+        for id in ids {
+            let sig_path = self.dir.join(format!("{}.sig", id));
+            if sig_path.exists() {
+                continue;
+            }
+            // Snapshot corrompu : le signer le rendrait valide
+            let data = self.read_checked(id)?;
+            fs::write(&sig_path, key.sign(id, &data))?;
+            tracing::info!(id = %id, "Unsigned snapshot signed");
+            signed += 1;
+        }
+        Ok(signed)
+    }
+
+    /// Saves data with zstd compression (or plain JSON), SHA256 checksum and
+    /// signature
+    fn save_compressed(&self, id: &str, data: &[u8]) -> Result<()> {
+        if let Signing::Unavailable(reason) = &self.signing {
+            return Err(PieuvreError::Security(reason.clone()));
+        }
+        fs::create_dir_all(&self.dir)?;
+
+        // Calculate SHA256 checksum
+        let checksum_hex = format::checksum(data);
+
+        // Save checksum
+        fs::write(self.dir.join(format!("{}.sha256", id)), &checksum_hex)?;
+
+        if let Signing::Key(key) = &self.signing {
+            fs::write(self.dir.join(format!("{}.sig", id)), key.sign(id, data))?;
+        }
+
+        if !self.compress {
+            fs::write(self.dir.join(format!("{}.json", id)), data)?;
+            tracing::debug!(id = id, size = data.len(), "Snapshot saved uncompressed");
+            return Ok(());
+        }
+
+        // Compress with zstd (level 3 = good size/speed ratio)
+        let compressed =
+            zstd::encode_all(data, 3).map_err(|e| PieuvreError::Io(std::io::Error::other(e)))?;
+        fs::write(self.dir.join(format!("{}.json.zst", id)), &compressed)?;
+
+        tracing::debug!(
+            id = id,
+            original_size = data.len(),
+            compressed_size = compressed.len(),
+            ratio = format!("{:.1}x", data.len() as f64 / compressed.len() as f64),
+            "Snapshot compressed"
+        );
+
+        Ok(())
+    }
+
+    /// Reads a snapshot with decompression, signature and checksum validation
+    fn load_compressed(&self, id: &str) -> Result<Vec<u8>> {
+        let data = self.read_data(id)?;
+        self.verify_signature(id, &data)?;
+        self.validate_checksum(id, &data)?;
+        Ok(data)
+    }
+
+    /// Uncompressed JSON of a snapshot, checksum validated but not signature
+    fn read_checked(&self, id: &str) -> Result<Vec<u8>> {
+        let data = self.read_data(id)?;
+        self.validate_checksum(id, &data)?;
+        Ok(data)
+    }
+
+    /// Uncompressed JSON of a snapshot, unchecked
+    fn read_data(&self, id: &str) -> Result<Vec<u8>> {
+        // Look for compressed or uncompressed file (backward compatibility)
+        let zst_path = self.dir.join(format!("{}.json.zst", id));
+        let json_path = self.dir.join(format!("{}.json", id));
+
+        let data = if zst_path.exists() {
+            let compressed = fs::read(&zst_path)?;
+            zstd::decode_all(compressed.as_slice())
+                .map_err(|e| PieuvreError::Parse(format!("Decompression failed: {}", e)))?
+        } else if json_path.exists() {
+            // Uncompressed JSON file (legacy or `compress = false`)
+            fs::read(&json_path)?
+        } else {
+            return Err(PieuvreError::SnapshotNotFound(id.to_string()));
+        };
+        Ok(data)
+    }
+
+    fn verify_signature(&self, id: &str, data: &[u8]) -> Result<()> {
+        let key = match &self.signing {
+            Signing::Off => return Ok(()),
+            Signing::Key(key) => key,
+            Signing::Unavailable(reason) => return Err(PieuvreError::Security(reason.clone())),
+        };
+
+        let sig_path = self.dir.join(format!("{}.sig", id));
+        if !sig_path.exists() {
+            return Err(PieuvreError::Security(format!(
+                "Snapshot {} is not signed; it may have been tampered with",
+                id
+            )));
+        }
+        key.verify(id, data, &fs::read_to_string(&sig_path)?)?;
+        tracing::debug!(id = id, "Signature validated");
+        Ok(())
+    }
+
+    /// Validates the checksum if present
+    fn validate_checksum(&self, id: &str, data: &[u8]) -> Result<()> {
+        let checksum_path = self.dir.join(format!("{}.sha256", id));
+        if checksum_path.exists() {
+            let expected = fs::read_to_string(&checksum_path)?;
+
+            let actual = format::checksum(data);
+
+            if expected.trim() != actual {
+                return Err(PieuvreError::Parse(format!(
+                    "Checksum mismatch: expected {}, got {}",
+                    expected.trim(),
+                    actual
+                )));
+            }
+            tracing::debug!(id = id, "Checksum validated");
+        }
+        Ok(())
+    }
+}
+
+impl SnapshotStore for DirectoryStore {
+    fn location(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    fn save_envelope(&self, envelope: &SnapshotEnvelope) -> Result<()> {
+        let json = format::encode(envelope)?;
+        self.save_compressed(&envelope.snapshot.id.to_string(), &json)
+    }
+
+    fn load_envelope(&self, id: &str) -> Result<StoredSnapshot> {
+        format::decode(&self.load_compressed(id)?)
+    }
+
+    fn ids(&self) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if filename == INDEX_FILE {
+                continue;
+            }
+
+            // Supporter .json et .json.zst
+            let id = if let Some(id) = filename.strip_suffix(".json.zst") {
+                id
+            } else if let Some(id) = filename.strip_suffix(".json") {
+                id
+            } else {
+                continue;
+            };
+            if !ids.iter().any(|known| known == id) {
+                ids.push(id.to_string());
+            }
+        }
+        Ok(ids)
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        let zst_path = self.dir.join(format!("{}.json.zst", id));
+        let json_path = self.dir.join(format!("{}.json", id));
+        if !zst_path.exists() && !json_path.exists() {
+            return Err(PieuvreError::SnapshotNotFound(id.to_string()));
+        }
+
+        for path in [
+            zst_path,
+            json_path,
+            self.dir.join(format!("{}.sha256", id)),
+            self.dir.join(format!("{}.sig", id)),
+        ] {
+            if path.exists() {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_index(&self) -> Result<Option<SnapshotIndex>> {
+        let path = self.dir.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read(&path)?;
+        // Épinglage et tags de l'index décident de la rotation et de la baseline
+        if let Err(e) = self.verify_signature(INDEX_FILE, &data) {
+            tracing::warn!(path = %path.display(), error = %e, "Unsigned snapshot index ignored");
+            return Ok(None);
+        }
+        match serde_json::from_slice(&data) {
+            Ok(index) => Ok(Some(index)),
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "Corrupt snapshot index ignored");
+                Ok(None)
+            }
+        }
+    }
+
+    fn write_index(&self, index: &SnapshotIndex) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let json =
+            serde_json::to_vec_pretty(index).map_err(|e| PieuvreError::Parse(e.to_string()))?;
+
+        // Écriture dans un fichier temporaire puis renommage : jamais d'index tronqué
+        let tmp = self.dir.join(format!("{}.tmp", INDEX_FILE));
+        fs::write(&tmp, &json)?;
+        fs::rename(&tmp, self.dir.join(INDEX_FILE))?;
+        // Signature écrite après : une coupure entre les deux fait reconstruire l'index
+        if let Signing::Key(key) = &self.signing {
+            fs::write(
+                self.dir.join(format!("{}.sig", INDEX_FILE)),
+                key.sign(INDEX_FILE, &json),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn stored_size(&self, id: &str) -> Option<u64> {
+        [format!("{}.json.zst", id), format!("{}.json", id)]
+            .iter()
+            .find_map(|name| fs::metadata(self.dir.join(name)).ok())
+            .map(|metadata| metadata.len())
+    }
+
+    fn retention(&self) -> RetentionPolicy {
+        self.retention.clone()
+    }
+}
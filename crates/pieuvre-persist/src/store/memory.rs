@@ -0,0 +1,111 @@
+//! In-memory snapshot store (tests and simulations)
+
+use super::SnapshotStore;
+use crate::format::{self, SnapshotEnvelope, StoredSnapshot};
+use crate::index::SnapshotIndex;
+use crate::retention::RetentionPolicy;
+use pieuvre_common::{PieuvreError, Result};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    snapshots: RwLock<BTreeMap<String, SnapshotEnvelope>>,
+    index: RwLock<Option<SnapshotIndex>>,
+    retention: RetentionPolicy,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_count(mut self, max_count: usize) -> Self {
+        self.retention.max_count = Some(max_count);
+        self
+    }
+
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.read().map(|s| s.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn poisoned<T>(_: T) -> PieuvreError {
+    PieuvreError::Internal("snapshot store lock poisoned".into())
+}
+
+impl SnapshotStore for MemoryStore {
+    fn location(&self) -> String {
+        "memory".to_string()
+    }
+
+    fn save_envelope(&self, envelope: &SnapshotEnvelope) -> Result<()> {
+        self.snapshots
+            .write()
+            .map_err(poisoned)?
+            .insert(envelope.snapshot.id.to_string(), envelope.clone());
+        Ok(())
+    }
+
+    fn load_envelope(&self, id: &str) -> Result<StoredSnapshot> {
+        let envelope = self
+            .snapshots
+            .read()
+            .map_err(poisoned)?
+            .get(id)
+            .cloned()
+            .ok_or_else(|| PieuvreError::SnapshotNotFound(id.to_string()))?;
+        Ok(StoredSnapshot {
+            stored_version: envelope.format_version,
+            checksum: format::checksum(&format::encode(&envelope)?),
+            envelope,
+        })
+    }
+
+    fn ids(&self) -> Result<Vec<String>> {
+        Ok(self
+            .snapshots
+            .read()
+            .map_err(poisoned)?
+            .keys()
+            .cloned()
+            .collect())
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        self.snapshots
+            .write()
+            .map_err(poisoned)?
+            .remove(id)
+            .map(|_| ())
+            .ok_or_else(|| PieuvreError::SnapshotNotFound(id.to_string()))
+    }
+
+    fn read_index(&self) -> Result<Option<SnapshotIndex>> {
+        Ok(self.index.read().map_err(poisoned)?.clone())
+    }
+
+    fn write_index(&self, index: &SnapshotIndex) -> Result<()> {
+        *self.index.write().map_err(poisoned)? = Some(index.clone());
+        Ok(())
+    }
+
+    fn stored_size(&self, id: &str) -> Option<u64> {
+        let snapshots = self.snapshots.read().ok()?;
+        let encoded = format::encode(snapshots.get(id)?).ok()?;
+        Some(encoded.len() as u64)
+    }
+
+    fn retention(&self) -> RetentionPolicy {
+        self.retention.clone()
+    }
+}
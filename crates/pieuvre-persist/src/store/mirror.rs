@@ -0,0 +1,85 @@
+//! Mirrored snapshot store
+//!
+//! Every snapshot is written to the primary store, then copied to the mirror
+//! (e.g. a network share). The mirror is best effort: when it is unreachable
+//! the primary store keeps working and the failure is logged.
+
+use super::{SharedSnapshotStore, SnapshotStore};
+use crate::format::{SnapshotEnvelope, StoredSnapshot};
+use crate::index::SnapshotIndex;
+use crate::retention::RetentionPolicy;
+use pieuvre_common::{PieuvreError, Result};
+
+pub struct MirrorStore {
+    primary: SharedSnapshotStore,
+    mirror: SharedSnapshotStore,
+}
+
+impl MirrorStore {
+    pub fn new(primary: SharedSnapshotStore, mirror: SharedSnapshotStore) -> Self {
+        Self { primary, mirror }
+    }
+}
+
+impl SnapshotStore for MirrorStore {
+    fn location(&self) -> String {
+        format!(
+            "{} (mirror: {})",
+            self.primary.location(),
+            self.mirror.location()
+        )
+    }
+
+    fn save_envelope(&self, envelope: &SnapshotEnvelope) -> Result<()> {
+        self.primary.save_envelope(envelope)?;
+        if let Err(e) = self.mirror.save_envelope(envelope) {
+            tracing::warn!(
+                id = %envelope.snapshot.id,
+                mirror = %self.mirror.location(),
+                error = %e,
+                "Snapshot mirror write failed"
+            );
+        }
+        Ok(())
+    }
+
+    /// Falls back to the mirror when the primary copy is missing or corrupt
+    fn load_envelope(&self, id: &str) -> Result<StoredSnapshot> {
+        self.primary
+            .load_envelope(id)
+            .or_else(|e| self.mirror.load_envelope(id).map_err(|_| e))
+    }
+
+    fn ids(&self) -> Result<Vec<String>> {
+        self.primary.ids()
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        self.primary.delete(id)?;
+        match self.mirror.delete(id) {
+            Ok(()) | Err(PieuvreError::SnapshotNotFound(_)) => {}
+            Err(e) => tracing::warn!(id = id, error = %e, "Snapshot mirror delete failed"),
+        }
+        Ok(())
+    }
+
+    fn read_index(&self) -> Result<Option<SnapshotIndex>> {
+        self.primary.read_index()
+    }
+
+    fn write_index(&self, index: &SnapshotIndex) -> Result<()> {
+        self.primary.write_index(index)?;
+        if let Err(e) = self.mirror.write_index(index) {
+            tracing::warn!(mirror = %self.mirror.location(), error = %e, "Snapshot mirror index write failed");
+        }
+        Ok(())
+    }
+
+    fn stored_size(&self, id: &str) -> Option<u64> {
+        self.primary.stored_size(id)
+    }
+
+    fn retention(&self) -> RetentionPolicy {
+        self.primary.retention()
+    }
+}
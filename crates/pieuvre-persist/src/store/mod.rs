@@ -0,0 +1,95 @@
+//! Snapshot Stores
+//!
+//! Storage backends for snapshots: a directory (zstd + SHA256 + HMAC), an in-memory
+//! store (tests, simulation) and a mirror writing to a second store.
+
+mod directory;
+mod memory;
+mod mirror;
+
+pub use directory::DirectoryStore;
+pub use memory::MemoryStore;
+pub use mirror::MirrorStore;
+
+use crate::format::{SnapshotEnvelope, StoredSnapshot};
+use crate::index::SnapshotIndex;
+use crate::retention::RetentionPolicy;
+use pieuvre_common::{config, Result, Snapshot};
+use std::sync::Arc;
+
+/// Shared handle to a snapshot store
+pub type SharedSnapshotStore = Arc<dyn SnapshotStore>;
+
+pub trait SnapshotStore: Send + Sync {
+    /// Where the snapshots live (logs, messages)
+    fn location(&self) -> String;
+
+    fn save_envelope(&self, envelope: &SnapshotEnvelope) -> Result<()>;
+
+    /// Loads a snapshot envelope by its full ID, migrated to the current
+    /// format (`SnapshotNotFound` if absent)
+    fn load_envelope(&self, id: &str) -> Result<StoredSnapshot>;
+
+    /// Saves a snapshot taken on this machine, in the current format
+    fn save(&self, snapshot: &Snapshot) -> Result<()> {
+        self.save_envelope(&SnapshotEnvelope::new(snapshot.clone()))
+    }
+
+    fn load(&self, id: &str) -> Result<Snapshot> {
+        Ok(self.load_envelope(id)?.envelope.snapshot)
+    }
+
+    /// IDs of the stored snapshots, in no particular order
+    fn ids(&self) -> Result<Vec<String>>;
+
+    fn delete(&self, id: &str) -> Result<()>;
+
+    /// Metadata index (`None`: never written or unreadable)
+    fn read_index(&self) -> Result<Option<SnapshotIndex>>;
+
+    /// Replaces the metadata index in one step
+    fn write_index(&self, index: &SnapshotIndex) -> Result<()>;
+
+    /// Size of a stored snapshot in bytes (`None`: unknown)
+    fn stored_size(&self, _id: &str) -> Option<u64> {
+        None
+    }
+
+    /// What rotation keeps (default: everything)
+    fn retention(&self) -> RetentionPolicy {
+        RetentionPolicy::default()
+    }
+}
+
+/// Store described by the configuration: `general.snapshot_dir` with the
+/// `[snapshot]` retention policy and compression, mirrored to
+/// `snapshot.mirror_dir\<COMPUTERNAME>` when set; both signed with the
+/// machine key
+pub fn configured_store() -> SharedSnapshotStore {
+    let primary: SharedSnapshotStore = Arc::new(DirectoryStore::from_config());
+    match configured_mirror() {
+        Some(mirror) => Arc::new(MirrorStore::new(primary, Arc::new(mirror))),
+        None => primary,
+    }
+}
+
+/// Directories of [`configured_store`], primary first
+pub fn configured_directories() -> Vec<DirectoryStore> {
+    let mut stores = vec![DirectoryStore::from_config()];
+    stores.extend(configured_mirror());
+    stores
+}
+
+fn configured_mirror() -> Option<DirectoryStore> {
+    let mirror_dir = &config().snapshot.mirror_dir;
+    if mirror_dir.as_os_str().is_empty() {
+        return None;
+    }
+
+    let machine = std::env::var("COMPUTERNAME").unwrap_or_else(|_| "Unknown".into());
+    Some(
+        DirectoryStore::new(mirror_dir.join(machine))
+            .with_compression(config().snapshot.compress)
+            .with_machine_key(),
+    )
+}
@@ -31,4 +31,1208 @@ mod snapshot_tests {
         // 4. Suppression (Nettoyage test)
         // Note: On pourrait ajouter une fonction de suppression si nécessaire pour le SOTA
     }
+
+    #[test]
+    fn test_snapshot_preserves_extended_records() {
+        use pieuvre_common::{DaclTarget, PowerSettingRecord};
+
+        let changes = vec![
+            ChangeRecord::PowerScheme {
+                original_scheme: Some("381b4222-f694-41f0-9685-ff5bb260df2e".to_string()),
+                settings: vec![PowerSettingRecord {
+                    scheme: "381b4222-f694-41f0-9685-ff5bb260df2e".to_string(),
+                    subgroup: "54533251-82be-4824-96c1-47b60b740d00".to_string(),
+                    setting: "0cc5b647-c1df-4637-891a-dec35c318583".to_string(),
+                    original_ac: Some(50),
+                    original_dc: None,
+                }],
+            },
+            ChangeRecord::MsiDevice {
+                device_path: r"SYSTEM\CurrentControlSet\Enum\PCI\X".to_string(),
+                original_msi_supported: None,
+            },
+            ChangeRecord::ScheduledTask {
+                path: r"\Microsoft\Windows\Application Experience\ProgramDataUpdater".to_string(),
+                original_enabled: true,
+            },
+            ChangeRecord::HostsBlock {
+                domains: vec!["vortex.data.microsoft.com".to_string()],
+            },
+            ChangeRecord::Bcd {
+                element: "disabledynamictick".to_string(),
+                original_value: None,
+            },
+            ChangeRecord::Dacl {
+                target: DaclTarget::Service {
+                    name: "DiagTrack".to_string(),
+                },
+                original_sddl: "D:(A;;CCLCSWRPWPDTLOCRRC;;;SY)".to_string(),
+            },
+        ];
+
+        let snap = snapshot::create("Extended records", changes.clone())
+            .expect("Failed to create snapshot");
+        let loaded = snapshot::load(&snap.id.to_string()).expect("Failed to load snapshot");
+        assert_eq!(loaded.changes, changes);
+        let _ = snapshot::delete(&snap.id.to_string());
+    }
+}
+
+#[cfg(test)]
+mod reboot_tests {
+    use crate::reboot::RebootQueue;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_reboot_queue_pruned_after_boot() {
+        let now = Utc::now();
+        let mut queue = RebootQueue::default();
+        queue.push("vbs", 3, now - Duration::hours(2));
+        queue.push("net_ipv6", 0, now - Duration::minutes(5));
+        queue.push("vbs", 2, now - Duration::hours(1));
+        assert_eq!(queue.entries.len(), 2);
+        assert_eq!(queue.change_count(), 3);
+
+        // Démarrage il y a 30 min : seul IPv6 attend encore
+        assert_eq!(queue.prune(now - Duration::minutes(30)), 1);
+        assert!(!queue.is_pending("vbs"));
+        assert!(queue.is_pending("net_ipv6"));
+
+        let json = serde_json::to_string(&queue).unwrap();
+        assert_eq!(serde_json::from_str::<RebootQueue>(&json).unwrap(), queue);
+    }
+}
+
+#[cfg(test)]
+mod store_tests {
+    use crate::snapshot;
+    use crate::store::{DirectoryStore, MemoryStore, MirrorStore, SnapshotStore};
+    use pieuvre_common::{ChangeRecord, PieuvreError, RegistryHive};
+    use std::sync::Arc;
+
+    fn changes() -> Vec<ChangeRecord> {
+        vec![ChangeRecord::Registry {
+            hive: RegistryHive::Hklm,
+            key: r"SOFTWARE\PieuvreTest".to_string(),
+            value_name: "Store".to_string(),
+            original_value: None,
+        }]
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("pieuvre-store-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_memory_store_rotation() {
+        let store = MemoryStore::new().with_max_count(2);
+        let first = snapshot::create_in(&store, "first", changes()).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        snapshot::create_in(&store, "second", changes()).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        snapshot::create_in(&store, "third", changes()).unwrap();
+
+        let list = snapshot::list_all_in(&store).unwrap();
+        let descriptions: Vec<&str> = list.iter().map(|s| s.description.as_str()).collect();
+        assert_eq!(descriptions, ["third", "second"]);
+        assert!(matches!(
+            store.load(&first.id.to_string()),
+            Err(PieuvreError::SnapshotNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_directory_store_roundtrip_and_checksum() {
+        for compress in [true, false] {
+            let dir = temp_dir(if compress { "zst" } else { "json" });
+            let store = DirectoryStore::new(&dir).with_compression(compress);
+            let snap = snapshot::create_in(&store, "directory", changes()).unwrap();
+            let id = snap.id.to_string();
+            assert_eq!(store.ids().unwrap(), vec![id.clone()]);
+            assert_eq!(store.load(&id).unwrap().changes, changes());
+
+            // Fichier altéré : le checksum est refusé
+            std::fs::write(dir.join(format!("{}.sha256", id)), "00").unwrap();
+            assert!(matches!(store.load(&id), Err(PieuvreError::Parse(_))));
+
+            store.delete(&id).unwrap();
+            assert!(store.ids().unwrap().is_empty());
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[test]
+    fn test_mirror_store_copies_and_falls_back() {
+        let dir = temp_dir("primary");
+        let primary = Arc::new(DirectoryStore::new(&dir));
+        let mirror = Arc::new(MemoryStore::new());
+        let store = MirrorStore::new(primary.clone(), mirror.clone());
+
+        let snap = snapshot::create_in(&store, "mirrored", changes()).unwrap();
+        let id = snap.id.to_string();
+        assert_eq!(mirror.len(), 1);
+
+        // Copie locale corrompue : lecture depuis le miroir
+        std::fs::write(dir.join(format!("{}.sha256", id)), "00").unwrap();
+        assert_eq!(store.load(&id).unwrap().description, "mirrored");
+
+        store.delete(&id).unwrap();
+        assert!(mirror.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
+
+#[cfg(test)]
+mod format_tests {
+    use crate::format::{self, SnapshotEnvelope, FORMAT_VERSION};
+    use crate::snapshot;
+    use crate::store::{DirectoryStore, SnapshotStore};
+    use chrono::Utc;
+    use pieuvre_common::{ChangeRecord, PieuvreError, Snapshot};
+    use uuid::Uuid;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("pieuvre-format-{}-{}", name, std::process::id()))
+    }
+
+    fn service_change(start_type: u32) -> Vec<ChangeRecord> {
+        vec![ChangeRecord::Service {
+            name: "DiagTrack".to_string(),
+            original_start_type: start_type,
+        }]
+    }
+
+    #[test]
+    fn test_legacy_snapshot_is_migrated() {
+        let dir = temp_dir("legacy");
+        std::fs::create_dir_all(&dir).unwrap();
+        let legacy = Snapshot {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            description: "v1".to_string(),
+            changes: service_change(2),
+            outcome: None,
+            origins: Vec::new(),
+        };
+        // v1 : snapshot écrit tel quel, sans enveloppe
+        std::fs::write(
+            dir.join(format!("{}.json", legacy.id)),
+            serde_json::to_vec(&legacy).unwrap(),
+        )
+        .unwrap();
+
+        let store = DirectoryStore::new(&dir);
+        let stored = store.load_envelope(&legacy.id.to_string()).unwrap();
+        assert_eq!(stored.stored_version, 1);
+        assert_eq!(stored.envelope.format_version, FORMAT_VERSION);
+        assert_eq!(stored.envelope.pieuvre_version, "unknown");
+        assert_eq!(stored.envelope.snapshot.changes, legacy.changes);
+
+        let checks = snapshot::check_all_in(&store).unwrap();
+        assert_eq!(checks.len(), 1);
+        assert!(checks[0].is_ok() && checks[0].is_legacy());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_envelope_roundtrip_and_future_format() {
+        let snap = Snapshot {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            description: "envelope".to_string(),
+            changes: service_change(3),
+            outcome: None,
+            origins: Vec::new(),
+        };
+        let data = format::encode(&SnapshotEnvelope::new(snap.clone())).unwrap();
+        let stored = format::decode(&data).unwrap();
+        assert_eq!(stored.stored_version, FORMAT_VERSION);
+        assert_eq!(stored.envelope.snapshot.id, snap.id);
+        assert_eq!(stored.envelope.pieuvre_version, env!("CARGO_PKG_VERSION"));
+
+        let mut future: serde_json::Value = serde_json::from_slice(&data).unwrap();
+        future["format_version"] = (FORMAT_VERSION + 1).into();
+        assert!(matches!(
+            format::decode(&serde_json::to_vec(&future).unwrap()),
+            Err(PieuvreError::Parse(msg)) if msg.contains("Unsupported")
+        ));
+    }
+
+    #[test]
+    fn test_check_reports_corrupt_and_invalid_snapshots() {
+        let dir = temp_dir("check");
+        let store = DirectoryStore::new(&dir);
+        let good = snapshot::create_in(&store, "good", service_change(4)).unwrap();
+        let invalid = snapshot::create_in(&store, "invalid", service_change(9)).unwrap();
+        let corrupt = snapshot::create_in(&store, "corrupt", service_change(2)).unwrap();
+        std::fs::write(dir.join(format!("{}.sha256", corrupt.id)), "00").unwrap();
+
+        let checks = snapshot::check_all_in(&store).unwrap();
+        let find = |id: Uuid| {
+            checks
+                .iter()
+                .find(|c| c.id == id.to_string())
+                .unwrap()
+                .clone()
+        };
+        assert!(find(good.id).is_ok());
+        let invalid = find(invalid.id);
+        assert_eq!(invalid.problems.len(), 1);
+        assert!(invalid.problems[0].starts_with("change #1"));
+        let corrupt = find(corrupt.id);
+        assert_eq!(corrupt.format_version, None);
+        assert!(corrupt.problems[0].contains("Checksum mismatch"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
+
+#[cfg(test)]
+mod index_tests {
+    use crate::snapshot;
+    use crate::store::{DirectoryStore, MemoryStore, SnapshotStore};
+    use pieuvre_common::ChangeRecord;
+
+    fn changes(count: usize) -> Vec<ChangeRecord> {
+        (0..count)
+            .map(|i| ChangeRecord::FirewallRule {
+                name: format!("pieuvre-{}", i),
+            })
+            .collect()
+    }
+
+    fn pause() {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_index_follows_create_delete_and_rotation() {
+        let store = MemoryStore::new().with_max_count(2);
+        let first = snapshot::create_in(&store, "first", changes(1)).unwrap();
+        pause();
+        let second = snapshot::create_in(&store, "second", changes(2)).unwrap();
+
+        let list = snapshot::list_in(&store).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].id, second.id.to_string());
+        assert_eq!(list[0].change_count, 2);
+        assert_eq!(store.read_index().unwrap().unwrap().entries, list);
+
+        // Rotation : l'entrée du plus ancien disparaît avec lui
+        pause();
+        snapshot::create_in(&store, "third", changes(3)).unwrap();
+        let index = store.read_index().unwrap().unwrap();
+        let descriptions: Vec<&str> = index
+            .entries
+            .iter()
+            .map(|e| e.description.as_str())
+            .collect();
+        assert_eq!(descriptions, ["third", "second"]);
+        assert!(index.get(&first.id.to_string()).is_none());
+
+        snapshot::delete_in(&store, &second.id.to_string()).unwrap();
+        assert_eq!(store.read_index().unwrap().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_index_rebuilt_and_reconciled() {
+        let dir = std::env::temp_dir().join(format!("pieuvre-index-{}", std::process::id()));
+        let store = DirectoryStore::new(&dir);
+        let kept = snapshot::create_in(&store, "kept", changes(1)).unwrap();
+        pause();
+        let removed = snapshot::create_in(&store, "removed", changes(1)).unwrap();
+
+        // Le checksum indexé est celui du fichier
+        let entry = snapshot::list_in(&store).unwrap().remove(1);
+        let sha = std::fs::read_to_string(dir.join(format!("{}.sha256", kept.id))).unwrap();
+        assert_eq!(entry.checksum, sha);
+
+        // Index perdu : reconstruit à la lecture
+        std::fs::remove_file(dir.join("index.json")).unwrap();
+        assert_eq!(snapshot::list_in(&store).unwrap().len(), 2);
+        assert!(dir.join("index.json").exists());
+
+        // Snapshot supprimé hors pieuvre : entrée retirée
+        std::fs::remove_file(dir.join(format!("{}.json.zst", removed.id))).unwrap();
+        let list = snapshot::list_in(&store).unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].id, kept.id.to_string());
+
+        std::fs::write(dir.join("index.json"), "{ corrupt").unwrap();
+        assert_eq!(snapshot::rebuild_index_in(&store).unwrap(), 1);
+        assert_eq!(store.ids().unwrap(), vec![kept.id.to_string()]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
+
+#[cfg(test)]
+mod inspect_tests {
+    use crate::inspect;
+    use crate::snapshot;
+    use crate::store::MemoryStore;
+    use pieuvre_common::{
+        ChangeRecord, MemoryRegistry, MemoryService, MemoryServiceManager, RegistryBackend,
+        RegistryHive, RegistryPath, RegistryValue,
+    };
+
+    fn registry_change(value: u32) -> ChangeRecord {
+        ChangeRecord::Registry {
+            hive: RegistryHive::Hklm,
+            key: r"SOFTWARE\PieuvreTest".to_string(),
+            value_name: "Inspect".to_string(),
+            original_value: Some(RegistryValue::Dword(value)),
+        }
+    }
+
+    fn service_change(start_type: u32) -> ChangeRecord {
+        ChangeRecord::Service {
+            name: "DiagTrack".to_string(),
+            original_start_type: start_type,
+        }
+    }
+
+    #[test]
+    fn test_show_stored_and_live_state() {
+        let store = MemoryStore::new();
+        let snap = snapshot::create_in(
+            &store,
+            "inspect",
+            vec![
+                registry_change(1),
+                service_change(2),
+                ChangeRecord::RegistryKeyCreated {
+                    hive: RegistryHive::Hkcu,
+                    key: r"Software\PieuvreCreated".to_string(),
+                },
+            ],
+        )
+        .unwrap();
+
+        let path = RegistryPath::hklm(r"SOFTWARE\PieuvreTest");
+        let registry = MemoryRegistry::new()
+            .with_value(path.clone(), "Inspect", RegistryValue::Dword(0))
+            .with_value(
+                RegistryPath::hkcu(r"Software\PieuvreCreated"),
+                "x",
+                RegistryValue::Dword(1),
+            );
+        let services = MemoryServiceManager::new().with_service(MemoryService::new("DiagTrack", 4));
+
+        let id = snap.id.to_string();
+        let view = inspect::show_in(&store, &id[..8], &registry, &services).unwrap();
+        assert_eq!(view.id, id);
+        assert_eq!(view.changes.len(), 3);
+        assert_eq!(view.changes[2].index, 3);
+        assert_eq!(view.changes[2].tweak, None);
+
+        let value = &view.changes[0];
+        assert_eq!(value.kind, "registry");
+        assert_eq!(value.target, r"HKLM\SOFTWARE\PieuvreTest : Inspect");
+        assert_ne!(value.current, None);
+        assert!(!value.is_current());
+        assert!(view.changes[1].stored.starts_with("Automatic"));
+        assert!(view.changes[1]
+            .current
+            .as_deref()
+            .unwrap()
+            .starts_with("Disabled"));
+        assert_eq!(view.changes[2].stored, "(absent)");
+        assert_eq!(view.changes[2].current.as_deref(), Some("present"));
+
+        // Valeur d'origine déjà en place : rien à restaurer
+        registry
+            .write_value(&path, "Inspect", &RegistryValue::Dword(1))
+            .unwrap();
+        let view = inspect::show_in(&store, &id, &registry, &services).unwrap();
+        assert!(view.changes[0].is_current());
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let store = MemoryStore::new();
+        let a =
+            snapshot::create_in(&store, "a", vec![registry_change(1), service_change(2)]).unwrap();
+        let b = snapshot::create_in(
+            &store,
+            "b",
+            vec![
+                registry_change(0),
+                ChangeRecord::HostsBlock {
+                    domains: vec!["telemetry.example.com".to_string()],
+                },
+            ],
+        )
+        .unwrap();
+
+        let diff = inspect::diff_in(&store, &a.id.to_string(), &b.id.to_string()[..8]).unwrap();
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].before, "1 (0x00000001)");
+        assert_eq!(diff.changed[0].after, "0 (0x00000000)");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].kind, "service");
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].target, "telemetry.example.com");
+        assert_eq!(diff.unchanged, 0);
+
+        let same = inspect::diff_snapshots(&a, &a);
+        assert!(same.is_empty());
+        assert_eq!(same.unchanged, 2);
+    }
+}
+
+#[cfg(test)]
+mod restore_tests {
+    use crate::restore::{ChangeKind, RestoreFilter};
+    use crate::snapshot;
+    use crate::store::MemoryStore;
+    use pieuvre_common::{
+        ChangeRecord, MemoryRegistry, MemoryServiceManager, PieuvreError, RegistryBackend,
+        RegistryHive, RegistryPath, RegistryValue, TransactionOutcome,
+    };
+    use pieuvre_sync::transaction::{StepReport, TransactionReport};
+
+    fn registry_change(key: &str, value: u32) -> ChangeRecord {
+        ChangeRecord::Registry {
+            hive: RegistryHive::Hklm,
+            key: key.to_string(),
+            value_name: "Restore".to_string(),
+            original_value: Some(RegistryValue::Dword(value)),
+        }
+    }
+
+    fn step(name: &str, changes: usize) -> StepReport {
+        StepReport {
+            name: name.to_string(),
+            changes,
+            error: None,
+        }
+    }
+
+    /// 1-2 : telemetry (registre), 3 : services (DiagTrack), 4 : firewall
+    fn recorded(store: &MemoryStore) -> String {
+        let report = TransactionReport {
+            name: "apply".to_string(),
+            outcome: TransactionOutcome::Success,
+            changes: vec![
+                registry_change(r"SOFTWARE\Policies\Microsoft\Windows\DataCollection", 1),
+                registry_change(r"SOFTWARE\PoliciesOld", 2),
+                ChangeRecord::Service {
+                    name: "DiagTrack".to_string(),
+                    original_start_type: 2,
+                },
+                ChangeRecord::FirewallRule {
+                    name: "pieuvre-block".to_string(),
+                },
+            ],
+            steps: vec![
+                step("telemetry", 2),
+                step("noop", 0),
+                step("services", 1),
+                step("firewall", 1),
+            ],
+            rollback_error: None,
+        };
+        let snap = snapshot::record_transaction_in(store, &report)
+            .unwrap()
+            .unwrap();
+        assert_eq!(snap.origin_of(2), Some("services"));
+        snap.id.to_string()
+    }
+
+    fn select(store: &MemoryStore, id: &str, filter: RestoreFilter) -> Vec<usize> {
+        let snap = crate::store::SnapshotStore::load(store, id).unwrap();
+        filter.select(&snap).unwrap()
+    }
+
+    #[test]
+    fn test_filter_selection() {
+        let store = MemoryStore::new();
+        let id = recorded(&store);
+
+        let all = select(&store, &id, RestoreFilter::default());
+        assert_eq!(all, [0, 1, 2, 3]);
+
+        let by_index = RestoreFilter {
+            indices: vec![4, 1],
+            ..Default::default()
+        };
+        assert_eq!(select(&store, &id, by_index), [0, 3]);
+
+        let by_kind = RestoreFilter {
+            kinds: vec!["Service".parse().unwrap(), ChangeKind::Firewall],
+            ..Default::default()
+        };
+        assert_eq!(select(&store, &id, by_kind), [2, 3]);
+        assert!(matches!(
+            "disk".parse::<ChangeKind>(),
+            Err(PieuvreError::Config(_))
+        ));
+
+        // Composants entiers : "Policies" ne couvre pas "PoliciesOld"
+        let by_key = RestoreFilter {
+            key_prefixes: vec![r"hklm\software\policies".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(select(&store, &id, by_key), [0]);
+        let other_hive = RestoreFilter {
+            key_prefixes: vec![r"HKCU\SOFTWARE\Policies".to_string()],
+            ..Default::default()
+        };
+        assert!(select(&store, &id, other_hive).is_empty());
+
+        let by_tweak = RestoreFilter {
+            tweaks: vec!["telemetry".to_string(), "firewall".to_string()],
+            indices: vec![1, 2, 3],
+            ..Default::default()
+        };
+        assert_eq!(select(&store, &id, by_tweak), [0, 1]);
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        let store = MemoryStore::new();
+        let id = recorded(&store);
+        let snap = crate::store::SnapshotStore::load(&store, &id).unwrap();
+        for index in [0, 5] {
+            let filter = RestoreFilter {
+                indices: vec![index],
+                ..Default::default()
+            };
+            assert!(matches!(filter.select(&snap), Err(PieuvreError::Config(_))));
+        }
+
+        // Snapshot sans origine (ancien format) : sélection par tweak impossible
+        let legacy = snapshot::create_in(&store, "legacy", vec![registry_change("X", 1)]).unwrap();
+        let filter = RestoreFilter {
+            tweaks: vec!["telemetry".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            filter.select(&legacy),
+            Err(PieuvreError::Config(_))
+        ));
+
+        let tagged =
+            snapshot::create_for_tweak_in(&store, "telemetry", "t", vec![registry_change("X", 1)])
+                .unwrap();
+        assert_eq!(filter.select(&tagged).unwrap(), [0]);
+    }
+
+    #[test]
+    fn test_restore_selected_changes_only() {
+        let store = MemoryStore::new();
+        let id = recorded(&store);
+        let registry = MemoryRegistry::new();
+        let services = MemoryServiceManager::new();
+        let filter = RestoreFilter {
+            kinds: vec![ChangeKind::Registry],
+            ..Default::default()
+        };
+
+        let summary = snapshot::restore_selected_in(&store, &id[..8], &filter, &|record| {
+            pieuvre_sync::rollback::restore_change_with(record, &registry, &services)
+        })
+        .unwrap();
+        assert_eq!(summary.selected, 2);
+        assert_eq!(summary.restored, 2);
+        assert_eq!(summary.failed, 0);
+        assert_eq!(
+            registry
+                .read_dword(&RegistryPath::hklm(r"SOFTWARE\PoliciesOld"), "Restore")
+                .unwrap(),
+            Some(2)
+        );
+        // DiagTrack absent du gestionnaire : aurait échoué s'il avait été sélectionné
+        let summary = snapshot::restore_selected_in(
+            &store,
+            &id,
+            &RestoreFilter {
+                indices: vec![3],
+                ..Default::default()
+            },
+            &|record| pieuvre_sync::rollback::restore_change_with(record, &registry, &services),
+        )
+        .unwrap();
+        assert_eq!((summary.restored, summary.failed), (0, 1));
+    }
+}
+
+#[cfg(test)]
+mod journal_tests {
+    use crate::journal::{self, Journal};
+    use crate::store::MemoryStore;
+    use pieuvre_common::{
+        MemoryRegistry, MemoryServiceManager, RegistryBackend, RegistryPath, RegistryValue,
+    };
+    use pieuvre_sync::changes::{record_journaled, ChangeJournal};
+    use pieuvre_sync::registry::set_dword_value_with;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    const KEY: &str = r"SOFTWARE\PieuvreJournal";
+
+    /// Session interrompue : deux valeurs modifiées, la dernière ligne coupée
+    fn crashed_session(name: &str) -> (std::path::PathBuf, MemoryRegistry) {
+        let dir = std::env::temp_dir().join(format!("pieuvre-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("journal.wal");
+        let registry =
+            MemoryRegistry::new().with_value(RegistryPath::hklm(KEY), "A", RegistryValue::Dword(1));
+
+        let journal = Arc::new(Journal::new(&path));
+        let (result, changes) = record_journaled(journal.clone(), || {
+            set_dword_value_with(&registry, KEY, "A", 10)?;
+            set_dword_value_with(&registry, KEY, "A", 11)?;
+            set_dword_value_with(&registry, KEY, "B", 20)
+        });
+        result.unwrap();
+        assert_eq!(changes.len(), 2);
+        // Pas de close : le processus s'est arrêté ici
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        file.write_all(br#"{"entry":"change","at":"2025-"#).unwrap();
+        (path, registry)
+    }
+
+    #[test]
+    fn test_journal_finished_into_snapshot() {
+        let (path, _) = crashed_session("journal-finish");
+        let pending = journal::pending_at(&path).unwrap().unwrap();
+        assert_eq!(pending.changes.len(), 2);
+        assert_eq!(pending.skipped, 1);
+        assert!(pending.started.is_some());
+
+        let store = MemoryStore::new();
+        let snap = journal::finish_in(&store, &pending).unwrap().unwrap();
+        assert_eq!(snap.changes, pending.changes);
+        assert!(snap.description.starts_with("Recovered"));
+        assert!(journal::pending_at(&path).unwrap().is_none());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_journal_rolled_back() {
+        let (path, registry) = crashed_session("journal-rollback");
+        let pending = journal::pending_at(&path).unwrap().unwrap();
+        let services = MemoryServiceManager::new();
+        let summary = journal::rollback_with(&pending, &|change| {
+            pieuvre_sync::rollback::restore_change_with(change, &registry, &services)
+        })
+        .unwrap();
+        assert_eq!((summary.restored, summary.failed), (2, 0));
+
+        let path_a = RegistryPath::hklm(KEY);
+        assert_eq!(registry.read_dword(&path_a, "A").unwrap(), Some(1));
+        assert_eq!(registry.read_value(&path_a, "B").unwrap(), None);
+        assert!(!path.exists());
+
+        // Fin normale : le journal est supprimé, sauf s'il en restait un inachevé
+        let journal = Journal::new(&path);
+        journal
+            .append(&pieuvre_common::ChangeRecord::FirewallRule {
+                name: "pieuvre".to_string(),
+            })
+            .unwrap();
+        journal.close().unwrap();
+        assert!(!path.exists());
+
+        std::fs::write(&path, "").unwrap();
+        Journal::new(&path).close().unwrap();
+        assert!(path.exists());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_journal_kept_when_snapshot_not_written() {
+        let dir = std::env::temp_dir().join(format!("pieuvre-journal-keep-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("journal.wal");
+
+        let journal = Journal::new(&path);
+        journal
+            .append(&pieuvre_common::ChangeRecord::FirewallRule {
+                name: "pieuvre".to_string(),
+            })
+            .unwrap();
+        journal.keep();
+        journal.close().unwrap();
+        let pending = journal::pending_at(&path).unwrap().unwrap();
+        assert_eq!(pending.changes.len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
+
+#[cfg(test)]
+mod baseline_tests {
+    use crate::baseline::{self, BASELINE_TAG};
+    use crate::index::SnapshotIndex;
+    use crate::restore::RestoreFilter;
+    use crate::snapshot;
+    use crate::store::{MemoryStore, SnapshotStore};
+    use pieuvre_common::{ChangeRecord, PieuvreError, Result};
+    use std::sync::Mutex;
+
+    fn service_change(name: &str) -> ChangeRecord {
+        ChangeRecord::Service {
+            name: name.to_string(),
+            original_start_type: 2,
+        }
+    }
+
+    #[test]
+    fn test_baseline_captured_once_and_protected() {
+        let store = MemoryStore::new().with_max_count(2);
+        let baseline = baseline::capture_if_missing_in(&store, || {
+            vec![service_change("DiagTrack"), service_change("SysMain")]
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(baseline.description, "Pristine baseline");
+
+        // Déjà capturée : le système n'est pas relu
+        let again = baseline::capture_if_missing_in(&store, || panic!("captured twice")).unwrap();
+        assert!(again.is_none());
+
+        // La rotation ne compte ni ne supprime la baseline
+        for i in 0..3 {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            snapshot::create_in(
+                &store,
+                &format!("run {}", i),
+                vec![service_change("WSearch")],
+            )
+            .unwrap();
+        }
+        let list = snapshot::list_in(&store).unwrap();
+        assert_eq!(list.len(), 3);
+        let entry = baseline::find_in(&store).unwrap().unwrap();
+        assert!(entry.pinned);
+        assert_eq!(entry.tags, [BASELINE_TAG]);
+
+        let id = baseline.id.to_string();
+        assert!(matches!(
+            snapshot::delete_in(&store, &id),
+            Err(PieuvreError::Config(_))
+        ));
+        // Tags et épinglage survivent à la reconstruction de l'index
+        snapshot::rebuild_index_in(&store).unwrap();
+        assert!(
+            store
+                .read_index()
+                .unwrap()
+                .unwrap()
+                .get(&id)
+                .unwrap()
+                .pinned
+        );
+
+        let loaded = baseline::load_in(&store).unwrap().unwrap();
+        assert_eq!(loaded.changes, baseline.changes);
+        let restored = Mutex::new(Vec::new());
+        let summary = baseline::restore_in(&store, &RestoreFilter::default(), &|change| {
+            restored.lock().unwrap().push(change.clone());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(summary.restored, 2);
+        assert_eq!(restored.into_inner().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_baseline_missing() {
+        let store = MemoryStore::new();
+        snapshot::create_in(&store, "earlier", vec![service_change("DiagTrack")]).unwrap();
+        assert!(baseline::load_in(&store).unwrap().is_none());
+        assert!(matches!(
+            baseline::restore_in(&store, &RestoreFilter::default(), &|_| Ok(())),
+            Err(PieuvreError::SnapshotNotFound(_))
+        ));
+
+        let captured = baseline::capture_if_missing_in(&store, Vec::new)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            captured.description,
+            "Baseline (captured after earlier changes)"
+        );
+    }
+
+    /// Snapshot non signé : listé par le store mais illisible
+    struct WithUnsigned(MemoryStore);
+
+    impl SnapshotStore for WithUnsigned {
+        fn location(&self) -> String {
+            self.0.location()
+        }
+        fn save_envelope(&self, envelope: &crate::format::SnapshotEnvelope) -> Result<()> {
+            self.0.save_envelope(envelope)
+        }
+        fn load_envelope(&self, id: &str) -> Result<crate::format::StoredSnapshot> {
+            match id {
+                "unsigned" => Err(PieuvreError::Security("not signed".to_string())),
+                _ => self.0.load_envelope(id),
+            }
+        }
+        fn ids(&self) -> Result<Vec<String>> {
+            let mut ids = self.0.ids()?;
+            ids.push("unsigned".to_string());
+            Ok(ids)
+        }
+        fn delete(&self, id: &str) -> Result<()> {
+            self.0.delete(id)
+        }
+        fn read_index(&self) -> Result<Option<SnapshotIndex>> {
+            self.0.read_index()
+        }
+        fn write_index(&self, index: &SnapshotIndex) -> Result<()> {
+            self.0.write_index(index)
+        }
+    }
+
+    #[test]
+    fn test_baseline_not_captured_over_unreadable_snapshots() {
+        let store = WithUnsigned(MemoryStore::new());
+        assert!(matches!(
+            baseline::capture_if_missing_in(&store, || panic!("system read")),
+            Err(PieuvreError::Security(_))
+        ));
+        assert!(baseline::find_in(&store).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_oldest_baseline_wins() {
+        let store = MemoryStore::new();
+        let first = baseline::capture_if_missing_in(&store, || vec![service_change("DiagTrack")])
+            .unwrap()
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        // Baseline signée après coup (--adopt-unsigned) : la plus récente
+        snapshot::create_pinned_in(
+            &store,
+            "Pristine baseline",
+            vec![service_change("SysMain")],
+            vec![BASELINE_TAG.to_string()],
+        )
+        .unwrap();
+        let entry = baseline::find_in(&store).unwrap().unwrap();
+        assert_eq!(entry.id, first.id.to_string());
+    }
+}
+
+#[cfg(test)]
+mod retention_tests {
+    use crate::baseline;
+    use crate::index::IndexEntry;
+    use crate::retention::RetentionPolicy;
+    use crate::snapshot;
+    use crate::store::MemoryStore;
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use pieuvre_common::{ChangeRecord, PieuvreError, SnapshotConfig};
+
+    fn now() -> DateTime<Utc> {
+        // Un mercredi, midi passé : même jour local quel que soit le fuseau
+        Utc.with_ymd_and_hms(2026, 3, 4, 12, 20, 0).unwrap()
+    }
+
+    fn entry(id: &str, age: Duration, size: u64) -> IndexEntry {
+        IndexEntry {
+            id: id.to_string(),
+            timestamp: now() - age,
+            description: id.to_string(),
+            change_count: 1,
+            outcome: None,
+            tags: Vec::new(),
+            pinned: false,
+            checksum: String::new(),
+            size,
+        }
+    }
+
+    #[test]
+    fn test_retention_limits() {
+        let entries = [
+            entry("recent", Duration::zero(), 100),
+            IndexEntry {
+                pinned: true,
+                ..entry("pinned", Duration::days(1), 10_000)
+            },
+            entry("two-days", Duration::days(2), 100),
+            entry("old", Duration::days(10), 100),
+        ];
+        let expired = |policy: RetentionPolicy| policy.expired(&entries, now());
+
+        assert!(RetentionPolicy::default().is_unlimited());
+        assert!(expired(RetentionPolicy::default()).is_empty());
+        // L'épinglé n'est ni compté ni supprimé
+        let count = RetentionPolicy {
+            max_count: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(expired(count), ["old"]);
+        let age = RetentionPolicy {
+            max_age: Some(Duration::days(5)),
+            ..Default::default()
+        };
+        assert_eq!(expired(age), ["old"]);
+        let size = RetentionPolicy {
+            max_total_size: Some(150),
+            ..Default::default()
+        };
+        assert_eq!(expired(size), ["two-days", "old"]);
+
+        let policy = RetentionPolicy::from_config(&SnapshotConfig {
+            max_age_days: 30,
+            max_total_size_mb: 2,
+            ..Default::default()
+        });
+        assert_eq!(policy.max_count, Some(10));
+        assert_eq!(policy.max_age, Some(Duration::days(30)));
+        assert_eq!(policy.max_total_size, Some(2 * 1024 * 1024));
+        assert!(RetentionPolicy::from_config(&SnapshotConfig::default())
+            .max_age
+            .is_none());
+    }
+
+    #[test]
+    fn test_retention_keeps_one_per_day_and_week() {
+        let entries = [
+            entry("today", Duration::zero(), 0),
+            entry("today-earlier", Duration::minutes(10), 0),
+            entry("yesterday", Duration::days(1), 0),
+            entry("two-days", Duration::days(2), 0),
+            entry("last-week", Duration::days(7), 0),
+            entry("last-week-monday", Duration::days(9), 0),
+            entry("month", Duration::days(30), 0),
+        ];
+        let daily = RetentionPolicy {
+            max_count: Some(1),
+            keep_daily: 2,
+            ..Default::default()
+        };
+        // Les gardés dépassent déjà max_count : tout le reste part
+        assert_eq!(
+            daily.expired(&entries, now()),
+            [
+                "today-earlier",
+                "two-days",
+                "last-week",
+                "last-week-monday",
+                "month"
+            ]
+        );
+
+        let weekly = RetentionPolicy {
+            max_age: Some(Duration::days(1)),
+            keep_weekly: 2,
+            ..Default::default()
+        };
+        assert_eq!(
+            weekly.expired(&entries, now()),
+            ["two-days", "last-week-monday", "month"]
+        );
+    }
+
+    #[test]
+    fn test_pin_and_unpin() {
+        let store = MemoryStore::new().with_max_count(1);
+        let change = || {
+            vec![ChangeRecord::FirewallRule {
+                name: "pieuvre".to_string(),
+            }]
+        };
+        let pause = || std::thread::sleep(std::time::Duration::from_millis(5));
+
+        let first = snapshot::create_in(&store, "before upgrade", change()).unwrap();
+        let id = first.id.to_string();
+        let pinned = snapshot::pin_in(&store, &id[..8], Some(" pre-24H2 ")).unwrap();
+        assert!(pinned.pinned);
+        assert_eq!(pinned.tags, ["pre-24H2"]);
+        assert!(matches!(
+            snapshot::delete_in(&store, &id),
+            Err(PieuvreError::Config(_))
+        ));
+
+        pause();
+        snapshot::create_in(&store, "second", change()).unwrap();
+        pause();
+        snapshot::create_in(&store, "third", change()).unwrap();
+        let descriptions = |store: &MemoryStore| -> Vec<String> {
+            snapshot::list_in(store)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.description)
+                .collect()
+        };
+        assert_eq!(descriptions(&store), ["third", "before upgrade"]);
+
+        // Désépinglé : de nouveau soumis à la rotation, le label reste
+        let unpinned = snapshot::unpin_in(&store, &id).unwrap();
+        assert!(!unpinned.pinned);
+        assert_eq!(unpinned.tags, ["pre-24H2"]);
+        pause();
+        snapshot::create_in(&store, "fourth", change()).unwrap();
+        assert_eq!(descriptions(&store), ["fourth"]);
+
+        let base = baseline::capture_if_missing_in(&store, Vec::new)
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            snapshot::unpin_in(&store, &base.id.to_string()),
+            Err(PieuvreError::Config(_))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod signing_tests {
+    use crate::format;
+    use crate::signing::SigningKey;
+    use crate::snapshot;
+    use crate::store::{DirectoryStore, SnapshotStore};
+    use pieuvre_common::{ChangeRecord, PieuvreError};
+    use std::sync::Arc;
+
+    fn changes() -> Vec<ChangeRecord> {
+        vec![ChangeRecord::FirewallRule {
+            name: "pieuvre".to_string(),
+        }]
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("pieuvre-signing-{}-{}", name, std::process::id()))
+    }
+
+    fn key(byte: u8) -> Arc<SigningKey> {
+        Arc::new(SigningKey::from_bytes([byte; 32]))
+    }
+
+    #[test]
+    fn test_signed_store_refuses_tampering() {
+        let dir = temp_dir("tamper");
+        let store = DirectoryStore::new(&dir)
+            .with_compression(false)
+            .with_signing_key(key(1));
+        let snap = snapshot::create_in(&store, "signed", changes()).unwrap();
+        let id = snap.id.to_string();
+        assert_eq!(store.load(&id).unwrap().description, "signed");
+
+        // Autre machine : autre clé
+        let other = DirectoryStore::new(&dir).with_signing_key(key(2));
+        assert!(matches!(other.load(&id), Err(PieuvreError::Security(_))));
+
+        // JSON modifié et checksum recalculé : seule la signature le détecte
+        let json_path = dir.join(format!("{}.json", id));
+        let json = std::fs::read_to_string(&json_path)
+            .unwrap()
+            .replace("signed", "forged");
+        std::fs::write(&json_path, &json).unwrap();
+        std::fs::write(
+            dir.join(format!("{}.sha256", id)),
+            format::checksum(json.as_bytes()),
+        )
+        .unwrap();
+        assert!(matches!(store.load(&id), Err(PieuvreError::Security(_))));
+        let checks = snapshot::check_all_in(&store).unwrap();
+        assert!(!checks[0].is_ok());
+
+        std::fs::remove_file(dir.join(format!("{}.sig", id))).unwrap();
+        assert!(matches!(store.load(&id), Err(PieuvreError::Security(_))));
+
+        store.delete(&id).unwrap();
+        assert!(std::fs::read_dir(&dir).unwrap().all(|entry| entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .starts_with("index")));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_forged_index_is_rebuilt() {
+        let dir = temp_dir("index");
+        let store = DirectoryStore::new(&dir)
+            .with_compression(false)
+            .with_signing_key(key(1));
+        let snap = snapshot::create_in(&store, "rotated", changes()).unwrap();
+        assert!(store.read_index().unwrap().is_some());
+
+        // Épinglage forgé dans l'index : signature invalide, index reconstruit
+        let index_path = dir.join("index.json");
+        let forged = std::fs::read_to_string(&index_path)
+            .unwrap()
+            .replace("\"pinned\": false", "\"pinned\": true");
+        std::fs::write(&index_path, forged).unwrap();
+        assert!(store.read_index().unwrap().is_none());
+
+        let entries = snapshot::list_in(&store).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, snap.id.to_string());
+        assert!(!entries[0].pinned);
+        assert!(store.read_index().unwrap().is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_key_file_access_check() {
+        use crate::signing::is_restricted_sddl;
+
+        assert!(is_restricted_sddl("O:BAD:P(A;;FA;;;SY)(A;;FA;;;BA)"));
+        assert!(is_restricted_sddl(
+            "O:S-1-5-18D:PAI(A;;FA;;;S-1-5-18)(A;;FA;;;S-1-5-32-544)"
+        ));
+        // Un refus n'ouvre aucun accès
+        assert!(is_restricted_sddl("O:SYD:P(D;;FA;;;WD)(A;;FA;;;BA)"));
+
+        // Clé déposée par un utilisateur : propriétaire non administrateur
+        assert!(!is_restricted_sddl(
+            "O:S-1-5-21-1-2-3-1001D:P(A;;FA;;;SY)(A;;FA;;;BA)"
+        ));
+        // Accès hérité du répertoire ou ouvert à d'autres comptes
+        assert!(!is_restricted_sddl("O:BAD:AI(A;ID;FA;;;SY)(A;ID;FA;;;BA)"));
+        assert!(!is_restricted_sddl("O:BAD:P(A;;FA;;;SY)(A;;FR;;;BU)"));
+        assert!(!is_restricted_sddl("O:BAD:NO_ACCESS_CONTROL"));
+        assert!(!is_restricted_sddl("D:P(A;;FA;;;SY)(A;;FA;;;BA)"));
+    }
+
+    #[test]
+    fn test_adopt_unsigned_snapshots() {
+        let dir = temp_dir("adopt");
+        let unsigned = DirectoryStore::new(&dir);
+        let intact = snapshot::create_in(&unsigned, "intact", changes()).unwrap();
+        let corrupt = snapshot::create_in(&unsigned, "corrupt", changes()).unwrap();
+        std::fs::write(dir.join(format!("{}.sha256", corrupt.id)), "00").unwrap();
+
+        // Une nouvelle clé ne signe rien d'elle-même
+        let store = DirectoryStore::new(&dir).with_signing_key(key(1));
+        assert!(matches!(
+            store.load(&intact.id.to_string()),
+            Err(PieuvreError::Security(_))
+        ));
+
+        // Seul le snapshot intact est proposé, puis signé sur demande
+        let listed = store.unsigned().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, intact.id);
+        assert!(matches!(
+            store.load(&intact.id.to_string()),
+            Err(PieuvreError::Security(_))
+        ));
+        assert!(store.adopt(&[corrupt.id.to_string()]).is_err());
+        assert_eq!(store.adopt(&[intact.id.to_string()]).unwrap(), 1);
+        assert_eq!(store.adopt(&[intact.id.to_string()]).unwrap(), 0);
+        assert!(store.unsigned().unwrap().is_empty());
+        assert_eq!(
+            store.load(&intact.id.to_string()).unwrap().description,
+            "intact"
+        );
+        assert!(store.load(&corrupt.id.to_string()).is_err());
+
+        // Sans clé, rien n'est signé
+        assert!(matches!(
+            unsigned.adopt(&[corrupt.id.to_string()]),
+            Err(PieuvreError::Security(_))
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -47,13 +47,14 @@ impl ScanEngine {
             tracing::warn!("Impossible d'acquérir les privilèges requis: {:?}", e);
         }
 
+        let quarantine_dir = &pieuvre_common::config().scan.quarantine_dir;
         Ok(Self {
             registry_walker: Arc::new(RegistryWalker::new()),
             fast_filter: Arc::new(FastFilter::new(BLITZ_PATTERNS)?),
             signature_engine: Arc::new(SignatureEngine::new(DEFAULT_RULES)?),
             browser_forensics: Arc::new(BrowserForensics::new()),
             lnk_forensics: Arc::new(crate::engine::lnk::LnkForensics::new()),
-            remediator: Arc::new(Remediator::new(r"C:\Pieuvre\Quarantine")),
+            remediator: Arc::new(Remediator::new(&quarantine_dir.to_string_lossy())),
         })
     }
 
@@ -2,13 +2,22 @@
 //!
 //! Suppression des bloatware et packages non desires.
 
-use pieuvre_common::Result;
+use pieuvre_common::{ChangeRecord, PieuvreError, Result};
 use std::process::Command;
 
 /// Supprime un package AppX par son nom
 pub fn remove_package(name: &str) -> Result<()> {
     tracing::info!("Suppression package: {}", name);
 
+    // Noms complets a signaler pour le rollback, avant la suppression
+    if crate::changes::is_recording() {
+        for full_name in installed_full_names(name) {
+            crate::changes::push(ChangeRecord::AppX {
+                package_full_name: full_name,
+            })?;
+        }
+    }
+
     // Utiliser PowerShell pour supprimer
     let output = Command::new("powershell")
         .args([
@@ -32,6 +41,52 @@ pub fn remove_package(name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Noms complets (PackageFullName) des packages installes correspondant a `name`
+fn installed_full_names(name: &str) -> Vec<String> {
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            &format!(
+                "Get-AppxPackage -Name '*{}*' | Select-Object -ExpandProperty PackageFullName",
+                name
+            ),
+        ])
+        .output();
+
+    match output {
+        Ok(o) => String::from_utf8_lossy(&o.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Reenregistre un package supprime depuis son manifeste (fichiers encore presents)
+pub fn reinstall_package(package_full_name: &str) -> Result<()> {
+    let script = format!(
+        "$m = Join-Path $env:ProgramFiles 'WindowsApps\\{}\\AppxManifest.xml'; \
+         if (-not (Test-Path $m)) {{ exit 2 }}; \
+         Add-AppxPackage -DisableDevelopmentMode -Register $m -ErrorAction Stop",
+        package_full_name
+    );
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .output()?;
+
+    if !output.status.success() {
+        return Err(PieuvreError::System(format!(
+            "Cannot reinstall {} (package files no longer present?)",
+            package_full_name
+        )));
+    }
+    tracing::info!("AppX reinstalle: {}", package_full_name);
+    Ok(())
+}
+
 /// Liste des bloatware a supprimer
 const BLOATWARE: &[&str] = &[
     // Bing apps
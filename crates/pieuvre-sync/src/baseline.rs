@@ -0,0 +1,379 @@
+//! Pristine Baseline
+//!
+//! State of everything pieuvre can touch (registry values of the catalog,
+//! service start types, scheduled tasks, power settings), read before the
+//! first change. A reset writes it back, so the machine returns to *its*
+//! defaults rather than to generic ones.
+
+use crate::catalog::{TweakAction, TweakCatalog};
+use pieuvre_common::{
+    ChangeRecord, NativeRegistry, NativeServiceManager, PieuvreError, PowerSettingRecord,
+    RegistryBackend, RegistryPath, Result, ServiceManager,
+};
+
+/// Power settings written by pieuvre (subgroup, setting)
+const POWER_SETTINGS: [(&str, &str); 5] = [
+    // USB selective suspend
+    (
+        "2a737441-1930-4402-8d77-b2bebba308a3",
+        "48e6b7a6-50f5-4782-a5d4-53bb8f07e226",
+    ),
+    // PCI Express ASPM
+    (
+        "501a4d13-42af-4429-9fd1-a8218c268e20",
+        "ee12f906-d277-404b-b6da-e5fa1a576df5",
+    ),
+    // Processor min / max state
+    (
+        crate::power::SUBGROUP_PROCESSOR,
+        "893dee8e-2bef-41e0-89c6-b55d0929964c",
+    ),
+    (
+        crate::power::SUBGROUP_PROCESSOR,
+        "bc5038f7-23e0-4960-96da-33abaf5935ec",
+    ),
+    (
+        crate::power::SUBGROUP_PROCESSOR,
+        crate::power::SETTING_CORE_PARKING_MIN_CORES,
+    ),
+];
+
+/// Security values written by the HVCI, VBS and Spectre/Meltdown commands
+const SECURITY_VALUES: [(&str, &str); 6] = [
+    (crate::hardening::HVCI_KEY, "Enabled"),
+    (
+        crate::hardening::DEVICE_GUARD_KEY,
+        "EnableVirtualizationBasedSecurity",
+    ),
+    (crate::hardening::DEVICE_GUARD_KEY, "LsaCfgFlags"),
+    (
+        crate::hardening::DEVICE_GUARD_KEY,
+        "RequirePlatformSecurityFeatures",
+    ),
+    (
+        crate::hardening::MEMORY_MANAGEMENT_KEY,
+        "FeatureSettingsOverride",
+    ),
+    (
+        crate::hardening::MEMORY_MANAGEMENT_KEY,
+        "FeatureSettingsOverrideMask",
+    ),
+];
+
+/// What a baseline covers, without duplicates (case-insensitive)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BaselineTargets {
+    pub registry_values: Vec<(RegistryPath, String)>,
+    pub services: Vec<String>,
+    pub tasks: Vec<String>,
+    /// (subgroup, setting) of the active scheme
+    pub power_settings: Vec<(String, String)>,
+}
+
+impl BaselineTargets {
+    /// Everything the actions of a catalog write
+    pub fn from_catalog(catalog: &TweakCatalog) -> Self {
+        let mut targets = Self::default();
+        for action in catalog.tweaks().iter().flat_map(|t| &t.actions) {
+            match action {
+                TweakAction::RegistrySet { key, value, .. }
+                | TweakAction::RegistryDelete { key, value } => {
+                    // Clés validées au chargement du catalogue
+                    if let Ok(path) = RegistryPath::parse(key) {
+                        targets.add_registry_value(path, value);
+                    }
+                }
+                TweakAction::Service { name, .. } => targets.add_service(name),
+                TweakAction::ScheduledTask { path, .. } => targets.add_task(path),
+                TweakAction::HostsBlock { .. } => {}
+            }
+        }
+        targets
+    }
+
+    /// Targets of the built-in commands and of `reset_to_defaults`
+    pub fn builtin() -> Self {
+        use crate::hardening::{
+            SERVICE_DIAGTRACK, SERVICE_DOSVC, SERVICE_SYSMAIN, SERVICE_UPDATE, SERVICE_USOSVC,
+            SERVICE_WAP_PUSH, SERVICE_WERSVC, SERVICE_WSEARCH,
+        };
+
+        let mut targets = Self::default();
+        targets.add_registry_value(
+            RegistryPath::hklm(crate::hardening::PRIORITY_CONTROL_KEY),
+            "Win32PrioritySeparation",
+        );
+        for (key, value) in SECURITY_VALUES {
+            targets.add_registry_value(RegistryPath::hklm(key), value);
+        }
+        for (key, value, _) in crate::security::UAC_VALUES {
+            targets.add_registry_value(RegistryPath::hklm(key), value);
+        }
+        for (key, value, _) in crate::explorer::EXPLORER_TWEAKS {
+            targets.add_registry_value(RegistryPath::hkcu(key), value);
+        }
+        for service in [
+            SERVICE_DIAGTRACK,
+            SERVICE_WAP_PUSH,
+            SERVICE_WERSVC,
+            SERVICE_SYSMAIN,
+            SERVICE_WSEARCH,
+            SERVICE_UPDATE,
+            SERVICE_USOSVC,
+            SERVICE_DOSVC,
+        ] {
+            targets.add_service(service);
+        }
+        for task in crate::scheduled_tasks::get_telemetry_tasks() {
+            targets.add_task(task);
+        }
+        targets.power_settings = POWER_SETTINGS
+            .iter()
+            .map(|(subgroup, setting)| (subgroup.to_string(), setting.to_string()))
+            .collect();
+        targets
+    }
+
+    pub fn merge(&mut self, other: BaselineTargets) {
+        for (path, value) in other.registry_values {
+            self.add_registry_value(path, &value);
+        }
+        for service in &other.services {
+            self.add_service(service);
+        }
+        for task in &other.tasks {
+            self.add_task(task);
+        }
+        for (subgroup, setting) in other.power_settings {
+            if !self
+                .power_settings
+                .iter()
+                .any(|(s, g)| s.eq_ignore_ascii_case(&subgroup) && g.eq_ignore_ascii_case(&setting))
+            {
+                self.power_settings.push((subgroup, setting));
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.registry_values.len()
+            + self.services.len()
+            + self.tasks.len()
+            + self.power_settings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn add_registry_value(&mut self, path: RegistryPath, value: &str) {
+        let known = self.registry_values.iter().any(|(p, v)| {
+            p.hive == path.hive
+                && p.key.eq_ignore_ascii_case(&path.key)
+                && v.eq_ignore_ascii_case(value)
+        });
+        if !known {
+            self.registry_values.push((path, value.to_string()));
+        }
+    }
+
+    fn add_service(&mut self, name: &str) {
+        if !self.services.iter().any(|s| s.eq_ignore_ascii_case(name)) {
+            self.services.push(name.to_string());
+        }
+    }
+
+    fn add_task(&mut self, path: &str) {
+        if !self.tasks.iter().any(|t| t.eq_ignore_ascii_case(path)) {
+            self.tasks.push(path.to_string());
+        }
+    }
+}
+
+/// `records` holds the original state of this registry value
+pub fn covers_value(records: &[ChangeRecord], path: &RegistryPath, value: &str) -> bool {
+    records.iter().any(|record| {
+        matches!(record, ChangeRecord::Registry { hive, key, value_name, .. }
+            if *hive == path.hive
+                && key.eq_ignore_ascii_case(&path.key)
+                && value_name.eq_ignore_ascii_case(value))
+    })
+}
+
+/// `records` holds the original start type of this service
+pub fn covers_service(records: &[ChangeRecord], name: &str) -> bool {
+    records.iter().any(
+        |record| matches!(record, ChangeRecord::Service { name: n, .. } if n.eq_ignore_ascii_case(name)),
+    )
+}
+
+/// `records` holds the original power scheme
+pub fn covers_power(records: &[ChangeRecord]) -> bool {
+    records
+        .iter()
+        .any(|record| matches!(record, ChangeRecord::PowerScheme { .. }))
+}
+
+/// Current state of the live system, as change records (the original state of
+/// a snapshot)
+pub fn capture(targets: &BaselineTargets) -> Vec<ChangeRecord> {
+    let mut records = capture_with(targets, &NativeRegistry, &NativeServiceManager);
+
+    // Tâche absente : rien à restaurer
+    for task in &targets.tasks {
+        if let Some(enabled) = crate::scheduled_tasks::task_state(task) {
+            records.push(ChangeRecord::ScheduledTask {
+                path: task.clone(),
+                original_enabled: enabled,
+            });
+        }
+    }
+
+    if let Ok(scheme) = crate::power::get_active_scheme_guid() {
+        let settings: Vec<PowerSettingRecord> = targets
+            .power_settings
+            .iter()
+            .filter_map(|(subgroup, setting)| {
+                let (ac, dc) = crate::power::read_setting(&scheme, subgroup, setting);
+                (ac.is_some() || dc.is_some()).then(|| PowerSettingRecord {
+                    scheme: scheme.clone(),
+                    subgroup: subgroup.clone(),
+                    setting: setting.clone(),
+                    original_ac: ac,
+                    original_dc: dc,
+                })
+            })
+            .collect();
+        records.push(ChangeRecord::PowerScheme {
+            original_scheme: Some(scheme),
+            settings,
+        });
+    }
+    records
+}
+
+/// Registry values (absent ones included) and start types of existing services
+pub fn capture_with(
+    targets: &BaselineTargets,
+    registry: &dyn RegistryBackend,
+    services: &dyn ServiceManager,
+) -> Vec<ChangeRecord> {
+    let mut records = Vec::new();
+    for (path, value_name) in &targets.registry_values {
+        match registry.read_value(path, value_name) {
+            Ok(original_value) => records.push(ChangeRecord::Registry {
+                hive: path.hive,
+                key: path.key.clone(),
+                value_name: value_name.clone(),
+                original_value,
+            }),
+            // Valeur illisible : la restaurer écraserait un état inconnu
+            Err(e) => tracing::debug!("{}\\{} not captured: {}", path, value_name, e),
+        }
+    }
+    for name in &targets.services {
+        if !services.exists(name) {
+            continue;
+        }
+        match services.start_type(name) {
+            Ok(original_start_type) => records.push(ChangeRecord::Service {
+                name: name.clone(),
+                original_start_type,
+            }),
+            Err(e) => tracing::debug!("Service {} not captured: {}", name, e),
+        }
+    }
+    records
+}
+
+/// Puts the system back in the state of `records` (live system)
+pub fn apply(records: &[ChangeRecord]) -> Result<()> {
+    apply_with(records, &NativeRegistry, &NativeServiceManager)
+}
+
+/// Writes go through the tracked helpers, so a reset is itself recorded and
+/// can be rolled back. Every record is attempted; an error reports the failures.
+pub fn apply_with(
+    records: &[ChangeRecord],
+    registry: &dyn RegistryBackend,
+    services: &dyn ServiceManager,
+) -> Result<()> {
+    let mut failed = 0;
+    for record in records {
+        if let Err(e) = apply_record(record, registry, services) {
+            tracing::warn!(change = ?record, error = %e, "Baseline value not restored");
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(PieuvreError::Internal(format!(
+            "{} of {} baseline value(s) not restored",
+            failed,
+            records.len()
+        )));
+    }
+    Ok(())
+}
+
+fn apply_record(
+    record: &ChangeRecord,
+    registry: &dyn RegistryBackend,
+    services: &dyn ServiceManager,
+) -> Result<()> {
+    match record {
+        ChangeRecord::Registry {
+            hive,
+            key,
+            value_name,
+            original_value,
+        } => {
+            let path = RegistryPath::new(*hive, key);
+            match original_value {
+                Some(value) => crate::registry::set_value_with(registry, &path, value_name, value),
+                None if registry.read_value(&path, value_name)?.is_some() => {
+                    crate::registry::delete_value_with(registry, &path, value_name)
+                }
+                None => Ok(()),
+            }
+        }
+        ChangeRecord::Service {
+            name,
+            original_start_type,
+        } => crate::services::set_service_start_type_with(services, name, *original_start_type),
+        ChangeRecord::ScheduledTask {
+            path,
+            original_enabled: true,
+        } => crate::scheduled_tasks::enable_task(path),
+        ChangeRecord::ScheduledTask {
+            path,
+            original_enabled: false,
+        } => crate::scheduled_tasks::disable_task(path),
+        ChangeRecord::PowerScheme {
+            original_scheme,
+            settings,
+        } => apply_power(original_scheme.as_deref(), settings),
+        other => crate::rollback::restore_change_with(other, registry, services),
+    }
+}
+
+fn apply_power(scheme: Option<&str>, settings: &[PowerSettingRecord]) -> Result<()> {
+    for setting in settings {
+        crate::power::write_setting(
+            &setting.scheme,
+            &setting.subgroup,
+            &setting.setting,
+            setting.original_ac,
+            setting.original_dc,
+        )?;
+    }
+    let active = crate::power::get_active_scheme_guid()?;
+    let Some(scheme) = scheme.filter(|s| !s.eq_ignore_ascii_case(&active)) else {
+        // Réactivation : les index écrits prennent effet
+        return crate::power::set_active_scheme(&active);
+    };
+    crate::changes::push(ChangeRecord::PowerScheme {
+        original_scheme: Some(active),
+        settings: Vec::new(),
+    })?;
+    crate::power::set_active_scheme(scheme)
+}
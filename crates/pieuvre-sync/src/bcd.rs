@@ -0,0 +1,80 @@
+//! Boot Configuration Data
+//!
+//! Lecture et écriture des éléments de l'entrée de démarrage courante via bcdedit.
+//! La valeur d'origine est signalée à `crate::changes` avant chaque modification.
+
+use pieuvre_common::{ChangeRecord, PieuvreError, Result};
+use std::process::Command;
+
+/// Valeur d'un élément de `{current}`, `None` s'il n'est pas défini
+pub fn read_element(element: &str) -> Result<Option<String>> {
+    let output = Command::new("bcdedit")
+        .args(["/enum", "{current}"])
+        .output()?;
+    if !output.status.success() {
+        return Err(PieuvreError::System(format!(
+            "bcdedit /enum failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(parse_element(
+        &String::from_utf8_lossy(&output.stdout),
+        element,
+    ))
+}
+
+/// Extrait un élément de la sortie de `bcdedit /enum` (`nom   valeur` par ligne)
+pub fn parse_element(output: &str, element: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (name, value) = line.trim().split_once(char::is_whitespace)?;
+        name.eq_ignore_ascii_case(element)
+            .then(|| value.trim().to_string())
+    })
+}
+
+/// `bcdedit /set {current} <element> <value>`
+pub fn set_element(element: &str, value: &str) -> Result<()> {
+    track(element, Some(value))?;
+    run(&["/set", "{current}", element, value])
+}
+
+/// `bcdedit /deletevalue {current} <element>`
+pub fn delete_element(element: &str) -> Result<()> {
+    track(element, None)?;
+    run(&["/deletevalue", "{current}", element])
+}
+
+fn track(element: &str, target: Option<&str>) -> Result<()> {
+    if !crate::changes::is_recording() {
+        return Ok(());
+    }
+    match read_element(element) {
+        Ok(original) => {
+            let unchanged = match (&original, target) {
+                (Some(current), Some(target)) => current.eq_ignore_ascii_case(target),
+                (None, None) => true,
+                _ => false,
+            };
+            if !unchanged {
+                crate::changes::push(ChangeRecord::Bcd {
+                    element: element.to_string(),
+                    original_value: original,
+                })?;
+            }
+        }
+        Err(e) => tracing::warn!("BCD element {} not recorded: {}", element, e),
+    }
+    Ok(())
+}
+
+fn run(args: &[&str]) -> Result<()> {
+    let output = Command::new("bcdedit").args(args).output()?;
+    if !output.status.success() {
+        return Err(PieuvreError::System(format!(
+            "bcdedit {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stdout).trim()
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,582 @@
+//! Declarative tweak catalog
+//!
+//! Tweaks described as data (TOML or JSON): metadata, primitive actions and an
+//! expected-state check. [`CatalogOperation`] turns a definition into a generic
+//! [`SyncOperation`], so site-specific tweak packs need no code.
+
+use crate::graph::TweakGraph;
+use crate::operation::SyncOperation;
+use async_trait::async_trait;
+use pieuvre_common::{
+    native_registry, native_service_manager, ChangeRecord, PieuvreError, PlannedChange,
+    RegistryBackend, RegistryPath, RegistryValue, Result, ServiceManager, ServiceStartType,
+    SharedRegistry, SharedServiceManager,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use tracing::instrument;
+
+/// Risk level declared by a tweak
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TweakRisk {
+    Safe,
+    Low,
+    Medium,
+    High,
+    Critical,
+    Performance,
+    Conditional,
+    Warning,
+}
+
+/// Registry data as written in a catalog (`data = 0` or `data = "text"`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CatalogValue {
+    Dword(u32),
+    String(String),
+}
+
+impl CatalogValue {
+    pub fn to_registry_value(&self) -> RegistryValue {
+        match self {
+            CatalogValue::Dword(v) => RegistryValue::Dword(*v),
+            CatalogValue::String(s) => RegistryValue::String(s.clone()),
+        }
+    }
+
+    fn matches(&self, value: &RegistryValue) -> bool {
+        match (self, value) {
+            (CatalogValue::Dword(a), RegistryValue::Dword(b)) => a == b,
+            (CatalogValue::String(a), RegistryValue::String(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Primitive action of a catalog tweak, also used to express expected states
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TweakAction {
+    /// Sets a registry value (`key = 'HKLM\SOFTWARE\...'`)
+    RegistrySet {
+        key: String,
+        value: String,
+        data: CatalogValue,
+    },
+    /// Deletes a registry value
+    RegistryDelete { key: String, value: String },
+    /// Sets the start type of a service
+    Service {
+        name: String,
+        start_type: ServiceStartType,
+    },
+    /// Enables or disables a scheduled task
+    ScheduledTask { path: String, enabled: bool },
+    /// Redirects domains to 0.0.0.0 in the hosts file
+    HostsBlock { domains: Vec<String> },
+}
+
+/// How [`CatalogOperation::is_applied`] decides a tweak is in place
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckMode {
+    /// Every action is already in its target state
+    Actions,
+    /// No reliable check: always reported as not applied
+    None,
+}
+
+/// Expected-state check: a mode, or an explicit list of states that must hold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TweakCheck {
+    Mode(CheckMode),
+    States(Vec<TweakAction>),
+}
+
+impl Default for TweakCheck {
+    fn default() -> Self {
+        TweakCheck::Mode(CheckMode::Actions)
+    }
+}
+
+/// Tweak described by a catalog
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TweakDefinition {
+    pub id: String,
+    pub label: String,
+    #[serde(default)]
+    pub description: String,
+    /// Section of the interface (`telemetry`, `privacy`, `services`...)
+    pub category: String,
+    pub risk: TweakRisk,
+    /// Selected by default in interactive mode and `tweak apply-all`
+    #[serde(default)]
+    pub default: bool,
+    /// Takes effect only after a reboot (tracked in the pending-reboot queue)
+    #[serde(default)]
+    pub reboot: bool,
+    pub actions: Vec<TweakAction>,
+    #[serde(default)]
+    pub check: TweakCheck,
+}
+
+/// Relations of a tweak with other tweak ids (catalog or built-in commands)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TweakRelation {
+    pub tweak: String,
+    /// Applied first, added to the selection if missing
+    #[serde(default)]
+    pub requires: Vec<String>,
+    /// Cannot be applied together with `tweak`
+    #[serde(default)]
+    pub conflicts: Vec<String>,
+    /// Already applied by `tweak` (shared command): skipped when both are selected
+    #[serde(default)]
+    pub implies: Vec<String>,
+    /// Shown when a conflict is refused
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// Set of tweak definitions, keyed by id
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TweakCatalog {
+    #[serde(default, rename = "tweak")]
+    tweaks: Vec<TweakDefinition>,
+    #[serde(default, rename = "relation")]
+    relations: Vec<TweakRelation>,
+}
+
+impl TweakCatalog {
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let catalog: Self = toml::from_str(content)
+            .map_err(|e| PieuvreError::Config(format!("Invalid tweak catalog: {}", e)))?;
+        catalog.validate()?;
+        Ok(catalog)
+    }
+
+    pub fn from_json(content: &str) -> Result<Self> {
+        let catalog: Self = serde_json::from_str(content)
+            .map_err(|e| PieuvreError::Config(format!("Invalid tweak catalog: {}", e)))?;
+        catalog.validate()?;
+        Ok(catalog)
+    }
+
+    /// Loads a `.toml` or `.json` pack, errors are prefixed with the file path
+    pub fn load_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let catalog = if is_json {
+            Self::from_json(&content)
+        } else {
+            Self::from_toml(&content)
+        };
+        catalog.map_err(|e| match e {
+            PieuvreError::Config(msg) => {
+                PieuvreError::Config(format!("{}: {}", path.display(), msg))
+            }
+            other => other,
+        })
+    }
+
+    pub fn tweaks(&self) -> &[TweakDefinition] {
+        &self.tweaks
+    }
+
+    pub fn get(&self, id: &str) -> Option<&TweakDefinition> {
+        self.tweaks.iter().find(|t| t.id == id)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn relations(&self) -> &[TweakRelation] {
+        &self.relations
+    }
+
+    /// Dependency/conflict graph declared by the `[[relation]]` entries
+    pub fn graph(&self) -> TweakGraph {
+        TweakGraph::from_relations(&self.relations)
+    }
+
+    /// Adds a pack: tweaks with a known id replace the existing definition
+    pub fn merge(&mut self, pack: TweakCatalog) {
+        for tweak in pack.tweaks {
+            match self.tweaks.iter_mut().find(|t| t.id == tweak.id) {
+                Some(existing) => {
+                    tracing::info!(id = %tweak.id, "Tweak overridden by pack");
+                    *existing = tweak;
+                }
+                None => self.tweaks.push(tweak),
+            }
+        }
+        // Les relations s'ajoutent à celles déjà déclarées
+        self.relations.extend(pack.relations);
+    }
+
+    /// Checks ids, categories and action parameters; all errors are reported together
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+        let mut seen = HashSet::new();
+
+        for tweak in &self.tweaks {
+            let id = &tweak.id;
+            if !is_valid_id(id) {
+                errors.push(format!(
+                    "'{}': id must be non-empty snake_case ([a-z0-9_])",
+                    id
+                ));
+            }
+            if !seen.insert(id.as_str()) {
+                errors.push(format!("'{}': duplicate id", id));
+            }
+            if tweak.label.trim().is_empty() {
+                errors.push(format!("'{}': label must not be empty", id));
+            }
+            if tweak.category.trim().is_empty() {
+                errors.push(format!("'{}': category must not be empty", id));
+            }
+            if tweak.actions.is_empty() {
+                errors.push(format!("'{}': at least one action is required", id));
+            }
+
+            let states = match &tweak.check {
+                TweakCheck::States(states) => states.as_slice(),
+                TweakCheck::Mode(_) => &[],
+            };
+            for action in tweak.actions.iter().chain(states) {
+                if let Err(e) = validate_action(action) {
+                    errors.push(format!("'{}': {}", id, e));
+                }
+            }
+        }
+
+        for relation in &self.relations {
+            let tweak = &relation.tweak;
+            let targets = relation
+                .requires
+                .iter()
+                .chain(&relation.conflicts)
+                .chain(&relation.implies);
+            for id in std::iter::once(tweak).chain(targets.clone()) {
+                if !is_valid_id(id) {
+                    errors.push(format!(
+                        "relation '{}': '{}' is not a valid tweak id",
+                        tweak, id
+                    ));
+                }
+            }
+            if targets.clone().next().is_none() {
+                errors.push(format!("relation '{}': no related tweak", tweak));
+            }
+            if targets.into_iter().any(|id| id == tweak) {
+                errors.push(format!("relation '{}': refers to itself", tweak));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PieuvreError::Config(format!(
+                "Invalid tweak catalog:\n  - {}",
+                errors.join("\n  - ")
+            )))
+        }
+    }
+}
+
+/// snake_case: `[a-z0-9_]+`
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn validate_action(action: &TweakAction) -> std::result::Result<(), String> {
+    match action {
+        TweakAction::RegistrySet { key, value, .. }
+        | TweakAction::RegistryDelete { key, value } => {
+            let path = RegistryPath::parse(key).map_err(|e| e.to_string())?;
+            if path.is_root() {
+                return Err(format!("registry key '{}' targets a hive root", key));
+            }
+            if value.is_empty() {
+                return Err(format!("empty value name under '{}'", key));
+            }
+        }
+        TweakAction::Service { name, start_type } => {
+            if name.trim().is_empty() {
+                return Err("empty service name".to_string());
+            }
+            if start_type.to_raw().is_none() {
+                return Err(format!("service '{}': start type cannot be Unknown", name));
+            }
+        }
+        TweakAction::ScheduledTask { path, .. } => {
+            if !path.starts_with('\\') {
+                return Err(format!(
+                    "scheduled task '{}' must be an absolute path (\\...)",
+                    path
+                ));
+            }
+        }
+        TweakAction::HostsBlock { domains } => {
+            if domains.is_empty() {
+                return Err("hosts_block without domains".to_string());
+            }
+            if let Some(bad) = domains
+                .iter()
+                .find(|d| d.is_empty() || d.contains(char::is_whitespace))
+            {
+                return Err(format!("invalid domain '{}'", bad));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Generic [`SyncOperation`] running the actions of a catalog tweak
+pub struct CatalogOperation {
+    pub definition: TweakDefinition,
+    pub registry: SharedRegistry,
+    pub services: SharedServiceManager,
+}
+
+impl CatalogOperation {
+    /// Operation on the live registry and SCM
+    pub fn new(definition: TweakDefinition) -> Self {
+        Self {
+            definition,
+            registry: native_registry(),
+            services: native_service_manager(),
+        }
+    }
+
+    /// Targets another registry backend (tests, simulation)
+    pub fn with_backend(mut self, registry: SharedRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Targets another service manager (tests, simulation)
+    pub fn with_services(mut self, services: SharedServiceManager) -> Self {
+        self.services = services;
+        self
+    }
+}
+
+/// Applies one action, returns the change to record (if any)
+fn apply_action(
+    registry: &dyn RegistryBackend,
+    services: &dyn ServiceManager,
+    action: &TweakAction,
+) -> Result<Option<ChangeRecord>> {
+    match action {
+        TweakAction::RegistrySet { key, value, data } => {
+            let path = RegistryPath::parse(key)?;
+            let original = registry.read_value(&path, value)?;
+            if original.as_ref().is_some_and(|v| data.matches(v)) {
+                return Ok(None);
+            }
+            registry.write_value(&path, value, &data.to_registry_value())?;
+            Ok(Some(ChangeRecord::Registry {
+                hive: path.hive,
+                key: path.key,
+                value_name: value.clone(),
+                original_value: original,
+            }))
+        }
+        TweakAction::RegistryDelete { key, value } => {
+            let path = RegistryPath::parse(key)?;
+            let Some(original) = registry.read_value(&path, value)? else {
+                return Ok(None);
+            };
+            registry.delete_value(&path, value)?;
+            Ok(Some(ChangeRecord::Registry {
+                hive: path.hive,
+                key: path.key,
+                value_name: value.clone(),
+                original_value: Some(original),
+            }))
+        }
+        TweakAction::Service { name, start_type } => {
+            let target = start_type.to_raw().ok_or_else(|| {
+                PieuvreError::Config(format!("Unknown start type for service {}", name))
+            })?;
+            let original = services.start_type(name)?;
+            if original == target {
+                return Ok(None);
+            }
+            services.set_start_type(name, target)?;
+            Ok(Some(ChangeRecord::Service {
+                name: name.clone(),
+                original_start_type: original,
+            }))
+        }
+        // Tâches et hosts : l'état d'origine est signalé par les modules eux-mêmes
+        TweakAction::ScheduledTask { path, enabled } => {
+            let (result, mut changes) = crate::changes::record(|| {
+                if *enabled {
+                    crate::scheduled_tasks::enable_task(path)
+                } else {
+                    crate::scheduled_tasks::disable_task(path)
+                }
+            });
+            result?;
+            Ok(changes.pop())
+        }
+        TweakAction::HostsBlock { domains } => {
+            let (result, mut changes) =
+                crate::changes::record(|| crate::hosts::block_domains(domains));
+            result?;
+            Ok(changes.pop())
+        }
+    }
+}
+
+/// Change `apply_action` would make, without modifying anything
+fn plan_action(
+    registry: &dyn RegistryBackend,
+    services: &dyn ServiceManager,
+    action: &TweakAction,
+) -> Result<Option<PlannedChange>> {
+    match action {
+        TweakAction::RegistrySet { key, value, data } => {
+            let path = RegistryPath::parse(key)?;
+            let current = registry.read_value(&path, value)?;
+            if current.as_ref().is_some_and(|v| data.matches(v)) {
+                return Ok(None);
+            }
+            Ok(Some(PlannedChange::Registry {
+                hive: path.hive,
+                key: path.key,
+                value_name: value.clone(),
+                current,
+                target: Some(data.to_registry_value()),
+            }))
+        }
+        TweakAction::RegistryDelete { key, value } => {
+            crate::plan::registry_value(registry, &RegistryPath::parse(key)?, value, None)
+        }
+        TweakAction::Service { name, start_type } => {
+            let target = start_type.to_raw().ok_or_else(|| {
+                PieuvreError::Config(format!("Unknown start type for service {}", name))
+            })?;
+            Ok(crate::plan::service_start_type(services, name, target))
+        }
+        TweakAction::ScheduledTask { path, enabled } => {
+            Ok(crate::plan::scheduled_task(path, *enabled))
+        }
+        TweakAction::HostsBlock { domains } => crate::plan::hosts_block(domains),
+    }
+}
+
+/// True if the state described by `action` already holds
+fn state_holds(
+    registry: &dyn RegistryBackend,
+    services: &dyn ServiceManager,
+    action: &TweakAction,
+) -> Result<bool> {
+    Ok(match action {
+        TweakAction::RegistrySet { key, value, data } => {
+            let current = registry.read_value(&RegistryPath::parse(key)?, value);
+            matches!(current, Ok(Some(v)) if data.matches(&v))
+        }
+        TweakAction::RegistryDelete { key, value } => {
+            matches!(
+                registry.read_value(&RegistryPath::parse(key)?, value),
+                Ok(None)
+            )
+        }
+        TweakAction::Service { name, start_type } => {
+            matches!(services.start_type(name), Ok(t) if Some(t) == start_type.to_raw())
+        }
+        TweakAction::ScheduledTask { path, enabled } => {
+            crate::scheduled_tasks::is_task_enabled(path) == *enabled
+        }
+        TweakAction::HostsBlock { domains } => crate::hosts::are_domains_blocked(domains),
+    })
+}
+
+#[async_trait]
+impl SyncOperation for CatalogOperation {
+    fn name(&self) -> &str {
+        &self.definition.id
+    }
+
+    #[instrument(skip(self), fields(id = %self.definition.id))]
+    async fn apply(&self) -> Result<Vec<ChangeRecord>> {
+        let registry = self.registry.clone();
+        let services = self.services.clone();
+        let actions = self.definition.actions.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut changes = Vec::new();
+            for action in &actions {
+                // Les actions déjà faites restent attachées à l'erreur (rollback)
+                match apply_action(registry.as_ref(), services.as_ref(), action) {
+                    Ok(Some(change)) => changes.push(change),
+                    Ok(None) => {}
+                    Err(e) => return Err(e.with_changes(changes)),
+                }
+            }
+            Ok(changes)
+        })
+        .await
+        .map_err(|e| PieuvreError::Internal(e.to_string()))?
+    }
+
+    #[instrument(skip(self), fields(id = %self.definition.id))]
+    async fn is_applied(&self) -> Result<bool> {
+        let states = match &self.definition.check {
+            TweakCheck::Mode(CheckMode::None) => return Ok(false),
+            TweakCheck::Mode(CheckMode::Actions) => self.definition.actions.clone(),
+            TweakCheck::States(states) => states.clone(),
+        };
+        let registry = self.registry.clone();
+        let services = self.services.clone();
+
+        tokio::task::spawn_blocking(move || {
+            for state in &states {
+                if !state_holds(registry.as_ref(), services.as_ref(), state)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })
+        .await
+        .map_err(|e| PieuvreError::Internal(e.to_string()))?
+    }
+    #[instrument(skip(self), fields(id = %self.definition.id))]
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        let registry = self.registry.clone();
+        let services = self.services.clone();
+        let actions = self.definition.actions.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut planned = Vec::new();
+            for action in &actions {
+                if let Some(change) = plan_action(registry.as_ref(), services.as_ref(), action)? {
+                    planned.push(change);
+                }
+            }
+            Ok(planned)
+        })
+        .await
+        .map_err(|e| PieuvreError::Internal(e.to_string()))?
+    }
+
+    fn requires_reboot(&self) -> bool {
+        self.definition.reboot
+    }
+}
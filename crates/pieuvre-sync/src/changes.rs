@@ -0,0 +1,241 @@
+//! Change recording
+//!
+//! Les fonctions d'écriture (registre, services, tâches planifiées, hosts, BCD,
+//! énergie, MSI, ACL) signalent ici l'état d'origine de ce qu'elles modifient.
+//! `record` collecte ces `ChangeRecord` pour le code exécuté sur le thread courant,
+//! afin que chaque opération renvoie de quoi restaurer la machine.
+//!
+//! Chaque changement est aussi transmis au `ChangeJournal` installé (journal
+//! write-ahead de `pieuvre-persist`) avant que la modification soit faite.
+
+use pieuvre_common::{ChangeRecord, Result};
+use std::cell::RefCell;
+use std::sync::{Arc, RwLock};
+
+/// Reçoit chaque changement avant la modification correspondante.
+/// Une erreur annule la modification.
+pub trait ChangeJournal: Send + Sync {
+    fn append(&self, change: &ChangeRecord) -> Result<()>;
+}
+
+/// Journal utilisé par les `record` de tous les threads
+static JOURNAL: RwLock<Option<Arc<dyn ChangeJournal>>> = RwLock::new(None);
+
+struct Recording {
+    changes: Vec<ChangeRecord>,
+    journal: Option<Arc<dyn ChangeJournal>>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recording>> = const { RefCell::new(None) };
+}
+
+/// Installe (ou retire avec `None`) le journal ; renvoie le précédent
+pub fn set_journal(journal: Option<Arc<dyn ChangeJournal>>) -> Option<Arc<dyn ChangeJournal>> {
+    match JOURNAL.write() {
+        Ok(mut current) => std::mem::replace(&mut *current, journal),
+        Err(poisoned) => std::mem::replace(&mut *poisoned.into_inner(), journal),
+    }
+}
+
+fn installed_journal() -> Option<Arc<dyn ChangeJournal>> {
+    match JOURNAL.read() {
+        Ok(journal) => journal.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+/// Exécute `f` en collectant les changements, y compris ceux faits avant une erreur
+pub fn record<T>(f: impl FnOnce() -> Result<T>) -> (Result<T>, Vec<ChangeRecord>) {
+    // Un enregistrement imbriqué garde le journal de l'appelant
+    let journal = RECORDER
+        .with(|r| r.borrow().as_ref().map(|outer| outer.journal.clone()))
+        .unwrap_or_else(installed_journal);
+    record_inner(journal, f)
+}
+
+/// `record` avec un journal explicite, quel que soit celui installé
+pub fn record_journaled<T>(
+    journal: Arc<dyn ChangeJournal>,
+    f: impl FnOnce() -> Result<T>,
+) -> (Result<T>, Vec<ChangeRecord>) {
+    record_inner(Some(journal), f)
+}
+
+fn record_inner<T>(
+    journal: Option<Arc<dyn ChangeJournal>>,
+    f: impl FnOnce() -> Result<T>,
+) -> (Result<T>, Vec<ChangeRecord>) {
+    let outer = RECORDER.with(|r| {
+        r.borrow_mut().replace(Recording {
+            changes: Vec::new(),
+            journal,
+        })
+    });
+    let result = f();
+    let changes = RECORDER.with(|r| {
+        let mut recorder = r.borrow_mut();
+        let changes = recorder.take().map(|r| r.changes).unwrap_or_default();
+        *recorder = outer;
+        changes
+    });
+    // Un enregistrement imbriqué remonte aussi ses changements à l'appelant
+    // (déjà journalisés)
+    RECORDER.with(|r| {
+        if let Some(outer) = r.borrow_mut().as_mut() {
+            for change in &changes {
+                merge(&mut outer.changes, change.clone());
+            }
+        }
+    });
+    (result, changes)
+}
+
+/// Vrai si un `record` est en cours sur ce thread (évite de lire l'état d'origine sinon)
+pub fn is_recording() -> bool {
+    RECORDER.with(|r| r.borrow().is_some())
+}
+
+/// Ajoute un changement, à appeler avant la modification : il est d'abord écrit
+/// au journal, dont l'erreur est renvoyée. Seul le premier état d'origine d'une
+/// cible est conservé.
+pub fn push(change: ChangeRecord) -> Result<()> {
+    let journal = RECORDER.with(|r| r.borrow().as_ref().map(|r| r.journal.clone()));
+    let Some(journal) = journal else {
+        return Ok(());
+    };
+    if let Some(journal) = journal {
+        journal.append(&change)?;
+    }
+    RECORDER.with(|r| {
+        if let Some(recording) = r.borrow_mut().as_mut() {
+            merge(&mut recording.changes, change);
+        }
+    });
+    Ok(())
+}
+
+/// Fusionne des changements (journal relu...) comme le fait `record`
+pub fn merged(changes: impl IntoIterator<Item = ChangeRecord>) -> Vec<ChangeRecord> {
+    let mut merged = Vec::new();
+    for change in changes {
+        merge(&mut merged, change);
+    }
+    merged
+}
+
+fn merge(changes: &mut Vec<ChangeRecord>, change: ChangeRecord) {
+    match change {
+        ChangeRecord::PowerScheme {
+            original_scheme,
+            settings,
+        } => {
+            let existing = changes
+                .iter_mut()
+                .find(|c| matches!(c, ChangeRecord::PowerScheme { .. }));
+            if let Some(ChangeRecord::PowerScheme {
+                original_scheme: first_scheme,
+                settings: first_settings,
+            }) = existing
+            {
+                if first_scheme.is_none() {
+                    *first_scheme = original_scheme;
+                }
+                for setting in settings {
+                    match first_settings.iter_mut().find(|s| {
+                        s.scheme == setting.scheme
+                            && s.subgroup == setting.subgroup
+                            && s.setting == setting.setting
+                    }) {
+                        Some(first) => {
+                            first.original_ac = first.original_ac.or(setting.original_ac);
+                            first.original_dc = first.original_dc.or(setting.original_dc);
+                        }
+                        None => first_settings.push(setting),
+                    }
+                }
+            } else {
+                changes.push(ChangeRecord::PowerScheme {
+                    original_scheme,
+                    settings,
+                });
+            }
+        }
+        ChangeRecord::HostsBlock { domains } => {
+            let existing = changes
+                .iter_mut()
+                .find(|c| matches!(c, ChangeRecord::HostsBlock { .. }));
+            if let Some(ChangeRecord::HostsBlock { domains: first }) = existing {
+                for domain in domains {
+                    if !first.contains(&domain) {
+                        first.push(domain);
+                    }
+                }
+            } else {
+                changes.push(ChangeRecord::HostsBlock { domains });
+            }
+        }
+        change => {
+            if !changes.iter().any(|c| same_target(c, &change)) {
+                changes.push(change);
+            }
+        }
+    }
+}
+
+/// Deux changements portant sur la même cible (clé/valeur, service, tâche...)
+fn same_target(a: &ChangeRecord, b: &ChangeRecord) -> bool {
+    match (a, b) {
+        (
+            ChangeRecord::Registry {
+                hive: h1,
+                key: k1,
+                value_name: v1,
+                ..
+            },
+            ChangeRecord::Registry {
+                hive: h2,
+                key: k2,
+                value_name: v2,
+                ..
+            },
+        ) => h1 == h2 && k1.eq_ignore_ascii_case(k2) && v1.eq_ignore_ascii_case(v2),
+        (
+            ChangeRecord::RegistryKeyCreated { hive: h1, key: k1 },
+            ChangeRecord::RegistryKeyCreated { hive: h2, key: k2 },
+        )
+        | (
+            ChangeRecord::RegistryKeyDeleted {
+                hive: h1, key: k1, ..
+            },
+            ChangeRecord::RegistryKeyDeleted {
+                hive: h2, key: k2, ..
+            },
+        ) => h1 == h2 && k1.eq_ignore_ascii_case(k2),
+        (ChangeRecord::Service { name: a, .. }, ChangeRecord::Service { name: b, .. }) => {
+            a.eq_ignore_ascii_case(b)
+        }
+        (ChangeRecord::FirewallRule { name: a }, ChangeRecord::FirewallRule { name: b }) => a == b,
+        (
+            ChangeRecord::AppX {
+                package_full_name: a,
+            },
+            ChangeRecord::AppX {
+                package_full_name: b,
+            },
+        ) => a == b,
+        (
+            ChangeRecord::MsiDevice { device_path: a, .. },
+            ChangeRecord::MsiDevice { device_path: b, .. },
+        ) => a.eq_ignore_ascii_case(b),
+        (
+            ChangeRecord::ScheduledTask { path: a, .. },
+            ChangeRecord::ScheduledTask { path: b, .. },
+        ) => a.eq_ignore_ascii_case(b),
+        (ChangeRecord::Bcd { element: a, .. }, ChangeRecord::Bcd { element: b, .. }) => {
+            a.eq_ignore_ascii_case(b)
+        }
+        (ChangeRecord::Dacl { target: a, .. }, ChangeRecord::Dacl { target: b, .. }) => a == b,
+        _ => false,
+    }
+}
@@ -1,9 +1,19 @@
 //! Context Menu Cleanup
 //!
 //! Remove Windows 11 context menu clutter.
+//! Deleted handler keys are recorded with their subtree and can be restored.
 
-use pieuvre_common::Result;
-use std::process::Command;
+use crate::registry::{delete_key_in_hive, key_exists_in_hive, set_string_value_in_hive};
+use pieuvre_common::{RegistryHive, Result};
+
+/// HKCR merges these two roots: handlers may live in either
+const HANDLER_ROOTS: &[(RegistryHive, &str)] = &[
+    (RegistryHive::Hklm, r"SOFTWARE\Classes"),
+    (RegistryHive::Hkcu, r"Software\Classes"),
+];
+
+/// Presence of this (empty) COM server restores the classic context menu
+const CLASSIC_MENU_KEY: &str = r"Software\Classes\CLSID\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}";
 
 /// Context menu items to remove
 const CONTEXT_MENU_CLSIDS: &[(&str, &str)] = &[
@@ -40,36 +50,28 @@ pub fn remove_context_menu_clutter() -> Result<u32> {
     let mut removed = 0u32;
 
     for (clsid, name) in CONTEXT_MENU_CLSIDS {
-        // Remove from HKCR
-        let key = format!(r"HKCR\*\shellex\ContextMenuHandlers\{}", clsid);
-        let output = Command::new("reg").args(["delete", &key, "/f"]).output();
+        let mut found = false;
+        for (hive, classes) in HANDLER_ROOTS {
+            let key = format!(r"{}\*\shellex\ContextMenuHandlers\{}", classes, clsid);
+            if key_exists_in_hive(*hive, &key) {
+                delete_key_in_hive(*hive, &key)?;
+                found = true;
+            }
+        }
 
-        if output.is_ok() {
+        if found {
             tracing::info!("Removed context menu: {}", name);
             removed += 1;
         }
-
-        // Also try HKCU
-        let key_user = format!(
-            r"HKCU\Software\Classes\*\shellex\ContextMenuHandlers\{}",
-            clsid
-        );
-        let _ = Command::new("reg")
-            .args(["delete", &key_user, "/f"])
-            .output();
     }
 
     // Disable "Show more options" (restore classic context menu)
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKCU\Software\Classes\CLSID\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\InprocServer32",
-            "/ve",
-            "/d",
-            "",
-            "/f",
-        ])
-        .output();
+    set_string_value_in_hive(
+        RegistryHive::Hkcu,
+        &format!(r"{}\InprocServer32", CLASSIC_MENU_KEY),
+        "",
+        "",
+    )?;
 
     tracing::info!("Removed {} context menu items", removed);
     Ok(removed)
@@ -77,13 +79,7 @@ pub fn remove_context_menu_clutter() -> Result<u32> {
 
 /// Restore Windows 11 modern context menu
 pub fn restore_modern_context_menu() -> Result<()> {
-    let _ = Command::new("reg")
-        .args([
-            "delete",
-            r"HKCU\Software\Classes\CLSID\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}",
-            "/f",
-        ])
-        .output();
+    delete_key_in_hive(RegistryHive::Hkcu, CLASSIC_MENU_KEY)?;
 
     tracing::info!("Restored modern context menu");
     Ok(())
@@ -91,12 +87,8 @@ pub fn restore_modern_context_menu() -> Result<()> {
 
 /// Check if classic context menu is enabled
 pub fn is_classic_context_menu() -> bool {
-    let output = Command::new("reg")
-        .args([
-            "query",
-            r"HKCU\Software\Classes\CLSID\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\InprocServer32",
-        ])
-        .output();
-
-    output.map(|o| o.status.success()).unwrap_or(false)
+    key_exists_in_hive(
+        RegistryHive::Hkcu,
+        &format!(r"{}\InprocServer32", CLASSIC_MENU_KEY),
+    )
 }
@@ -2,38 +2,20 @@
 //!
 //! Core parking, processor power management, and memory compression.
 
+use crate::power::{SETTING_CORE_PARKING_MIN_CORES, SUBGROUP_PROCESSOR};
 use pieuvre_common::Result;
 use std::process::Command;
 
 /// Disable CPU Core Parking - keeps all cores active
 /// Prevents latency from core wake-up
 pub fn disable_core_parking() -> Result<()> {
-    // Core Parking - Min Cores (AC)
-    let _ = Command::new("powercfg")
-        .args([
-            "/setacvalueindex",
-            "scheme_current",
-            "54533251-82be-4824-96c1-47b60b740d00",
-            "0cc5b647-c1df-4637-891a-edc3318ea174",
-            "100",
-        ])
-        .output();
-
-    // Core Parking - Min Cores (DC)
-    let _ = Command::new("powercfg")
-        .args([
-            "/setdcvalueindex",
-            "scheme_current",
-            "54533251-82be-4824-96c1-47b60b740d00",
-            "0cc5b647-c1df-4637-891a-edc3318ea174",
-            "100",
-        ])
-        .output();
-
-    // Apply changes
-    let _ = Command::new("powercfg")
-        .args(["/setactive", "scheme_current"])
-        .output();
+    // Core Parking - Min Cores (AC + DC), applied to the active scheme
+    crate::power::write_active_setting(
+        SUBGROUP_PROCESSOR,
+        SETTING_CORE_PARKING_MIN_CORES,
+        Some(100),
+        Some(100),
+    )?;
 
     tracing::info!("CPU Core Parking disabled - all cores active");
     Ok(())
@@ -41,19 +23,12 @@ pub fn disable_core_parking() -> Result<()> {
 
 /// Enable CPU Core Parking (restore default)
 pub fn enable_core_parking() -> Result<()> {
-    let _ = Command::new("powercfg")
-        .args([
-            "/setacvalueindex",
-            "scheme_current",
-            "54533251-82be-4824-96c1-47b60b740d00",
-            "0cc5b647-c1df-4637-891a-edc3318ea174",
-            "0",
-        ])
-        .output();
-
-    let _ = Command::new("powercfg")
-        .args(["/setactive", "scheme_current"])
-        .output();
+    crate::power::write_active_setting(
+        SUBGROUP_PROCESSOR,
+        SETTING_CORE_PARKING_MIN_CORES,
+        Some(0),
+        None,
+    )?;
 
     tracing::info!("CPU Core Parking enabled (default)");
     Ok(())
@@ -91,27 +66,10 @@ pub fn enable_memory_compression() -> Result<()> {
 /// Disable Superfetch/SysMain memory prefetch
 /// Already in services.rs but with registry backup
 pub fn disable_superfetch_registry() -> Result<()> {
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SYSTEM\CurrentControlSet\Control\Session Manager\Memory Management\PrefetchParameters",
-            "/v", "EnableSuperfetch",
-            "/t", "REG_DWORD",
-            "/d", "0",
-            "/f"
-        ])
-        .output();
-
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SYSTEM\CurrentControlSet\Control\Session Manager\Memory Management\PrefetchParameters",
-            "/v", "EnablePrefetcher",
-            "/t", "REG_DWORD",
-            "/d", "0",
-            "/f"
-        ])
-        .output();
+    let path =
+        r"SYSTEM\CurrentControlSet\Control\Session Manager\Memory Management\PrefetchParameters";
+    crate::registry::set_dword_value(path, "EnableSuperfetch", 0)?;
+    crate::registry::set_dword_value(path, "EnablePrefetcher", 0)?;
 
     tracing::info!("Superfetch/Prefetch disabled via registry");
     Ok(())
@@ -181,20 +139,14 @@ pub fn is_memory_compression_enabled() -> bool {
 
 /// Check if Core Parking is disabled (all cores at 100%)
 pub fn is_core_parking_disabled() -> bool {
-    let output = Command::new("powercfg")
-        .args([
-            "/query",
-            "scheme_current",
-            "54533251-82be-4824-96c1-47b60b740d00",
-            "0cc5b647-c1df-4637-891a-edc3318ea174",
-        ])
-        .output();
-
-    match output {
-        Ok(o) => {
-            let stdout = String::from_utf8_lossy(&o.stdout);
-            stdout.contains("0x00000064") // 100 in hex
-        }
-        Err(_) => false,
-    }
+    crate::power::get_active_scheme_guid()
+        .map(|scheme| {
+            let (ac, _) = crate::power::read_setting(
+                &scheme,
+                SUBGROUP_PROCESSOR,
+                SETTING_CORE_PARKING_MIN_CORES,
+            );
+            ac == Some(100)
+        })
+        .unwrap_or(false)
 }
@@ -28,11 +28,11 @@ impl SyncOperation for ConfigureDohOperation {
         let mut changes = Vec::new();
 
         // 1. Enable DoH in Registry
-        let op = RegistryDwordOperation {
-            key: r"SYSTEM\CurrentControlSet\Services\Dnscache\Parameters".to_string(),
-            value: "EnableAutoDoh".to_string(),
-            target_data: 2, // 2 = Required
-        };
+        let op = RegistryDwordOperation::new(
+            r"SYSTEM\CurrentControlSet\Services\Dnscache\Parameters",
+            "EnableAutoDoh",
+            2, // 2 = Required
+        );
         changes.extend(op.apply().await?);
 
         // Note: La configuration des IPs DNS nécessite normalement des appels netsh ou WMI.
@@ -78,11 +78,11 @@ impl SyncOperation for FlushDnsOperation {
 pub fn set_doh_provider(_provider: DNSProvider) -> Result<()> {
     // Les opérations de registre sont synchrones, mais SyncOperation::apply est async.
     // Pour le SOTA, on appelle directement la logique de registre ici.
-    let op = RegistryDwordOperation {
-        key: r"SYSTEM\CurrentControlSet\Services\Dnscache\Parameters".to_string(),
-        value: "EnableAutoDoh".to_string(),
-        target_data: 2,
-    };
+    let op = RegistryDwordOperation::new(
+        r"SYSTEM\CurrentControlSet\Services\Dnscache\Parameters",
+        "EnableAutoDoh",
+        2,
+    );
     // On utilise une approche synchrone directe pour éviter le overhead async
     crate::registry::set_dword_value(&op.key, &op.value, op.target_data)?;
     Ok(())
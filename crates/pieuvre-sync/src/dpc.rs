@@ -4,24 +4,15 @@
 //! Addresses micro-stutters, input lag, and audio crackling.
 
 use pieuvre_common::Result;
-use std::process::Command;
+
+const MEMORY_MANAGEMENT_KEY: &str =
+    r"SYSTEM\CurrentControlSet\Control\Session Manager\Memory Management";
+const KERNEL_KEY: &str = r"SYSTEM\CurrentControlSet\Control\Session Manager\Kernel";
 
 /// Keep kernel code in RAM (prevent paging)
 /// Reduces latency spikes from disk access
 pub fn disable_paging_executive() -> Result<()> {
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SYSTEM\CurrentControlSet\Control\Session Manager\Memory Management",
-            "/v",
-            "DisablePagingExecutive",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "1",
-            "/f",
-        ])
-        .output();
+    crate::registry::set_dword_value(MEMORY_MANAGEMENT_KEY, "DisablePagingExecutive", 1)?;
 
     tracing::info!("DisablePagingExecutive enabled - kernel stays in RAM");
     Ok(())
@@ -29,19 +20,7 @@ pub fn disable_paging_executive() -> Result<()> {
 
 /// Enable kernel paging (restore default)
 pub fn enable_paging_executive() -> Result<()> {
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SYSTEM\CurrentControlSet\Control\Session Manager\Memory Management",
-            "/v",
-            "DisablePagingExecutive",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
+    crate::registry::set_dword_value(MEMORY_MANAGEMENT_KEY, "DisablePagingExecutive", 0)?;
 
     tracing::info!("DisablePagingExecutive disabled (default)");
     Ok(())
@@ -50,9 +29,7 @@ pub fn enable_paging_executive() -> Result<()> {
 /// Disable Dynamic Tick for consistent timer behavior
 /// Requires reboot
 pub fn disable_dynamic_tick() -> Result<()> {
-    let _ = Command::new("bcdedit")
-        .args(["/set", "disabledynamictick", "yes"])
-        .output();
+    set_bcd("disabledynamictick", Some("yes"));
 
     tracing::info!("Dynamic tick disabled - reboot required");
     Ok(())
@@ -60,9 +37,7 @@ pub fn disable_dynamic_tick() -> Result<()> {
 
 /// Enable Dynamic Tick (restore default)
 pub fn enable_dynamic_tick() -> Result<()> {
-    let _ = Command::new("bcdedit")
-        .args(["/set", "disabledynamictick", "no"])
-        .output();
+    set_bcd("disabledynamictick", Some("no"));
 
     tracing::info!("Dynamic tick enabled (default)");
     Ok(())
@@ -70,9 +45,7 @@ pub fn enable_dynamic_tick() -> Result<()> {
 
 /// Set TSC sync policy to enhanced for better timer precision
 pub fn set_tsc_sync_enhanced() -> Result<()> {
-    let _ = Command::new("bcdedit")
-        .args(["/set", "tscsyncpolicy", "enhanced"])
-        .output();
+    set_bcd("tscsyncpolicy", Some("enhanced"));
 
     tracing::info!("TSC sync policy set to enhanced");
     Ok(())
@@ -80,9 +53,7 @@ pub fn set_tsc_sync_enhanced() -> Result<()> {
 
 /// Reset TSC sync policy to default
 pub fn reset_tsc_sync() -> Result<()> {
-    let _ = Command::new("bcdedit")
-        .args(["/deletevalue", "tscsyncpolicy"])
-        .output();
+    set_bcd("tscsyncpolicy", None);
 
     tracing::info!("TSC sync policy reset to default");
     Ok(())
@@ -91,9 +62,7 @@ pub fn reset_tsc_sync() -> Result<()> {
 /// Disable HPET (High Precision Event Timer)
 /// Impact varies by hardware - test with LatencyMon
 pub fn disable_hpet() -> Result<()> {
-    let _ = Command::new("bcdedit")
-        .args(["/set", "useplatformclock", "false"])
-        .output();
+    set_bcd("useplatformclock", Some("false"));
 
     tracing::info!("HPET disabled via bcdedit");
     Ok(())
@@ -101,9 +70,7 @@ pub fn disable_hpet() -> Result<()> {
 
 /// Enable HPET
 pub fn enable_hpet() -> Result<()> {
-    let _ = Command::new("bcdedit")
-        .args(["/set", "useplatformclock", "true"])
-        .output();
+    set_bcd("useplatformclock", Some("true"));
 
     tracing::info!("HPET enabled");
     Ok(())
@@ -111,19 +78,7 @@ pub fn enable_hpet() -> Result<()> {
 
 /// Set interrupt affinity policy to spread across cores
 pub fn set_interrupt_affinity_spread() -> Result<()> {
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SYSTEM\CurrentControlSet\Control\Session Manager\Kernel",
-            "/v",
-            "InterruptAffinityPolicy",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "2",
-            "/f",
-        ])
-        .output();
+    crate::registry::set_dword_value(KERNEL_KEY, "InterruptAffinityPolicy", 2)?;
 
     tracing::info!("Interrupt affinity set to spread across cores");
     Ok(())
@@ -131,20 +86,23 @@ pub fn set_interrupt_affinity_spread() -> Result<()> {
 
 /// Reset interrupt affinity policy
 pub fn reset_interrupt_affinity() -> Result<()> {
-    let _ = Command::new("reg")
-        .args([
-            "delete",
-            r"HKLM\SYSTEM\CurrentControlSet\Control\Session Manager\Kernel",
-            "/v",
-            "InterruptAffinityPolicy",
-            "/f",
-        ])
-        .output();
+    crate::registry::delete_value(KERNEL_KEY, "InterruptAffinityPolicy")?;
 
     tracing::info!("Interrupt affinity policy reset");
     Ok(())
 }
 
+/// bcdedit failures are logged but not fatal (elements unsupported on some firmwares)
+fn set_bcd(element: &str, value: Option<&str>) {
+    let result = match value {
+        Some(value) => crate::bcd::set_element(element, value),
+        None => crate::bcd::delete_element(element),
+    };
+    if let Err(e) = result {
+        tracing::warn!("{}", e);
+    }
+}
+
 /// Apply all DPC latency optimizations
 pub fn apply_all_dpc_optimizations() -> Result<()> {
     disable_paging_executive()?;
@@ -169,20 +127,6 @@ pub fn reset_all_dpc_optimizations() -> Result<()> {
 
 /// Check if DisablePagingExecutive is enabled
 pub fn is_paging_executive_disabled() -> bool {
-    let output = Command::new("reg")
-        .args([
-            "query",
-            r"HKLM\SYSTEM\CurrentControlSet\Control\Session Manager\Memory Management",
-            "/v",
-            "DisablePagingExecutive",
-        ])
-        .output();
-
-    match output {
-        Ok(o) => {
-            let stdout = String::from_utf8_lossy(&o.stdout);
-            stdout.contains("0x1")
-        }
-        Err(_) => false,
-    }
+    crate::registry::read_dword_value(MEMORY_MANAGEMENT_KEY, "DisablePagingExecutive")
+        .is_ok_and(|v| v == 1)
 }
@@ -2,100 +2,30 @@
 //!
 //! Disable and remove Microsoft Edge browser.
 
-use pieuvre_common::Result;
-use std::process::Command;
+use pieuvre_common::{RegistryHive, Result};
+
+const EDGE_POLICY_KEY: &str = r"SOFTWARE\Policies\Microsoft\Edge";
+const EDGE_UPDATE_POLICY_KEY: &str = r"SOFTWARE\Policies\Microsoft\EdgeUpdate";
 
 /// Disable Microsoft Edge features without full removal
 pub fn disable_edge() -> Result<()> {
-    // Disable Edge auto-start
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SOFTWARE\Policies\Microsoft\Edge",
-            "/v",
-            "HideFirstRunExperience",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "1",
-            "/f",
-        ])
-        .output();
-
-    // Disable Edge sidebar
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SOFTWARE\Policies\Microsoft\Edge",
-            "/v",
-            "HubsSidebarEnabled",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
-
-    // Disable Edge desktop shortcut creation
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SOFTWARE\Policies\Microsoft\EdgeUpdate",
-            "/v",
-            "CreateDesktopShortcutDefault",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
-
-    // Disable Edge as default PDF handler
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SOFTWARE\Policies\Microsoft\Edge",
-            "/v",
-            "DefaultBrowserSettingEnabled",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
-
-    // Disable Edge collections
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SOFTWARE\Policies\Microsoft\Edge",
-            "/v",
-            "EdgeCollectionsEnabled",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
-
-    // Disable Edge shopping
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SOFTWARE\Policies\Microsoft\Edge",
-            "/v",
-            "EdgeShoppingAssistantEnabled",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
+    let settings = [
+        // Disable Edge auto-start
+        (EDGE_POLICY_KEY, "HideFirstRunExperience", 1),
+        // Disable Edge sidebar
+        (EDGE_POLICY_KEY, "HubsSidebarEnabled", 0),
+        // Disable Edge desktop shortcut creation
+        (EDGE_UPDATE_POLICY_KEY, "CreateDesktopShortcutDefault", 0),
+        // Disable Edge as default PDF handler
+        (EDGE_POLICY_KEY, "DefaultBrowserSettingEnabled", 0),
+        // Disable Edge collections
+        (EDGE_POLICY_KEY, "EdgeCollectionsEnabled", 0),
+        // Disable Edge shopping
+        (EDGE_POLICY_KEY, "EdgeShoppingAssistantEnabled", 0),
+    ];
+    for (key, value, data) in settings {
+        crate::registry::set_dword_value(key, value, data)?;
+    }
 
     tracing::info!("Microsoft Edge features disabled");
     Ok(())
@@ -111,11 +41,7 @@ pub fn remove_edge_tasks() -> Result<u32> {
 
     let mut removed = 0u32;
     for task in tasks {
-        let output = Command::new("schtasks")
-            .args(["/Change", "/TN", task, "/Disable"])
-            .output();
-
-        if output.is_ok() {
+        if crate::scheduled_tasks::disable_task(task).is_ok() {
             removed += 1;
         }
     }
@@ -126,29 +52,18 @@ pub fn remove_edge_tasks() -> Result<u32> {
 
 /// Check if Edge is the default browser
 pub fn is_edge_default_browser() -> bool {
-    let output = Command::new("reg")
-        .args([
-            "query",
-            r"HKCU\Software\Microsoft\Windows\Shell\Associations\UrlAssociations\http\UserChoice",
-            "/v",
-            "ProgId",
-        ])
-        .output();
-
-    match output {
-        Ok(o) => {
-            let stdout = String::from_utf8_lossy(&o.stdout);
-            stdout.contains("Edge")
-        }
-        Err(_) => false,
-    }
+    crate::registry::read_string_value_in_hive(
+        RegistryHive::Hkcu,
+        r"Software\Microsoft\Windows\Shell\Associations\UrlAssociations\http\UserChoice",
+        "ProgId",
+    )
+    .map(|prog_id| prog_id.contains("Edge"))
+    .unwrap_or(false)
 }
 
-/// Re-enable Edge features
+/// Re-enable Edge features (the policy key is recorded before deletion)
 pub fn enable_edge() -> Result<()> {
-    let _ = Command::new("reg")
-        .args(["delete", r"HKLM\SOFTWARE\Policies\Microsoft\Edge", "/f"])
-        .output();
+    crate::registry::delete_key_recursive(EDGE_POLICY_KEY)?;
 
     tracing::info!("Microsoft Edge re-enabled");
     Ok(())
@@ -2,130 +2,39 @@
 //!
 //! Optimize Windows Explorer behavior and appearance.
 
-use pieuvre_common::Result;
+use crate::registry::set_dword_value_in_hive;
+use pieuvre_common::{RegistryHive, Result};
 use std::process::Command;
 
-/// Apply Explorer performance tweaks
-pub fn apply_explorer_tweaks() -> Result<()> {
-    // Show file extensions
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
-            "/v",
-            "HideFileExt",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
+const EXPLORER_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Explorer";
+const EXPLORER_ADVANCED_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced";
+const SEARCH_SETTINGS_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\SearchSettings";
 
+/// Explorer performance tweaks (HKCU key, value, data)
+pub const EXPLORER_TWEAKS: [(&str, &str, u32); 8] = [
+    // Show file extensions
+    (EXPLORER_ADVANCED_KEY, "HideFileExt", 0),
     // Show hidden files
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
-            "/v",
-            "Hidden",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "1",
-            "/f",
-        ])
-        .output();
-
+    (EXPLORER_ADVANCED_KEY, "Hidden", 1),
     // Disable recent files in Quick Access
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer",
-            "/v",
-            "ShowRecent",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
-
+    (EXPLORER_KEY, "ShowRecent", 0),
     // Disable frequent folders in Quick Access
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer",
-            "/v",
-            "ShowFrequent",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
-
+    (EXPLORER_KEY, "ShowFrequent", 0),
     // Open Explorer to This PC instead of Quick Access
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
-            "/v",
-            "LaunchTo",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "1",
-            "/f",
-        ])
-        .output();
-
+    (EXPLORER_ADVANCED_KEY, "LaunchTo", 1),
     // Disable search highlights
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKCU\Software\Microsoft\Windows\CurrentVersion\SearchSettings",
-            "/v",
-            "IsDynamicSearchBoxEnabled",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
-
+    (SEARCH_SETTINGS_KEY, "IsDynamicSearchBoxEnabled", 0),
     // Disable chat icon on taskbar
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
-            "/v",
-            "TaskbarMn",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
-
+    (EXPLORER_ADVANCED_KEY, "TaskbarMn", 0),
     // Disable task view button
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
-            "/v",
-            "ShowTaskViewButton",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "0",
-            "/f",
-        ])
-        .output();
+    (EXPLORER_ADVANCED_KEY, "ShowTaskViewButton", 0),
+];
+
+/// Apply Explorer performance tweaks
+pub fn apply_explorer_tweaks() -> Result<()> {
+    for (key, value, data) in EXPLORER_TWEAKS {
+        set_dword_value_in_hive(RegistryHive::Hkcu, key, value, data)?;
+    }
 
     tracing::info!("Explorer tweaks applied");
     Ok(())
@@ -148,27 +57,15 @@ pub fn restart_explorer() -> Result<()> {
 /// Restore default Explorer settings
 pub fn restore_explorer_defaults() -> Result<()> {
     let keys = [
-        ("HideFileExt", "1"),
-        ("Hidden", "2"),
-        ("ShowRecent", "1"),
-        ("ShowFrequent", "1"),
-        ("LaunchTo", "2"),
+        ("HideFileExt", 1),
+        ("Hidden", 2),
+        ("ShowRecent", 1),
+        ("ShowFrequent", 1),
+        ("LaunchTo", 2),
     ];
 
     for (key, value) in keys {
-        let _ = Command::new("reg")
-            .args([
-                "add",
-                r"HKCU\Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced",
-                "/v",
-                key,
-                "/t",
-                "REG_DWORD",
-                "/d",
-                value,
-                "/f",
-            ])
-            .output();
+        let _ = set_dword_value_in_hive(RegistryHive::Hkcu, EXPLORER_ADVANCED_KEY, key, value);
     }
 
     tracing::info!("Explorer defaults restored");
@@ -3,7 +3,7 @@
 //! Création de règles Windows Firewall pour bloquer la télémétrie.
 //! Utilise l'interface COM INetFwPolicy2 pour une gestion native.
 
-use pieuvre_common::{PieuvreError, Result};
+use pieuvre_common::{ChangeRecord, PieuvreError, Result};
 use windows::core::BSTR;
 use windows::Win32::Foundation::VARIANT_BOOL;
 use windows::Win32::NetworkManagement::WindowsFirewall::{
@@ -115,6 +115,9 @@ pub fn create_telemetry_block_rules() -> Result<Vec<String>> {
         rule.SetEnabled(VARIANT_BOOL::from(true))
             .map_err(|e| PieuvreError::Internal(e.to_string()))?;
 
+        crate::changes::push(ChangeRecord::FirewallRule {
+            name: rule_name.to_string(),
+        })?;
         rules
             .Add(&rule)
             .map_err(|e| PieuvreError::Internal(format!("Failed to add rule: {}", e)))?;
@@ -0,0 +1,255 @@
+//! Tweak relation graph
+//!
+//! Dépendances (`requires`), conflits et implications (`implies`) entre ids de
+//! tweaks. [`TweakGraph::resolve`] transforme une sélection en ordre d'exécution :
+//! dépendances ajoutées, tweaks déjà couverts retirés, conflits refusés.
+
+use crate::catalog::TweakRelation;
+use pieuvre_common::{PieuvreError, Result};
+use std::collections::{HashMap, HashSet};
+
+/// Deux tweaks qui ne peuvent pas être appliqués ensemble
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub first: String,
+    pub second: String,
+    pub reason: String,
+}
+
+/// Sélection résolue
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Resolution {
+    /// Ids à exécuter, dépendances d'abord
+    pub order: Vec<String>,
+    /// Dépendances ajoutées : (id ajouté, requis par)
+    pub added: Vec<(String, String)>,
+    /// Ids déjà appliqués par un autre tweak de la sélection : (id retiré, couvert par)
+    pub skipped: Vec<(String, String)>,
+}
+
+impl Resolution {
+    /// Explication lisible des ajouts et retraits
+    pub fn notes(&self) -> Vec<String> {
+        let added = self
+            .added
+            .iter()
+            .map(|(id, by)| format!("{} added (required by {})", id, by));
+        let skipped = self
+            .skipped
+            .iter()
+            .map(|(id, by)| format!("{} skipped (already applied by {})", id, by));
+        added.chain(skipped).collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TweakGraph {
+    requires: HashMap<String, Vec<String>>,
+    implies: HashMap<String, Vec<String>>,
+    conflicts: Vec<Conflict>,
+}
+
+impl TweakGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_relations(relations: &[TweakRelation]) -> Self {
+        let mut graph = Self::new();
+        for relation in relations {
+            for required in &relation.requires {
+                graph.add_requires(&relation.tweak, required);
+            }
+            for implied in &relation.implies {
+                graph.add_implies(&relation.tweak, implied);
+            }
+            for other in &relation.conflicts {
+                graph.add_conflict(&relation.tweak, other, &relation.reason);
+            }
+        }
+        graph
+    }
+
+    /// `id` a besoin de `required`, appliqué avant lui
+    pub fn add_requires(&mut self, id: &str, required: &str) {
+        push_unique(self.requires.entry(id.to_string()).or_default(), required);
+    }
+
+    /// Appliquer `id` applique aussi `implied`
+    pub fn add_implies(&mut self, id: &str, implied: &str) {
+        push_unique(self.implies.entry(id.to_string()).or_default(), implied);
+    }
+
+    /// Relation symétrique
+    pub fn add_conflict(&mut self, first: &str, second: &str, reason: &str) {
+        self.conflicts.push(Conflict {
+            first: first.to_string(),
+            second: second.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+
+    /// Ids appliqués par `id` (implications transitives, `id` exclu)
+    pub fn implied(&self, id: &str) -> HashSet<&str> {
+        let mut seen = HashSet::new();
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            for next in self.implies.get(current).into_iter().flatten() {
+                if next != id && seen.insert(next.as_str()) {
+                    stack.push(next);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Conflits entre tweaks sélectionnés, implications comprises
+    pub fn conflicts_in(&self, selection: &[String]) -> Vec<Conflict> {
+        let effective: Vec<HashSet<&str>> = selection
+            .iter()
+            .map(|id| {
+                let mut set = self.implied(id);
+                set.insert(id);
+                set
+            })
+            .collect();
+
+        let mut found = Vec::new();
+        for (i, first) in selection.iter().enumerate() {
+            for (j, second) in selection.iter().enumerate().skip(i + 1) {
+                let conflict = self.conflicts.iter().find(|c| {
+                    (effective[i].contains(c.first.as_str())
+                        && effective[j].contains(c.second.as_str()))
+                        || (effective[i].contains(c.second.as_str())
+                            && effective[j].contains(c.first.as_str()))
+                });
+                if let Some(conflict) = conflict {
+                    found.push(Conflict {
+                        first: first.clone(),
+                        second: second.clone(),
+                        reason: conflict.reason.clone(),
+                    });
+                }
+            }
+        }
+        found
+    }
+
+    /// Ordre d'exécution d'une sélection ; refuse les conflits et les cycles
+    pub fn resolve(&self, selection: &[String]) -> Result<Resolution> {
+        let mut resolution = Resolution::default();
+
+        // 1. Sélection + dépendances (sauf si déjà couvertes par une implication)
+        let mut expanded: Vec<String> = Vec::new();
+        for id in selection {
+            push_unique(&mut expanded, id);
+        }
+        let mut index = 0;
+        while index < expanded.len() {
+            let id = expanded[index].clone();
+            let missing = self
+                .requires
+                .get(&id)
+                .into_iter()
+                .flatten()
+                .find(|required| {
+                    !expanded
+                        .iter()
+                        .any(|e| e == *required || self.implied(e).contains(required.as_str()))
+                });
+            match missing {
+                // Insérée juste avant le tweak qui la requiert, puis examinée à son tour
+                Some(required) => {
+                    expanded.insert(index, required.clone());
+                    resolution.added.push((required.clone(), id));
+                }
+                None => index += 1,
+            }
+        }
+
+        // 2. Conflits
+        let conflicts = self.conflicts_in(&expanded);
+        if !conflicts.is_empty() {
+            let lines: Vec<String> = conflicts
+                .iter()
+                .map(|c| {
+                    if c.reason.is_empty() {
+                        format!("{} / {}", c.first, c.second)
+                    } else {
+                        format!("{} / {}: {}", c.first, c.second, c.reason)
+                    }
+                })
+                .collect();
+            return Err(PieuvreError::Config(format!(
+                "Conflicting tweaks:\n  - {}",
+                lines.join("\n  - ")
+            )));
+        }
+
+        // 3. Déduplication : un tweak appliqué par un autre de la sélection est retiré
+        let mut kept: Vec<String> = Vec::new();
+        for id in &expanded {
+            if let Some(by) = kept.iter().find(|k| self.implied(k).contains(id.as_str())) {
+                resolution.skipped.push((id.clone(), by.clone()));
+                continue;
+            }
+            let implied = self.implied(id);
+            kept.retain(|k| {
+                let covered = implied.contains(k.as_str());
+                if covered {
+                    resolution.skipped.push((k.clone(), id.clone()));
+                }
+                !covered
+            });
+            kept.push(id.clone());
+        }
+
+        // 4. Tri topologique stable : à égalité, l'ordre de la sélection est conservé
+        let owner = |id: &str| -> Option<&String> {
+            kept.iter()
+                .find(|k| k.as_str() == id || self.implied(k).contains(id))
+        };
+        let mut before: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for id in &kept {
+            let mut deps = HashSet::new();
+            let mut covered = self.implied(id);
+            covered.insert(id);
+            for member in covered {
+                for required in self.requires.get(member).into_iter().flatten() {
+                    if let Some(dep) = owner(required).filter(|d| *d != id) {
+                        deps.insert(dep.as_str());
+                    }
+                }
+            }
+            before.insert(id.as_str(), deps);
+        }
+
+        let mut done: HashSet<&str> = HashSet::new();
+        while resolution.order.len() < kept.len() {
+            let next = kept.iter().find(|id| {
+                !done.contains(id.as_str()) && before[id.as_str()].iter().all(|d| done.contains(d))
+            });
+            let Some(next) = next else {
+                let remaining: Vec<&str> = kept
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|id| !done.contains(id))
+                    .collect();
+                return Err(PieuvreError::Config(format!(
+                    "Dependency cycle between tweaks: {}",
+                    remaining.join(", ")
+                )));
+            };
+            done.insert(next.as_str());
+            resolution.order.push(next.clone());
+        }
+
+        Ok(resolution)
+    }
+}
+
+fn push_unique(list: &mut Vec<String>, id: &str) {
+    if !list.iter().any(|existing| existing == id) {
+        list.push(id.to_string());
+    }
+}
@@ -3,17 +3,21 @@
 //! Verrouillage des clés de registre via ACLs pour empêcher les réinitialisations.
 //! Utilise SDDL (Security Descriptor Definition Language) pour une précision maximale.
 
-use pieuvre_common::{PieuvreError, Result};
+use pieuvre_common::{
+    ChangeRecord, DaclTarget, NativeServiceManager, PieuvreError, Result, ServiceManager,
+};
 use windows::core::{PCWSTR, PWSTR};
 use windows::Win32::Foundation::{LocalFree, HANDLE, HLOCAL, LUID};
 use windows::Win32::Security::Authorization::{
-    ConvertStringSecurityDescriptorToSecurityDescriptorW, SetNamedSecurityInfoW, SE_REGISTRY_KEY,
-    SE_SERVICE,
+    ConvertSecurityDescriptorToStringSecurityDescriptorW,
+    ConvertStringSecurityDescriptorToSecurityDescriptorW, GetNamedSecurityInfoW,
+    SetNamedSecurityInfoW, SE_REGISTRY_KEY,
 };
 use windows::Win32::Security::{
     AdjustTokenPrivileges, LookupPrivilegeValueW, DACL_SECURITY_INFORMATION, LUID_AND_ATTRIBUTES,
-    PROTECTED_DACL_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID, SE_PRIVILEGE_ENABLED,
-    TOKEN_ADJUST_PRIVILEGES, TOKEN_PRIVILEGES, TOKEN_QUERY,
+    OBJECT_SECURITY_INFORMATION, PROTECTED_DACL_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID,
+    SE_PRIVILEGE_ENABLED, TOKEN_ADJUST_PRIVILEGES, TOKEN_PRIVILEGES, TOKEN_QUERY,
+    UNPROTECTED_DACL_SECURITY_INFORMATION,
 };
 use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
 
@@ -52,7 +56,7 @@ pub fn lock_registry_key(key_path: &str) -> Result<()> {
             key_path
         )));
     }
-    apply_sddl(key_path, "D:P(A;;KR;;;WD)(A;;KA;;;SY)")
+    set_registry_key_sddl(key_path, "D:P(A;;KR;;;WD)(A;;KA;;;SY)")
 }
 
 /// Déverrouille une clé (Contrôle total pour tout le monde - Temporaire pour modif)
@@ -60,98 +64,123 @@ pub fn lock_registry_key(key_path: &str) -> Result<()> {
 /// Déverrouille une clé (Contrôle total pour tout le monde - Temporaire pour modif)
 /// SDDL: D:P(A;;KA;;;WD)(A;;KA;;;SY) -> Allow All (KA) to Everyone (WD), Full Control (KA) to SYSTEM (SY)
 pub fn unlock_registry_key(key_path: &str) -> Result<()> {
-    apply_sddl(key_path, "D:P(A;;KA;;;WD)(A;;KA;;;SY)")
-}
-
-/// Verrouille un service
-/// Empêche l'arrêt et la modification par tout le monde sauf SYSTEM
-pub fn lock_service(service_name: &str) -> Result<()> {
-    // Vérifier si le service existe avant de tenter quoi que ce soit
-    if !service_exists(service_name) {
-        return Err(PieuvreError::ServiceNotFound(service_name.to_string()));
-    }
-    apply_sddl_service(service_name, "D:P(A;;LCRP;;;WD)(A;;KA;;;SY)")
+    set_registry_key_sddl(key_path, "D:P(A;;KA;;;WD)(A;;KA;;;SY)")
 }
 
-fn service_exists(name: &str) -> bool {
-    use windows::Win32::System::Services::{
-        CloseServiceHandle, OpenSCManagerW, OpenServiceW, SC_MANAGER_CONNECT, SERVICE_QUERY_CONFIG,
-    };
-    unsafe {
-        let scm = match OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_CONNECT) {
-            Ok(s) => s,
-            Err(_) => return false,
-        };
-        let name_wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
-        let service = OpenServiceW(scm, PCWSTR(name_wide.as_ptr()), SERVICE_QUERY_CONFIG);
-        let exists = service.is_ok();
-        if let Ok(s) = service {
-            let _ = CloseServiceHandle(s);
+/// Applique une DACL (SDDL) à une clé HKLM ; la DACL d'origine est signalée à `crate::changes`
+pub fn set_registry_key_sddl(key_path: &str, sddl: &str) -> Result<()> {
+    if crate::changes::is_recording() {
+        match registry_key_sddl(key_path) {
+            Ok(original) if original != sddl => crate::changes::push(ChangeRecord::Dacl {
+                target: DaclTarget::RegistryKey {
+                    key: key_path.to_string(),
+                },
+                original_sddl: original,
+            })?,
+            Ok(_) => {}
+            Err(e) => tracing::warn!("DACL of {} not recorded: {}", key_path, e),
         }
-        let _ = CloseServiceHandle(scm);
-        exists
     }
+    apply_sddl(key_path, sddl)
 }
 
-fn apply_sddl_service(service_name: &str, sddl: &str) -> Result<()> {
+/// DACL actuelle d'une clé HKLM au format SDDL
+pub fn registry_key_sddl(key_path: &str) -> Result<String> {
     unsafe {
-        let _ = enable_privilege("SeTakeOwnershipPrivilege");
-        let _ = enable_privilege("SeRestorePrivilege");
-
-        let sddl_wide: Vec<u16> = sddl.encode_utf16().chain(std::iter::once(0)).collect();
+        let full_path = format!("MACHINE\\{}", key_path);
+        let path_wide: Vec<u16> = full_path.encode_utf16().chain(std::iter::once(0)).collect();
+        let mut sd = PSECURITY_DESCRIPTOR::default();
 
-        let mut sd: PSECURITY_DESCRIPTOR = PSECURITY_DESCRIPTOR::default();
-        ConvertStringSecurityDescriptorToSecurityDescriptorW(
-            PCWSTR(sddl_wide.as_ptr()),
-            SDDL_REVISION_1,
-            &mut sd,
+        let result = GetNamedSecurityInfoW(
+            PCWSTR(path_wide.as_ptr()),
+            SE_REGISTRY_KEY,
+            DACL_SECURITY_INFORMATION,
             None,
-        )
-        .map_err(|e| PieuvreError::Internal(format!("SDDL conversion failed: {}", e)))?;
-
-        let mut dacl = std::ptr::null_mut();
-        let mut dacl_present = 0i32;
-        let mut dacl_defaulted = 0i32;
-
-        let _ = windows::Win32::Security::GetSecurityDescriptorDacl(
-            sd,
-            &mut dacl_present as *mut i32 as *mut _,
-            &mut dacl,
-            &mut dacl_defaulted as *mut i32 as *mut _,
-        );
-
-        // Tentative via SetNamedSecurityInfoW avec préfixe SERVICE\\ pour plus de fiabilité
-        let full_service_path = format!("SERVICE\\{}", service_name);
-        let full_service_path_wide: Vec<u16> = full_service_path
-            .encode_utf16()
-            .chain(std::iter::once(0))
-            .collect();
-
-        let result = SetNamedSecurityInfoW(
-            PCWSTR(full_service_path_wide.as_ptr()),
-            SE_SERVICE,
-            DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
             None,
             None,
-            Some(dacl),
             None,
-        )
-        .ok();
-
-        let _ = LocalFree(Some(HLOCAL(sd.0 as *mut _)));
-        if let Err(e) = result {
+            &mut sd,
+        );
+        if result.is_err() {
             return Err(PieuvreError::Internal(format!(
-                "Failed to lock service {}: {:?}",
-                service_name, e
+                "GetNamedSecurityInfo failed for {}: {:?}",
+                full_path, result
             )));
         }
 
-        tracing::info!(service = %service_name, "Service verrouillé avec succès");
-        Ok(())
+        let mut sddl = PWSTR::null();
+        let converted = ConvertSecurityDescriptorToStringSecurityDescriptorW(
+            sd,
+            SDDL_REVISION_1,
+            DACL_SECURITY_INFORMATION,
+            &mut sddl,
+            None,
+        );
+        let text = converted
+            .ok()
+            .and_then(|_| sddl.to_string().ok())
+            .ok_or_else(|| {
+                PieuvreError::Internal(format!("SDDL conversion failed for {}", full_path))
+            });
+
+        if !sddl.is_null() {
+            let _ = LocalFree(Some(HLOCAL(sddl.0 as *mut _)));
+        }
+        let _ = LocalFree(Some(HLOCAL(sd.0 as *mut _)));
+        text
     }
 }
 
+/// DACL appliquée aux services verrouillés
+/// SDDL: D:P(A;;LCRP;;;WD)(A;;KA;;;SY) -> List/Read (LCRP) to Everyone (WD), Full Control (KA) to SYSTEM (SY)
+pub const LOCKED_SERVICE_SDDL: &str = "D:P(A;;LCRP;;;WD)(A;;KA;;;SY)";
+
+/// Verrouille un service
+/// Empêche l'arrêt et la modification par tout le monde sauf SYSTEM
+pub fn lock_service(service_name: &str) -> Result<()> {
+    lock_service_with(&NativeServiceManager, service_name)
+}
+
+pub fn lock_service_with(services: &dyn ServiceManager, service_name: &str) -> Result<()> {
+    // Vérifier si le service existe avant de tenter quoi que ce soit
+    if !services.exists(service_name) {
+        return Err(PieuvreError::ServiceNotFound(service_name.to_string()));
+    }
+    if crate::changes::is_recording() {
+        let original = services.security_descriptor(service_name)?;
+        if !original.eq_ignore_ascii_case(LOCKED_SERVICE_SDDL) {
+            crate::changes::push(ChangeRecord::Dacl {
+                target: DaclTarget::Service {
+                    name: service_name.to_string(),
+                },
+                original_sddl: original,
+            })?;
+        }
+    }
+    services
+        .set_security_descriptor(service_name, LOCKED_SERVICE_SDDL)
+        .map_err(|e| match e {
+            PieuvreError::ServiceNotFound(_) => e,
+            e => PieuvreError::Internal(format!("Failed to lock service {}: {}", service_name, e)),
+        })?;
+    tracing::info!(service = %service_name, "Service verrouillé avec succès");
+    Ok(())
+}
+
+/// Vrai si le service porte déjà la DACL de verrouillage
+pub fn is_service_locked_with(services: &dyn ServiceManager, service_name: &str) -> Result<bool> {
+    let sddl = services.security_descriptor(service_name)?;
+    Ok(sddl.eq_ignore_ascii_case(LOCKED_SERVICE_SDDL))
+}
+
 fn apply_sddl(key_path: &str, sddl: &str) -> Result<()> {
+    // Une DACL protégée (D:P) coupe l'héritage ; sinon on le rétablit
+    let security_info: OBJECT_SECURITY_INFORMATION = if sddl.starts_with("D:P") {
+        DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION
+    } else {
+        DACL_SECURITY_INFORMATION | UNPROTECTED_DACL_SECURITY_INFORMATION
+    };
+
     unsafe {
         let _ = enable_privilege("SeTakeOwnershipPrivilege");
         let _ = enable_privilege("SeRestorePrivilege");
@@ -193,7 +222,7 @@ fn apply_sddl(key_path: &str, sddl: &str) -> Result<()> {
         let mut result = SetNamedSecurityInfoW(
             PCWSTR(path_wide.as_ptr()),
             SE_REGISTRY_KEY,
-            DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+            security_info,
             None,
             None,
             Some(dacl),
@@ -213,7 +242,7 @@ fn apply_sddl(key_path: &str, sddl: &str) -> Result<()> {
                     result = SetNamedSecurityInfoW(
                         PCWSTR(path_wide.as_ptr()),
                         SE_REGISTRY_KEY,
-                        DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+                        security_info,
                         None,
                         None,
                         Some(dacl),
@@ -2,7 +2,7 @@
 //!
 //! Block telemetry domains via Windows hosts file.
 
-use pieuvre_common::{PieuvreError, Result};
+use pieuvre_common::{config, machine_config_dir, ChangeRecord, PieuvreError, Result};
 use std::fs;
 
 const HOSTS_PATH: &str = r"C:\Windows\System32\drivers\etc\hosts";
@@ -66,36 +66,156 @@ const TELEMETRY_HOSTS: &[&str] = &[
     "cy2.vortex.data.microsoft.com",
 ];
 
+/// Domains to block: `snapshot.telemetry_domains_file` if present, built-in list otherwise
+fn telemetry_hosts() -> Vec<String> {
+    let path = config().telemetry_domains_path(&machine_config_dir());
+    match fs::read_to_string(&path) {
+        Ok(content) => parse_domain_list(&content),
+        Err(_) => TELEMETRY_HOSTS.iter().map(|d| d.to_string()).collect(),
+    }
+}
+
+/// One domain per line, `#` comments and blank lines ignored
+pub fn parse_domain_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or("").trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Entries written by `add_telemetry_blocks` (each domain and its `www.` variant)
+pub fn telemetry_block_entries() -> Vec<String> {
+    telemetry_hosts()
+        .iter()
+        .flat_map(|d| [d.clone(), format!("www.{}", d)])
+        .collect()
+}
+
 /// Add telemetry block entries to hosts file
 pub fn add_telemetry_blocks() -> Result<u32> {
-    let hosts_content = fs::read_to_string(HOSTS_PATH).map_err(PieuvreError::Io)?;
+    let domains = telemetry_hosts();
+    let entries = telemetry_block_entries();
 
-    // Check if already added
-    if hosts_content.contains(PIEUVRE_MARKER_START) {
+    if block_domains(&entries)? == 0 {
         tracing::info!("Hosts block already exists");
         return Ok(0);
     }
 
-    // Build block section
-    let mut block = String::new();
-    block.push('\n');
-    block.push_str(PIEUVRE_MARKER_START);
-    block.push('\n');
+    tracing::info!("Added {} domains to hosts file", domains.len());
+    Ok(domains.len() as u32)
+}
 
-    for domain in TELEMETRY_HOSTS {
-        block.push_str(&format!("0.0.0.0 {}\n", domain));
-        block.push_str(&format!("0.0.0.0 www.{}\n", domain));
+/// Adds `domains` to the pieuvre block (created if missing), returns the number added
+pub fn block_domains(domains: &[String]) -> Result<u32> {
+    let hosts_content = fs::read_to_string(HOSTS_PATH).map_err(PieuvreError::Io)?;
+    let missing = missing_domains(&hosts_content, domains);
+    let (new_content, added) = with_blocked_domains(&hosts_content, domains);
+    if added > 0 {
+        crate::changes::push(ChangeRecord::HostsBlock { domains: missing })?;
+        fs::write(HOSTS_PATH, new_content).map_err(PieuvreError::Io)?;
     }
+    Ok(added)
+}
 
-    block.push_str(PIEUVRE_MARKER_END);
-    block.push('\n');
+/// Removes `domains` from the pieuvre block (rollback of `block_domains`)
+pub fn unblock_domains(domains: &[String]) -> Result<()> {
+    let hosts_content = fs::read_to_string(HOSTS_PATH).map_err(PieuvreError::Io)?;
+    let new_content = without_blocked_domains(&hosts_content, domains);
+    if new_content != hosts_content {
+        fs::write(HOSTS_PATH, new_content).map_err(PieuvreError::Io)?;
+    }
+    Ok(())
+}
 
-    // Append to hosts
-    let new_content = format!("{}{}", hosts_content, block);
-    fs::write(HOSTS_PATH, new_content).map_err(PieuvreError::Io)?;
+/// Domains `block_domains` would add (read-only)
+pub fn pending_domains(domains: &[String]) -> Result<Vec<String>> {
+    let hosts_content = fs::read_to_string(HOSTS_PATH).map_err(PieuvreError::Io)?;
+    Ok(missing_domains(&hosts_content, domains))
+}
+
+/// Check if every domain is already redirected by the hosts file
+pub fn are_domains_blocked(domains: &[String]) -> bool {
+    match fs::read_to_string(HOSTS_PATH) {
+        Ok(content) => domains.iter().all(|d| is_blocked_in(&content, d)),
+        Err(_) => false,
+    }
+}
+
+/// Domains of `domains` not yet redirected, without duplicates
+fn missing_domains(content: &str, domains: &[String]) -> Vec<String> {
+    let mut missing: Vec<String> = Vec::new();
+    for domain in domains {
+        if !is_blocked_in(content, domain) && !missing.contains(domain) {
+            missing.push(domain.clone());
+        }
+    }
+    missing
+}
+
+/// Hosts content with the missing `domains` inserted in the pieuvre block
+pub fn with_blocked_domains(content: &str, domains: &[String]) -> (String, u32) {
+    let missing = missing_domains(content, domains);
+    if missing.is_empty() {
+        return (content.to_string(), 0);
+    }
+
+    let entries: String = missing.iter().map(|d| format!("0.0.0.0 {}\n", d)).collect();
+    let new_content = match content.find(PIEUVRE_MARKER_END) {
+        Some(end) => format!("{}{}{}", &content[..end], entries, &content[end..]),
+        None => format!(
+            "{}\n{}\n{}{}\n",
+            content.trim_end(),
+            PIEUVRE_MARKER_START,
+            entries,
+            PIEUVRE_MARKER_END
+        ),
+    };
+    (new_content, missing.len() as u32)
+}
+
+/// Hosts content without the pieuvre entries for `domains`; an emptied block is removed
+pub fn without_blocked_domains(content: &str, domains: &[String]) -> String {
+    let (Some(start), Some(end)) = (
+        content.find(PIEUVRE_MARKER_START),
+        content.find(PIEUVRE_MARKER_END),
+    ) else {
+        return content.to_string();
+    };
+    if end < start {
+        return content.to_string();
+    }
+
+    let block_start = start + PIEUVRE_MARKER_START.len();
+    let kept: Vec<&str> = content[block_start..end]
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !domains.iter().any(|d| is_blocked_in(line, d)))
+        .collect();
+
+    if kept.is_empty() {
+        let after = &content[end + PIEUVRE_MARKER_END.len()..];
+        return format!(
+            "{}\n{}",
+            content[..start].trim_end(),
+            after.trim_start_matches(['\r', '\n'])
+        );
+    }
+    let entries: String = kept.iter().map(|line| format!("{}\n", line)).collect();
+    format!(
+        "{}\n{}{}",
+        &content[..block_start],
+        entries,
+        &content[end..]
+    )
+}
 
-    tracing::info!("Added {} domains to hosts file", TELEMETRY_HOSTS.len());
-    Ok(TELEMETRY_HOSTS.len() as u32)
+fn is_blocked_in(content: &str, domain: &str) -> bool {
+    content.lines().any(|line| {
+        let mut fields = line.split('#').next().unwrap_or("").split_whitespace();
+        matches!(fields.next(), Some("0.0.0.0" | "127.0.0.1"))
+            && fields.any(|host| host.eq_ignore_ascii_case(domain))
+    })
 }
 
 /// Remove pieuvre entries from hosts file
@@ -135,5 +255,5 @@ pub fn is_hosts_blocking_active() -> bool {
 
 /// Get count of blocked domains
 pub fn get_blocked_domains_count() -> usize {
-    TELEMETRY_HOSTS.len()
+    telemetry_hosts().len()
 }
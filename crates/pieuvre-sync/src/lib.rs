@@ -9,6 +9,10 @@ pub mod sentinel {
     pub mod monitor;
 }
 pub mod appx;
+pub mod baseline;
+pub mod bcd;
+pub mod catalog;
+pub mod changes;
 pub mod context_menu;
 pub mod cpu;
 pub mod dpc;
@@ -18,101 +22,148 @@ pub mod edge;
 pub mod explorer;
 pub mod firewall;
 pub mod game_mode;
+pub mod graph;
 pub mod hardening;
 pub mod hosts;
 pub mod msi;
 pub mod network;
 pub mod onedrive;
 pub mod operation;
+pub mod plan;
 pub mod power;
 pub mod privacy_o_o;
+pub mod profile;
 pub mod registry;
 pub mod rollback;
 pub mod scheduled_tasks;
 pub mod security;
 pub mod services;
 pub mod timer;
+pub mod transaction;
 pub mod windows_update;
 
 #[cfg(test)]
 mod tests;
 
-use crate::operation::SyncOperation;
-use pieuvre_common::Result;
+use crate::transaction::{Transaction, TransactionReport};
+use pieuvre_common::{ChangeRecord, Result};
 use tracing::instrument;
 
 // apply_profile has been removed in v0.5.0 in favor of granular interactive execution.
 
-#[instrument]
-pub async fn reset_to_defaults() -> Result<()> {
-    tracing::info!("Resetting to defaults...");
+/// Remet les réglages par défaut en une transaction : selon `[transaction]`,
+/// un échec annule ce qui a déjà été appliqué.
+/// Avec une baseline (état d'origine capturé au premier lancement), elle est
+/// réécrite en dernier ; les valeurs par défaut génériques ne restent que pour
+/// ce qu'elle ne couvre pas.
+#[instrument(skip(baseline))]
+pub async fn reset_to_defaults(baseline: Option<Vec<ChangeRecord>>) -> Result<TransactionReport> {
+    use crate::baseline::{covers_power, covers_service, covers_value};
+    use crate::hardening::{
+        DEVICE_GUARD_KEY, HVCI_KEY, PRIORITY_CONTROL_KEY, SERVICE_DIAGTRACK, SERVICE_DOSVC,
+        SERVICE_SYSMAIN, SERVICE_UPDATE, SERVICE_USOSVC, SERVICE_WAP_PUSH, SERVICE_WERSVC,
+        SERVICE_WSEARCH,
+    };
+    use crate::operation::{RecordedOperation, RegistryDwordOperation, ServiceOperation};
+    use pieuvre_common::RegistryPath;
 
-    use crate::operation::{RegistryDwordOperation, ServiceOperation};
-    use tokio::task::JoinSet;
+    let baseline = baseline.unwrap_or_default();
+    let mut transaction = if baseline.is_empty() {
+        tracing::info!("Resetting to defaults...");
+        Transaction::new("Reset to defaults")
+    } else {
+        tracing::info!(
+            changes = baseline.len(),
+            "Resetting to pristine baseline..."
+        );
+        Transaction::new("Reset to baseline")
+    };
 
-    let operations: Vec<Box<dyn SyncOperation>> = vec![
-        // 1. Services essentiels (Télémétrie & Diagnostics)
-        Box::new(ServiceOperation {
-            name: crate::hardening::SERVICE_DIAGTRACK.to_string(),
-            target_start_type: 2, // Auto
-        }),
-        Box::new(ServiceOperation {
-            name: crate::hardening::SERVICE_WAP_PUSH.to_string(),
-            target_start_type: 3, // Manual
-        }),
-        Box::new(ServiceOperation {
-            name: crate::hardening::SERVICE_WERSVC.to_string(),
-            target_start_type: 3, // Manual
-        }),
-        Box::new(ServiceOperation {
-            name: crate::hardening::SERVICE_SYSMAIN.to_string(),
-            target_start_type: 2, // Auto
-        }),
-        Box::new(ServiceOperation {
-            name: crate::hardening::SERVICE_WSEARCH.to_string(),
-            target_start_type: 2, // Auto
-        }),
-        Box::new(ServiceOperation {
-            name: crate::hardening::SERVICE_UPDATE.to_string(),
-            target_start_type: 3, // Manual
-        }),
-        Box::new(ServiceOperation {
-            name: crate::hardening::SERVICE_USOSVC.to_string(),
-            target_start_type: 3, // Manual
-        }),
-        Box::new(ServiceOperation {
-            name: crate::hardening::SERVICE_DOSVC.to_string(),
-            target_start_type: 3, // Manual
-        }),
-        // 2. Registre par défaut (Performance & Privacy)
-        Box::new(RegistryDwordOperation {
-            key: crate::hardening::PRIORITY_CONTROL_KEY.to_string(),
-            value: "Win32PrioritySeparation".to_string(),
-            target_data: 0x2,
-        }),
-    ];
-
-    // Appliquer les paramètres de confidentialité par défaut (via O&O logic)
-    // Note: On pourrait aussi créer une opération dédiée pour cela si on veut du rollback
-    let _ = crate::privacy_o_o::apply_all_recommended_privacy();
-    let _ = crate::security::enable_memory_integrity();
-    let _ = crate::security::enable_vbs();
-
-    let mut set = JoinSet::new();
-    for op in operations {
-        set.spawn(async move { op.apply().await });
+    // Paramètres de confidentialité par défaut (via O&O logic)
+    transaction.push(RecordedOperation::new(
+        "O&O Privacy",
+        crate::privacy_o_o::apply_all_recommended_privacy,
+    ));
+    if !covers_value(&baseline, &RegistryPath::hklm(HVCI_KEY), "Enabled") {
+        transaction.push(RecordedOperation::new(
+            "Memory Integrity",
+            crate::security::enable_memory_integrity,
+        ));
+    }
+    if !covers_value(
+        &baseline,
+        &RegistryPath::hklm(DEVICE_GUARD_KEY),
+        "EnableVirtualizationBasedSecurity",
+    ) {
+        transaction.push(RecordedOperation::new("VBS", crate::security::enable_vbs));
     }
 
-    let mut all_changes = Vec::new();
-    while let Some(res) = set.join_next().await {
-        if let Ok(Ok(changes)) = res {
-            all_changes.extend(changes);
+    // 1. Services essentiels (Télémétrie & Diagnostics)
+    for (service, start_type) in [
+        (SERVICE_DIAGTRACK, 2), // Auto
+        (SERVICE_WAP_PUSH, 3),  // Manual
+        (SERVICE_WERSVC, 3),    // Manual
+        (SERVICE_SYSMAIN, 2),   // Auto
+        (SERVICE_WSEARCH, 2),   // Auto
+        (SERVICE_UPDATE, 3),    // Manual
+        (SERVICE_USOSVC, 3),    // Manual
+        (SERVICE_DOSVC, 3),     // Manual
+    ] {
+        if !covers_service(&baseline, service) {
+            transaction.push(ServiceOperation::new(service, start_type));
         }
     }
 
+    // 2. Registre par défaut (Performance & Privacy)
+    if !covers_value(
+        &baseline,
+        &RegistryPath::hklm(PRIORITY_CONTROL_KEY),
+        "Win32PrioritySeparation",
+    ) {
+        transaction.push(RegistryDwordOperation::new(
+            PRIORITY_CONTROL_KEY,
+            "Win32PrioritySeparation",
+            0x2,
+        ));
+    }
+
     // 3. Power plan Balanced
-    let _ = tokio::task::spawn_blocking(|| power::set_power_plan(power::PowerPlan::Balanced)).await;
+    if !covers_power(&baseline) {
+        transaction.push(RecordedOperation::new("Power Plan Balanced", || {
+            power::set_power_plan(power::PowerPlan::Balanced)
+        }));
+    }
+
+    // 4. Baseline, en dernier : elle l'emporte sur les valeurs génériques (O&O)
+    for (name, group) in [
+        (
+            "Baseline registry",
+            baseline_group(&baseline, |r| matches!(r, ChangeRecord::Registry { .. })),
+        ),
+        (
+            "Baseline services",
+            baseline_group(&baseline, |r| matches!(r, ChangeRecord::Service { .. })),
+        ),
+        (
+            "Baseline tasks",
+            baseline_group(&baseline, |r| {
+                matches!(r, ChangeRecord::ScheduledTask { .. })
+            }),
+        ),
+        (
+            "Baseline power",
+            baseline_group(&baseline, |r| matches!(r, ChangeRecord::PowerScheme { .. })),
+        ),
+    ] {
+        if !group.is_empty() {
+            transaction.push(RecordedOperation::new(name, move || {
+                baseline::apply(&group)
+            }));
+        }
+    }
+    transaction.run().await
+}
 
-    tracing::info!("Reset completed ({} changes)", all_changes.len());
-    Ok(())
+fn baseline_group(records: &[ChangeRecord], kind: fn(&ChangeRecord) -> bool) -> Vec<ChangeRecord> {
+    records.iter().filter(|r| kind(r)).cloned().collect()
 }
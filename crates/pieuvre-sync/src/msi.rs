@@ -2,7 +2,7 @@
 //!
 //! Activation du mode MSI (Message Signaled Interrupts) pour réduire la latence.
 
-use pieuvre_common::{PieuvreError, Result};
+use pieuvre_common::{ChangeRecord, PieuvreError, Result};
 use windows::core::PCWSTR;
 use windows::Win32::System::Registry::{
     RegCloseKey, RegEnumKeyExW, RegOpenKeyExW, RegQueryValueExW, RegSetValueExW,
@@ -227,7 +227,18 @@ pub fn disable_msi(full_registry_path: &str) -> Result<()> {
     set_msi_value(full_registry_path, 0)
 }
 
-fn set_msi_value(key_path: &str, value: u32) -> Result<()> {
+/// Écrit `MSISupported` ; l'état d'origine du device est signalé à `crate::changes`
+pub fn set_msi_value(key_path: &str, value: u32) -> Result<()> {
+    if crate::changes::is_recording() {
+        let original = crate::registry::read_dword_value(key_path, "MSISupported").ok();
+        if original != Some(value) {
+            crate::changes::push(ChangeRecord::MsiDevice {
+                device_path: key_path.to_string(),
+                original_msi_supported: original,
+            })?;
+        }
+    }
+
     unsafe {
         let subkey_wide: Vec<u16> = key_path.encode_utf16().chain(std::iter::once(0)).collect();
         let mut hkey = Default::default();
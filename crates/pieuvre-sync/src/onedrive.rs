@@ -2,11 +2,13 @@
 //!
 //! Uninstall and disable OneDrive completely.
 
-use pieuvre_common::Result;
+use pieuvre_common::{RegistryHive, Result};
 use std::env;
 use std::path::PathBuf;
 use std::process::Command;
 
+const ONEDRIVE_POLICY_KEY: &str = r"SOFTWARE\Policies\Microsoft\Windows\OneDrive";
+
 /// Uninstall OneDrive completely
 pub fn uninstall_onedrive() -> Result<()> {
     tracing::info!("Uninstalling OneDrive...");
@@ -49,19 +51,7 @@ pub fn uninstall_onedrive() -> Result<()> {
     }
 
     // Remove from explorer sidebar via registry
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SOFTWARE\Policies\Microsoft\Windows\OneDrive",
-            "/v",
-            "DisableFileSyncNGSC",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "1",
-            "/f",
-        ])
-        .output();
+    crate::registry::set_dword_value(ONEDRIVE_POLICY_KEY, "DisableFileSyncNGSC", 1)?;
 
     tracing::info!("OneDrive completely removed");
     Ok(())
@@ -70,30 +60,14 @@ pub fn uninstall_onedrive() -> Result<()> {
 /// Disable OneDrive sync without uninstalling
 pub fn disable_onedrive() -> Result<()> {
     // Disable via Group Policy registry
-    let _ = Command::new("reg")
-        .args([
-            "add",
-            r"HKLM\SOFTWARE\Policies\Microsoft\Windows\OneDrive",
-            "/v",
-            "DisableFileSyncNGSC",
-            "/t",
-            "REG_DWORD",
-            "/d",
-            "1",
-            "/f",
-        ])
-        .output();
+    crate::registry::set_dword_value(ONEDRIVE_POLICY_KEY, "DisableFileSyncNGSC", 1)?;
 
     // Disable startup
-    let _ = Command::new("reg")
-        .args([
-            "delete",
-            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run",
-            "/v",
-            "OneDrive",
-            "/f",
-        ])
-        .output();
+    crate::registry::delete_value_in_hive(
+        RegistryHive::Hkcu,
+        r"Software\Microsoft\Windows\CurrentVersion\Run",
+        "OneDrive",
+    )?;
 
     tracing::info!("OneDrive disabled");
     Ok(())
@@ -108,15 +82,7 @@ pub fn is_onedrive_installed() -> bool {
 
 /// Re-enable OneDrive (for rollback)
 pub fn enable_onedrive() -> Result<()> {
-    let _ = Command::new("reg")
-        .args([
-            "delete",
-            r"HKLM\SOFTWARE\Policies\Microsoft\Windows\OneDrive",
-            "/v",
-            "DisableFileSyncNGSC",
-            "/f",
-        ])
-        .output();
+    crate::registry::delete_value(ONEDRIVE_POLICY_KEY, "DisableFileSyncNGSC")?;
 
     tracing::info!("OneDrive re-enabled (requires reinstall from Microsoft Store)");
     Ok(())
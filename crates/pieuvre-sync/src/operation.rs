@@ -3,7 +3,11 @@
 //! Abstraction for all synchronization and optimization operations.
 
 use async_trait::async_trait;
-use pieuvre_common::{ChangeRecord, Result};
+use pieuvre_common::{
+    native_registry, native_service_manager, ChangeRecord, PieuvreError, PlannedChange,
+    RegistryPath, RegistryValue, Result, SharedRegistry, SharedServiceManager,
+};
+use std::sync::Arc;
 use tracing::instrument;
 
 /// A unified synchronization operation
@@ -17,12 +21,59 @@ pub trait SyncOperation: Send + Sync {
 
     /// Checks if the optimization is already applied
     async fn is_applied(&self) -> Result<bool>;
+
+    /// Changes `apply` would make (current -> target), without modifying anything
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        Err(PieuvreError::Unsupported(format!(
+            "{}: no dry-run preview",
+            self.name()
+        )))
+    }
+    /// Takes effect only after the next boot
+    fn requires_reboot(&self) -> bool {
+        false
+    }
+}
+
+/// Runs `f` on a blocking thread and returns the changes it recorded
+/// (attached to the error if `f` fails midway)
+async fn apply_recorded<F>(f: F) -> Result<Vec<ChangeRecord>>
+where
+    F: FnOnce() -> Result<()> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let (result, changes) = crate::changes::record(f);
+        match result {
+            Ok(()) => Ok(changes),
+            Err(e) => Err(e.with_changes(changes)),
+        }
+    })
+    .await
+    .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))?
 }
 
 /// Operation on a Windows service
 pub struct ServiceOperation {
     pub name: String,
     pub target_start_type: u32, // 2=Auto, 3=Manual, 4=Disabled
+    pub services: SharedServiceManager,
+}
+
+impl ServiceOperation {
+    /// Operation on the live SCM
+    pub fn new(name: impl Into<String>, target_start_type: u32) -> Self {
+        Self {
+            name: name.into(),
+            target_start_type,
+            services: native_service_manager(),
+        }
+    }
+
+    /// Targets another service manager (tests, simulation)
+    pub fn with_services(mut self, services: SharedServiceManager) -> Self {
+        self.services = services;
+        self
+    }
 }
 
 #[async_trait]
@@ -33,13 +84,14 @@ impl SyncOperation for ServiceOperation {
 
     #[instrument(skip(self))]
     async fn apply(&self) -> Result<Vec<ChangeRecord>> {
+        let services = self.services.clone();
         let name = self.name.clone();
         let target = self.target_start_type;
 
         tokio::task::spawn_blocking(move || {
-            let original = crate::services::get_service_start_type(&name)?;
+            let original = services.start_type(&name)?;
             if original != target {
-                crate::services::set_service_start_type(&name, target)?;
+                services.set_start_type(&name, target)?;
                 Ok(vec![ChangeRecord::Service {
                     name,
                     original_start_type: original,
@@ -54,21 +106,54 @@ impl SyncOperation for ServiceOperation {
 
     #[instrument(skip(self))]
     async fn is_applied(&self) -> Result<bool> {
+        let services = self.services.clone();
+        let name = self.name.clone();
+        let target = self.target_start_type;
+        tokio::task::spawn_blocking(move || Ok(services.start_type(&name)? == target))
+            .await
+            .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))?
+    }
+    #[instrument(skip(self))]
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        let services = self.services.clone();
         let name = self.name.clone();
         let target = self.target_start_type;
         tokio::task::spawn_blocking(move || {
-            Ok(crate::services::get_service_start_type(&name)? == target)
+            Ok(
+                crate::plan::service_start_type(services.as_ref(), &name, target)
+                    .into_iter()
+                    .collect(),
+            )
         })
         .await
-        .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))?
+        .map_err(|e| PieuvreError::Internal(e.to_string()))?
     }
 }
 
-/// Registry operation (DWORD)
+/// Registry operation (DWORD, HKLM)
 pub struct RegistryDwordOperation {
     pub key: String,
     pub value: String,
     pub target_data: u32,
+    pub backend: SharedRegistry,
+}
+
+impl RegistryDwordOperation {
+    /// Operation on the live registry
+    pub fn new(key: impl Into<String>, value: impl Into<String>, target_data: u32) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            target_data,
+            backend: native_registry(),
+        }
+    }
+
+    /// Targets another registry backend (tests, simulation)
+    pub fn with_backend(mut self, backend: SharedRegistry) -> Self {
+        self.backend = backend;
+        self
+    }
 }
 
 #[async_trait]
@@ -79,23 +164,27 @@ impl SyncOperation for RegistryDwordOperation {
 
     #[instrument(skip(self))]
     async fn apply(&self) -> Result<Vec<ChangeRecord>> {
+        let backend = self.backend.clone();
         let key = self.key.clone();
         let value = self.value.clone();
         let data = self.target_data;
 
         tokio::task::spawn_blocking(move || {
-            // Capture original state BEFORE any modification
-            let original = crate::registry::read_dword_value(&key, &value).ok();
+            let path = RegistryPath::hklm(&key);
+
+            // Capture original state BEFORE any modification; unreadable, a
+            // rollback would delete the value
+            let original = backend.read_value(&path, &value)?;
 
             // Apply modification
-            crate::registry::set_dword_value(&key, &value, data)?;
+            backend.write_dword(&path, &value, data)?;
 
             // If operation succeeded, return change record
             Ok(vec![ChangeRecord::Registry {
                 hive: pieuvre_common::RegistryHive::Hklm,
                 key,
                 value_name: value,
-                original_value: original.map(pieuvre_common::RegistryValue::Dword),
+                original_value: original,
             }])
         })
         .await
@@ -104,15 +193,35 @@ impl SyncOperation for RegistryDwordOperation {
 
     #[instrument(skip(self))]
     async fn is_applied(&self) -> Result<bool> {
+        let backend = self.backend.clone();
         let key = self.key.clone();
         let value = self.value.clone();
         let data = self.target_data;
         tokio::task::spawn_blocking(move || {
-            Ok(crate::registry::read_dword_value(&key, &value).unwrap_or(u32::MAX) == data)
+            let current = backend.read_dword(&RegistryPath::hklm(&key), &value);
+            Ok(matches!(current, Ok(Some(v)) if v == data))
         })
         .await
         .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))?
     }
+    #[instrument(skip(self))]
+    async fn plan(&self) -> Result<Vec<PlannedChange>> {
+        let backend = self.backend.clone();
+        let key = self.key.clone();
+        let value = self.value.clone();
+        let data = self.target_data;
+        tokio::task::spawn_blocking(move || {
+            let planned = crate::plan::registry_value(
+                backend.as_ref(),
+                &RegistryPath::hklm(&key),
+                &value,
+                Some(RegistryValue::Dword(data)),
+            )?;
+            Ok(planned.into_iter().collect())
+        })
+        .await
+        .map_err(|e| PieuvreError::Internal(e.to_string()))?
+    }
 }
 
 /// MSI Interrupt operation
@@ -130,12 +239,7 @@ impl SyncOperation for MsiOperation {
     async fn apply(&self) -> Result<Vec<ChangeRecord>> {
         let devices = self.devices.clone();
         let priority = self.priority.clone();
-        tokio::task::spawn_blocking(move || {
-            crate::msi::configure_msi_for_devices(&devices, &priority)?;
-            Ok(vec![]) // MSI rollback not supported for now
-        })
-        .await
-        .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))?
+        apply_recorded(move || crate::msi::configure_msi_for_devices(&devices, &priority)).await
     }
 
     async fn is_applied(&self) -> Result<bool> {
@@ -156,14 +260,13 @@ impl SyncOperation for AppxOperation {
 
     async fn apply(&self) -> Result<Vec<ChangeRecord>> {
         let packages = self.packages_to_remove.clone();
-        tokio::task::spawn_blocking(move || {
+        apply_recorded(move || {
             for pkg in packages {
                 let _ = crate::appx::remove_package(&pkg);
             }
-            Ok(vec![])
+            Ok(())
         })
         .await
-        .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))?
     }
 
     async fn is_applied(&self) -> Result<bool> {
@@ -184,18 +287,14 @@ impl SyncOperation for PowerPlanOperation {
 
     async fn apply(&self) -> Result<Vec<ChangeRecord>> {
         let plan = self.plan.clone();
-        tokio::task::spawn_blocking(move || {
-            match plan.as_str() {
-                "ultimate_performance" => crate::power::apply_gaming_power_config()?,
-                "high_performance" => {
-                    crate::power::set_power_plan(crate::power::PowerPlan::HighPerformance)?
-                }
-                _ => crate::power::set_power_plan(crate::power::PowerPlan::Balanced)?,
+        apply_recorded(move || match plan.as_str() {
+            "ultimate_performance" => crate::power::apply_gaming_power_config(),
+            "high_performance" => {
+                crate::power::set_power_plan(crate::power::PowerPlan::HighPerformance)
             }
-            Ok(vec![])
+            _ => crate::power::set_power_plan(crate::power::PowerPlan::Balanced),
         })
         .await
-        .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))?
     }
 
     async fn is_applied(&self) -> Result<bool> {
@@ -217,7 +316,7 @@ impl SyncOperation for CpuOptimizationOperation {
     }
 
     async fn apply(&self) -> Result<Vec<ChangeRecord>> {
-        tokio::task::spawn_blocking({
+        apply_recorded({
             let cp = self.disable_core_parking;
             let mc = self.disable_memory_compression;
             let sf = self.disable_superfetch;
@@ -225,17 +324,17 @@ impl SyncOperation for CpuOptimizationOperation {
                 if cp {
                     crate::cpu::disable_core_parking()?;
                 }
+                // MMAgent : pas de ChangeRecord, réactivable via enable_memory_compression
                 if mc {
                     crate::cpu::disable_memory_compression()?;
                 }
                 if sf {
                     crate::cpu::disable_superfetch_registry()?;
                 }
-                Ok(vec![])
+                Ok(())
             }
         })
         .await
-        .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))?
     }
 
     async fn is_applied(&self) -> Result<bool> {
@@ -274,7 +373,7 @@ impl SyncOperation for MemoryOptimizationOperation {
     }
 
     async fn apply(&self) -> Result<Vec<ChangeRecord>> {
-        tokio::task::spawn_blocking({
+        apply_recorded({
             let lsc = self.enable_large_system_cache;
             let iopl = self.io_page_lock_limit_mb;
             move || {
@@ -284,15 +383,47 @@ impl SyncOperation for MemoryOptimizationOperation {
                 if let Some(mb) = iopl {
                     crate::memory::set_io_page_lock_limit(mb * 1024 * 1024)?;
                 }
-                crate::memory::trim_current_working_set()?;
-                Ok(vec![])
+                crate::memory::trim_current_working_set()
             }
         })
         .await
-        .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))?
     }
 
     async fn is_applied(&self) -> Result<bool> {
         Ok(false) // Always apply trim
     }
 }
+
+/// Blocking step whose changes are recorded by the `changes` module
+pub struct RecordedOperation {
+    pub name: String,
+    pub action: Arc<dyn Fn() -> Result<()> + Send + Sync>,
+}
+
+impl RecordedOperation {
+    pub fn new(
+        name: impl Into<String>,
+        action: impl Fn() -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            action: Arc::new(action),
+        }
+    }
+}
+
+#[async_trait]
+impl SyncOperation for RecordedOperation {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn apply(&self) -> Result<Vec<ChangeRecord>> {
+        let action = self.action.clone();
+        apply_recorded(move || action()).await
+    }
+
+    async fn is_applied(&self) -> Result<bool> {
+        Ok(false)
+    }
+}
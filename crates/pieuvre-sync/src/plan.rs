@@ -0,0 +1,67 @@
+//! Dry-run planning
+//!
+//! Calcule l'état actuel et l'état cible de ce qu'une action modifierait, sans
+//! rien écrire. `None` : la cible est déjà dans l'état voulu (l'action serait sans effet).
+
+use pieuvre_common::{
+    PlannedChange, RegistryBackend, RegistryPath, RegistryValue, Result, ServiceManager,
+};
+
+/// Valeur de registre à écrire (`Some`) ou à supprimer (`None`)
+pub fn registry_value(
+    backend: &dyn RegistryBackend,
+    path: &RegistryPath,
+    value_name: &str,
+    target: Option<RegistryValue>,
+) -> Result<Option<PlannedChange>> {
+    let current = backend.read_value(path, value_name)?;
+    if current == target {
+        return Ok(None);
+    }
+    Ok(Some(PlannedChange::Registry {
+        hive: path.hive,
+        key: path.key.clone(),
+        value_name: value_name.to_string(),
+        current,
+        target,
+    }))
+}
+
+/// Type de démarrage d'un service ; un service introuvable apparaît avec `current: None`
+pub fn service_start_type(
+    services: &dyn ServiceManager,
+    name: &str,
+    target: u32,
+) -> Option<PlannedChange> {
+    let current = services.start_type(name).ok();
+    if current == Some(target) {
+        return None;
+    }
+    Some(PlannedChange::Service {
+        name: name.to_string(),
+        current_start_type: current,
+        target_start_type: target,
+    })
+}
+
+/// État d'une tâche planifiée ; une tâche absente est ignorée, comme à l'application
+pub fn scheduled_task(path: &str, enabled: bool) -> Option<PlannedChange> {
+    let current = crate::scheduled_tasks::task_state(path)?;
+    if current == enabled {
+        return None;
+    }
+    Some(PlannedChange::ScheduledTask {
+        path: path.to_string(),
+        current_enabled: current,
+        target_enabled: enabled,
+    })
+}
+
+/// Domaines que `hosts::block_domains` ajouterait
+pub fn hosts_block(domains: &[String]) -> Result<Option<PlannedChange>> {
+    let domains = crate::hosts::pending_domains(domains)?;
+    if domains.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(PlannedChange::HostsBlock { domains }))
+}
@@ -3,15 +3,26 @@
 //! Management of power plans and energy settings.
 //! Uses native Windows APIs (PowerGetActiveScheme, PowerSetActiveScheme).
 
-use pieuvre_common::{PieuvreError, Result};
+use pieuvre_common::{
+    ChangeRecord, NativeRegistry, PieuvreError, PowerSettingRecord, RegistryHive, RegistryPath,
+    RegistryValue, Result,
+};
+use serde::{Deserialize, Serialize};
 use windows::core::GUID;
 use windows::Win32::Foundation::{LocalFree, HLOCAL};
 use windows::Win32::System::Power::{
-    PowerGetActiveScheme, PowerSetActiveScheme, PowerWriteACValueIndex,
+    PowerGetActiveScheme, PowerReadACValueIndex, PowerReadDCValueIndex, PowerSetActiveScheme,
+    PowerWriteACValueIndex, PowerWriteDCValueIndex,
 };
 
+/// Processor power management subgroup
+pub const SUBGROUP_PROCESSOR: &str = "54533251-82be-4824-96c1-47b60b740d00";
+/// Core parking: minimum unparked cores (%)
+pub const SETTING_CORE_PARKING_MIN_CORES: &str = "0cc5b647-c1df-4637-891a-edc3318ea174";
+
 /// Predefined power plans
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PowerPlan {
     /// Power Saver
     PowerSaver,
@@ -100,8 +111,29 @@ fn guid_to_string(guid: &GUID) -> String {
     )
 }
 
-/// Retrieves the active power plan via native API
+/// Retrieves the active power plan via native API (name if known, GUID otherwise)
 pub fn get_active_power_plan() -> Result<String> {
+    let guid_str = get_active_scheme_guid()?;
+
+    // Convert to readable name if known
+    let name = match guid_str.as_str() {
+        s if s == PowerPlan::PowerSaver.guid() => PowerPlan::PowerSaver.name().to_string(),
+        s if s == PowerPlan::Balanced.guid() => PowerPlan::Balanced.name().to_string(),
+        s if s == PowerPlan::HighPerformance.guid() => {
+            PowerPlan::HighPerformance.name().to_string()
+        }
+        s if s == PowerPlan::UltimatePerformance.guid() => {
+            PowerPlan::UltimatePerformance.name().to_string()
+        }
+        "bd0b9fdc-5b4f-49d2-b2d0-76c179101054" => "Bitsum Highest Performance".to_string(),
+        _ => guid_str,
+    };
+
+    Ok(name)
+}
+
+/// GUID of the active power scheme (lowercase)
+pub fn get_active_scheme_guid() -> Result<String> {
     unsafe {
         let mut scheme_guid: *mut GUID = std::ptr::null_mut();
 
@@ -120,31 +152,41 @@ pub fn get_active_power_plan() -> Result<String> {
         }
 
         let guid = *scheme_guid;
-        let guid_str = guid_to_string(&guid);
 
         // Free memory allocated by Windows
         let _ = LocalFree(Some(HLOCAL(scheme_guid as *mut std::ffi::c_void)));
 
-        // Convert to readable name if known
-        let name = match guid_str.as_str() {
-            s if s == PowerPlan::PowerSaver.guid() => PowerPlan::PowerSaver.name().to_string(),
-            s if s == PowerPlan::Balanced.guid() => PowerPlan::Balanced.name().to_string(),
-            s if s == PowerPlan::HighPerformance.guid() => {
-                PowerPlan::HighPerformance.name().to_string()
-            }
-            s if s == PowerPlan::UltimatePerformance.guid() => {
-                PowerPlan::UltimatePerformance.name().to_string()
-            }
-            "bd0b9fdc-5b4f-49d2-b2d0-76c179101054" => "Bitsum Highest Performance".to_string(),
-            _ => guid_str,
-        };
+        Ok(guid_to_string(&guid))
+    }
+}
 
-        Ok(name)
+/// Activates a power scheme by GUID (rollback, or to apply modified settings)
+pub fn set_active_scheme(guid: &str) -> Result<()> {
+    let guid = parse_guid(guid);
+    unsafe {
+        if PowerSetActiveScheme(None, Some(&guid)).is_err() {
+            return Err(PieuvreError::Unsupported(format!(
+                "PowerSetActiveScheme failed for {}",
+                guid_to_string(&guid)
+            )));
+        }
     }
+    Ok(())
 }
 
 /// Sets the active power plan via native API
 pub fn set_power_plan(plan: PowerPlan) -> Result<()> {
+    if crate::changes::is_recording() {
+        if let Ok(current) = get_active_scheme_guid() {
+            if current != plan.guid() {
+                crate::changes::push(ChangeRecord::PowerScheme {
+                    original_scheme: Some(current),
+                    settings: vec![],
+                })?;
+            }
+        }
+    }
+
     unsafe {
         let guid = plan.as_guid();
 
@@ -199,78 +241,164 @@ fn create_ultimate_performance_plan() -> Result<()> {
     Ok(())
 }
 
-/// Configures specific power settings via native API
-pub fn configure_power_settings(
-    usb_selective_suspend: bool,
-    pci_aspm: bool,
-    processor_min: u8,
-    processor_max: u8,
+/// Writes the AC and/or DC index of a setting in `scheme`; original indices are recorded
+pub fn write_setting(
+    scheme: &str,
+    subgroup: &str,
+    setting: &str,
+    ac: Option<u32>,
+    dc: Option<u32>,
 ) -> Result<()> {
+    let scheme_guid = parse_guid(scheme);
+    let subgroup_guid = parse_guid(subgroup);
+    let setting_guid = parse_guid(setting);
+
+    if crate::changes::is_recording() {
+        let (original_ac, original_dc) = read_setting(scheme, subgroup, setting);
+        crate::changes::push(ChangeRecord::PowerScheme {
+            original_scheme: None,
+            settings: vec![PowerSettingRecord {
+                scheme: scheme.to_string(),
+                subgroup: subgroup.to_string(),
+                setting: setting.to_string(),
+                original_ac: ac.and(original_ac),
+                original_dc: dc.and(original_dc),
+            }],
+        })?;
+    }
+
     unsafe {
-        let mut scheme_guid_ptr: *mut GUID = std::ptr::null_mut();
-        if PowerGetActiveScheme(None, &mut scheme_guid_ptr).is_err() {
-            return Err(PieuvreError::Unsupported(
-                "Cannot get active power scheme".to_string(),
-            ));
+        if let Some(value) = ac {
+            if PowerWriteACValueIndex(
+                None,
+                &scheme_guid,
+                Some(&subgroup_guid),
+                Some(&setting_guid),
+                value,
+            )
+            .is_err()
+            {
+                return Err(PieuvreError::Unsupported(format!(
+                    "PowerWriteACValueIndex failed for {}",
+                    setting
+                )));
+            }
         }
-        let scheme_guid = *scheme_guid_ptr;
-
-        // GUIDs for settings (Source: Microsoft Documentation)
-        let subgroup_usb = parse_guid("2a737441-1930-4402-8d77-b2bebba308a3");
-        let setting_usb_suspend = parse_guid("48e6b7a6-50f5-4782-a5d4-53bb8f07e226");
-
-        let subgroup_pci = parse_guid("501a4d13-42af-4429-9fd1-a8218c268e20");
-        let setting_pci_aspm = parse_guid("ee12f906-d277-404b-b6da-e5fa1a576df5");
+        if let Some(value) = dc {
+            if PowerWriteDCValueIndex(
+                None,
+                &scheme_guid,
+                Some(&subgroup_guid),
+                Some(&setting_guid),
+                value,
+            ) != 0
+            {
+                return Err(PieuvreError::Unsupported(format!(
+                    "PowerWriteDCValueIndex failed for {}",
+                    setting
+                )));
+            }
+        }
+    }
+    Ok(())
+}
 
-        let subgroup_cpu = parse_guid("54533251-82be-4824-96c1-47b60b740d00");
-        let setting_cpu_min = parse_guid("893dee8e-2bef-41e0-89c6-b55d0929964c");
-        let setting_cpu_max = parse_guid("bc5038f7-23e0-4960-96da-33abaf5935ec");
+/// Current (AC, DC) indices of a setting, `None` when unreadable
+pub fn read_setting(scheme: &str, subgroup: &str, setting: &str) -> (Option<u32>, Option<u32>) {
+    let scheme_guid = parse_guid(scheme);
+    let subgroup_guid = parse_guid(subgroup);
+    let setting_guid = parse_guid(setting);
+    let mut ac = 0u32;
+    let mut dc = 0u32;
 
-        // Apply values
-        let _ = PowerWriteACValueIndex(
-            None,
-            &scheme_guid,
-            Some(&subgroup_usb),
-            Some(&setting_usb_suspend),
-            if usb_selective_suspend { 1 } else { 0 },
-        );
-        let _ = PowerWriteACValueIndex(
+    unsafe {
+        let ac_ok = PowerReadACValueIndex(
             None,
-            &scheme_guid,
-            Some(&subgroup_pci),
-            Some(&setting_pci_aspm),
-            if pci_aspm { 1 } else { 0 },
-        );
-        let _ = PowerWriteACValueIndex(
+            Some(&scheme_guid),
+            Some(&subgroup_guid),
+            Some(&setting_guid),
+            &mut ac,
+        )
+        .is_ok();
+        let dc_ok = PowerReadDCValueIndex(
             None,
-            &scheme_guid,
-            Some(&subgroup_cpu),
-            Some(&setting_cpu_min),
+            Some(&scheme_guid),
+            Some(&subgroup_guid),
+            Some(&setting_guid),
+            &mut dc,
+        ) == 0;
+        (ac_ok.then_some(ac), dc_ok.then_some(dc))
+    }
+}
+
+/// Writes a setting of the active scheme and re-activates it so the change applies
+pub fn write_active_setting(
+    subgroup: &str,
+    setting: &str,
+    ac: Option<u32>,
+    dc: Option<u32>,
+) -> Result<()> {
+    let scheme = get_active_scheme_guid()?;
+    write_setting(&scheme, subgroup, setting, ac, dc)?;
+    set_active_scheme(&scheme)
+}
+
+/// Configures specific power settings via native API
+pub fn configure_power_settings(
+    usb_selective_suspend: bool,
+    pci_aspm: bool,
+    processor_min: u8,
+    processor_max: u8,
+) -> Result<()> {
+    let scheme = get_active_scheme_guid()
+        .map_err(|_| PieuvreError::Unsupported("Cannot get active power scheme".to_string()))?;
+
+    // GUIDs for settings (Source: Microsoft Documentation)
+    let settings = [
+        // USB selective suspend
+        (
+            "2a737441-1930-4402-8d77-b2bebba308a3",
+            "48e6b7a6-50f5-4782-a5d4-53bb8f07e226",
+            usb_selective_suspend as u32,
+        ),
+        // PCI Express ASPM
+        (
+            "501a4d13-42af-4429-9fd1-a8218c268e20",
+            "ee12f906-d277-404b-b6da-e5fa1a576df5",
+            pci_aspm as u32,
+        ),
+        // Processor min / max state
+        (
+            SUBGROUP_PROCESSOR,
+            "893dee8e-2bef-41e0-89c6-b55d0929964c",
             processor_min as u32,
-        );
-        let _ = PowerWriteACValueIndex(
-            None,
-            &scheme_guid,
-            Some(&subgroup_cpu),
-            Some(&setting_cpu_max),
+        ),
+        (
+            SUBGROUP_PROCESSOR,
+            "bc5038f7-23e0-4960-96da-33abaf5935ec",
             processor_max as u32,
-        );
+        ),
+    ];
 
-        // Apply changes
-        let _ = PowerSetActiveScheme(None, Some(&scheme_guid));
+    // Apply values (a missing setting on this machine is not fatal)
+    for (subgroup, setting, value) in settings {
+        if let Err(e) = write_setting(&scheme, subgroup, setting, Some(value), None) {
+            tracing::debug!("{}", e);
+        }
+    }
 
-        let _ = LocalFree(Some(HLOCAL(scheme_guid_ptr as *mut std::ffi::c_void)));
+    // Apply changes
+    let _ = set_active_scheme(&scheme);
 
-        tracing::info!(
-            usb_suspend = usb_selective_suspend,
-            pci_aspm = pci_aspm,
-            cpu_min = processor_min,
-            cpu_max = processor_max,
-            "Power settings configured via native API"
-        );
+    tracing::info!(
+        usb_suspend = usb_selective_suspend,
+        pci_aspm = pci_aspm,
+        cpu_min = processor_min,
+        cpu_max = processor_max,
+        "Power settings configured via native API"
+    );
 
-        Ok(())
-    }
+    Ok(())
 }
 
 /// Disables CPU throttling (max performance)
@@ -285,10 +413,21 @@ pub fn apply_gaming_power_config() -> Result<()> {
     Ok(())
 }
 
-/// Disables hibernation to free disk space (removes hiberfil.sys)
+/// Hibernation state, as written by `powercfg /hibernate`
+pub const HIBERNATE_KEY: &str = r"SYSTEM\CurrentControlSet\Control\Power";
+
+/// Disables hibernation to free disk space (removes hiberfil.sys).
+/// The original `HibernateEnabled` is reported to `crate::changes`; rollback restores it.
 pub fn disable_hibernation() -> Result<()> {
     use std::process::Command;
 
+    crate::registry::track(
+        &NativeRegistry,
+        &RegistryPath::new(RegistryHive::Hklm, HIBERNATE_KEY),
+        "HibernateEnabled",
+        Some(RegistryValue::Dword(0)),
+    )?;
+
     let output = Command::new("powercfg")
         .args(["/hibernate", "off"])
         .output()
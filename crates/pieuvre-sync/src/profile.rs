@@ -0,0 +1,277 @@
+//! Optimization profiles
+//!
+//! A profile is a named set of tweak ids plus parameters (timer resolution,
+//! power plan). Profiles can extend a parent: tweaks are inherited, `exclude`
+//! removes some of them and parameters set by the child win.
+
+use crate::power::PowerPlan;
+use pieuvre_common::{PieuvreError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Timer resolution bounds, in 100 ns units (0.5 ms .. 15.625 ms)
+const TIMER_RESOLUTION_RANGE: std::ops::RangeInclusive<u32> = 5_000..=156_250;
+
+/// Parameters applied alongside the tweaks; `None` keeps the inherited value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileParams {
+    /// Timer resolution in 100 ns units (5000 = 0.5 ms)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timer_resolution: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power_plan: Option<PowerPlan>,
+}
+
+impl ProfileParams {
+    /// Values of `child` override those of `self`
+    pub fn overridden_by(self, child: ProfileParams) -> Self {
+        Self {
+            timer_resolution: child.timer_resolution.or(self.timer_resolution),
+            power_plan: child.power_plan.or(self.power_plan),
+        }
+    }
+}
+
+/// Profile as written in a profile file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Parent profile
+    #[serde(default)]
+    pub extends: Option<String>,
+    /// Tweaks added on top of the parent ones
+    #[serde(default)]
+    pub tweaks: Vec<String>,
+    /// Inherited tweaks removed from this profile
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub params: ProfileParams,
+}
+
+/// Profile flattened along its inheritance chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedProfile {
+    pub name: String,
+    pub description: String,
+    /// Inheritance chain, root first (`["base", "gaming"]`)
+    pub chain: Vec<String>,
+    /// Tweak ids in application order, without duplicates
+    pub tweaks: Vec<String>,
+    pub params: ProfileParams,
+}
+
+/// Differences between two resolved profiles
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileDiff {
+    /// Tweaks only in the right-hand profile
+    pub added: Vec<String>,
+    /// Tweaks only in the left-hand profile
+    pub removed: Vec<String>,
+    /// Parameters that differ: (name, left, right)
+    pub params: Vec<(String, String, String)>,
+}
+
+impl ProfileDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.params.is_empty()
+    }
+}
+
+impl ResolvedProfile {
+    /// What changes when going from `self` to `other`
+    pub fn diff(&self, other: &ResolvedProfile) -> ProfileDiff {
+        let added = other
+            .tweaks
+            .iter()
+            .filter(|t| !self.tweaks.contains(t))
+            .cloned()
+            .collect();
+        let removed = self
+            .tweaks
+            .iter()
+            .filter(|t| !other.tweaks.contains(t))
+            .cloned()
+            .collect();
+
+        let mut params = Vec::new();
+        if self.params.timer_resolution != other.params.timer_resolution {
+            params.push((
+                "timer_resolution".to_string(),
+                format_param(self.params.timer_resolution),
+                format_param(other.params.timer_resolution),
+            ));
+        }
+        if self.params.power_plan != other.params.power_plan {
+            params.push((
+                "power_plan".to_string(),
+                format_param(self.params.power_plan.map(|p| p.name())),
+                format_param(other.params.power_plan.map(|p| p.name())),
+            ));
+        }
+
+        ProfileDiff {
+            added,
+            removed,
+            params,
+        }
+    }
+}
+
+fn format_param(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+/// Set of profile definitions, keyed by name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileSet {
+    #[serde(default, rename = "profile")]
+    profiles: Vec<ProfileDefinition>,
+}
+
+impl ProfileSet {
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let set: Self = toml::from_str(content)
+            .map_err(|e| PieuvreError::Config(format!("Invalid profile file: {}", e)))?;
+        set.validate()?;
+        Ok(set)
+    }
+
+    /// Loads a profile file, errors are prefixed with the file path
+    pub fn load_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_toml(&content).map_err(|e| match e {
+            PieuvreError::Config(msg) => {
+                PieuvreError::Config(format!("{}: {}", path.display(), msg))
+            }
+            other => other,
+        })
+    }
+
+    pub fn profiles(&self) -> &[ProfileDefinition] {
+        &self.profiles
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ProfileDefinition> {
+        self.profiles
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Adds user profiles: a known name replaces the existing definition
+    pub fn merge(&mut self, other: ProfileSet) {
+        for profile in other.profiles {
+            match self
+                .profiles
+                .iter_mut()
+                .find(|p| p.name.eq_ignore_ascii_case(&profile.name))
+            {
+                Some(existing) => {
+                    tracing::info!(name = %profile.name, "Profile overridden");
+                    *existing = profile;
+                }
+                None => self.profiles.push(profile),
+            }
+        }
+    }
+
+    /// Checks names, parents and parameters; all errors are reported together
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = Vec::new();
+        let mut seen = HashSet::new();
+
+        for profile in &self.profiles {
+            let name = &profile.name;
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+            {
+                errors.push(format!(
+                    "'{}': name must be non-empty lowercase ([a-z0-9_-])",
+                    name
+                ));
+            }
+            if !seen.insert(name.as_str()) {
+                errors.push(format!("'{}': duplicate profile", name));
+            }
+            if profile.tweaks.iter().any(|t| t.trim().is_empty()) {
+                errors.push(format!("'{}': empty tweak id", name));
+            }
+            if let Some(res) = profile.params.timer_resolution {
+                if !TIMER_RESOLUTION_RANGE.contains(&res) {
+                    errors.push(format!(
+                        "'{}': timer_resolution {} is out of range ({}..={})",
+                        name,
+                        res,
+                        TIMER_RESOLUTION_RANGE.start(),
+                        TIMER_RESOLUTION_RANGE.end()
+                    ));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PieuvreError::Config(format!(
+                "Invalid profile file:\n  - {}",
+                errors.join("\n  - ")
+            )))
+        }
+    }
+
+    /// Flattens a profile with its parents (unknown parent or cycle is an error)
+    pub fn resolve(&self, name: &str) -> Result<ResolvedProfile> {
+        let mut chain: Vec<&ProfileDefinition> = Vec::new();
+        let mut current = Some(name.to_string());
+
+        while let Some(next) = current {
+            let profile = self.get(&next).ok_or_else(|| match chain.last() {
+                Some(child) => PieuvreError::Config(format!(
+                    "Profile '{}' extends unknown profile '{}'",
+                    child.name, next
+                )),
+                None => PieuvreError::Config(format!("Unknown profile '{}'", next)),
+            })?;
+            if chain.iter().any(|p| p.name == profile.name) {
+                let cycle: Vec<&str> = chain.iter().map(|p| p.name.as_str()).collect();
+                return Err(PieuvreError::Config(format!(
+                    "Profile inheritance cycle: {} -> {}",
+                    cycle.join(" -> "),
+                    profile.name
+                )));
+            }
+            chain.push(profile);
+            current = profile.extends.clone();
+        }
+        chain.reverse();
+
+        let mut tweaks: Vec<String> = Vec::new();
+        let mut params = ProfileParams::default();
+        for profile in &chain {
+            tweaks.retain(|t| !profile.exclude.contains(t));
+            for tweak in &profile.tweaks {
+                if !tweaks.contains(tweak) {
+                    tweaks.push(tweak.clone());
+                }
+            }
+            params = params.overridden_by(profile.params);
+        }
+
+        let leaf = chain.last().expect("chain contains the requested profile");
+        Ok(ResolvedProfile {
+            name: leaf.name.clone(),
+            description: leaf.description.clone(),
+            chain: chain.iter().map(|p| p.name.clone()).collect(),
+            tweaks,
+            params,
+        })
+    }
+}
@@ -1,142 +1,210 @@
 //! Modifications registre atomiques SOTA
 //! Support natif 64-bit et multi-ruches (HKLM + HKU)
-
-use pieuvre_common::{PieuvreError, Result};
-use windows::core::{PCWSTR, PWSTR};
-use windows::Win32::System::Registry::{
-    RegCloseKey, RegCreateKeyExW, RegDeleteTreeW, RegDeleteValueW, RegEnumKeyExW, RegOpenKeyExW,
-    RegQueryValueExW, RegSetValueExW, HKEY, HKEY_LOCAL_MACHINE, HKEY_USERS, KEY_READ,
-    KEY_SET_VALUE, KEY_WOW64_64KEY, KEY_WRITE, REG_DWORD, REG_OPTION_NON_VOLATILE, REG_SZ,
+//!
+//! Toutes les fonctions passent par un `RegistryBackend` : les variantes `_with`
+//! acceptent un backend arbitraire (mémoire, ruche hors-ligne), les autres ciblent
+//! le registre natif. Les écritures et suppressions sont signalées à
+//! `crate::changes` avec la valeur d'origine ; les clés créées ou supprimées aussi,
+//! avec une copie de l'arborescence supprimée.
+
+use pieuvre_common::{
+    ChangeRecord, NativeRegistry, PieuvreError, RegistryBackend, RegistryHive, RegistryPath,
+    RegistryValue, Result,
 };
 
+/// Signale la valeur d'origine avant une modification vers `target` (`None` : suppression).
+/// Sans `changes::record` en cours, ou si la valeur est déjà à `target`, rien n'est signalé.
+/// Une valeur d'origine illisible refuse la modification : le rollback la supprimerait.
+pub(crate) fn track(
+    backend: &dyn RegistryBackend,
+    path: &RegistryPath,
+    value_name: &str,
+    target: Option<RegistryValue>,
+) -> Result<()> {
+    if !crate::changes::is_recording() {
+        return Ok(());
+    }
+    let original = backend.read_value(path, value_name).map_err(|e| {
+        PieuvreError::Registry(format!(
+            "{}\\{} not modified, original value unreadable: {}",
+            path, value_name, e
+        ))
+    })?;
+    if original == target {
+        return Ok(());
+    }
+    crate::changes::push(ChangeRecord::Registry {
+        hive: path.hive,
+        key: path.key.clone(),
+        value_name: value_name.to_string(),
+        original_value: original,
+    })
+}
+
+/// Signale la plus haute clé absente du chemin, qui va être créée par l'écriture
+fn track_key_creation(backend: &dyn RegistryBackend, path: &RegistryPath) -> Result<()> {
+    if !crate::changes::is_recording() || path.is_root() || backend.key_exists(path) {
+        return Ok(());
+    }
+    let mut created = path.clone();
+    while let Some(parent) = created.parent() {
+        if parent.is_root() || backend.key_exists(&parent) {
+            break;
+        }
+        created = parent;
+    }
+    crate::changes::push(ChangeRecord::RegistryKeyCreated {
+        hive: created.hive,
+        key: created.key,
+    })
+}
+
+/// Écrit une valeur de n'importe quel type REG_* ; clé et valeur d'origine sont signalées
+pub fn set_value_with(
+    backend: &dyn RegistryBackend,
+    path: &RegistryPath,
+    value_name: &str,
+    value: &RegistryValue,
+) -> Result<()> {
+    track_key_creation(backend, path)?;
+    track(backend, path, value_name, Some(value.clone()))?;
+    backend.write_value(path, value_name, value)
+}
+
+fn write_dword_tracked(
+    backend: &dyn RegistryBackend,
+    path: &RegistryPath,
+    value_name: &str,
+    value: u32,
+) -> Result<()> {
+    set_value_with(backend, path, value_name, &RegistryValue::Dword(value))
+}
+
+fn write_string_tracked(
+    backend: &dyn RegistryBackend,
+    path: &RegistryPath,
+    value_name: &str,
+    value: &str,
+) -> Result<()> {
+    set_value_with(
+        backend,
+        path,
+        value_name,
+        &RegistryValue::String(value.to_string()),
+    )
+}
+
+/// Crée une clé (et ses parents) ; la plus haute clé créée est signalée
+pub fn create_key_with(backend: &dyn RegistryBackend, path: &RegistryPath) -> Result<()> {
+    track_key_creation(backend, path)?;
+    backend.create_key(path)
+}
+
+/// Supprime une clé et ses sous-clés ; l'arborescence est copiée avant suppression
+pub fn delete_key_with(backend: &dyn RegistryBackend, path: &RegistryPath) -> Result<()> {
+    if crate::changes::is_recording() && backend.key_exists(path) {
+        match backend.export_tree(path) {
+            Ok(tree) => crate::changes::push(ChangeRecord::RegistryKeyDeleted {
+                hive: path.hive,
+                key: path.key.clone(),
+                tree,
+            })?,
+            Err(e) => tracing::warn!("Key {} not recorded: {}", path, e),
+        }
+    }
+    backend.delete_key(path)
+}
+
 /// Écrit une valeur DWORD dans une ruche spécifique avec support 64-bit
 pub fn set_dword_value_in_hive(
-    hive: HKEY,
+    hive: RegistryHive,
     subkey: &str,
     value_name: &str,
     value: u32,
 ) -> Result<()> {
-    unsafe {
-        let mut hkey = Default::default();
-        let subkey_wide: Vec<u16> = subkey.encode_utf16().chain(std::iter::once(0)).collect();
-
-        RegCreateKeyExW(
-            hive,
-            PCWSTR(subkey_wide.as_ptr()),
-            Some(0),
-            None,
-            REG_OPTION_NON_VOLATILE,
-            KEY_WRITE | KEY_WOW64_64KEY,
-            None,
-            &mut hkey,
-            None,
-        )
-        .ok()
-        .map_err(|e| {
-            PieuvreError::Registry(format!(
-                "Cannot create/open key: {} in hive {:?}: {}",
-                subkey, hive, e
-            ))
-        })?;
-
-        let value_wide: Vec<u16> = value_name
-            .encode_utf16()
-            .chain(std::iter::once(0))
-            .collect();
-        let data_bytes = value.to_le_bytes();
-
-        let result = RegSetValueExW(
-            hkey,
-            PCWSTR(value_wide.as_ptr()),
-            Some(0),
-            REG_DWORD,
-            Some(&data_bytes),
-        )
-        .ok();
-
-        let _ = RegCloseKey(hkey);
-
-        result.map_err(|e| {
-            PieuvreError::Registry(format!(
-                "Cannot set value: {} in {}: {}",
-                value_name, subkey, e
-            ))
-        })?;
-
-        Ok(())
-    }
+    write_dword_tracked(
+        &NativeRegistry,
+        &RegistryPath::new(hive, subkey),
+        value_name,
+        value,
+    )
 }
 
 /// Écrit une valeur STRING dans une ruche spécifique avec support 64-bit
 pub fn set_string_value_in_hive(
-    hive: HKEY,
+    hive: RegistryHive,
     subkey: &str,
     value_name: &str,
     value: &str,
 ) -> Result<()> {
-    unsafe {
-        let mut hkey = Default::default();
-        let subkey_wide: Vec<u16> = subkey.encode_utf16().chain(std::iter::once(0)).collect();
-
-        RegCreateKeyExW(
-            hive,
-            PCWSTR(subkey_wide.as_ptr()),
-            Some(0),
-            None,
-            REG_OPTION_NON_VOLATILE,
-            KEY_WRITE | KEY_WOW64_64KEY,
-            None,
-            &mut hkey,
-            None,
-        )
-        .ok()
-        .map_err(|e| {
-            PieuvreError::Registry(format!(
-                "Cannot create/open key: {} in hive {:?}: {}",
-                subkey, hive, e
-            ))
-        })?;
-
-        let value_wide: Vec<u16> = value_name
-            .encode_utf16()
-            .chain(std::iter::once(0))
-            .collect();
-        let data_wide: Vec<u16> = value.encode_utf16().chain(std::iter::once(0)).collect();
-
-        let result = RegSetValueExW(
-            hkey,
-            PCWSTR(value_wide.as_ptr()),
-            Some(0),
-            REG_SZ,
-            Some(std::slice::from_raw_parts(
-                data_wide.as_ptr() as *const u8,
-                data_wide.len() * 2,
-            )),
-        )
-        .ok();
-
-        let _ = RegCloseKey(hkey);
-
-        result.map_err(|e| {
-            PieuvreError::Registry(format!(
-                "Cannot set value: {} in {}: {}",
-                value_name, subkey, e
-            ))
-        })?;
-
-        Ok(())
-    }
+    write_string_tracked(
+        &NativeRegistry,
+        &RegistryPath::new(hive, subkey),
+        value_name,
+        value,
+    )
+}
+
+/// Supprime une valeur dans une ruche spécifique (absente : rien à faire)
+pub fn delete_value_in_hive(hive: RegistryHive, subkey: &str, value_name: &str) -> Result<()> {
+    delete_value_with(
+        &NativeRegistry,
+        &RegistryPath::new(hive, subkey),
+        value_name,
+    )
+}
+
+/// Supprime une valeur sur un backend donné ; la valeur d'origine est signalée
+pub fn delete_value_with(
+    backend: &dyn RegistryBackend,
+    path: &RegistryPath,
+    value_name: &str,
+) -> Result<()> {
+    track(backend, path, value_name, None)?;
+    backend.delete_value(path, value_name)
+}
+
+/// Supprime une clé et ses sous-clés dans une ruche spécifique (absente : rien à faire)
+pub fn delete_key_in_hive(hive: RegistryHive, subkey: &str) -> Result<()> {
+    delete_key_with(&NativeRegistry, &RegistryPath::new(hive, subkey))
+}
+
+pub fn key_exists_in_hive(hive: RegistryHive, subkey: &str) -> bool {
+    NativeRegistry.key_exists(&RegistryPath::new(hive, subkey))
+}
+
+/// Lit une valeur chaîne (REG_SZ ou REG_EXPAND_SZ) ; erreur si la valeur est absente
+pub fn read_string_value_in_hive(
+    hive: RegistryHive,
+    subkey: &str,
+    value_name: &str,
+) -> Result<String> {
+    NativeRegistry
+        .read_string(&RegistryPath::new(hive, subkey), value_name)?
+        .ok_or_else(|| {
+            PieuvreError::Registry(format!("Cannot read value {}: not found", value_name))
+        })
 }
 
 /// Applique une valeur DWORD à HKLM et à toutes les ruches utilisateurs chargées (HKU)
 pub fn set_value_multi_hive_dword(subkey: &str, value_name: &str, value: u32) -> Result<()> {
-    set_dword_value_in_hive(HKEY_LOCAL_MACHINE, subkey, value_name, value)?;
-    let users = list_subkeys_in_hive(HKEY_USERS)?;
+    set_value_multi_hive_dword_with(&NativeRegistry, subkey, value_name, value)
+}
+
+/// Variante de `set_value_multi_hive_dword` sur un backend donné
+pub fn set_value_multi_hive_dword_with(
+    backend: &dyn RegistryBackend,
+    subkey: &str,
+    value_name: &str,
+    value: u32,
+) -> Result<()> {
+    write_dword_tracked(backend, &RegistryPath::hklm(subkey), value_name, value)?;
+    let users = backend.list_subkeys(&RegistryPath::hku(""))?;
     for user_sid in users {
         if user_sid.starts_with("S-1-5-21") || user_sid == ".DEFAULT" {
-            let _ = set_dword_value_in_hive(
-                HKEY_USERS,
-                &format!("{}\\{}", user_sid, subkey),
+            let _ = write_dword_tracked(
+                backend,
+                &RegistryPath::hku(format!("{}\\{}", user_sid, subkey)),
                 value_name,
                 value,
             );
@@ -147,234 +215,73 @@ pub fn set_value_multi_hive_dword(subkey: &str, value_name: &str, value: u32) ->
 }
 
 /// Liste les sous-clés d'une ruche spécifique
-pub fn list_subkeys_in_hive(hive: HKEY) -> Result<Vec<String>> {
-    unsafe {
-        let mut hkey = Default::default();
-        RegOpenKeyExW(hive, None, Some(0), KEY_READ | KEY_WOW64_64KEY, &mut hkey)
-            .ok()
-            .map_err(|e| {
-                PieuvreError::Registry(format!("Cannot open hive for enumeration: {}", e))
-            })?;
-
-        let mut subkeys = Vec::new();
-        let mut index = 0;
-        let mut name_buffer = vec![0u16; 256];
-
-        loop {
-            let mut name_len = name_buffer.len() as u32;
-            let result = RegEnumKeyExW(
-                hkey,
-                index,
-                Some(PWSTR(name_buffer.as_mut_ptr())),
-                &mut name_len,
-                None,
-                None,
-                None,
-                None,
-            );
-
-            if result.is_err() {
-                break;
-            }
-
-            let name = String::from_utf16_lossy(&name_buffer[..name_len as usize]);
-            subkeys.push(name);
-            index += 1;
-        }
-
-        let _ = RegCloseKey(hkey);
-        Ok(subkeys)
-    }
+pub fn list_subkeys_in_hive(hive: RegistryHive) -> Result<Vec<String>> {
+    NativeRegistry.list_subkeys(&RegistryPath::new(hive, ""))
 }
 
 // --- Fonctions de compatibilité existantes ---
 
 pub fn set_dword_value(subkey: &str, value_name: &str, value: u32) -> Result<()> {
-    set_dword_value_in_hive(HKEY_LOCAL_MACHINE, subkey, value_name, value)
+    set_dword_value_with(&NativeRegistry, subkey, value_name, value)
+}
+
+pub fn set_dword_value_with(
+    backend: &dyn RegistryBackend,
+    subkey: &str,
+    value_name: &str,
+    value: u32,
+) -> Result<()> {
+    write_dword_tracked(backend, &RegistryPath::hklm(subkey), value_name, value)
 }
 
 pub fn set_string_value(subkey: &str, value_name: &str, value: &str) -> Result<()> {
-    set_string_value_in_hive(HKEY_LOCAL_MACHINE, subkey, value_name, value)
+    set_string_value_with(&NativeRegistry, subkey, value_name, value)
+}
+
+pub fn set_string_value_with(
+    backend: &dyn RegistryBackend,
+    subkey: &str,
+    value_name: &str,
+    value: &str,
+) -> Result<()> {
+    write_string_tracked(backend, &RegistryPath::hklm(subkey), value_name, value)
 }
 
 pub fn delete_key_recursive(subkey: &str) -> Result<()> {
-    unsafe {
-        let subkey_wide: Vec<u16> = subkey.encode_utf16().chain(std::iter::once(0)).collect();
-        let result = RegDeleteTreeW(HKEY_LOCAL_MACHINE, PCWSTR(subkey_wide.as_ptr()));
-        if result.is_err() && result.0 as u32 != 2 {
-            return Err(PieuvreError::Registry(format!(
-                "Failed to delete key: {}",
-                subkey
-            )));
-        }
-        Ok(())
-    }
+    delete_key_in_hive(RegistryHive::Hklm, subkey)
 }
 
 pub fn delete_value(subkey: &str, value_name: &str) -> Result<()> {
-    unsafe {
-        let mut hkey = Default::default();
-        let subkey_wide: Vec<u16> = subkey.encode_utf16().chain(std::iter::once(0)).collect();
-        if RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(subkey_wide.as_ptr()),
-            Some(0),
-            KEY_SET_VALUE | KEY_WOW64_64KEY,
-            &mut hkey,
-        )
-        .is_ok()
-        {
-            let value_wide: Vec<u16> = value_name
-                .encode_utf16()
-                .chain(std::iter::once(0))
-                .collect();
-            let _ = RegDeleteValueW(hkey, PCWSTR(value_wide.as_ptr()));
-            let _ = RegCloseKey(hkey);
-        }
-        Ok(())
-    }
+    delete_value_in_hive(RegistryHive::Hklm, subkey, value_name)
 }
 
 pub fn read_dword_value(subkey: &str, value_name: &str) -> Result<u32> {
-    unsafe {
-        let mut hkey = Default::default();
-        let subkey_wide: Vec<u16> = subkey.encode_utf16().chain(std::iter::once(0)).collect();
-        RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(subkey_wide.as_ptr()),
-            Some(0),
-            KEY_READ | KEY_WOW64_64KEY,
-            &mut hkey,
-        )
-        .ok()
-        .map_err(|e| PieuvreError::Registry(format!("Cannot open key {}: {}", subkey, e)))?;
-
-        let value_wide: Vec<u16> = value_name
-            .encode_utf16()
-            .chain(std::iter::once(0))
-            .collect();
-        let mut data = 0u32;
-        let mut data_size = 4u32;
-        let mut val_type = REG_DWORD;
-
-        let res = RegQueryValueExW(
-            hkey,
-            PCWSTR(value_wide.as_ptr()),
-            None,
-            Some(&mut val_type),
-            Some(&mut data as *mut u32 as *mut u8),
-            Some(&mut data_size),
-        );
-        let _ = RegCloseKey(hkey);
-
-        res.ok().map_err(|e| {
-            PieuvreError::Registry(format!("Cannot read value {}: {}", value_name, e))
-        })?;
-        Ok(data)
-    }
+    read_dword_value_with(&NativeRegistry, subkey, value_name)
+}
+
+/// Lit un DWORD HKLM ; erreur si la valeur est absente
+pub fn read_dword_value_with(
+    backend: &dyn RegistryBackend,
+    subkey: &str,
+    value_name: &str,
+) -> Result<u32> {
+    backend
+        .read_dword(&RegistryPath::hklm(subkey), value_name)?
+        .ok_or_else(|| {
+            PieuvreError::Registry(format!("Cannot read value {}: not found", value_name))
+        })
 }
 
 pub fn list_subkeys(subkey: &str) -> Result<Vec<String>> {
-    unsafe {
-        let mut hkey = Default::default();
-        let subkey_wide: Vec<u16> = subkey.encode_utf16().chain(std::iter::once(0)).collect();
-        RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(subkey_wide.as_ptr()),
-            Some(0),
-            KEY_READ | KEY_WOW64_64KEY,
-            &mut hkey,
-        )
-        .ok()
-        .map_err(|e| PieuvreError::Registry(format!("Cannot open key {}: {}", subkey, e)))?;
-
-        let mut subkeys = Vec::new();
-        let mut index = 0;
-        let mut name_buffer = vec![0u16; 256];
-        loop {
-            let mut name_len = name_buffer.len() as u32;
-            if RegEnumKeyExW(
-                hkey,
-                index,
-                Some(PWSTR(name_buffer.as_mut_ptr())),
-                &mut name_len,
-                None,
-                None,
-                None,
-                None,
-            )
-            .is_err()
-            {
-                break;
-            }
-            subkeys.push(String::from_utf16_lossy(&name_buffer[..name_len as usize]));
-            index += 1;
-        }
-        let _ = RegCloseKey(hkey);
-        Ok(subkeys)
-    }
+    NativeRegistry.list_subkeys(&RegistryPath::hklm(subkey))
 }
 
 pub fn read_string_value(subkey: &str, value_name: &str) -> Result<String> {
-    unsafe {
-        let mut hkey = Default::default();
-        let subkey_wide: Vec<u16> = subkey.encode_utf16().chain(std::iter::once(0)).collect();
-        RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(subkey_wide.as_ptr()),
-            Some(0),
-            KEY_READ | KEY_WOW64_64KEY,
-            &mut hkey,
-        )
-        .ok()
-        .map_err(|e| PieuvreError::Registry(format!("Cannot open key {}: {}", subkey, e)))?;
-
-        let value_wide: Vec<u16> = value_name
-            .encode_utf16()
-            .chain(std::iter::once(0))
-            .collect();
-        let mut buffer = vec![0u8; 1024];
-        let mut data_size = buffer.len() as u32;
-        let mut val_type = REG_SZ;
-
-        let res = RegQueryValueExW(
-            hkey,
-            PCWSTR(value_wide.as_ptr()),
-            None,
-            Some(&mut val_type),
-            Some(buffer.as_mut_ptr()),
-            Some(&mut data_size),
-        );
-        let _ = RegCloseKey(hkey);
-
-        res.ok().map_err(|e| {
-            PieuvreError::Registry(format!("Cannot read value {}: {}", value_name, e))
-        })?;
-        let chars = data_size as usize / 2;
-        Ok(String::from_utf16_lossy(std::slice::from_raw_parts(
-            buffer.as_ptr() as *const u16,
-            chars.saturating_sub(1),
-        )))
-    }
+    read_string_value_in_hive(RegistryHive::Hklm, subkey, value_name)
 }
 
 pub fn key_exists(subkey: &str) -> bool {
-    unsafe {
-        let mut hkey = Default::default();
-        let subkey_wide: Vec<u16> = subkey.encode_utf16().chain(std::iter::once(0)).collect();
-        let exists = RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(subkey_wide.as_ptr()),
-            Some(0),
-            KEY_READ | KEY_WOW64_64KEY,
-            &mut hkey,
-        )
-        .is_ok();
-        if exists {
-            let _ = RegCloseKey(hkey);
-        }
-        exists
-    }
+    NativeRegistry.key_exists(&RegistryPath::hklm(subkey))
 }
 
 pub fn configure_mmcss_gaming() -> Result<()> {
@@ -1,145 +1,293 @@
 //! Rollback Manager
 //!
 //! Logique de retour arrière automatique basée sur les ChangeRecords.
+//! `restore_change` est le point unique de restauration, utilisé aussi par les snapshots.
 
-use pieuvre_common::{ChangeRecord, Result};
-use tracing::{info, instrument};
+use pieuvre_common::{
+    ChangeRecord, DaclTarget, NativeRegistry, NativeServiceManager, PieuvreError,
+    PowerSettingRecord, RegistryBackend, RegistryHive, RegistryPath, Result, ServiceManager,
+    SharedRegistry, SharedServiceManager,
+};
+use std::sync::Arc;
+use tracing::{info, instrument, warn};
+
+/// Valeur de registre portant l'état MSI d'un device
+const MSI_VALUE: &str = "MSISupported";
 
 /// Gère le rollback d'une liste de changements
 #[instrument(skip(changes))]
 pub async fn rollback_changes(changes: Vec<ChangeRecord>) -> Result<()> {
+    rollback_each(changes, Arc::new(restore_change)).await
+}
+
+/// Rollback with registry values and services restored through the given backends
+#[instrument(skip(changes, registry, services))]
+pub async fn rollback_changes_with(
+    changes: Vec<ChangeRecord>,
+    registry: SharedRegistry,
+    services: SharedServiceManager,
+) -> Result<()> {
+    rollback_each(
+        changes,
+        Arc::new(move |record: &ChangeRecord| {
+            restore_change_with(record, registry.as_ref(), services.as_ref())
+        }),
+    )
+    .await
+}
+
+type Restorer = Arc<dyn Fn(&ChangeRecord) -> Result<()> + Send + Sync>;
+
+/// Restaure en ordre inverse (LIFO) ; les échecs n'interrompent pas le rollback
+async fn rollback_each(changes: Vec<ChangeRecord>, restore: Restorer) -> Result<()> {
     info!(
         "Lancement du rollback automatique ({} changements)...",
         changes.len()
     );
 
-    // Inverser l'ordre des changements pour le rollback (LIFO)
-    let mut changes = changes;
-    changes.reverse();
-
-    for record in changes {
-        match record {
-            ChangeRecord::Registry {
-                key,
-                value_name,
-                hive,
-                original_value,
-            } => {
-                rollback_registry_full(hive, &key, &value_name, original_value).await?;
-            }
-            ChangeRecord::Service {
-                name,
-                original_start_type,
-            } => {
-                rollback_service(&name, original_start_type).await?;
-            }
-            ChangeRecord::FirewallRule { name } => {
-                rollback_firewall(&name).await?;
-            }
-            ChangeRecord::AppX { package_full_name } => {
-                rollback_appx(&package_full_name).await?;
-            }
+    let mut failed = 0;
+    for record in changes.into_iter().rev() {
+        let restore = restore.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let result = restore(&record);
+            (record, result)
+        })
+        .await
+        .map_err(|e| PieuvreError::Internal(e.to_string()))?;
+
+        if let (record, Err(e)) = result {
+            warn!(change = ?record, error = %e, "Restauration impossible");
+            failed += 1;
         }
     }
 
+    if failed > 0 {
+        return Err(PieuvreError::Internal(format!(
+            "Rollback incomplet : {} changement(s) non restauré(s)",
+            failed
+        )));
+    }
     info!("Rollback termine avec succes.");
     Ok(())
 }
 
-async fn rollback_registry_full(
-    hive: pieuvre_common::RegistryHive,
-    key: &str,
-    value: &str,
-    original_value: Option<pieuvre_common::RegistryValue>,
+/// Restaure un changement sur le système (appel bloquant)
+pub fn restore_change(record: &ChangeRecord) -> Result<()> {
+    match restore_change_with(record, &NativeRegistry, &NativeServiceManager) {
+        // Clé verrouillée par le hardening : déverrouiller puis réessayer
+        Err(_) if is_hklm_value(record) => {
+            if let ChangeRecord::Registry { key, .. } = record {
+                crate::hardening::unlock_registry_key(key)?;
+            }
+            restore_change_with(record, &NativeRegistry, &NativeServiceManager)
+        }
+        result => result,
+    }
+}
+
+fn is_hklm_value(record: &ChangeRecord) -> bool {
+    matches!(
+        record,
+        ChangeRecord::Registry {
+            hive: RegistryHive::Hklm,
+            ..
+        }
+    )
+}
+
+/// Restaure un changement ; registre et services passent par les backends donnés
+pub fn restore_change_with(
+    record: &ChangeRecord,
+    registry: &dyn RegistryBackend,
+    services: &dyn ServiceManager,
 ) -> Result<()> {
-    info!(key, value, "Restauration registre...");
-
-    // Déverrouillage
-    let _ = tokio::task::spawn_blocking({
-        let key = key.to_string();
-        move || crate::hardening::unlock_registry_key(&key)
-    })
-    .await;
-
-    let key_clone = key.to_string();
-    let value_clone = value.to_string();
-
-    tokio::task::spawn_blocking(move || {
-        let hive_handle = match hive {
-            pieuvre_common::RegistryHive::Hklm => {
-                windows::Win32::System::Registry::HKEY_LOCAL_MACHINE
+    match record {
+        ChangeRecord::Registry {
+            hive,
+            key,
+            value_name,
+            original_value,
+        } => {
+            info!(key, value_name, "Restauration registre...");
+            let path = RegistryPath::new(*hive, key);
+            match original_value {
+                Some(value) => registry.write_value(&path, value_name, value),
+                // Valeur absente à l'origine : on la supprime
+                None => registry.delete_value(&path, value_name),
+            }
+        }
+        ChangeRecord::RegistryKeyCreated { hive, key } => {
+            info!(key, "Suppression de la clé créée...");
+            registry.delete_key(&RegistryPath::new(*hive, key))
+        }
+        ChangeRecord::RegistryKeyDeleted { hive, key, tree } => {
+            info!(key, "Restauration de la clé supprimée...");
+            registry.import_tree(&RegistryPath::new(*hive, key), tree)
+        }
+        ChangeRecord::Service {
+            name,
+            original_start_type,
+        } => {
+            info!(name, original_start_type, "Restauration service...");
+            services.set_start_type(name, *original_start_type)
+        }
+        ChangeRecord::FirewallRule { name } => {
+            info!(name, "Suppression regle firewall (Native COM)...");
+            crate::firewall::remove_pieuvre_rules().map(|_| ())
+        }
+        ChangeRecord::AppX { package_full_name } => {
+            info!(package_full_name, "Restauration AppX...");
+            crate::appx::reinstall_package(package_full_name)
+        }
+        ChangeRecord::PowerScheme {
+            original_scheme,
+            settings,
+        } => restore_power_scheme(original_scheme.as_deref(), settings),
+        ChangeRecord::MsiDevice {
+            device_path,
+            original_msi_supported,
+        } => {
+            info!(device_path, "Restauration MSI...");
+            let path = RegistryPath::hklm(device_path);
+            match original_msi_supported {
+                Some(value) => registry.write_dword(&path, MSI_VALUE, *value),
+                None => registry.delete_value(&path, MSI_VALUE),
             }
-            pieuvre_common::RegistryHive::Hku => windows::Win32::System::Registry::HKEY_USERS,
-            pieuvre_common::RegistryHive::Hkcu => {
-                windows::Win32::System::Registry::HKEY_CURRENT_USER
+        }
+        ChangeRecord::ScheduledTask {
+            path,
+            original_enabled,
+        } => {
+            info!(path, original_enabled, "Restauration tache planifiee...");
+            if *original_enabled {
+                crate::scheduled_tasks::enable_task(path)
+            } else {
+                crate::scheduled_tasks::disable_task(path)
             }
-        };
-
-        if let Some(val) = original_value {
-            match val {
-                pieuvre_common::RegistryValue::Dword(d) => {
-                    crate::registry::set_dword_value_in_hive(
-                        hive_handle,
-                        &key_clone,
-                        &value_clone,
-                        d,
-                    )
-                }
-                pieuvre_common::RegistryValue::String(s) => {
-                    crate::registry::set_string_value_in_hive(
-                        hive_handle,
-                        &key_clone,
-                        &value_clone,
-                        &s,
-                    )
-                }
-                _ => Ok(()), // Binary non supporté pour l'instant
+        }
+        ChangeRecord::HostsBlock { domains } => {
+            info!(
+                count = domains.len(),
+                "Retrait des domaines du fichier hosts..."
+            );
+            crate::hosts::unblock_domains(domains)
+        }
+        ChangeRecord::Bcd {
+            element,
+            original_value,
+        } => {
+            info!(element, "Restauration BCD...");
+            match original_value {
+                Some(value) => crate::bcd::set_element(element, value),
+                None => crate::bcd::delete_element(element),
             }
-        } else {
-            // Si pas de valeur originale, on supprime la valeur (état initial = inexistant)
-            crate::registry::delete_value(&key_clone, &value_clone)
         }
-    })
-    .await
-    .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))??;
-
-    Ok(())
+        ChangeRecord::Dacl {
+            target,
+            original_sddl,
+        } => match target {
+            DaclTarget::RegistryKey { key } => {
+                info!(key, "Restauration DACL registre...");
+                crate::hardening::set_registry_key_sddl(key, original_sddl)
+            }
+            DaclTarget::Service { name } => {
+                info!(name, "Restauration DACL service...");
+                services.set_security_descriptor(name, original_sddl)
+            }
+        },
+    }
 }
 
-async fn rollback_service(name: &str, start_type: u32) -> Result<()> {
-    info!(name, start_type, "Restauration service...");
-
-    tokio::task::spawn_blocking({
-        let name = name.to_string();
-        move || crate::services::set_service_start_type(&name, start_type)
-    })
-    .await
-    .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))??;
-
-    Ok(())
+/// Checks that a change can be restored, without touching the system
+/// (`rollback --check`)
+pub fn validate_change(record: &ChangeRecord) -> Result<()> {
+    let problem = match record {
+        ChangeRecord::Registry { key, .. }
+        | ChangeRecord::RegistryKeyCreated { key, .. }
+        | ChangeRecord::RegistryKeyDeleted { key, .. } => non_empty("registry key", key),
+        ChangeRecord::Service {
+            name,
+            original_start_type,
+        } => non_empty("service name", name).or_else(|| {
+            // 0 (Boot) .. 4 (Disabled)
+            (*original_start_type > 4)
+                .then(|| format!("invalid start type {} for {}", original_start_type, name))
+        }),
+        ChangeRecord::FirewallRule { name } => non_empty("firewall rule", name),
+        ChangeRecord::AppX { package_full_name } => non_empty("AppX package", package_full_name),
+        ChangeRecord::PowerScheme {
+            original_scheme,
+            settings,
+        } => original_scheme
+            .iter()
+            .map(String::as_str)
+            .chain(
+                settings
+                    .iter()
+                    .flat_map(|s| [s.scheme.as_str(), s.subgroup.as_str(), s.setting.as_str()]),
+            )
+            .find(|guid| !is_guid(guid))
+            .map(|guid| format!("invalid power GUID '{}'", guid)),
+        ChangeRecord::MsiDevice { device_path, .. } => non_empty("device path", device_path),
+        ChangeRecord::ScheduledTask { path, .. } => {
+            (!path.starts_with('\\')).then(|| format!("invalid task path '{}'", path))
+        }
+        ChangeRecord::HostsBlock { domains } => domains
+            .iter()
+            .find(|d| d.is_empty() || d.contains(char::is_whitespace))
+            .map(|d| format!("invalid hosts domain '{}'", d)),
+        ChangeRecord::Bcd { element, .. } => non_empty("BCD element", element),
+        ChangeRecord::Dacl {
+            target,
+            original_sddl,
+        } => match target {
+            DaclTarget::RegistryKey { key } => non_empty("registry key", key),
+            DaclTarget::Service { name } => non_empty("service name", name),
+        }
+        .or_else(|| {
+            (!original_sddl.contains(':')).then(|| format!("invalid SDDL '{}'", original_sddl))
+        }),
+    };
+    match problem {
+        Some(problem) => Err(PieuvreError::Parse(problem)),
+        None => Ok(()),
+    }
 }
 
-async fn rollback_firewall(name: &str) -> Result<()> {
-    info!(name, "Suppression regle firewall (Native COM)...");
-
-    tokio::task::spawn_blocking({
-        let _name = name.to_string();
-        move || {
-            // Utiliser l'API COM native au lieu de netsh
-            crate::firewall::remove_pieuvre_rules().map(|_| ())
-        }
-    })
-    .await
-    .map_err(|e| pieuvre_common::PieuvreError::Internal(e.to_string()))??;
+fn non_empty(what: &str, value: &str) -> Option<String> {
+    value.trim().is_empty().then(|| format!("empty {}", what))
+}
 
-    Ok(())
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`
+fn is_guid(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    parts.len() == 5
+        && parts
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(part, len)| part.len() == len && part.chars().all(|c| c.is_ascii_hexdigit()))
 }
 
-async fn rollback_appx(package_full_name: &str) -> Result<()> {
+/// Index d'origine d'abord, puis réactivation du plan d'origine (ou du plan courant)
+fn restore_power_scheme(
+    original_scheme: Option<&str>,
+    settings: &[PowerSettingRecord],
+) -> Result<()> {
     info!(
-        package_full_name,
-        "Restauration AppX (Note: Reinstallation non supportee)..."
+        settings = settings.len(),
+        "Restauration plan d'alimentation..."
     );
-    // TODO: Implémenter la réinstallation via Add-AppxPackage si nécessaire
-    Ok(())
+    for setting in settings {
+        crate::power::write_setting(
+            &setting.scheme,
+            &setting.subgroup,
+            &setting.setting,
+            setting.original_ac,
+            setting.original_dc,
+        )?;
+    }
+    match original_scheme {
+        Some(scheme) => crate::power::set_active_scheme(scheme),
+        None => crate::power::set_active_scheme(&crate::power::get_active_scheme_guid()?),
+    }
 }
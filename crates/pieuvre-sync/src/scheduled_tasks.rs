@@ -2,7 +2,7 @@
 //!
 //! Disable Windows telemetry and diagnostic scheduled tasks.
 
-use pieuvre_common::Result;
+use pieuvre_common::{ChangeRecord, Result};
 use std::process::Command;
 
 /// Telemetry scheduled tasks to disable
@@ -69,6 +69,7 @@ pub fn disable_telemetry_tasks() -> Result<Vec<String>> {
 
 /// Disable a single scheduled task
 pub fn disable_task(task_path: &str) -> Result<()> {
+    track(task_path, false)?;
     let _ = Command::new("schtasks")
         .args(["/Change", "/TN", task_path, "/Disable"])
         .output()?;
@@ -79,6 +80,7 @@ pub fn disable_task(task_path: &str) -> Result<()> {
 
 /// Enable a single scheduled task (for rollback)
 pub fn enable_task(task_path: &str) -> Result<()> {
+    track(task_path, true)?;
     let _ = Command::new("schtasks")
         .args(["/Change", "/TN", task_path, "/Enable"])
         .output()?;
@@ -88,16 +90,36 @@ pub fn enable_task(task_path: &str) -> Result<()> {
 
 /// Check if a task is currently enabled
 pub fn is_task_enabled(task_path: &str) -> bool {
+    task_state(task_path).unwrap_or(false)
+}
+
+/// Enabled state of a task, `None` if the task does not exist
+pub fn task_state(task_path: &str) -> Option<bool> {
     let output = Command::new("schtasks")
         .args(["/Query", "/TN", task_path, "/FO", "CSV", "/NH"])
-        .output();
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(!stdout.contains("Disabled"))
+}
 
-    match output {
-        Ok(o) => {
-            let stdout = String::from_utf8_lossy(&o.stdout);
-            !stdout.contains("Disabled")
+/// Records the original state when an existing task is about to change
+fn track(task_path: &str, target_enabled: bool) -> Result<()> {
+    if !crate::changes::is_recording() {
+        return Ok(());
+    }
+    match task_state(task_path) {
+        Some(enabled) if enabled != target_enabled => {
+            crate::changes::push(ChangeRecord::ScheduledTask {
+                path: task_path.to_string(),
+                original_enabled: enabled,
+            })
         }
-        Err(_) => false,
+        _ => Ok(()),
     }
 }
 
@@ -9,6 +9,12 @@ use crate::hardening::*;
 use crate::registry::set_dword_value;
 use pieuvre_common::Result;
 
+/// UAC « Never Notify » (HKLM key, value, data)
+pub const UAC_VALUES: &[(&str, &str, u32)] = &[
+    (UAC_POLICIES_KEY, "ConsentPromptBehaviorAdmin", 0),
+    (UAC_POLICIES_KEY, "PromptOnSecureDesktop", 0),
+];
+
 // ============================================
 // MEMORY INTEGRITY (HVCI)
 // ============================================
@@ -3,8 +3,10 @@
 //! Active monitoring of critical registry keys and services.
 //! Instant restoration upon drift detection.
 
-use crate::hardening::CRITICAL_KEYS;
-use pieuvre_common::Result;
+use crate::hardening::{CRITICAL_KEYS, CRITICAL_SERVICES};
+use pieuvre_common::{
+    native_service_manager, PieuvreError, Result, ServiceManager, SharedServiceManager,
+};
 use windows::core::PCWSTR;
 use windows::Win32::Foundation::WAIT_OBJECT_0;
 use windows::Win32::System::Registry::{
@@ -18,10 +20,15 @@ pub struct Sentinel;
 impl Sentinel {
     /// Starts background monitoring (Event-Driven)
     pub fn start_monitoring() -> Result<()> {
+        Self::start_monitoring_with(native_service_manager())
+    }
+
+    /// Same as `start_monitoring`, services handled through `services`
+    pub fn start_monitoring_with(services: SharedServiceManager) -> Result<()> {
         tracing::info!("System Monitor started - Event-Driven mode");
 
         // Perform initial restoration to start from a clean state
-        let _ = Self::check_and_restore();
+        let _ = Self::check_and_restore(services.as_ref());
 
         // Monitor registry keys via native notifications (filtered by existence)
         for key_path in CRITICAL_KEYS {
@@ -42,10 +49,11 @@ impl Sentinel {
         }
 
         // Monitor services via dedicated threads
-        for service_name in crate::hardening::CRITICAL_SERVICES {
+        for service_name in CRITICAL_SERVICES {
             let service_name = service_name.to_string();
+            let services = services.clone();
             std::thread::spawn(move || {
-                if let Err(e) = Self::monitor_service(&service_name) {
+                if let Err(e) = Self::monitor_service(services.as_ref(), &service_name) {
                     tracing::error!(
                         "Sentinel Service Monitor error for {}: {:?}",
                         service_name,
@@ -130,35 +138,76 @@ impl Sentinel {
         }
     }
 
-    fn monitor_service(service_name: &str) -> Result<()> {
+    fn monitor_service(services: &dyn ServiceManager, service_name: &str) -> Result<()> {
         // Note: NotifyServiceStatusChange is complex to use in a simple thread loop
         // We use a smart polling approach with exponential backoff or event-driven if possible
         // For v0.4.0, we implement a robust polling with 5s interval for services
         loop {
-            if let Err(e) = crate::hardening::lock_service(service_name) {
-                match e {
-                    pieuvre_common::PieuvreError::ServiceNotFound(_) => {
-                        // Si le service n'existe pas, on arrête le monitoring pour ce service
-                        tracing::debug!(
-                            "Sentinel: Service {} not found, stopping monitor for it.",
-                            service_name
-                        );
-                        return Ok(());
-                    }
-                    _ => {
-                        tracing::error!(
-                            "Sentinel failed to restore service {}: {:?}",
-                            service_name,
-                            e
-                        );
-                    }
+            match Self::repair_service(services, service_name) {
+                Err(PieuvreError::ServiceNotFound(_)) => {
+                    // Si le service n'existe pas, on arrête le monitoring pour ce service
+                    tracing::debug!(
+                        "Sentinel: Service {} not found, stopping monitor for it.",
+                        service_name
+                    );
+                    return Ok(());
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Sentinel failed to restore service {}: {:?}",
+                        service_name,
+                        e
+                    );
                 }
+                Ok(_) => {}
             }
             std::thread::sleep(std::time::Duration::from_secs(5));
         }
     }
 
-    fn check_and_restore() -> Result<()> {
+    /// Re-locks `service_name` if its DACL drifted. Returns true if a repair was needed.
+    pub fn repair_service(services: &dyn ServiceManager, service_name: &str) -> Result<bool> {
+        match crate::hardening::is_service_locked_with(services, service_name) {
+            Ok(true) => Ok(false),
+            Err(e @ PieuvreError::ServiceNotFound(_)) => Err(e),
+            // DACL drifted or unreadable: re-apply the lock
+            _ => {
+                tracing::warn!(
+                    "System Monitor: Drift detected on service {}, restoring lock...",
+                    service_name
+                );
+                crate::hardening::lock_service_with(services, service_name)?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Repairs every critical service once, returns the services that drifted
+    pub fn repair_services(services: &dyn ServiceManager) -> Vec<String> {
+        let mut repaired = Vec::new();
+        for service in CRITICAL_SERVICES {
+            match Self::repair_service(services, service) {
+                Ok(true) => repaired.push(service.to_string()),
+                Ok(false) => {}
+                Err(PieuvreError::ServiceNotFound(_)) => {
+                    tracing::debug!(
+                        "Initial restoration: Service {} not found, skipping.",
+                        service
+                    );
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Initial restoration failed for service {}: {:?}",
+                        service,
+                        e
+                    );
+                }
+            }
+        }
+        repaired
+    }
+
+    fn check_and_restore(services: &dyn ServiceManager) -> Result<()> {
         for key in CRITICAL_KEYS {
             // Skip non-existent keys (already handled by lock_registry_key, but avoid log spam)
             if !crate::hardening::key_exists(key) {
@@ -172,25 +221,7 @@ impl Sentinel {
             let _ = crate::hardening::lock_registry_key(key);
         }
 
-        for service in crate::hardening::CRITICAL_SERVICES {
-            if let Err(e) = crate::hardening::lock_service(service) {
-                match e {
-                    pieuvre_common::PieuvreError::ServiceNotFound(_) => {
-                        tracing::debug!(
-                            "Initial restoration: Service {} not found, skipping.",
-                            service
-                        );
-                    }
-                    _ => {
-                        tracing::error!(
-                            "Initial restoration failed for service {}: {:?}",
-                            service,
-                            e
-                        );
-                    }
-                }
-            }
-        }
+        Self::repair_services(services);
 
         Ok(())
     }
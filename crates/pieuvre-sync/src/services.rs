@@ -1,16 +1,9 @@
 //! Gestion des services Windows
+//!
+//! Thin wrappers over the live SCM; the `_with` variants accept any
+//! `ServiceManager` (in-memory fixtures for tests).
 
-use pieuvre_common::{PieuvreError, Result};
-use windows::core::PCWSTR;
-use windows::Win32::System::Services::{
-    ChangeServiceConfigW, CloseServiceHandle, OpenSCManagerW, OpenServiceW, QueryServiceConfigW,
-    ENUM_SERVICE_TYPE, QUERY_SERVICE_CONFIGW, SC_MANAGER_ALL_ACCESS, SERVICE_CHANGE_CONFIG,
-    SERVICE_ERROR, SERVICE_QUERY_CONFIG, SERVICE_START_TYPE,
-};
-
-/// Constantes pour ChangeServiceConfigW
-const SERVICE_NO_CHANGE_TYPE: ENUM_SERVICE_TYPE = ENUM_SERVICE_TYPE(0xFFFFFFFF);
-const SERVICE_NO_CHANGE_ERROR: SERVICE_ERROR = SERVICE_ERROR(0xFFFFFFFF);
+use pieuvre_common::{ChangeRecord, NativeServiceManager, Result, ServiceManager};
 
 /// Désactive un service
 pub fn disable_service(name: &str) -> Result<()> {
@@ -29,86 +22,32 @@ pub fn set_service_automatic(name: &str) -> Result<()> {
 
 /// Récupère le start type actuel d'un service (pour snapshot)
 pub fn get_service_start_type(name: &str) -> Result<u32> {
-    unsafe {
-        let scm = OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_ALL_ACCESS)
-            .map_err(|e| PieuvreError::Permission(e.to_string()))?;
-
-        let name_wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
-
-        let service = match OpenServiceW(scm, PCWSTR(name_wide.as_ptr()), SERVICE_QUERY_CONFIG) {
-            Ok(s) => s,
-            Err(_) => {
-                let _ = CloseServiceHandle(scm);
-                return Err(PieuvreError::ServiceNotFound(name.to_string()));
-            }
-        };
-
-        // Premier appel pour obtenir la taille
-        let mut bytes_needed = 0u32;
-        let _ = QueryServiceConfigW(service, None, 0, &mut bytes_needed);
-
-        if bytes_needed == 0 {
-            let _ = CloseServiceHandle(service);
-            let _ = CloseServiceHandle(scm);
-            return Err(PieuvreError::ServiceNotFound(name.to_string()));
-        }
-
-        let mut buffer = vec![0u8; bytes_needed as usize];
-        let config_ptr = buffer.as_mut_ptr() as *mut QUERY_SERVICE_CONFIGW;
-
-        let result =
-            QueryServiceConfigW(service, Some(config_ptr), bytes_needed, &mut bytes_needed);
-
-        let start_type = if result.is_ok() {
-            (*config_ptr).dwStartType.0
-        } else {
-            3 // Default to Manual if error
-        };
-
-        let _ = CloseServiceHandle(service);
-        let _ = CloseServiceHandle(scm);
+    get_service_start_type_with(&NativeServiceManager, name)
+}
 
-        Ok(start_type)
-    }
+pub fn get_service_start_type_with(services: &dyn ServiceManager, name: &str) -> Result<u32> {
+    services.start_type(name)
 }
 
 /// Définit le type de démarrage d'un service
 pub fn set_service_start_type(name: &str, start_type: u32) -> Result<()> {
-    unsafe {
-        let start_type_enum = SERVICE_START_TYPE(start_type);
-        let scm = OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_ALL_ACCESS)
-            .map_err(|e| PieuvreError::Permission(e.to_string()))?;
-
-        let name_wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
-
-        let service = match OpenServiceW(scm, PCWSTR(name_wide.as_ptr()), SERVICE_CHANGE_CONFIG) {
-            Ok(s) => s,
-            Err(_) => {
-                let _ = CloseServiceHandle(scm);
-                return Err(PieuvreError::ServiceNotFound(name.to_string()));
-            }
-        };
-
-        let result = ChangeServiceConfigW(
-            service,
-            SERVICE_NO_CHANGE_TYPE,
-            start_type_enum,
-            SERVICE_NO_CHANGE_ERROR,
-            PCWSTR::null(),
-            PCWSTR::null(),
-            None,
-            PCWSTR::null(),
-            PCWSTR::null(),
-            PCWSTR::null(),
-            PCWSTR::null(),
-        );
-
-        let _ = CloseServiceHandle(service);
-        let _ = CloseServiceHandle(scm);
-
-        result.map_err(|e| PieuvreError::Registry(e.to_string()))?;
+    set_service_start_type_with(&NativeServiceManager, name, start_type)
+}
 
-        tracing::info!("Service {} start_type -> {}", name, start_type);
-        Ok(())
+pub fn set_service_start_type_with(
+    services: &dyn ServiceManager,
+    name: &str,
+    start_type: u32,
+) -> Result<()> {
+    if crate::changes::is_recording() {
+        let original = services.start_type(name)?;
+        if original == start_type {
+            return Ok(());
+        }
+        crate::changes::push(ChangeRecord::Service {
+            name: name.to_string(),
+            original_start_type: original,
+        })?;
     }
+    services.set_start_type(name, start_type)
 }
@@ -192,6 +192,21 @@ fn test_is_nagle_disabled_returns_bool() {
     let _result = crate::network::is_nagle_disabled();
 }
 
+#[test]
+fn test_parse_domain_list_skips_comments() {
+    let domains = crate::hosts::parse_domain_list(
+        "# Telemetry\n\nvortex.data.microsoft.com\n  watson.microsoft.com # WER\n",
+    );
+    assert_eq!(
+        domains,
+        vec!["vortex.data.microsoft.com", "watson.microsoft.com"]
+    );
+
+    let shipped =
+        crate::hosts::parse_domain_list(include_str!("../../../config/telemetry-domains.txt"));
+    assert!(shipped.iter().all(|d| !d.contains(char::is_whitespace)));
+}
+
 // ============================================================================
 // TESTS REGISTRY (Read-only via audit crate)
 // ============================================================================
@@ -224,7 +239,96 @@ fn test_registry_key_paths_valid() {
 // TESTS PROFILE APPLICATION (Structure validation)
 // ============================================================================
 
-// test_profile_names_valid and apply_profile tests removed in v0.5.0
+const TEST_PROFILES: &str = r#"
+[[profile]]
+name = "base"
+tweaks = ["diagtrack", "telemetry_level", "svc_search"]
+params = { power_plan = "balanced" }
+
+[[profile]]
+name = "gaming"
+extends = "base"
+tweaks = ["timer", "diagtrack"]
+exclude = ["svc_search"]
+params = { timer_resolution = 5000, power_plan = "ultimate_performance" }
+
+[[profile]]
+name = "lan-party"
+extends = "gaming"
+params = { timer_resolution = 10000 }
+"#;
+
+#[test]
+fn test_profile_inheritance_and_overrides() {
+    use crate::power::PowerPlan;
+    use crate::profile::ProfileSet;
+
+    let set = ProfileSet::from_toml(TEST_PROFILES).unwrap();
+
+    let gaming = set.resolve("gaming").unwrap();
+    assert_eq!(gaming.chain, ["base", "gaming"]);
+    assert_eq!(gaming.tweaks, ["diagtrack", "telemetry_level", "timer"]);
+    assert_eq!(
+        gaming.params.power_plan,
+        Some(PowerPlan::UltimatePerformance)
+    );
+
+    // Paramètre surchargé, le reste est hérité
+    let lan = set.resolve("LAN-Party").unwrap();
+    assert_eq!(lan.chain, ["base", "gaming", "lan-party"]);
+    assert_eq!(lan.tweaks, gaming.tweaks);
+    assert_eq!(lan.params.timer_resolution, Some(10000));
+    assert_eq!(lan.params.power_plan, Some(PowerPlan::UltimatePerformance));
+
+    let diff = set.resolve("base").unwrap().diff(&gaming);
+    assert_eq!(diff.added, ["timer"]);
+    assert_eq!(diff.removed, ["svc_search"]);
+    assert_eq!(diff.params.len(), 2);
+    assert!(lan.diff(&lan).is_empty());
+}
+
+#[test]
+fn test_profile_errors_and_user_override() {
+    use crate::profile::ProfileSet;
+    use pieuvre_common::PieuvreError;
+
+    let mut set = ProfileSet::from_toml(TEST_PROFILES).unwrap();
+    assert!(set.resolve("unknown").is_err());
+
+    let err = ProfileSet::from_toml(
+        r#"
+[[profile]]
+name = "Bad Name"
+params = { timer_resolution = 1 }
+"#,
+    )
+    .unwrap_err();
+    let PieuvreError::Config(msg) = err else {
+        panic!("expected a config error");
+    };
+    assert!(msg.contains("name must be"), "{}", msg);
+    assert!(msg.contains("timer_resolution 1"), "{}", msg);
+
+    // Profil utilisateur : remplace "base" et introduit un cycle
+    set.merge(
+        ProfileSet::from_toml(
+            r#"
+[[profile]]
+name = "base"
+extends = "lan-party"
+"#,
+        )
+        .unwrap(),
+    );
+    let err = set.resolve("gaming").unwrap_err().to_string();
+    assert!(err.contains("cycle"), "{}", err);
+
+    set.merge(
+        ProfileSet::from_toml("[[profile]]\nname = \"base\"\nextends = \"missing\"\n").unwrap(),
+    );
+    let err = set.resolve("gaming").unwrap_err().to_string();
+    assert!(err.contains("unknown profile 'missing'"), "{}", err);
+}
 
 // ============================================================================
 // TESTS CONSTANTS VALIDATION
@@ -283,3 +387,1184 @@ fn test_unicode_service_name() {
         "Unicode service name should fail gracefully"
     );
 }
+
+// ============================================================================
+// TESTS REGISTRY BACKEND (en mémoire, aucun accès au registre réel)
+// ============================================================================
+
+#[test]
+fn test_memory_registry_case_insensitive() {
+    use pieuvre_common::{MemoryRegistry, RegistryBackend, RegistryPath};
+
+    let reg = MemoryRegistry::new();
+    let path = RegistryPath::hklm(r"SOFTWARE\Pieuvre\Test");
+    reg.write_dword(&path, "Value", 42).unwrap();
+
+    let upper = RegistryPath::hklm(r"software\PIEUVRE\test");
+    assert_eq!(reg.read_dword(&upper, "VALUE").unwrap(), Some(42));
+    assert_eq!(
+        reg.list_subkeys(&RegistryPath::hklm(r"SOFTWARE\Pieuvre"))
+            .unwrap(),
+        vec!["Test".to_string()]
+    );
+
+    reg.delete_key(&RegistryPath::hklm(r"SOFTWARE\Pieuvre"))
+        .unwrap();
+    assert!(!reg.key_exists(&path));
+    assert!(matches!(reg.read_value(&path, "Value"), Ok(None)));
+    assert!(reg
+        .read_string(&RegistryPath::hklm("X"), "Missing")
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_registry_dword_operation_records_original() {
+    use crate::operation::{RegistryDwordOperation, SyncOperation};
+    use pieuvre_common::{
+        ChangeRecord, MemoryRegistry, RegistryBackend, RegistryPath, RegistryValue,
+    };
+    use std::sync::Arc;
+
+    let key = r"SOFTWARE\Policies\Microsoft\Windows\DataCollection";
+    let reg = Arc::new(MemoryRegistry::new().with_value(
+        RegistryPath::hklm(key),
+        "AllowTelemetry",
+        RegistryValue::Dword(3),
+    ));
+    let op = RegistryDwordOperation::new(key, "AllowTelemetry", 0).with_backend(reg.clone());
+
+    assert!(!op.is_applied().await.unwrap());
+    let changes = op.apply().await.unwrap();
+    assert!(op.is_applied().await.unwrap());
+    assert_eq!(
+        reg.read_dword(&RegistryPath::hklm(key), "AllowTelemetry")
+            .unwrap(),
+        Some(0)
+    );
+
+    match &changes[..] {
+        [ChangeRecord::Registry {
+            original_value: Some(RegistryValue::Dword(3)),
+            ..
+        }] => {}
+        other => panic!("Unexpected change records: {:?}", other),
+    }
+}
+
+#[test]
+fn test_privacy_applies_to_user_hives() {
+    use crate::hardening::DATA_COLLECTION_KEY;
+    use pieuvre_common::{MemoryRegistry, RegistryBackend, RegistryPath};
+
+    let reg = MemoryRegistry::new();
+    for sid in ["S-1-5-21-1000", ".DEFAULT", "S-1-5-18"] {
+        reg.create_key(&RegistryPath::hku(sid)).unwrap();
+    }
+
+    crate::privacy_o_o::apply_all_recommended_privacy_with(&reg).unwrap();
+
+    let hklm = RegistryPath::hklm(DATA_COLLECTION_KEY);
+    assert_eq!(reg.read_dword(&hklm, "AllowTelemetry").unwrap(), Some(0));
+    let user = RegistryPath::hku(format!(r"S-1-5-21-1000\{}", DATA_COLLECTION_KEY));
+    assert_eq!(reg.read_dword(&user, "AllowTelemetry").unwrap(), Some(0));
+    // Les comptes système (S-1-5-18) ne sont pas modifiés
+    let system = RegistryPath::hku(format!(r"S-1-5-18\{}", DATA_COLLECTION_KEY));
+    assert!(!reg.key_exists(&system));
+}
+
+// ============================================================================
+// TESTS SERVICE MANAGER (SCM en mémoire, aucun service réel modifié)
+// ============================================================================
+
+#[tokio::test]
+async fn test_service_operation_apply_and_rollback() {
+    use crate::operation::{ServiceOperation, SyncOperation};
+    use pieuvre_common::{MemoryRegistry, MemoryService, MemoryServiceManager, ServiceManager};
+    use std::sync::Arc;
+
+    let scm =
+        Arc::new(MemoryServiceManager::new().with_service(MemoryService::new("DiagTrack", 2)));
+    let op = ServiceOperation::new("DiagTrack", 4).with_services(scm.clone());
+
+    assert!(!op.is_applied().await.unwrap());
+    let changes = op.apply().await.unwrap();
+    assert_eq!(scm.start_type("diagtrack").unwrap(), 4);
+    // Déjà appliqué : aucun nouveau ChangeRecord
+    assert!(op.apply().await.unwrap().is_empty());
+
+    crate::rollback::rollback_changes_with(changes, Arc::new(MemoryRegistry::new()), scm.clone())
+        .await
+        .unwrap();
+    assert_eq!(scm.start_type("DiagTrack").unwrap(), 2);
+}
+
+#[test]
+fn test_sentinel_repairs_service_drift() {
+    use crate::hardening::{LOCKED_SERVICE_SDDL, SERVICE_DIAGTRACK, SERVICE_SYSMAIN};
+    use crate::sentinel::monitor::Sentinel;
+    use pieuvre_common::{MemoryService, MemoryServiceManager, ServiceCall};
+
+    let scm = MemoryServiceManager::new()
+        .with_service(MemoryService::new(SERVICE_DIAGTRACK, 4))
+        .with_service(MemoryService::new(SERVICE_SYSMAIN, 4).sddl(LOCKED_SERVICE_SDDL));
+
+    // Seul DiagTrack a dérivé ; les services absents sont ignorés
+    assert_eq!(Sentinel::repair_services(&scm), vec![SERVICE_DIAGTRACK]);
+    assert_eq!(
+        scm.service(SERVICE_DIAGTRACK).unwrap().sddl,
+        LOCKED_SERVICE_SDDL
+    );
+    assert!(!scm.calls().contains(&(
+        ServiceCall::SetSecurityDescriptor,
+        SERVICE_SYSMAIN.to_string()
+    )));
+
+    assert!(Sentinel::repair_services(&scm).is_empty());
+}
+
+#[test]
+fn test_lock_service_scripted_failure() {
+    use pieuvre_common::{MemoryService, MemoryServiceManager, PieuvreError, ServiceCall};
+
+    let scm = MemoryServiceManager::new()
+        .with_service(MemoryService::new("WerSvc", 3))
+        .fail_on(ServiceCall::SetSecurityDescriptor, "WerSvc");
+
+    assert!(matches!(
+        crate::hardening::lock_service_with(&scm, "WerSvc"),
+        Err(PieuvreError::Internal(_))
+    ));
+    assert!(matches!(
+        crate::hardening::lock_service_with(&scm, "Missing"),
+        Err(PieuvreError::ServiceNotFound(_))
+    ));
+}
+
+// ============================================================================
+// TESTS CATALOGUE DE TWEAKS (backends en mémoire)
+// ============================================================================
+
+const TEST_CATALOG: &str = r#"
+[[tweak]]
+id = "site_telemetry"
+label = "Télémétrie site"
+category = "privacy"
+risk = "Low"
+default = true
+
+[[tweak.actions]]
+type = "registry_set"
+key = 'HKLM\SOFTWARE\Policies\Microsoft\Windows\DataCollection'
+value = "AllowTelemetry"
+data = 0
+
+[[tweak.actions]]
+type = "registry_delete"
+key = 'HKCU\Software\Pieuvre'
+value = "Legacy"
+
+[[tweak.actions]]
+type = "service"
+name = "DiagTrack"
+start_type = "Disabled"
+
+[[tweak]]
+id = "site_banner"
+label = "Bannière"
+category = "site"
+risk = "Safe"
+
+[[tweak.actions]]
+type = "registry_set"
+key = 'HKLM\SOFTWARE\Pieuvre'
+value = "Banner"
+data = "managed"
+
+[[tweak.check]]
+type = "registry_set"
+key = 'HKLM\SOFTWARE\Pieuvre'
+value = "Managed"
+data = 1
+"#;
+
+#[tokio::test]
+async fn test_catalog_operation_apply_and_check() {
+    use crate::catalog::{CatalogOperation, TweakCatalog, TweakCheck, TweakRisk};
+    use crate::operation::SyncOperation;
+    use pieuvre_common::{
+        ChangeRecord, MemoryRegistry, MemoryService, MemoryServiceManager, RegistryBackend,
+        RegistryPath, RegistryValue, ServiceManager,
+    };
+    use std::sync::Arc;
+
+    let catalog = TweakCatalog::from_toml(TEST_CATALOG).unwrap();
+    assert_eq!(catalog.tweaks().len(), 2);
+    let tweak = catalog.get("site_telemetry").unwrap();
+    assert_eq!(tweak.risk, TweakRisk::Low);
+    assert_eq!(tweak.check, TweakCheck::default());
+
+    let reg = Arc::new(MemoryRegistry::new().with_value(
+        RegistryPath::hkcu(r"Software\Pieuvre"),
+        "Legacy",
+        RegistryValue::Dword(1),
+    ));
+    let scm =
+        Arc::new(MemoryServiceManager::new().with_service(MemoryService::new("DiagTrack", 2)));
+    let op = CatalogOperation::new(tweak.clone())
+        .with_backend(reg.clone())
+        .with_services(scm.clone());
+
+    assert!(!op.is_applied().await.unwrap());
+    let changes = op.apply().await.unwrap();
+    assert_eq!(changes.len(), 3);
+    assert!(matches!(
+        &changes[1],
+        ChangeRecord::Registry {
+            original_value: Some(RegistryValue::Dword(1)),
+            ..
+        }
+    ));
+    assert_eq!(scm.start_type("DiagTrack").unwrap(), 4);
+    assert!(op.is_applied().await.unwrap());
+    // Idempotent : rien à enregistrer au second passage
+    assert!(op.apply().await.unwrap().is_empty());
+
+    // Vérification explicite : indépendante des actions
+    let banner = CatalogOperation::new(catalog.get("site_banner").unwrap().clone())
+        .with_backend(reg.clone());
+    banner.apply().await.unwrap();
+    assert!(!banner.is_applied().await.unwrap());
+    reg.write_dword(&RegistryPath::hklm(r"SOFTWARE\Pieuvre"), "Managed", 1)
+        .unwrap();
+    assert!(banner.is_applied().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_catalog_plan_is_read_only() {
+    use crate::catalog::{CatalogOperation, TweakCatalog};
+    use crate::operation::SyncOperation;
+    use pieuvre_common::{
+        MemoryRegistry, MemoryService, MemoryServiceManager, PlannedChange, RegistryBackend,
+        RegistryHive, RegistryPath, RegistryValue, ServiceManager,
+    };
+    use std::sync::Arc;
+
+    let catalog = TweakCatalog::from_toml(TEST_CATALOG).unwrap();
+    let telemetry = RegistryPath::hklm(r"SOFTWARE\Policies\Microsoft\Windows\DataCollection");
+    let reg = Arc::new(
+        MemoryRegistry::new()
+            .with_value(telemetry.clone(), "AllowTelemetry", RegistryValue::Dword(3))
+            .with_value(
+                RegistryPath::hkcu(r"Software\Pieuvre"),
+                "Legacy",
+                RegistryValue::Dword(1),
+            ),
+    );
+    let scm =
+        Arc::new(MemoryServiceManager::new().with_service(MemoryService::new("DiagTrack", 2)));
+    let op = CatalogOperation::new(catalog.get("site_telemetry").unwrap().clone())
+        .with_backend(reg.clone())
+        .with_services(scm.clone());
+
+    let planned = op.plan().await.unwrap();
+    assert_eq!(
+        planned,
+        vec![
+            PlannedChange::Registry {
+                hive: RegistryHive::Hklm,
+                key: telemetry.key.clone(),
+                value_name: "AllowTelemetry".to_string(),
+                current: Some(RegistryValue::Dword(3)),
+                target: Some(RegistryValue::Dword(0)),
+            },
+            PlannedChange::Registry {
+                hive: RegistryHive::Hkcu,
+                key: r"Software\Pieuvre".to_string(),
+                value_name: "Legacy".to_string(),
+                current: Some(RegistryValue::Dword(1)),
+                target: None,
+            },
+            PlannedChange::Service {
+                name: "DiagTrack".to_string(),
+                current_start_type: Some(2),
+                target_start_type: 4,
+            },
+        ]
+    );
+    // Rien n'a été modifié
+    assert_eq!(
+        reg.read_dword(&telemetry, "AllowTelemetry").unwrap(),
+        Some(3)
+    );
+    assert_eq!(scm.start_type("DiagTrack").unwrap(), 2);
+
+    // Le plan annonce exactement ce que l'application enregistre
+    assert_eq!(op.apply().await.unwrap().len(), planned.len());
+    assert!(op.plan().await.unwrap().is_empty());
+}
+
+#[test]
+fn test_catalog_validation_and_merge() {
+    use crate::catalog::TweakCatalog;
+    use pieuvre_common::PieuvreError;
+
+    let invalid = r#"
+[[tweak]]
+id = "Bad Id"
+label = "x"
+category = "privacy"
+risk = "Low"
+actions = [{ type = "registry_set", key = 'HKXX\Software', value = "v", data = 1 }]
+
+[[tweak]]
+id = "dup"
+label = "x"
+category = "privacy"
+risk = "Low"
+actions = [{ type = "scheduled_task", path = "Microsoft\\Task", enabled = false }]
+
+[[tweak]]
+id = "dup"
+label = "x"
+category = "privacy"
+risk = "Low"
+actions = []
+"#;
+    match TweakCatalog::from_toml(invalid) {
+        Err(PieuvreError::Config(msg)) => {
+            for expected in [
+                "snake_case",
+                "HKXX",
+                "absolute path",
+                "duplicate id",
+                "at least one",
+            ] {
+                assert!(msg.contains(expected), "missing '{}' in {}", expected, msg);
+            }
+        }
+        other => panic!("Expected config error, got {:?}", other),
+    }
+    assert!(TweakCatalog::from_toml("[[tweak]]\nid = \"x\"\nunknown = 1\n").is_err());
+
+    let mut catalog = TweakCatalog::from_toml(TEST_CATALOG).unwrap();
+    let pack = TweakCatalog::from_json(
+        r#"{"tweak": [
+            {"id": "site_banner", "label": "Bannière v2", "category": "site", "risk": "Safe",
+             "check": "none",
+             "actions": [{"type": "hosts_block", "domains": ["ads.example.com"]}]},
+            {"id": "site_extra", "label": "Extra", "category": "site", "risk": "Low",
+             "actions": [{"type": "service", "name": "Spooler", "start_type": "Manual"}]}
+        ]}"#,
+    )
+    .unwrap();
+    catalog.merge(pack);
+    assert_eq!(catalog.tweaks().len(), 3);
+    assert_eq!(catalog.get("site_banner").unwrap().label, "Bannière v2");
+    assert!(catalog.contains("site_extra"));
+}
+
+#[test]
+fn test_tweak_graph_resolution() {
+    use crate::catalog::TweakCatalog;
+    use pieuvre_common::PieuvreError;
+
+    let catalog = TweakCatalog::from_toml(
+        r#"
+[[relation]]
+tweak = "lock"
+requires = ["level"]
+conflicts = ["unlock"]
+reason = "lock and unlock are mutually exclusive"
+
+[[relation]]
+tweak = "privacy"
+implies = ["widgets", "wudo"]
+
+[[relation]]
+tweak = "widgets"
+implies = ["privacy"]
+
+[[relation]]
+tweak = "wudo"
+implies = ["privacy"]
+
+[[relation]]
+tweak = "reset"
+conflicts = ["widgets"]
+"#,
+    )
+    .unwrap();
+    let graph = catalog.graph();
+    let ids = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    // Dépendance ajoutée et placée avant ; tweaks partageant une commande dédupliqués
+    let resolution = graph
+        .resolve(&ids(&["widgets", "lock", "timer", "wudo", "privacy"]))
+        .unwrap();
+    assert_eq!(
+        resolution.order,
+        ids(&["widgets", "level", "lock", "timer"])
+    );
+    assert_eq!(
+        resolution.added,
+        vec![("level".to_string(), "lock".to_string())]
+    );
+    assert_eq!(resolution.skipped.len(), 2);
+    assert!(resolution.notes()[0].contains("required by lock"));
+
+    // Conflit direct, ou hérité d'une implication
+    for selection in [&["unlock", "lock"][..], &["privacy", "reset"][..]] {
+        match graph.resolve(&ids(selection)) {
+            Err(PieuvreError::Config(msg)) => assert!(msg.contains("Conflicting"), "{}", msg),
+            other => panic!("Expected conflict, got {:?}", other),
+        }
+    }
+    assert_eq!(
+        graph.conflicts_in(&ids(&["lock", "unlock"]))[0].reason,
+        "lock and unlock are mutually exclusive"
+    );
+
+    let mut cyclic = crate::graph::TweakGraph::new();
+    cyclic.add_requires("a", "b");
+    cyclic.add_requires("b", "a");
+    assert!(cyclic.resolve(&ids(&["a"])).is_err());
+
+    assert!(TweakCatalog::from_toml("[[relation]]\ntweak = \"a\"\nrequires = [\"a\"]\n").is_err());
+}
+
+#[test]
+fn test_baseline_capture_and_apply() {
+    use crate::baseline::{apply_with, capture_with, BaselineTargets};
+    use crate::catalog::TweakCatalog;
+    use crate::changes::record;
+    use pieuvre_common::{
+        ChangeRecord, MemoryRegistry, MemoryService, MemoryServiceManager, RegistryBackend,
+        RegistryPath, RegistryValue, ServiceManager,
+    };
+
+    let catalog = TweakCatalog::from_toml(TEST_CATALOG).unwrap();
+    let mut targets = BaselineTargets::from_catalog(&catalog);
+    assert_eq!(targets.registry_values.len(), 3);
+    assert_eq!(targets.services, vec!["DiagTrack".to_string()]);
+    // Doublons ignorés, casse comprise
+    targets.merge(BaselineTargets::from_catalog(&catalog));
+    targets.merge(BaselineTargets {
+        services: vec!["diagtrack".to_string(), "Missing".to_string()],
+        ..Default::default()
+    });
+    assert_eq!(targets.len(), 5);
+
+    let telemetry = RegistryPath::hklm(r"SOFTWARE\Policies\Microsoft\Windows\DataCollection");
+    let legacy = RegistryPath::hkcu(r"Software\Pieuvre");
+    let reg = MemoryRegistry::new()
+        .with_value(telemetry.clone(), "AllowTelemetry", RegistryValue::Dword(3))
+        .with_value(
+            legacy.clone(),
+            "Legacy",
+            RegistryValue::String("old".into()),
+        );
+    let scm = MemoryServiceManager::new().with_service(MemoryService::new("DiagTrack", 2));
+
+    let baseline = capture_with(&targets, &reg, &scm);
+    // 3 valeurs (dont Banner, absente) et DiagTrack ; Missing n'existe pas
+    assert_eq!(baseline.len(), 4);
+    assert!(baseline.contains(&ChangeRecord::Registry {
+        hive: pieuvre_common::RegistryHive::Hklm,
+        key: r"SOFTWARE\Pieuvre".to_string(),
+        value_name: "Banner".to_string(),
+        original_value: None,
+    }));
+
+    // Le système dérive, puis la baseline est réappliquée
+    reg.write_dword(&telemetry, "AllowTelemetry", 0).unwrap();
+    reg.delete_value(&legacy, "Legacy").unwrap();
+    reg.write_string(
+        &RegistryPath::hklm(r"SOFTWARE\Pieuvre"),
+        "Banner",
+        "managed",
+    )
+    .unwrap();
+    scm.set_start_type("DiagTrack", 4).unwrap();
+
+    let (result, changes) = record(|| apply_with(&baseline, &reg, &scm));
+    result.unwrap();
+    assert_eq!(
+        reg.read_dword(&telemetry, "AllowTelemetry").unwrap(),
+        Some(3)
+    );
+    assert_eq!(
+        reg.read_string(&legacy, "Legacy").unwrap(),
+        Some("old".to_string())
+    );
+    assert_eq!(
+        reg.read_value(&RegistryPath::hklm(r"SOFTWARE\Pieuvre"), "Banner")
+            .unwrap(),
+        None
+    );
+    assert_eq!(scm.start_type("DiagTrack").unwrap(), 2);
+    // La remise à zéro est elle-même enregistrée (annulable)
+    assert_eq!(changes.len(), 4);
+    assert!(changes.contains(&ChangeRecord::Service {
+        name: "DiagTrack".to_string(),
+        original_start_type: 4,
+    }));
+
+    // Déjà conforme : rien à écrire, rien d'enregistré
+    let (result, changes) = record(|| apply_with(&baseline, &reg, &scm));
+    result.unwrap();
+    assert!(changes.is_empty());
+}
+
+#[test]
+fn test_baseline_builtin_covers_security_values() {
+    use crate::baseline::{
+        capture_with, covers_power, covers_service, covers_value, BaselineTargets,
+    };
+    use crate::hardening::{HVCI_KEY, MEMORY_MANAGEMENT_KEY, UAC_POLICIES_KEY};
+    use pieuvre_common::{MemoryRegistry, MemoryServiceManager, RegistryPath, RegistryValue};
+
+    let hvci = RegistryPath::hklm(HVCI_KEY);
+    let reg = MemoryRegistry::new().with_value(hvci.clone(), "Enabled", RegistryValue::Dword(1));
+    let records = capture_with(
+        &BaselineTargets::builtin(),
+        &reg,
+        &MemoryServiceManager::new(),
+    );
+
+    // HVCI, mitigations (absentes) et UAC : la remise à zéro les réécrit à l'identique
+    assert!(covers_value(&records, &hvci, "enabled"));
+    assert!(covers_value(
+        &records,
+        &RegistryPath::hklm(MEMORY_MANAGEMENT_KEY),
+        "FeatureSettingsOverride"
+    ));
+    assert!(covers_value(
+        &records,
+        &RegistryPath::hklm(UAC_POLICIES_KEY),
+        "PromptOnSecureDesktop"
+    ));
+    // Services absents, pas de plan capturé : valeurs génériques
+    assert!(!covers_service(&records, "DiagTrack"));
+    assert!(!covers_power(&records));
+}
+
+#[test]
+fn test_hosts_block_inserts_missing_domains() {
+    use crate::hosts::with_blocked_domains;
+
+    let domains = vec!["a.example.com".to_string(), "b.example.com".to_string()];
+    let (content, added) = with_blocked_domains("127.0.0.1 localhost\n", &domains);
+    assert_eq!(added, 2);
+    assert!(content.contains("0.0.0.0 a.example.com\n0.0.0.0 b.example.com\n# ==="));
+
+    // Bloc existant : seuls les domaines manquants sont ajoutés, dans le bloc
+    let more = vec!["b.example.com".to_string(), "c.example.com".to_string()];
+    let (updated, added) = with_blocked_domains(&content, &more);
+    assert_eq!(added, 1);
+    assert!(updated
+        .trim_end()
+        .ends_with("0.0.0.0 c.example.com\n# === PIEUVRE TELEMETRY BLOCK END ==="));
+    assert_eq!(with_blocked_domains(&updated, &more).1, 0);
+}
+
+#[test]
+fn test_hosts_unblock_removes_only_pieuvre_entries() {
+    use crate::hosts::{with_blocked_domains, without_blocked_domains};
+
+    let original = "127.0.0.1 localhost\n";
+    let domains = vec!["a.example.com".to_string(), "b.example.com".to_string()];
+    let (blocked, _) = with_blocked_domains(original, &domains);
+
+    let partial = without_blocked_domains(&blocked, &domains[..1]);
+    assert!(!partial.contains("a.example.com"));
+    assert!(partial.contains("0.0.0.0 b.example.com"));
+
+    // Bloc vidé : marqueurs retirés, le reste du fichier est intact
+    let restored = without_blocked_domains(&partial, &domains[1..]);
+    assert!(!restored.contains("PIEUVRE"));
+    assert!(restored.contains("127.0.0.1 localhost"));
+}
+
+// ============================================================================
+// TESTS ENREGISTREMENT DES CHANGEMENTS ET ROLLBACK (backends en mémoire)
+// ============================================================================
+
+#[test]
+fn test_recorded_registry_changes_restore() {
+    use crate::changes::record;
+    use crate::registry::{delete_value_with, set_dword_value_with};
+    use crate::rollback::restore_change_with;
+    use pieuvre_common::{
+        ChangeRecord, MemoryRegistry, MemoryServiceManager, PieuvreError, RegistryBackend,
+        RegistryPath, RegistryValue,
+    };
+
+    let key = r"SOFTWARE\Pieuvre\Recorded";
+    let path = RegistryPath::hklm(key);
+    let reg = MemoryRegistry::new()
+        .with_value(path.clone(), "Existing", RegistryValue::Dword(7))
+        .with_value(path.clone(), "Removed", RegistryValue::String("x".into()));
+
+    let (result, changes) = record(|| {
+        set_dword_value_with(&reg, key, "Existing", 1)?;
+        // Même cible : seul le premier état d'origine est conservé
+        set_dword_value_with(&reg, key, "existing", 2)?;
+        set_dword_value_with(&reg, key, "Created", 5)?;
+        // Valeur déjà à la cible : aucun changement
+        set_dword_value_with(&reg, key, "Created", 5)?;
+        let (inner, nested) = record(|| delete_value_with(&reg, &path, "Removed"));
+        assert_eq!(nested.len(), 1);
+        inner?;
+        Err::<(), _>(PieuvreError::Internal("stop".into()))
+    });
+    assert!(result.is_err());
+    assert_eq!(changes.len(), 3);
+    assert_eq!(
+        changes[0],
+        ChangeRecord::Registry {
+            hive: pieuvre_common::RegistryHive::Hklm,
+            key: key.to_string(),
+            value_name: "Existing".to_string(),
+            original_value: Some(RegistryValue::Dword(7)),
+        }
+    );
+
+    let scm = MemoryServiceManager::new();
+    for change in changes.iter().rev() {
+        restore_change_with(change, &reg, &scm).unwrap();
+    }
+    assert_eq!(reg.read_dword(&path, "Existing").unwrap(), Some(7));
+    assert_eq!(reg.read_value(&path, "Created").unwrap(), None);
+    assert_eq!(
+        reg.read_string(&path, "Removed").unwrap(),
+        Some("x".to_string())
+    );
+    // Hors enregistrement, rien n'est collecté
+    assert!(!crate::changes::is_recording());
+}
+
+/// Registre dont la lecture de « Locked » est refusée (ACL), le reste délégué
+mod unreadable {
+    use pieuvre_common::{
+        MemoryRegistry, PieuvreError, RegistryBackend, RegistryPath, RegistryValue, Result,
+    };
+
+    pub struct Unreadable(pub MemoryRegistry);
+
+    impl RegistryBackend for Unreadable {
+        fn name(&self) -> &str {
+            "unreadable"
+        }
+        fn key_exists(&self, path: &RegistryPath) -> bool {
+            self.0.key_exists(path)
+        }
+        fn read_value(&self, path: &RegistryPath, name: &str) -> Result<Option<RegistryValue>> {
+            if name == "Locked" {
+                return Err(PieuvreError::Registry("access denied".into()));
+            }
+            self.0.read_value(path, name)
+        }
+        fn write_value(
+            &self,
+            path: &RegistryPath,
+            name: &str,
+            value: &RegistryValue,
+        ) -> Result<()> {
+            self.0.write_value(path, name, value)
+        }
+        fn delete_value(&self, path: &RegistryPath, name: &str) -> Result<()> {
+            self.0.delete_value(path, name)
+        }
+        fn create_key(&self, path: &RegistryPath) -> Result<()> {
+            self.0.create_key(path)
+        }
+        fn delete_key(&self, path: &RegistryPath) -> Result<()> {
+            self.0.delete_key(path)
+        }
+        fn list_subkeys(&self, path: &RegistryPath) -> Result<Vec<String>> {
+            self.0.list_subkeys(path)
+        }
+        fn list_values(&self, path: &RegistryPath) -> Result<Vec<(String, RegistryValue)>> {
+            self.0.list_values(path)
+        }
+    }
+}
+
+#[test]
+fn test_unreadable_original_value_refuses_write() {
+    use crate::changes::record;
+    use crate::registry::set_dword_value_with;
+    use pieuvre_common::{MemoryRegistry, RegistryBackend, RegistryPath, RegistryValue};
+
+    let key = r"SOFTWARE\Pieuvre\Unreadable";
+    let path = RegistryPath::hklm(key);
+    let reg = unreadable::Unreadable(MemoryRegistry::new().with_value(
+        path.clone(),
+        "Locked",
+        RegistryValue::Dword(7),
+    ));
+
+    let (result, changes) = record(|| set_dword_value_with(&reg, key, "Locked", 1));
+    assert!(result.is_err());
+    // Rien d'enregistré, rien d'écrit : un rollback ne supprimera pas la valeur
+    assert!(changes.is_empty());
+    assert_eq!(reg.0.read_dword(&path, "Locked").unwrap(), Some(7));
+}
+
+#[tokio::test]
+async fn test_registry_operation_unreadable_original_value_refuses_write() {
+    use crate::operation::{RegistryDwordOperation, SyncOperation};
+    use pieuvre_common::{MemoryRegistry, RegistryBackend, RegistryPath, RegistryValue};
+    use std::sync::Arc;
+
+    let key = r"SOFTWARE\Pieuvre\Unreadable";
+    let path = RegistryPath::hklm(key);
+    let reg = Arc::new(unreadable::Unreadable(MemoryRegistry::new().with_value(
+        path.clone(),
+        "Locked",
+        RegistryValue::Dword(7),
+    )));
+    let op = RegistryDwordOperation::new(key, "Locked", 1).with_backend(reg.clone());
+
+    assert!(op.apply().await.is_err());
+    assert_eq!(reg.0.read_dword(&path, "Locked").unwrap(), Some(7));
+}
+
+#[tokio::test]
+async fn test_catalog_unreadable_original_value_refuses_write() {
+    use crate::catalog::{CatalogOperation, TweakCatalog};
+    use crate::operation::SyncOperation;
+    use pieuvre_common::{MemoryRegistry, RegistryBackend, RegistryPath, RegistryValue};
+    use std::sync::Arc;
+
+    let catalog = TweakCatalog::from_toml(
+        r#"
+[[tweak]]
+id = "site_locked"
+label = "Valeur protégée"
+category = "site"
+risk = "Safe"
+
+[[tweak.actions]]
+type = "registry_set"
+key = 'HKLM\SOFTWARE\Pieuvre'
+value = "Locked"
+data = 1
+"#,
+    )
+    .unwrap();
+    let path = RegistryPath::hklm(r"SOFTWARE\Pieuvre");
+    let reg = Arc::new(unreadable::Unreadable(MemoryRegistry::new().with_value(
+        path.clone(),
+        "Locked",
+        RegistryValue::Dword(7),
+    )));
+    let op = CatalogOperation::new(catalog.get("site_locked").unwrap().clone())
+        .with_backend(reg.clone());
+
+    // Valeur d'origine inconnue : pas d'écriture qu'un rollback effacerait
+    assert!(op.apply().await.is_err());
+    assert_eq!(reg.0.read_dword(&path, "Locked").unwrap(), Some(7));
+}
+
+#[test]
+fn test_changes_journaled_before_mutation() {
+    use crate::changes::{record, record_journaled, ChangeJournal};
+    use crate::registry::set_dword_value_with;
+    use pieuvre_common::{
+        ChangeRecord, MemoryRegistry, PieuvreError, RegistryBackend, RegistryPath, RegistryValue,
+    };
+    use std::sync::{Arc, Mutex};
+
+    const KEY: &str = r"SOFTWARE\Pieuvre\Journal";
+
+    /// Note la valeur en place au moment de la journalisation
+    struct Journal {
+        registry: Arc<MemoryRegistry>,
+        entries: Mutex<Vec<(ChangeRecord, Option<u32>)>>,
+        fail: bool,
+    }
+
+    impl ChangeJournal for Journal {
+        fn append(&self, change: &ChangeRecord) -> pieuvre_common::Result<()> {
+            if self.fail {
+                return Err(PieuvreError::Internal("disk full".into()));
+            }
+            let current = self
+                .registry
+                .read_dword(&RegistryPath::hklm(KEY), "Level")
+                .unwrap();
+            self.entries.lock().unwrap().push((change.clone(), current));
+            Ok(())
+        }
+    }
+
+    let registry = Arc::new(MemoryRegistry::new().with_value(
+        RegistryPath::hklm(KEY),
+        "Level",
+        RegistryValue::Dword(0),
+    ));
+    let journal = Arc::new(Journal {
+        registry: registry.clone(),
+        entries: Mutex::new(Vec::new()),
+        fail: false,
+    });
+
+    // Un record imbriqué écrit dans le journal de l'appelant
+    let (result, changes) = record_journaled(journal.clone(), || {
+        record(|| set_dword_value_with(registry.as_ref(), KEY, "Level", 1)).0
+    });
+    result.unwrap();
+    let entries = journal.entries.lock().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].0, changes[0]);
+    assert_eq!(entries[0].1, Some(0));
+
+    // Journal en échec : la modification n'est pas faite
+    let failing = Arc::new(Journal {
+        registry: registry.clone(),
+        entries: Mutex::new(Vec::new()),
+        fail: true,
+    });
+    let (result, changes) = record_journaled(failing, || {
+        set_dword_value_with(registry.as_ref(), KEY, "Level", 2)
+    });
+    assert!(result.is_err());
+    assert!(changes.is_empty());
+    assert_eq!(
+        registry
+            .read_dword(&RegistryPath::hklm(KEY), "Level")
+            .unwrap(),
+        Some(1)
+    );
+}
+
+#[test]
+fn test_key_creation_and_deletion_restored() {
+    use crate::changes::record;
+    use crate::registry::{delete_key_with, set_value_with};
+    use crate::rollback::restore_change_with;
+    use pieuvre_common::{
+        ChangeRecord, MemoryRegistry, MemoryServiceManager, RegistryBackend, RegistryHive,
+        RegistryPath, RegistryValue,
+    };
+
+    let user = RegistryPath::hku(r"S-1-5-21-1\Software\Classes\Handler");
+    let reg = MemoryRegistry::new()
+        .with_value(
+            user.clone(),
+            "Path",
+            RegistryValue::ExpandString(r"%ProgramFiles%\x.dll".into()),
+        )
+        .with_value(
+            user.join("Sub"),
+            "List",
+            RegistryValue::MultiString(vec!["a".into(), "b".into()]),
+        )
+        .with_value(user.join("Sub"), "Big", RegistryValue::Qword(1 << 40));
+    reg.create_key(&RegistryPath::hklm("SOFTWARE")).unwrap();
+    let created = RegistryPath::hklm(r"SOFTWARE\Pieuvre\New\Deep");
+
+    let (result, changes) = record(|| {
+        set_value_with(&reg, &created, "Flag", &RegistryValue::Dword(1))?;
+        delete_key_with(&reg, &user)?;
+        // Clé absente : rien à enregistrer
+        delete_key_with(&reg, &RegistryPath::hku(r"S-1-5-21-1\Missing"))
+    });
+    result.unwrap();
+    assert_eq!(changes.len(), 3);
+    // Seule la plus haute clé absente est signalée
+    assert_eq!(
+        changes[0],
+        ChangeRecord::RegistryKeyCreated {
+            hive: RegistryHive::Hklm,
+            key: r"SOFTWARE\Pieuvre".to_string(),
+        }
+    );
+    assert!(!reg.key_exists(&user));
+
+    let scm = MemoryServiceManager::new();
+    for change in changes.iter().rev() {
+        restore_change_with(change, &reg, &scm).unwrap();
+    }
+    assert!(!reg.key_exists(&RegistryPath::hklm(r"SOFTWARE\Pieuvre")));
+    assert!(reg.key_exists(&RegistryPath::hklm("SOFTWARE")));
+    assert_eq!(
+        reg.read_string(&user, "Path").unwrap().as_deref(),
+        Some(r"%ProgramFiles%\x.dll")
+    );
+    assert_eq!(
+        reg.read_value(&user.join("Sub"), "Big").unwrap(),
+        Some(RegistryValue::Qword(1 << 40))
+    );
+    assert_eq!(
+        reg.read_value(&user.join("Sub"), "List").unwrap(),
+        Some(RegistryValue::MultiString(vec!["a".into(), "b".into()]))
+    );
+}
+
+#[test]
+fn test_service_dacl_lock_recorded_and_restored() {
+    use crate::hardening::{lock_service_with, LOCKED_SERVICE_SDDL};
+    use pieuvre_common::{
+        ChangeRecord, DaclTarget, MemoryRegistry, MemoryService, MemoryServiceManager,
+        ServiceManager,
+    };
+
+    let original = "D:(A;;CCLCSWRPWPDTLOCRRC;;;SY)(A;;CCLCSWLOCRRC;;;BA)";
+    let scm =
+        MemoryServiceManager::new().with_service(MemoryService::new("WerSvc", 3).sddl(original));
+
+    let (result, changes) = crate::changes::record(|| lock_service_with(&scm, "WerSvc"));
+    result.unwrap();
+    assert_eq!(
+        scm.security_descriptor("WerSvc").unwrap(),
+        LOCKED_SERVICE_SDDL
+    );
+    assert_eq!(
+        changes,
+        vec![ChangeRecord::Dacl {
+            target: DaclTarget::Service {
+                name: "WerSvc".to_string()
+            },
+            original_sddl: original.to_string(),
+        }]
+    );
+
+    crate::rollback::restore_change_with(&changes[0], &MemoryRegistry::new(), &scm).unwrap();
+    assert_eq!(scm.security_descriptor("WerSvc").unwrap(), original);
+}
+
+#[test]
+fn test_power_and_hosts_records_are_merged() {
+    use crate::changes::{push, record};
+    use pieuvre_common::{ChangeRecord, PowerSettingRecord};
+
+    let setting = |ac: Option<u32>| PowerSettingRecord {
+        scheme: "scheme".to_string(),
+        subgroup: "sub".to_string(),
+        setting: "cores".to_string(),
+        original_ac: ac,
+        original_dc: None,
+    };
+    let (_, changes) = record(|| {
+        push(ChangeRecord::PowerScheme {
+            original_scheme: Some("balanced".to_string()),
+            settings: vec![setting(Some(50))],
+        })?;
+        push(ChangeRecord::PowerScheme {
+            original_scheme: Some("high".to_string()),
+            settings: vec![setting(Some(100))],
+        })?;
+        push(ChangeRecord::HostsBlock {
+            domains: vec!["a.example.com".to_string()],
+        })?;
+        push(ChangeRecord::HostsBlock {
+            domains: vec!["a.example.com".to_string(), "b.example.com".to_string()],
+        })?;
+        Ok(())
+    });
+
+    assert_eq!(
+        changes,
+        vec![
+            ChangeRecord::PowerScheme {
+                original_scheme: Some("balanced".to_string()),
+                settings: vec![setting(Some(50))],
+            },
+            ChangeRecord::HostsBlock {
+                domains: vec!["a.example.com".to_string(), "b.example.com".to_string()],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_bcd_parse_element() {
+    use crate::bcd::parse_element;
+
+    let output = "Windows Boot Loader\n\
+                  -------------------\n\
+                  identifier              {current}\n\
+                  disabledynamictick      Yes\n\
+                  useplatformtick         No\n";
+    assert_eq!(
+        parse_element(output, "DisableDynamicTick"),
+        Some("Yes".to_string())
+    );
+    assert_eq!(parse_element(output, "useplatformclock"), None);
+}
+
+// ============================================================================
+// TESTS TRANSACTIONS (backends en mémoire)
+// ============================================================================
+
+#[tokio::test]
+async fn test_transaction_rolls_back_on_failure() {
+    use crate::catalog::{CatalogOperation, TweakCatalog};
+    use crate::operation::RegistryDwordOperation;
+    use crate::transaction::Transaction;
+    use pieuvre_common::{
+        MemoryRegistry, MemoryServiceManager, RegistryBackend, RegistryPath, RegistryValue,
+        RollbackPolicy, TransactionConfig, TransactionOutcome,
+    };
+    use std::sync::Arc;
+
+    let legacy = RegistryPath::hkcu(r"Software\Pieuvre");
+    let priority = RegistryPath::hklm(r"SYSTEM\CurrentControlSet\Control\PriorityControl");
+    let catalog = TweakCatalog::from_toml(TEST_CATALOG).unwrap();
+    let fresh = || {
+        Arc::new(MemoryRegistry::new().with_value(
+            legacy.clone(),
+            "Legacy",
+            RegistryValue::Dword(1),
+        ))
+    };
+    // DiagTrack absent : la 3e action du tweak échoue après les deux premières
+    let scm = Arc::new(MemoryServiceManager::new());
+    let build = |reg: &Arc<MemoryRegistry>, policy: TransactionConfig| {
+        let mut tx = Transaction::new("test")
+            .with_policy(policy)
+            .with_backends(reg.clone(), scm.clone());
+        tx.push(
+            RegistryDwordOperation::new(priority.key.as_str(), "Win32PrioritySeparation", 0x26)
+                .with_backend(reg.clone()),
+        );
+        tx.push(
+            CatalogOperation::new(catalog.get("site_telemetry").unwrap().clone())
+                .with_backend(reg.clone())
+                .with_services(scm.clone()),
+        );
+        tx.push(
+            CatalogOperation::new(catalog.get("site_banner").unwrap().clone())
+                .with_backend(reg.clone()),
+        );
+        tx
+    };
+
+    // Premier échec : tout est annulé, y compris la partie faite par l'étape en échec
+    let reg = fresh();
+    let report = build(&reg, TransactionConfig::default())
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(report.outcome, TransactionOutcome::RolledBack);
+    assert_eq!(report.steps.len(), 2);
+    assert_eq!(report.steps[1].changes, 2);
+    assert_eq!(report.changes.len(), 3);
+    assert_eq!(
+        reg.read_dword(&priority, "Win32PrioritySeparation")
+            .unwrap(),
+        None
+    );
+    assert_eq!(reg.read_dword(&legacy, "Legacy").unwrap(), Some(1));
+
+    // Étape non critique : on continue et les changements sont conservés
+    let critical = |names: &[&str]| TransactionConfig {
+        rollback_on: RollbackPolicy::Critical,
+        critical: names.iter().map(|n| n.to_string()).collect(),
+    };
+    let reg = fresh();
+    let report = build(&reg, critical(&["site_banner"])).run().await.unwrap();
+    assert_eq!(report.outcome, TransactionOutcome::Partial);
+    assert_eq!(report.steps.len(), 3);
+    assert_eq!(report.failed_steps().count(), 1);
+    assert_eq!(report.changes.len(), 4);
+    assert_eq!(reg.read_dword(&legacy, "Legacy").unwrap(), None);
+    assert_eq!(
+        reg.read_string(&RegistryPath::hklm(r"SOFTWARE\Pieuvre"), "Banner")
+            .unwrap()
+            .as_deref(),
+        Some("managed")
+    );
+
+    // Étape critique en échec : rollback
+    let reg = fresh();
+    let report = build(&reg, critical(&["site_telemetry"]))
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(report.outcome, TransactionOutcome::RolledBack);
+    assert_eq!(reg.read_dword(&legacy, "Legacy").unwrap(), Some(1));
+}
+
+#[test]
+fn test_validate_change() {
+    use crate::rollback::validate_change;
+    use pieuvre_common::{ChangeRecord, DaclTarget, PowerSettingRecord, RegistryHive};
+
+    let valid = [
+        ChangeRecord::Registry {
+            hive: RegistryHive::Hklm,
+            key: r"SOFTWARE\Pieuvre".to_string(),
+            value_name: String::new(),
+            original_value: None,
+        },
+        ChangeRecord::Service {
+            name: "DiagTrack".to_string(),
+            original_start_type: 2,
+        },
+        ChangeRecord::PowerScheme {
+            original_scheme: Some("381b4222-f694-41f0-9685-ff5bb260df2e".to_string()),
+            settings: vec![],
+        },
+        ChangeRecord::ScheduledTask {
+            path: r"\Microsoft\Windows\Application Experience\ProgramDataUpdater".to_string(),
+            original_enabled: true,
+        },
+        ChangeRecord::Dacl {
+            target: DaclTarget::Service {
+                name: "WerSvc".to_string(),
+            },
+            original_sddl: "D:(A;;CCLCSWRPWPDTLOCRRC;;;SY)".to_string(),
+        },
+    ];
+    for record in &valid {
+        assert!(validate_change(record).is_ok(), "{:?}", record);
+    }
+
+    let invalid = [
+        ChangeRecord::RegistryKeyCreated {
+            hive: RegistryHive::Hkcu,
+            key: " ".to_string(),
+        },
+        ChangeRecord::Service {
+            name: "DiagTrack".to_string(),
+            original_start_type: 7,
+        },
+        ChangeRecord::PowerScheme {
+            original_scheme: None,
+            settings: vec![PowerSettingRecord {
+                scheme: "balanced".to_string(),
+                subgroup: "54533251-82be-4824-96c1-47b60b740d00".to_string(),
+                setting: "0cc5b647-c1df-4637-891a-dec35c318583".to_string(),
+                original_ac: Some(50),
+                original_dc: None,
+            }],
+        },
+        ChangeRecord::ScheduledTask {
+            path: "NoLeadingSlash".to_string(),
+            original_enabled: false,
+        },
+        ChangeRecord::HostsBlock {
+            domains: vec!["ok.example.com".to_string(), "bad domain".to_string()],
+        },
+        ChangeRecord::Dacl {
+            target: DaclTarget::RegistryKey {
+                key: r"SYSTEM\CurrentControlSet\Services\WerSvc".to_string(),
+            },
+            original_sddl: "garbage".to_string(),
+        },
+    ];
+    for record in &invalid {
+        assert!(
+            matches!(
+                validate_change(record),
+                Err(pieuvre_common::PieuvreError::Parse(_))
+            ),
+            "{:?}",
+            record
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Transactions
+//!
+//! Applique une suite de `SyncOperation` en collectant leurs changements.
+//! Un échec fatal (selon `[transaction]` : premier échec ou étape critique) annule
+//! tout ce qui a été appliqué, y compris la partie faite par l'étape en échec.
+
+use crate::operation::SyncOperation;
+use pieuvre_common::{
+    config, ChangeRecord, Result, SharedRegistry, SharedServiceManager, TransactionConfig,
+    TransactionOutcome,
+};
+use tracing::{info, instrument, warn};
+
+/// Résultat d'une étape
+#[derive(Debug, Clone)]
+pub struct StepReport {
+    pub name: String,
+    /// Changements conservés par l'étape (avant un éventuel rollback)
+    pub changes: usize,
+    pub error: Option<String>,
+}
+
+/// Bilan d'une transaction
+#[derive(Debug, Clone)]
+pub struct TransactionReport {
+    pub name: String,
+    pub outcome: TransactionOutcome,
+    /// Tout ce qui a été appliqué, dans l'ordre (restauré si `RolledBack`)
+    pub changes: Vec<ChangeRecord>,
+    /// Étapes exécutées ; celles après un échec fatal n'y figurent pas
+    pub steps: Vec<StepReport>,
+    /// Erreur du rollback, s'il est incomplet
+    pub rollback_error: Option<String>,
+}
+
+impl TransactionReport {
+    pub fn failed_steps(&self) -> impl Iterator<Item = &StepReport> {
+        self.steps.iter().filter(|s| s.error.is_some())
+    }
+
+    /// Résumé d'une ligne
+    pub fn summary(&self) -> String {
+        let failed = self.failed_steps().count();
+        match self.outcome {
+            TransactionOutcome::Success => format!(
+                "success: {} step(s), {} change(s)",
+                self.steps.len(),
+                self.changes.len()
+            ),
+            TransactionOutcome::RolledBack => format!(
+                "rolled back after {} failure(s), {} change(s) restored",
+                failed,
+                self.changes.len()
+            ),
+            TransactionOutcome::Partial => format!(
+                "partial: {} of {} step(s) failed, {} change(s) kept",
+                failed,
+                self.steps.len(),
+                self.changes.len()
+            ),
+        }
+    }
+}
+
+type StepObserver = Box<dyn FnMut(usize, &StepReport) + Send>;
+
+/// Exécution séquentielle avec rollback automatique
+pub struct Transaction {
+    name: String,
+    operations: Vec<Box<dyn SyncOperation>>,
+    policy: TransactionConfig,
+    backends: Option<(SharedRegistry, SharedServiceManager)>,
+    observer: Option<StepObserver>,
+}
+
+impl Transaction {
+    /// Transaction suivant la politique `[transaction]` de la configuration
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            operations: Vec::new(),
+            policy: config().transaction.clone(),
+            backends: None,
+            observer: None,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: TransactionConfig) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Rollback through the given backends instead of the live system (tests, simulation)
+    pub fn with_backends(
+        mut self,
+        registry: SharedRegistry,
+        services: SharedServiceManager,
+    ) -> Self {
+        self.backends = Some((registry, services));
+        self
+    }
+
+    /// Appelé après chaque étape avec son index (progression)
+    pub fn on_step(mut self, observer: impl FnMut(usize, &StepReport) + Send + 'static) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    pub fn push(&mut self, operation: impl SyncOperation + 'static) {
+        self.operations.push(Box::new(operation));
+    }
+
+    pub fn push_boxed(&mut self, operation: Box<dyn SyncOperation>) {
+        self.operations.push(operation);
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    #[instrument(skip(self), fields(name = %self.name, steps = self.operations.len()))]
+    pub async fn run(mut self) -> Result<TransactionReport> {
+        let mut changes = Vec::new();
+        let mut steps = Vec::with_capacity(self.operations.len());
+        let mut fatal = false;
+
+        for (index, operation) in self.operations.iter().enumerate() {
+            let name = operation.name().to_string();
+            let step = match operation.apply().await {
+                Ok(applied) => {
+                    let step = StepReport {
+                        name,
+                        changes: applied.len(),
+                        error: None,
+                    };
+                    changes.extend(applied);
+                    step
+                }
+                Err(e) => {
+                    let (error, applied) = e.split_changes();
+                    warn!(step = %name, error = %error, "Transaction step failed");
+                    fatal = self.policy.is_fatal(&name);
+                    let step = StepReport {
+                        name,
+                        changes: applied.len(),
+                        error: Some(error.to_string()),
+                    };
+                    changes.extend(applied);
+                    step
+                }
+            };
+
+            if let Some(observer) = self.observer.as_mut() {
+                observer(index, &step);
+            }
+            steps.push(step);
+            if fatal {
+                break;
+            }
+        }
+
+        let mut rollback_error = None;
+        let outcome = if fatal {
+            let result = match self.backends.take() {
+                Some((registry, services)) => {
+                    crate::rollback::rollback_changes_with(changes.clone(), registry, services)
+                        .await
+                }
+                None => crate::rollback::rollback_changes(changes.clone()).await,
+            };
+            match result {
+                Ok(()) => TransactionOutcome::RolledBack,
+                Err(e) => {
+                    warn!(error = %e, "Transaction rollback incomplete");
+                    rollback_error = Some(e.to_string());
+                    TransactionOutcome::Partial
+                }
+            }
+        } else if steps.iter().any(|s| s.error.is_some()) {
+            TransactionOutcome::Partial
+        } else {
+            TransactionOutcome::Success
+        };
+
+        let report = TransactionReport {
+            name: self.name,
+            outcome,
+            changes,
+            steps,
+            rollback_error,
+        };
+        info!("Transaction {}: {}", report.name, report.summary());
+        Ok(report)
+    }
+}